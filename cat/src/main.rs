@@ -1,40 +1,46 @@
 #![no_std]
 #![no_main]
 
+extern crate alloc;
+
 use core::panic::PanicInfo;
 
-use kernel::{filesystem::{FileDescriptor, StdFileDescriptor}, print, println, shell::shell_util, sys_call};
+use alloc::vec;
+
+use kernel::{eprintln, filesystem::{FileDescriptor, StdFileDescriptor}, print, println, shell::shell_util, sys_call};
 
 use rrt::{_start, env};
 
 #[inline(never)]
 #[no_mangle]
 pub extern "C" fn main() {
-    let args = env::get_args();
-    if args.is_none() || args.unwrap().trim().is_empty() {
-        println!("please input file path");
+    // 第一个参数是程序名，第二个参数才是文件路径
+    let input_path = env::args().nth(1);
+    if input_path.is_none() || input_path.unwrap().trim().is_empty() {
+        eprintln!("please input file path");
         return;
     }
-    let input_path = args.unwrap().trim();
-    let buff: &mut [u8; 20] = sys_call::malloc(20);
-    let cwd = sys_call::get_cwd(buff);
+    let input_path = input_path.unwrap().trim();
+    let mut cwd_buff = vec![0u8; 20];
+    let cwd = sys_call::get_cwd(&mut cwd_buff);
 
-    let buff: &mut [u8; 20] = sys_call::malloc(100);
-    let abs_path = shell_util::get_abs_path(cwd, input_path, buff).unwrap();
+    let mut path_buff = vec![0u8; 100];
+    let abs_path = shell_util::get_abs_path(cwd, input_path, &mut path_buff).unwrap();
 
     let file = sys_call::File::open(abs_path);
     
     if file.is_err() {
-        println!("failed to cat, file:{} {:?}", abs_path, file.unwrap_err());
+        eprintln!("failed to cat, file:{} {:?}", abs_path, file.unwrap_err());
         return;
     }
     let file = file.unwrap();
+    let mut buff = vec![0u8; 100];
     loop {
         // 清空缓冲区
         unsafe { buff.as_mut_ptr().write_bytes(0, buff.len()) };
 
         // read file data from file and to buffer
-        let read_bytes = file.read(buff);
+        let read_bytes = file.read(&mut buff);
         if read_bytes == 0 {
             break;
         }
@@ -107,6 +107,26 @@ pub const MAIN_THREAD_NAME: &str = "main";
 */
 pub const TASK_DEFAULT_PRIORITY: u8 = 5;
 
+/**
+ * 任务的最低优先级
+ */
+pub const TASK_MIN_PRIORITY: u8 = 1;
+
+/**
+ * 任务的最高优先级
+ */
+pub const TASK_MAX_PRIORITY: u8 = 31;
+
+/**
+ * 多级反馈队列的级数。第0级优先级最高；每往下一级，时间片翻倍
+ */
+pub const MLFQ_LEVEL_CNT: usize = 3;
+
+/**
+ * 每隔多少个tick，把所有任务提升到第0级（防止低级别的任务饿死）
+ */
+pub const MLFQ_AGING_TICKS: u32 = 100;
+
 
 pub const KEYBOARD_KEY_COUNT: usize = 0x3B;
 
@@ -125,6 +145,16 @@ pub const USER_STACK_BASE_ADDR: usize = 0xc0000000;
  */
 pub const USER_STACK_TOP_ADDR: usize = USER_STACK_BASE_ADDR - 0x1000;
 
+/**
+ * 用户进程的栈最大的大小（8MB）。栈在这个范围内向下增长，缺页的时候按需分配
+ */
+pub const USER_STACK_MAX_SIZE: usize = 0x800000;
+
+/**
+ * 用户进程的栈，最低能增长到的地址
+ */
+pub const USER_STACK_LIMIT_ADDR: usize = USER_STACK_BASE_ADDR - USER_STACK_MAX_SIZE;
+
 /**
  * 系统调用的函数数量
  */
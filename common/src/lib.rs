@@ -8,6 +8,7 @@ pub mod vga;
 pub mod sd;
 pub mod gdt;
 pub mod reg_cr0;
+pub mod reg_cr2;
 pub mod reg_cr3;
 pub mod selector;
 pub mod instruction;
@@ -30,4 +31,5 @@ pub mod reg_eflags;
 pub mod queue;
 pub mod cstring_utils;
 pub mod domain;
-pub mod array_deque;
\ No newline at end of file
+pub mod array_deque;
+pub mod time;
\ No newline at end of file
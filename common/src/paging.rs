@@ -92,6 +92,11 @@ impl <'a> Iterator for PageTableEntryIterator<'a> {
      }
  }
  
+ /**
+  * 页表项中，写时复制的标记位（AVL位，CPU不使用）
+  */
+ const PAGE_COW_FLAG: u32 = 1 << 9;
+
  /**
   * 页表项的结构：<https://wiki.osdev.org/Page_table#Page_Table>
   */
@@ -172,6 +177,39 @@ impl <'a> Iterator for PageTableEntryIterator<'a> {
     pub fn get_data(&self) -> u32 {
         self.data
     }
+
+    /**
+     * 该页是否可写（R/W位）
+     */
+    #[inline(never)]
+    pub fn writable(&self) -> bool {
+        self.data & 0x00000002 == 0x2
+    }
+
+    pub fn set_writable(&mut self, writable: bool) {
+        if writable {
+            self.data |= 0x2;
+        } else {
+            self.data &= !0x2;
+        }
+    }
+
+    /**
+     * 该页是否是写时复制的页。
+     * 使用页表项中留给操作系统的位（第9位），fork之后共享的页会设置该位，并且去掉R/W位
+     */
+    #[inline(never)]
+    pub fn is_cow(&self) -> bool {
+        self.data & PAGE_COW_FLAG == PAGE_COW_FLAG
+    }
+
+    pub fn set_cow(&mut self, cow: bool) {
+        if cow {
+            self.data |= PAGE_COW_FLAG;
+        } else {
+            self.data &= !PAGE_COW_FLAG;
+        }
+    }
     
     /**
      * 获取物理地址（页表项的高20位就是物理地址）
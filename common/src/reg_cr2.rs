@@ -0,0 +1,23 @@
+use core::arch::asm;
+
+/**
+ * 本文件是对cr2寄存器的操作
+ * 发生page fault的时候，cr2寄存器保存的是引发缺页的虚拟地址：<https://wiki.osdev.org/CPU_Registers_x86#CR2>
+ */
+
+/**
+ * 读取cr2寄存器，得到引发缺页的虚拟地址
+ */
+#[cfg(all(not(test), target_arch = "x86"))]
+pub fn load_cr2() -> u32 {
+    let cr2: u32;
+    unsafe {
+        asm!("mov {:e}, cr2", out(reg) cr2, options(nomem, nostack, preserves_flags));
+    }
+    cr2
+}
+
+#[cfg(any(test, not(target_arch = "x86")))]
+pub fn load_cr2() -> u32 {
+    todo!()
+}
@@ -0,0 +1,86 @@
+use core::fmt::Display;
+
+/**
+ * 日期时间（UTC）。
+ * 时间戳：1970-01-01 00:00:00 以来的秒数
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+/**
+ * 一天的秒数
+ */
+const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+
+/**
+ * 公元0年3月1日，到1970年1月1日的天数
+ */
+const DAYS_TO_UNIX_EPOCH: u32 = 719468;
+
+/**
+ * 400年的天数（一个完整的闰年周期）
+ */
+const DAYS_PER_ERA: u32 = 146097;
+
+impl DateTime {
+
+    /**
+     * 把时间戳，转成日期时间
+     * 算法见：<https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+     */
+    pub fn from_timestamp(timestamp: u32) -> Self {
+        let days = timestamp / SECONDS_PER_DAY + DAYS_TO_UNIX_EPOCH;
+        let secs_of_day = timestamp % SECONDS_PER_DAY;
+
+        // 以3月1日为一年的开始，这样闰年多出来的2月29日，就是一年的最后一天
+        let era = days / DAYS_PER_ERA;
+        let day_of_era = days - era * DAYS_PER_ERA;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_from_march = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+        let month = if month_from_march < 10 { month_from_march + 3 } else { month_from_march - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        Self {
+            year,
+            month,
+            day,
+            hour: secs_of_day / 3600,
+            minute: secs_of_day % 3600 / 60,
+            second: secs_of_day % 60,
+        }
+    }
+
+    /**
+     * 把日期时间，转成时间戳。早于1970年的时间，返回0
+     * 算法见：<https://howardhinnant.github.io/date_algorithms.html#days_from_civil>
+     */
+    pub fn to_timestamp(&self) -> u32 {
+        if self.year < 1970 {
+            return 0;
+        }
+        let year = if self.month <= 2 { self.year - 1 } else { self.year };
+        let era = year / 400;
+        let year_of_era = year - era * 400;
+        let month_from_march = if self.month > 2 { self.month - 3 } else { self.month + 9 };
+        let day_of_year = (153 * month_from_march + 2) / 5 + self.day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * DAYS_PER_ERA + day_of_era - DAYS_TO_UNIX_EPOCH;
+
+        days * SECONDS_PER_DAY + self.hour * 3600 + self.minute * 60 + self.second
+    }
+}
+
+impl Display for DateTime {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02} {:02}:{:02}:{:02}", self.year, self.month, self.day, self.hour, self.minute, self.second)
+    }
+}
@@ -16,6 +16,7 @@ use core::fmt::{self, Write};
  */
 use volatile::Volatile;
 
+use crate::port::Port;
 use crate::utils::bool_to_int;
 use crate::racy_cell::RacyCell;
 #[no_mangle]
@@ -51,6 +52,13 @@ pub fn clear_all() {
     unsafe { WRITER.get_mut().clear_all() };
 }
 
+/**
+ * 把屏幕上闪烁的光标，移动到下一个字符要输出的位置
+ */
+pub fn sync_cursor() {
+    unsafe { WRITER.get_mut().sync_cursor() };
+}
+
 impl Write for Writer {
     fn write_str(&mut self, s: &str) -> core::fmt::Result {
         unsafe {WRITER.get_mut().write_string(s)};
@@ -115,6 +123,22 @@ impl SingleChar {
     }
 }
 
+/**
+ * 控制字符：光标左移一格，不擦除字符（0x8退格会擦除字符）。shell行编辑的时候使用
+ */
+pub const CURSOR_LEFT: u8 = 0x1D;
+
+/**
+ * CRT控制器的地址寄存器和数据寄存器的端口。先往地址寄存器写入索引，再读写数据寄存器
+ */
+const CRT_ADDR_PORT: u16 = 0x3D4;
+const CRT_DATA_PORT: u16 = 0x3D5;
+/**
+ * 光标位置的高8位、低8位寄存器的索引
+ */
+const CRT_CURSOR_HIGH: u8 = 0x0E;
+const CRT_CURSOR_LOW: u8 = 0x0F;
+
 /**
  * 要输出到屏幕的缓冲区。
  * 一屏幕就是80 * 25
@@ -272,6 +296,11 @@ impl Writer {
             self._backspace();
             return;
         }
+        // 光标左移，不擦除
+        if CURSOR_LEFT == byte {
+            self._backspace();
+            return;
+        }
         self.do_write_byte(byte);
     }
     
@@ -285,6 +314,20 @@ impl Writer {
         }
     }
 
+    /**
+     * 把硬件光标移动到下一个要写入的位置
+     */
+    #[inline(never)]
+    pub fn sync_cursor(&self) {
+        let pos = (self.row_pos * BUFFER_WIDTH + self.col_pos) as u16;
+        let addr_port = Port::<u8>::new(CRT_ADDR_PORT);
+        let data_port = Port::<u8>::new(CRT_DATA_PORT);
+        addr_port.write(CRT_CURSOR_HIGH);
+        data_port.write((pos >> 8) as u8);
+        addr_port.write(CRT_CURSOR_LOW);
+        data_port.write(pos as u8);
+    }
+
     #[inline(never)]
     pub fn clear_current_row(&mut self) {
         self._clear_row(self.row_pos);
@@ -10,12 +10,13 @@ use rrt::{_start, env};
 #[inline(never)]
 #[no_mangle]
 pub extern "C" fn main() {
-    let args = env::get_args();
-    if args.is_none() || args.unwrap().trim().is_empty() {
-        return;
+    // 跳过程序名，把每个参数用空格隔开输出
+    for (idx, arg) in env::args().skip(1).enumerate() {
+        if idx > 0 {
+            sys_call::write(FileDescriptor::new(StdFileDescriptor::StdOutputNo as usize), " ".as_bytes());
+        }
+        sys_call::write(FileDescriptor::new(StdFileDescriptor::StdOutputNo as usize), arg.as_bytes());
     }
-    let args = args.unwrap().trim();
-    sys_call::write(FileDescriptor::new(StdFileDescriptor::StdOutputNo as usize), args.as_bytes());
 }
 
 #[panic_handler]
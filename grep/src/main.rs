@@ -1,9 +1,13 @@
 #![no_std]
 #![no_main]
 
+extern crate alloc;
+
 use core::panic::PanicInfo;
 
-use kernel::{ascii::AsciiKey, filesystem::{FileDescriptor, StdFileDescriptor}, print, println, scancode::{Key, KeyCode, ScanCodeType}, shell::{shell_util, Shell}, sys_call};
+use alloc::boxed::Box;
+
+use kernel::{ascii::AsciiKey, filesystem::{FileDescriptor, StdFileDescriptor}, eprintln, print, println, scancode::{Key, KeyCode, ScanCodeType}, shell::{shell_util, Shell}, sys_call};
 
 use os_in_rust_common::{constants::KERNEL_ADDR, queue::{ArrayQueue, Queue}, vga::print};
 use rrt::{_start, env};
@@ -11,17 +15,16 @@ use rrt::{_start, env};
 #[inline(never)]
 #[no_mangle]
 pub extern "C" fn main() {
-    let args = env::get_args();
-    if args.is_none() || args.unwrap().trim().is_empty() {
-        println!("please input string need to grep");
+    // 要搜索的字符串。第一个参数是程序名
+    let grep_str = env::args().nth(1);
+    if grep_str.is_none() || grep_str.unwrap().trim().is_empty() {
+        eprintln!("please input string need to grep");
         return;
     }
-    // 要搜索的字符串
-    let grep_str = args.unwrap().trim();
+    let grep_str = grep_str.unwrap().trim();
     
-    let input_shell: &mut Shell<20, 1000> = sys_call::malloc(size_of::<Shell<20, 1000>>());
+    let mut input_shell: Box<Shell<20, 1000>> = Box::new(Shell::new([0; 20], [0; 1000]));
 
-    let mut last_key = AsciiKey::NUL;
     let mut capital = false;
     loop {
 
@@ -31,10 +34,6 @@ pub extern "C" fn main() {
         if key == AsciiKey::NUL {
             break;
         }
-        // 如果是contrl + c，那么就停止
-        if last_key == AsciiKey::DC1 && key == AsciiKey::c {
-            break;
-        }
         // 如果是回车键，那么就要处理过滤了
         if key == AsciiKey::CR || key == AsciiKey::LF {
             let input = input_shell.get_input();
@@ -46,7 +45,6 @@ pub extern "C" fn main() {
             input_shell.clear_input();
             continue;
         }
-        last_key = key;
 
         // 转成字符
         let key_char = key as u8 as char;
@@ -57,7 +55,7 @@ pub extern "C" fn main() {
         }
 
         assert!(input_shell.get_input().len() < 1000);
-        input_shell.append_input(key_char);
+        input_shell.get_editor().insert(key_char as u8);
     }
     println!();
 }
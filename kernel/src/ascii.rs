@@ -135,4 +135,11 @@ pub enum AsciiKey {
     RBRACE = 0x7D, // '}'
     TILDE = 0x7E, // '~'
     DEL  = 0x7F, // Delete
+    // 下面的键没有对应的ascii码，使用ascii范围之外的值
+    UP = 0x80, // 上方向键
+    DOWN = 0x81, // 下方向键
+    LEFT = 0x82, // 左方向键
+    RIGHT = 0x83, // 右方向键
+    HOME = 0x84, // Home
+    END = 0x85, // End
 }
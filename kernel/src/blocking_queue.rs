@@ -71,6 +71,20 @@ impl <'a, T: Copy + Sized> ArrayBlockingQueue<'a, T> {
     pub fn end(&mut self) {
         self.end.store(true, core::sync::atomic::Ordering::Release);
     }
+
+    /**
+     * 和take一样取出元素，但是阻塞的时候可以被信号打断
+     * 返回None：队列已经空了并且不再生产数据，或者被信号打断了
+     */
+    #[inline(never)]
+    pub fn take_interruptible(&mut self) -> Option<T> {
+        if !self.end.load(core::sync::atomic::Ordering::Acquire) && !self.consumer.down_interruptible() {
+            return Option::None;
+        }
+        let ele = self.queue.poll();
+        self.producer.up();
+        ele
+    }
 }
 
 impl <'a, T: Copy + Sized> Queue<T> for ArrayBlockingQueue<'a, T> {
@@ -4,15 +4,20 @@ pub struct ExecParam<'a> {
      */
     file_path: &'a str,
     /**
-     * 执行某个文件的参数
+     * 执行某个文件的参数（不包含程序名。程序名默认是argv[0]）
      */
-    args: Option<&'a str>,
+    args: &'a [&'a str],
+    /**
+     * 执行某个文件的环境变量。格式是KEY=VALUE
+     */
+    envs: &'a [&'a str],
 }
 impl <'a> ExecParam<'a> {
-    pub fn new(file_path: &'a str, args: Option<&'a str>) -> Self {
+    pub fn new(file_path: &'a str, args: &'a [&'a str], envs: &'a [&'a str]) -> Self {
         Self {
             file_path,
             args,
+            envs,
         }
     }
 
@@ -20,7 +25,11 @@ impl <'a> ExecParam<'a> {
         self.file_path
     }
 
-    pub fn get_args(&self) -> Option<&str> {
+    pub fn get_args(&self) -> &[&str] {
         self.args
     }
-}
\ No newline at end of file
+
+    pub fn get_envs(&self) -> &[&str] {
+        self.envs
+    }
+}
@@ -0,0 +1,31 @@
+use crate::filesystem::FileDescriptor;
+
+pub struct MmapParam {
+    /**
+     * 要映射的长度（单位字节），向上取整到页
+     */
+    pub len: usize,
+    /**
+     * 访问权限。PROT_READ、PROT_WRITE、PROT_EXEC的组合
+     */
+    pub prot: u8,
+    /**
+     * 要映射的文件。None表示匿名映射
+     */
+    pub fd: Option<FileDescriptor>,
+    /**
+     * 从文件的哪个偏移量开始映射（需要按页对齐）
+     */
+    pub offset: u32,
+}
+
+impl MmapParam {
+    pub fn new(len: usize, prot: u8, fd: Option<FileDescriptor>, offset: u32) -> Self {
+        Self {
+            len,
+            prot,
+            fd,
+            offset,
+        }
+    }
+}
@@ -3,11 +3,12 @@
 pub struct OpenFileDto<'a> {
     pub file_path: &'a str,
     pub append: bool,
+    pub truncate: bool,
 }
 
 impl <'a> OpenFileDto<'a> {
     #[inline(never)]
     pub fn new(path: &'a str, append: bool) -> Self {
-        Self { file_path: path, append: append }
+        Self { file_path: path, append: append, truncate: false }
     }
 }
\ No newline at end of file
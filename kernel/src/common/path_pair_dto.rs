@@ -0,0 +1,21 @@
+/**
+ * 两个路径参数的系统调用（重命名、硬链接、符号链接）的参数
+ */
+#[derive(Debug)]
+pub struct PathPairDto<'a> {
+    /**
+     * 原来的路径
+     */
+    pub old_path: &'a str,
+    /**
+     * 新的路径
+     */
+    pub new_path: &'a str,
+}
+
+impl <'a> PathPairDto<'a> {
+    #[inline(never)]
+    pub fn new(old_path: &'a str, new_path: &'a str) -> Self {
+        Self { old_path, new_path }
+    }
+}
@@ -0,0 +1,21 @@
+/**
+ * 读取符号链接的系统调用的参数
+ */
+#[derive(Debug)]
+pub struct ReadLinkDto<'a> {
+    /**
+     * 符号链接的路径
+     */
+    pub path: &'a str,
+    /**
+     * 存放目标路径的缓冲区
+     */
+    pub buff: &'a mut [u8],
+}
+
+impl <'a> ReadLinkDto<'a> {
+    #[inline(never)]
+    pub fn new(path: &'a str, buff: &'a mut [u8]) -> Self {
+        Self { path, buff }
+    }
+}
@@ -47,15 +47,19 @@ impl Console {
     #[inline(never)]
     pub fn print(&self, args: fmt::Arguments) {
         vga::print(args);
+        vga::sync_cursor();
     }
     // 输出单个字符
     pub fn print_char(&self, ch: char) {
         vga::print_char(ch);
+        vga::sync_cursor();
     }
     pub fn clear_row(&self) {
-        vga::clear_current_row()
+        vga::clear_current_row();
+        vga::sync_cursor();
     }
     pub fn clear_all(&self) {
         vga::clear_all();
+        vga::sync_cursor();
     }
 }
\ No newline at end of file
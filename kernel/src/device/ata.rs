@@ -5,6 +5,7 @@ use os_in_rust_common::{constants, cstring_utils, domain::LbaAddr, elem2entry, l
 
 use crate::{memory, sync::{Lock, Semaphore}};
 
+use super::block_cache;
 use super::pio::{self, CommandBlockRegister, CommandRegister, DeviceRegister, PIOCommand, StatusRegister};
 
 /**
@@ -216,10 +217,18 @@ impl Disk {
         self.read_sectors(lba_start, sec_cnt, buf);
     }
     /**
-     * 从lba_start为起始地址的扇区中，读取连续sec_cnt扇区的数据，到buf缓冲区中
+     * 从lba_start为起始地址的扇区中，读取连续sec_cnt扇区的数据，到buf缓冲区中。经过扇区缓存
      */
     #[inline(never)]
     pub fn read_sectors(&mut self, lba_start: LbaAddr, sec_cnt: usize, buf: &mut [u8]) {
+        block_cache::read_sectors(self, lba_start, sec_cnt, buf);
+    }
+
+    /**
+     * 从lba_start为起始地址的扇区中，读取连续sec_cnt扇区的数据，到buf缓冲区中。不经过缓存，直接读取硬盘
+     */
+    #[inline(never)]
+    pub fn read_sectors_direct(&mut self, lba_start: LbaAddr, sec_cnt: usize, buf: &mut [u8]) {
         let lba_start = lba_start.get_lba() as usize;
         let lba_end = lba_start + sec_cnt;
         if lba_end > (constants::DISK_MAX_SIZE / constants::DISK_SECTOR_SIZE) {
@@ -270,10 +279,18 @@ impl Disk {
     }
 
     /**
-     * 把buf的数据写入到lba_start起始的地址 的连续 sec_cnt个扇区中
+     * 把buf的数据写入到lba_start起始的地址 的连续 sec_cnt个扇区中。经过扇区缓存，脏扇区稍后才写回硬盘
      */
     #[inline(never)]
     pub fn write_sector(&mut self, buf: &[u8], lba_start: LbaAddr, sec_cnt: usize) {
+        block_cache::write_sectors(self, buf, lba_start, sec_cnt);
+    }
+
+    /**
+     * 把buf的数据写入到lba_start起始的地址 的连续 sec_cnt个扇区中。不经过缓存，直接写入硬盘
+     */
+    #[inline(never)]
+    pub fn write_sectors_direct(&mut self, buf: &[u8], lba_start: LbaAddr, sec_cnt: usize) {
         let lba_start = lba_start.get_lba() as usize;
         let lba_end = lba_start + sec_cnt;
         if lba_end > (constants::DISK_MAX_SIZE / constants::DISK_SECTOR_SIZE) {
@@ -0,0 +1,276 @@
+use core::{mem::size_of, ptr, slice};
+
+use os_in_rust_common::{constants, domain::LbaAddr, racy_cell::RacyCell, ASSERT};
+
+use crate::{memory, sync::Lock, thread::ThreadArg, thread_management, timer};
+
+use super::ata::Disk;
+
+/**
+ * 硬盘扇区的缓存。
+ * 每次读写硬盘都是一次PIO操作，很慢。而文件系统会反复读写同一些扇区（目录项、inode数组、位图）
+ *  - 以（硬盘，扇区LBA地址）为key，缓存最近使用的扇区
+ *  - 缓存满了，淘汰最久没有使用的扇区（LRU）
+ *  - 写入只修改缓存，并且标记为脏扇区。淘汰、sync、定时刷新的时候才写回硬盘（write-back）
+ */
+
+/**
+ * 缓存的扇区数量
+ */
+const CACHE_SECTOR_CNT: usize = 64;
+
+/**
+ * 一次读写超过这么多扇区（比如安装文件系统、加载位图、加载程序），不放入缓存，直接读写硬盘
+ */
+const CACHE_MAX_SECTORS_PER_IO: usize = 8;
+
+/**
+ * 定时把脏扇区写回硬盘的间隔。单位：毫秒
+ */
+const FLUSH_INTERVAL_MS: u32 = 5000;
+
+/**
+ * 刷新线程的名称
+ */
+const FLUSH_THREAD_NAME: &str = "flusher";
+
+/**
+ * 一个缓存的扇区
+ */
+struct CachedSector {
+    /**
+     * 该扇区所在的硬盘。为空，表示这个缓存位置没有使用
+     */
+    disk: *mut Disk,
+    /**
+     * 扇区的LBA地址
+     */
+    lba: LbaAddr,
+    /**
+     * 是否修改过，还没有写回硬盘
+     */
+    dirty: bool,
+    /**
+     * 最后一次使用的时间（BlockCache的时钟）。越小越久没有使用
+     */
+    last_used: u64,
+    /**
+     * 扇区的数据
+     */
+    data: [u8; constants::DISK_SECTOR_SIZE],
+}
+
+impl CachedSector {
+    fn empty() -> Self {
+        Self {
+            disk: ptr::null_mut(),
+            lba: LbaAddr::empty(),
+            dirty: false,
+            last_used: 0,
+            data: [0; constants::DISK_SECTOR_SIZE],
+        }
+    }
+
+    fn is_for(&self, disk: *mut Disk, lba: u32) -> bool {
+        !self.disk.is_null() && self.disk == disk && self.lba.get_lba() == lba
+    }
+
+    /**
+     * 如果是脏扇区，写回硬盘
+     */
+    fn write_back(&mut self) {
+        if self.disk.is_null() || !self.dirty {
+            return;
+        }
+        let disk = unsafe { &mut *self.disk };
+        disk.write_sectors_direct(&self.data, self.lba, 1);
+        self.dirty = false;
+    }
+}
+
+unsafe impl Send for CachedSector {}
+unsafe impl Sync for CachedSector {}
+
+struct BlockCache {
+    /**
+     * 所有缓存的扇区
+     */
+    sectors: &'static mut [CachedSector],
+    /**
+     * 每次访问缓存，时钟+1。用于LRU
+     */
+    clock: u64,
+    /**
+     * 锁。读写硬盘会阻塞，多个任务可能同时操作缓存
+     */
+    lock: Lock,
+}
+
+impl BlockCache {
+    /**
+     * 找到disk硬盘lba扇区的缓存。如果没有缓存，淘汰一个扇区，用来缓存它
+     *  - load: 是否要从硬盘读取这个扇区的数据（马上要整个覆盖的扇区，不需要读取）
+     */
+    fn get_sector(&mut self, disk: *mut Disk, lba: u32, load: bool) -> &mut CachedSector {
+        self.clock += 1;
+        let clock = self.clock;
+
+        let find = self.sectors.iter().position(|sector| sector.is_for(disk, lba));
+        if find.is_some() {
+            let sector = &mut self.sectors[find.unwrap()];
+            sector.last_used = clock;
+            return sector;
+        }
+
+        // 没有缓存。优先使用空的位置，否则淘汰最久没有使用的扇区
+        let victim_idx = self.sectors.iter().enumerate()
+            .min_by_key(|(_, sector)| if sector.disk.is_null() { 0 } else { sector.last_used })
+            .map(|(idx, _)| idx)
+            .unwrap();
+        let victim = &mut self.sectors[victim_idx];
+        victim.write_back();
+
+        victim.disk = disk;
+        victim.lba = LbaAddr::new(lba);
+        victim.dirty = false;
+        victim.last_used = clock;
+        if load {
+            unsafe { &mut *disk }.read_sectors_direct(victim.lba, 1, &mut victim.data);
+        }
+        victim
+    }
+
+    /**
+     * disk硬盘中，[lba_start, lba_end)范围内的缓存扇区
+     */
+    fn sectors_in_range(&mut self, disk: *mut Disk, lba_start: u32, lba_end: u32) -> impl Iterator<Item = &mut CachedSector> {
+        self.sectors.iter_mut()
+            .filter(move |sector| !sector.disk.is_null() && sector.disk == disk && sector.lba.get_lba() >= lba_start && sector.lba.get_lba() < lba_end)
+    }
+}
+
+static BLOCK_CACHE: RacyCell<Option<BlockCache>> = RacyCell::new(Option::None);
+
+fn get_block_cache() -> Option<&'static mut BlockCache> {
+    unsafe { BLOCK_CACHE.get_mut() }.as_mut()
+}
+
+/**
+ * 初始化扇区缓存，并且启动定时刷新的线程
+ */
+#[inline(never)]
+pub fn block_cache_init() {
+    let bytes = size_of::<CachedSector>() * CACHE_SECTOR_CNT;
+    let sectors = unsafe { slice::from_raw_parts_mut(memory::sys_malloc(bytes) as *mut CachedSector, CACHE_SECTOR_CNT) };
+    for sector in sectors.iter_mut() {
+        unsafe { ptr::write(sector, CachedSector::empty()) };
+    }
+    *unsafe { BLOCK_CACHE.get_mut() } = Option::Some(BlockCache {
+        sectors,
+        clock: 0,
+        lock: Lock::new(),
+    });
+
+    thread_management::thread_start(FLUSH_THREAD_NAME, constants::TASK_DEFAULT_PRIORITY, flush_thread, 0);
+}
+
+/**
+ * 从disk硬盘lba_start开始，读取sec_cnt个扇区到buf中
+ */
+#[inline(never)]
+pub fn read_sectors(disk: &mut Disk, lba_start: LbaAddr, sec_cnt: usize, buf: &mut [u8]) {
+    let cache = self::get_block_cache();
+    // 缓存还没有初始化
+    if cache.is_none() {
+        disk.read_sectors_direct(lba_start, sec_cnt, buf);
+        return;
+    }
+    ASSERT!(buf.len() >= sec_cnt * constants::DISK_SECTOR_SIZE);
+    let cache = cache.unwrap();
+    let disk_ptr = disk as *mut Disk;
+    let lba_start = lba_start.get_lba();
+    cache.lock.lock();
+
+    if sec_cnt > CACHE_MAX_SECTORS_PER_IO {
+        // 直接从硬盘读取
+        disk.read_sectors_direct(LbaAddr::new(lba_start), sec_cnt, buf);
+        // 缓存中的扇区可能还没有写回硬盘，比硬盘中的新
+        for sector in cache.sectors_in_range(disk_ptr, lba_start, lba_start + sec_cnt as u32) {
+            let off = (sector.lba.get_lba() - lba_start) as usize * constants::DISK_SECTOR_SIZE;
+            buf[off .. off + constants::DISK_SECTOR_SIZE].copy_from_slice(&sector.data);
+        }
+    } else {
+        for idx in 0 .. sec_cnt {
+            let sector = cache.get_sector(disk_ptr, lba_start + idx as u32, true);
+            let off = idx * constants::DISK_SECTOR_SIZE;
+            buf[off .. off + constants::DISK_SECTOR_SIZE].copy_from_slice(&sector.data);
+        }
+    }
+    cache.lock.unlock();
+}
+
+/**
+ * 把buf的数据，写入到disk硬盘lba_start开始的sec_cnt个扇区中
+ */
+#[inline(never)]
+pub fn write_sectors(disk: &mut Disk, buf: &[u8], lba_start: LbaAddr, sec_cnt: usize) {
+    let cache = self::get_block_cache();
+    // 缓存还没有初始化
+    if cache.is_none() {
+        disk.write_sectors_direct(buf, lba_start, sec_cnt);
+        return;
+    }
+    ASSERT!(buf.len() >= sec_cnt * constants::DISK_SECTOR_SIZE);
+    let cache = cache.unwrap();
+    let disk_ptr = disk as *mut Disk;
+    let lba_start = lba_start.get_lba();
+    cache.lock.lock();
+
+    if sec_cnt > CACHE_MAX_SECTORS_PER_IO {
+        // 直接写入硬盘
+        disk.write_sectors_direct(buf, LbaAddr::new(lba_start), sec_cnt);
+        // 缓存中的扇区，和硬盘保持一致
+        for sector in cache.sectors_in_range(disk_ptr, lba_start, lba_start + sec_cnt as u32) {
+            let off = (sector.lba.get_lba() - lba_start) as usize * constants::DISK_SECTOR_SIZE;
+            sector.data.copy_from_slice(&buf[off .. off + constants::DISK_SECTOR_SIZE]);
+            sector.dirty = false;
+        }
+    } else {
+        for idx in 0 .. sec_cnt {
+            // 整个扇区都会被覆盖，不需要从硬盘读取
+            let sector = cache.get_sector(disk_ptr, lba_start + idx as u32, false);
+            let off = idx * constants::DISK_SECTOR_SIZE;
+            sector.data.copy_from_slice(&buf[off .. off + constants::DISK_SECTOR_SIZE]);
+            sector.dirty = true;
+        }
+    }
+    cache.lock.unlock();
+}
+
+/**
+ * 把所有的脏扇区写回硬盘
+ */
+#[inline(never)]
+pub fn sync_block_cache() {
+    let cache = self::get_block_cache();
+    if cache.is_none() {
+        return;
+    }
+    let cache = cache.unwrap();
+    cache.lock.lock();
+    for sector in cache.sectors.iter_mut() {
+        sector.write_back();
+    }
+    cache.lock.unlock();
+}
+
+/**
+ * 定时刷新的线程：每隔FLUSH_INTERVAL_MS，把脏扇区写回硬盘
+ */
+#[inline(never)]
+extern "C" fn flush_thread(_arg: ThreadArg) {
+    loop {
+        timer::sleep_ms(FLUSH_INTERVAL_MS);
+        self::sync_block_cache();
+    }
+}
@@ -3,11 +3,21 @@ mod ata;
 mod init;
 mod pio;
 mod drive;
+mod rtc;
+mod block_cache;
 
 pub use init::get_all_partition;
 pub use init::ata_init;
 pub use init::get_ata_channel;
 
+pub use rtc::rtc_init;
+pub use rtc::read_rtc;
+pub use rtc::current_timestamp;
+pub use rtc::hour_to_24;
+
+pub use block_cache::block_cache_init;
+pub use block_cache::sync_block_cache;
+
 
 pub use ata::Partition;
 pub use ata::ChannelIrqNoEnum;
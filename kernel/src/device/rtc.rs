@@ -0,0 +1,143 @@
+use os_in_rust_common::{constants, instruction, port::Port, racy_cell::RacyCell, time::DateTime};
+
+use crate::timer;
+
+/**
+ * CMOS中的实时时钟（Real Time Clock）
+ * 通过0x70端口选择寄存器，通过0x71端口读取寄存器的值
+ * 关于RTC可以看：<https://wiki.osdev.org/CMOS#The_Real-Time_Clock>
+ */
+
+/**
+ * 选择CMOS寄存器的端口
+ */
+const CMOS_ADDRESS_PORT: u16 = 0x70;
+/**
+ * 读取CMOS寄存器的端口
+ */
+const CMOS_DATA_PORT: u16 = 0x71;
+
+const RTC_SECOND_REGISTER: u8 = 0x00;
+const RTC_MINUTE_REGISTER: u8 = 0x02;
+const RTC_HOUR_REGISTER: u8 = 0x04;
+const RTC_DAY_REGISTER: u8 = 0x07;
+const RTC_MONTH_REGISTER: u8 = 0x08;
+const RTC_YEAR_REGISTER: u8 = 0x09;
+/**
+ * 状态寄存器A。最高位为1，表示RTC正在更新时间
+ */
+const RTC_STATUS_A_REGISTER: u8 = 0x0A;
+/**
+ * 状态寄存器B。
+ *  - 第1位为1：24小时制，否则是12小时制（小时的最高位为1表示下午）
+ *  - 第2位为1：二进制格式，否则是BCD格式
+ */
+const RTC_STATUS_B_REGISTER: u8 = 0x0B;
+
+/**
+ * 开机时候的时间戳
+ */
+static BOOT_TIMESTAMP: RacyCell<u32> = RacyCell::new(0);
+/**
+ * 开机时候，时钟中断的次数
+ */
+static BOOT_TICKS: RacyCell<u64> = RacyCell::new(0);
+
+/**
+ * 初始化RTC。读取一次开机的时间，之后的时间由时钟中断的次数推算
+ */
+#[inline(never)]
+pub fn rtc_init() {
+    let boot_time = self::read_rtc();
+    *unsafe { BOOT_TIMESTAMP.get_mut() } = boot_time.to_timestamp();
+    *unsafe { BOOT_TICKS.get_mut() } = timer::ticks();
+}
+
+/**
+ * 当前的时间戳（1970-01-01 00:00:00以来的秒数）
+ */
+#[inline(never)]
+pub fn current_timestamp() -> u32 {
+    let boot_timestamp = *unsafe { BOOT_TIMESTAMP.get_mut() };
+    let boot_ticks = *unsafe { BOOT_TICKS.get_mut() };
+    let elapsed_secs = (timer::ticks() - boot_ticks) / constants::TIMER_INTR_FREQUENCY as u64;
+    boot_timestamp + elapsed_secs as u32
+}
+
+/**
+ * 从CMOS中读取当前的日期时间
+ */
+#[inline(never)]
+pub fn read_rtc() -> DateTime {
+    let old_status = instruction::disable_interrupt();
+    // RTC每秒更新一次，更新的过程中读到的值可能不一致。连续读取两次，直到两次的结果一样
+    let mut date_time = self::read_rtc_registers();
+    loop {
+        let again = self::read_rtc_registers();
+        if again == date_time {
+            break;
+        }
+        date_time = again;
+    }
+    let status_b = self::read_register(RTC_STATUS_B_REGISTER);
+    instruction::set_interrupt(old_status);
+
+    // 12小时制，小时的最高位表示下午
+    let twelve_hour = status_b & 0x02 == 0;
+    let pm = date_time.hour & 0x80 != 0;
+    date_time.hour &= 0x7F;
+
+    // BCD格式，转成二进制
+    if status_b & 0x04 == 0 {
+        date_time.second = self::bcd_to_binary(date_time.second);
+        date_time.minute = self::bcd_to_binary(date_time.minute);
+        date_time.hour = self::bcd_to_binary(date_time.hour);
+        date_time.day = self::bcd_to_binary(date_time.day);
+        date_time.month = self::bcd_to_binary(date_time.month);
+        date_time.year = self::bcd_to_binary(date_time.year);
+    }
+    if twelve_hour {
+        date_time.hour = self::hour_to_24(date_time.hour, pm);
+    }
+    // 年份寄存器只有后两位
+    date_time.year += 2000;
+    date_time
+}
+
+/**
+ * 读取一次所有的时间寄存器（原始值，可能是BCD格式）
+ */
+fn read_rtc_registers() -> DateTime {
+    // 等待RTC更新完成
+    while self::read_register(RTC_STATUS_A_REGISTER) & 0x80 != 0 {}
+    DateTime {
+        year: self::read_register(RTC_YEAR_REGISTER) as u32,
+        month: self::read_register(RTC_MONTH_REGISTER) as u32,
+        day: self::read_register(RTC_DAY_REGISTER) as u32,
+        hour: self::read_register(RTC_HOUR_REGISTER) as u32,
+        minute: self::read_register(RTC_MINUTE_REGISTER) as u32,
+        second: self::read_register(RTC_SECOND_REGISTER) as u32,
+    }
+}
+
+fn read_register(register: u8) -> u8 {
+    Port::<u8>::new(CMOS_ADDRESS_PORT).write(register);
+    Port::<u8>::new(CMOS_DATA_PORT).read()
+}
+
+/**
+ * 12小时制的小时（1~12）转成24小时制。上午12点是0点，下午12点是12点
+ */
+pub fn hour_to_24(hour: u32, pm: bool) -> u32 {
+    if pm && hour != 12 {
+        hour + 12
+    } else if !pm && hour == 12 {
+        0
+    } else {
+        hour
+    }
+}
+
+fn bcd_to_binary(bcd: u32) -> u32 {
+    (bcd >> 4) * 10 + (bcd & 0x0F)
+}
@@ -0,0 +1,284 @@
+/**
+ * 本文件，对ELF32格式的可执行文件进行解析
+ * 参考：https://refspecs.linuxfoundation.org/elf/elf.pdf
+ */
+
+use os_in_rust_common::constants;
+
+/**
+ * ELF文件的魔数："\x7fELF"
+ */
+const ELF_MAGIC: [u8; 4] = [0x7f, b'E', b'L', b'F'];
+
+/**
+ * e_ident[4]: 文件的类别。1表示32位
+ */
+const ELF_CLASS_32: u8 = 1;
+
+/**
+ * e_ident[5]: 数据编码。1表示小端
+ */
+const ELF_DATA_LSB: u8 = 1;
+
+/**
+ * e_type: 可执行文件
+ */
+const ELF_TYPE_EXEC: u16 = 2;
+
+/**
+ * e_machine: Intel 80386
+ */
+const ELF_MACHINE_386: u16 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ElfError {
+    /**
+     * 不是ELF文件（魔数不对）
+     */
+    NotElf,
+    /**
+     * 不是32位、小端的ELF文件
+     */
+    UnsupportedClass,
+    /**
+     * 不是可执行文件
+     */
+    NotExecutable,
+    /**
+     * 不是x86平台的文件
+     */
+    UnsupportedMachine,
+    /**
+     * 程序头的大小不对
+     */
+    InvalidProgramHeader,
+    /**
+     * 文件不完整，读取不到足够的数据
+     */
+    Truncated,
+    /**
+     * 段的地址、大小不合法（地址溢出、超出了用户空间，或者文件中的大小比内存中的大小还大）
+     */
+    InvalidSegment,
+    /**
+     * 入口地址不在任何一个要加载的段中
+     */
+    InvalidEntry,
+}
+
+/**
+ * ELF32的文件头。位于文件的开头，共52字节
+ */
+#[derive(Debug, Clone, Copy)]
+#[repr(C, packed)]
+pub struct Elf32Header {
+    /**
+     * 魔数、类别、编码等信息
+     */
+    pub e_ident: [u8; 16],
+    /**
+     * 文件类型
+     */
+    pub e_type: u16,
+    /**
+     * 运行的平台
+     */
+    pub e_machine: u16,
+    pub e_version: u32,
+    /**
+     * 程序的入口地址
+     */
+    pub e_entry: u32,
+    /**
+     * 程序头表在文件中的偏移量
+     */
+    pub e_phoff: u32,
+    /**
+     * 节头表在文件中的偏移量
+     */
+    pub e_shoff: u32,
+    pub e_flags: u32,
+    /**
+     * 文件头的大小
+     */
+    pub e_ehsize: u16,
+    /**
+     * 每个程序头的大小
+     */
+    pub e_phentsize: u16,
+    /**
+     * 程序头的数量
+     */
+    pub e_phnum: u16,
+    pub e_shentsize: u16,
+    pub e_shnum: u16,
+    pub e_shstrndx: u16,
+}
+
+impl Elf32Header {
+    pub const fn empty() -> Self {
+        Self {
+            e_ident: [0; 16],
+            e_type: 0,
+            e_machine: 0,
+            e_version: 0,
+            e_entry: 0,
+            e_phoff: 0,
+            e_shoff: 0,
+            e_flags: 0,
+            e_ehsize: 0,
+            e_phentsize: 0,
+            e_phnum: 0,
+            e_shentsize: 0,
+            e_shnum: 0,
+            e_shstrndx: 0,
+        }
+    }
+
+    /**
+     * 把这个文件头，转成一个缓冲区，用来读取文件
+     */
+    #[inline(never)]
+    pub fn as_buff(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self as *mut _ as *mut u8, size_of::<Self>()) }
+    }
+
+    /**
+     * 校验这个文件头，是否是可以执行的ELF32文件
+     */
+    #[inline(never)]
+    pub fn check(&self) -> Result<(), ElfError> {
+        if self.e_ident[0..4] != ELF_MAGIC {
+            return Result::Err(ElfError::NotElf);
+        }
+        if self.e_ident[4] != ELF_CLASS_32 || self.e_ident[5] != ELF_DATA_LSB {
+            return Result::Err(ElfError::UnsupportedClass);
+        }
+        if self.e_type != ELF_TYPE_EXEC {
+            return Result::Err(ElfError::NotExecutable);
+        }
+        if self.e_machine != ELF_MACHINE_386 {
+            return Result::Err(ElfError::UnsupportedMachine);
+        }
+        if self.e_phentsize as usize != size_of::<Elf32ProgramHeader>() {
+            return Result::Err(ElfError::InvalidProgramHeader);
+        }
+        return Result::Ok(());
+    }
+
+    /**
+     * 第idx个程序头，在文件中的偏移量
+     */
+    #[inline(never)]
+    pub fn program_header_off(&self, idx: usize) -> u32 {
+        self.e_phoff + (idx * self.e_phentsize as usize) as u32
+    }
+}
+
+/**
+ * 程序头（段）的类型
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[repr(u32)]
+pub enum SegmentType {
+    Null = 0,
+    /**
+     * 需要加载到内存中的段
+     */
+    Load = 1,
+    Dynamic = 2,
+    Interp = 3,
+    Note = 4,
+}
+
+/**
+ * ELF32的程序头。描述一个段，共32字节
+ */
+#[derive(Debug, Clone, Copy)]
+#[repr(C, packed)]
+pub struct Elf32ProgramHeader {
+    /**
+     * 段的类型
+     */
+    pub p_type: u32,
+    /**
+     * 段在文件中的偏移量
+     */
+    pub p_offset: u32,
+    /**
+     * 段要加载到的虚拟地址
+     */
+    pub p_vaddr: u32,
+    pub p_paddr: u32,
+    /**
+     * 段在文件中的大小
+     */
+    pub p_filesz: u32,
+    /**
+     * 段在内存中的大小。比p_filesz多出来的部分（bss），需要清零
+     */
+    pub p_memsz: u32,
+    /**
+     * 段的权限
+     */
+    pub p_flags: u32,
+    pub p_align: u32,
+}
+
+impl Elf32ProgramHeader {
+    pub const fn empty() -> Self {
+        Self {
+            p_type: 0,
+            p_offset: 0,
+            p_vaddr: 0,
+            p_paddr: 0,
+            p_filesz: 0,
+            p_memsz: 0,
+            p_flags: 0,
+            p_align: 0,
+        }
+    }
+
+    /**
+     * 把这个程序头，转成一个缓冲区，用来读取文件
+     */
+    #[inline(never)]
+    pub fn as_buff(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self as *mut _ as *mut u8, size_of::<Self>()) }
+    }
+
+    /**
+     * 这个段是否需要加载到内存
+     */
+    #[inline(never)]
+    pub fn is_load(&self) -> bool {
+        self.p_type == SegmentType::Load as u32
+    }
+
+    /**
+     * 校验段在内存中的范围。段覆盖的页（按页对齐之后）必须在limit以下。返回段的结束地址（p_vaddr + p_memsz）
+     * 这些值都来自文件，不可信，计算的时候不能溢出
+     */
+    #[inline(never)]
+    pub fn check_range(&self, limit: usize) -> Result<usize, ElfError> {
+        if self.p_filesz > self.p_memsz {
+            return Result::Err(ElfError::InvalidSegment);
+        }
+        // ELF32的地址是32位的，按照u32计算
+        let page_size = constants::PAGE_SIZE;
+        let seg_end = self.p_vaddr.checked_add(self.p_memsz);
+        let page_end = seg_end.and_then(|seg_end| seg_end.checked_add(page_size - 1)).map(|end| end & !(page_size - 1));
+        if page_end.is_none() || page_end.unwrap() as usize > limit {
+            return Result::Err(ElfError::InvalidSegment);
+        }
+        return Result::Ok(seg_end.unwrap() as usize);
+    }
+
+    /**
+     * 地址addr是否在这个段的内存范围中
+     */
+    pub fn contains(&self, addr: usize) -> bool {
+        let start = self.p_vaddr as usize;
+        addr >= start && addr - start < self.p_memsz as usize
+    }
+}
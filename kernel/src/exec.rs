@@ -1,29 +1,99 @@
 use core::arch::asm;
 
-use os_in_rust_common::{constants, cstr_write, instruction, utils, ASSERT};
+use os_in_rust_common::{constants, cstr_write, instruction};
 
-use crate::{common::exec_dto::ExecParam, filesystem::{self}, interrupt, memory, thread};
+use crate::{common::exec_dto::ExecParam, elf::{self, Elf32Header, Elf32ProgramHeader}, filesystem::{self, constant, SeekFrom}, interrupt, memory, signal, thread::{self, TaskStruct}, userprog};
 
 #[derive(Debug)]
 pub enum ExecError {
     Init,
     OpenFileError(filesystem::FileError),
+    /**
+     * 不是合法的ELF可执行文件
+     */
+    ElfError(elf::ElfError),
+    /**
+     * 参数和环境变量太长了
+     */
+    ArgumentTooLong,
 }
 
-const USER_PROC_ENTRY_ADDR: usize = 0xc048000;
+/**
+ * 用户进程初始栈（参数、环境变量）最多占用的字节数
+ */
+const EXEC_ARG_MAX: usize = 1024;
 
 #[inline(never)]
 pub fn execv(param: &ExecParam) -> Result<(), ExecError> {
-    
-    // 把这个文件，加载到0xc048000
-    self::load(param.get_file_path(), USER_PROC_ENTRY_ADDR)?;
-
     let cur_pcb = thread::current_thread();
-    cstr_write!(cur_pcb.task_struct.get_name_mut(), "{}", param.get_file_path());
+
+    // 参数、路径可能在用户的brk堆中（比如rrt的分配器），先复制到内核空间，后面不再读取用户内存
+    // 在内核空间构建用户进程的初始栈（argc、argv、envp以及字符串），以及程序的路径
+    let stack_image: &mut [u8; EXEC_ARG_MAX] = memory::malloc_system(EXEC_ARG_MAX);
+    let image_len = self::build_user_stack(param, stack_image);
+    if image_len.is_err() {
+        memory::free_system(stack_image.as_ptr());
+        return Result::Err(image_len.unwrap_err());
+    }
+    let image_len = image_len.unwrap();
+    let path_buf: &mut [u8; constant::MAX_FILE_PATH_LEN] = memory::malloc_system(constant::MAX_FILE_PATH_LEN);
+    let path_addr = path_buf.as_ptr();
+    let file_path = self::copy_path(param.get_file_path(), path_buf);
+    if file_path.is_err() {
+        memory::free_system(path_addr);
+        memory::free_system(stack_image.as_ptr());
+        return Result::Err(file_path.unwrap_err());
+    }
+    let file_path = file_path.unwrap();
+
+    // 打开文件，校验ELF文件头和所有的程序头。校验失败，旧程序还是完整的，可以直接返回错误
+    let check_res = self::open_and_check(file_path);
+    if check_res.is_err() {
+        memory::free_system(path_addr);
+        memory::free_system(stack_image.as_ptr());
+        return Result::Err(check_res.unwrap_err());
+    }
+    let (mut exec_file, elf_header) = check_res.unwrap();
+    cstr_write!(cur_pcb.task_struct.get_name_mut(), "{}", file_path);
+    memory::free_system(path_addr);
+
+    // 从这里开始，不再返回旧程序。旧程序mmap映射的区域，以及旧程序的各个段，新程序用不到了
+    self::release_vma(&mut cur_pcb.task_struct);
+    self::release_image(&mut cur_pcb.task_struct);
+
+    // 把这个ELF文件的各个段，加载到内存中，得到程序的入口地址
+    let load_res = self::load(&mut exec_file, &elf_header);
+    if load_res.is_err() {
+        // 已经校验过了，只有读取文件出错才会走到这里。旧程序的内存已经释放了，只能结束进程
+        memory::free_system(stack_image.as_ptr());
+        drop(exec_file);
+        userprog::exit_by_signal(signal::SIGSEGV);
+        return Result::Err(load_res.unwrap_err());
+    }
+    drop(exec_file);
+    let (entry, image_end) = load_res.unwrap();
+
+    // 新程序加载成功之后，再释放旧程序的brk堆
+    self::release_heap(&mut cur_pcb.task_struct, image_end);
+    // 旧程序的信号处理函数也不存在了，全部恢复默认动作
+    signal::reset_actions(&mut cur_pcb.task_struct);
+    // brk堆，从程序加载的最高地址开始
+    cur_pcb.task_struct.heap_start = userprog::page_align_up(image_end);
+    cur_pcb.task_struct.heap_end = cur_pcb.task_struct.heap_start;
+
+    // 用户栈所在的页，如果没有就申请
+    if !cur_pcb.task_struct.vaddr_pool.is_set(constants::USER_STACK_TOP_ADDR) {
+        memory::malloc_user_page_by_vaddr(&mut cur_pcb.task_struct.vaddr_pool, constants::USER_STACK_TOP_ADDR);
+    }
+    // 把构建好的初始栈，拷贝到用户栈的栈顶
+    let user_esp = constants::USER_STACK_BASE_ADDR - image_len;
+    let user_stack = unsafe { core::slice::from_raw_parts_mut(user_esp as *mut u8, image_len) };
+    user_stack.copy_from_slice(&stack_image[EXEC_ARG_MAX - image_len..]);
+    memory::free_system(stack_image.as_ptr());
 
     let intr_stack = &mut cur_pcb.interrupt_stack;
-    // 这个文件的起始地址，就是执行入口
-    intr_stack.init_exec(USER_PROC_ENTRY_ADDR.try_into().unwrap(), param.get_args());
+    // ELF文件头中的入口地址，就是执行入口
+    intr_stack.init_exec(entry, user_esp as u32);
 
     let intr_stack_addr = intr_stack as *const _ as u32;
     cur_pcb.task_struct.kernel_stack = intr_stack_addr;
@@ -36,35 +106,290 @@ pub fn execv(param: &ExecParam) -> Result<(), ExecError> {
     return Result::Ok(());
 }
 
+/**
+ * 取消当前任务所有mmap映射的区域，清空VMA列表
+ */
+#[inline(never)]
+fn release_vma(task: &mut TaskStruct) {
+    for vma in task.vma_list.iter() {
+        for page_addr in (vma.start .. vma.end).step_by(constants::PAGE_SIZE as usize) {
+            memory::unmap_user_page(&mut task.vaddr_pool, page_addr);
+        }
+    }
+    task.vma_list.clear();
+}
+
+/**
+ * 释放旧程序的各个段占用的页：用户栈以下，除了brk堆以外，所有映射了的页
+ * 新程序的段重新申请，旧程序比新程序大的部分也不会遗留下来
+ */
+#[inline(never)]
+fn release_image(task: &mut TaskStruct) {
+    let table_span = constants::PAGE_SIZE as usize * constants::PAGE_TABLE_ENTRY_COUNT;
+    let image_start = constants::USER_PROCESS_ADDR_START & !(table_span - 1);
+    // 跨度是1个页表的地址，页目录项不存在，那么整个页表的地址都没有映射
+    for table_addr in (image_start .. constants::USER_STACK_LIMIT_ADDR).step_by(table_span) {
+        if !memory::page_util::addr_to_pde(table_addr).present() {
+            continue;
+        }
+        let page_start = table_addr.max(constants::USER_PROCESS_ADDR_START);
+        let page_end = (table_addr + table_span).min(constants::USER_STACK_LIMIT_ADDR);
+        for page_addr in (page_start .. page_end).step_by(constants::PAGE_SIZE as usize) {
+            // brk堆，等新程序加载成功之后再释放
+            let in_heap = page_addr >= task.heap_start && page_addr < userprog::page_align_up(task.heap_end);
+            if in_heap || !task.vaddr_pool.is_set(page_addr) {
+                continue;
+            }
+            memory::unmap_user_page(&mut task.vaddr_pool, page_addr);
+        }
+    }
+}
+
+/**
+ * 释放当前任务brk堆占用的页。image_end以下的页，已经被新程序的段复用了，不能释放
+ */
+#[inline(never)]
+fn release_heap(task: &mut TaskStruct, image_end: usize) {
+    if task.heap_start == 0 {
+        return;
+    }
+    let release_start = task.heap_start.max(userprog::page_align_up(image_end));
+    for page_addr in (release_start .. userprog::page_align_up(task.heap_end)).step_by(constants::PAGE_SIZE as usize) {
+        memory::unmap_user_page(&mut task.vaddr_pool, page_addr);
+    }
+    task.heap_start = 0;
+    task.heap_end = 0;
+}
+
+/**
+ * 构建用户进程的初始栈（System V的格式），从buff的末尾往前填充。栈顶到栈底依次是：
+ *    - argc
+ *    - argv[0] .. argv[argc-1], NULL
+ *    - envp[0] .. envp[n-1], NULL
+ *    - 各个参数、环境变量字符串（以\0结尾）
+ * buff的末尾，对应用户栈的栈底USER_STACK_BASE_ADDR。返回初始栈占用的字节数
+ */
+#[inline(never)]
+fn build_user_stack(param: &ExecParam, buff: &mut [u8]) -> Result<usize, ExecError> {
+    let buff_len = buff.len();
+    // argv[0]是程序的路径
+    let argc = 1 + param.get_args().len();
+    let envc = param.get_envs().len();
+
+    // 所有字符串占用的空间
+    let str_size: usize = core::iter::once(param.get_file_path())
+        .chain(param.get_args().iter().copied())
+        .chain(param.get_envs().iter().copied())
+        .map(|s| s.len() + 1)
+        .sum();
+    // 指针区占用的空间：argc + argv[] + NULL + envp[] + NULL
+    let ptr_size = (1 + argc + 1 + envc + 1) * size_of::<u32>();
+    if str_size + ptr_size + 16 > buff_len {
+        return Result::Err(ExecError::ArgumentTooLong);
+    }
+    // 栈顶按照16字节对齐
+    let esp_off = (buff_len - str_size - ptr_size) & !0xf;
+
+    // buff中的偏移量，转成用户栈中的地址
+    let to_user_addr = |off: usize| (constants::USER_STACK_BASE_ADDR - (buff_len - off)) as u32;
+
+    let mut str_off = buff_len - str_size;
+    let mut ptr_off = esp_off;
+    self::write_u32(buff, &mut ptr_off, argc as u32);
+    // argv
+    for arg in core::iter::once(param.get_file_path()).chain(param.get_args().iter().copied()) {
+        let user_addr = to_user_addr(str_off);
+        self::write_cstr(buff, &mut str_off, arg);
+        self::write_u32(buff, &mut ptr_off, user_addr);
+    }
+    self::write_u32(buff, &mut ptr_off, 0);
+    // envp
+    for env in param.get_envs().iter() {
+        let user_addr = to_user_addr(str_off);
+        self::write_cstr(buff, &mut str_off, env);
+        self::write_u32(buff, &mut ptr_off, user_addr);
+    }
+    self::write_u32(buff, &mut ptr_off, 0);
+
+    return Result::Ok(buff_len - esp_off);
+}
+
+/**
+ * 把程序的路径复制到内核的缓冲区buff中（以\0结尾）
+ */
 #[inline(never)]
-fn load(file_path: &str, addr: usize) -> Result<(), ExecError> {
+fn copy_path<'a>(path: &str, buff: &'a mut [u8]) -> Result<&'a str, ExecError> {
+    if path.len() >= buff.len() {
+        return Result::Err(ExecError::OpenFileError(filesystem::FileError::FilePathIllegal));
+    }
+    buff[.. path.len()].copy_from_slice(path.as_bytes());
+    buff[path.len()] = 0;
+    return Result::Ok(unsafe { core::str::from_utf8_unchecked(&buff[.. path.len()]) });
+}
+
+/**
+ * 在buff的off处写入一个u32，off往后移动
+ */
+#[inline(never)]
+fn write_u32(buff: &mut [u8], off: &mut usize, data: u32) {
+    buff[*off .. *off + size_of::<u32>()].copy_from_slice(&data.to_le_bytes());
+    *off += size_of::<u32>();
+}
+
+/**
+ * 在buff的off处写入一个以\0结尾的字符串，off往后移动
+ */
+#[inline(never)]
+fn write_cstr(buff: &mut [u8], off: &mut usize, s: &str) {
+    buff[*off .. *off + s.len()].copy_from_slice(s.as_bytes());
+    buff[*off + s.len()] = 0;
+    *off += s.len() + 1;
+}
+
+/**
+ * 打开ELF可执行文件，加载之前做完所有的校验
+ *    - 读取并校验ELF文件头
+ *    - 读取每个程序头，PT_LOAD的段在文件中的数据必须完整
+ * 返回打开的文件，以及ELF文件头
+ */
+#[inline(never)]
+fn open_and_check(file_path: &str) -> Result<(filesystem::File, Elf32Header), ExecError> {
     // 打开文件
     let exec_file = filesystem::File::open(file_path);
     if exec_file.is_err() {
         return Result::Err(ExecError::OpenFileError(exec_file.unwrap_err()));
     }
-    let exec_file = exec_file.unwrap();
-    
-    let cur_pcb = thread::current_thread();
-    
-    let file_size = exec_file.get_size().unwrap();
-    let page_cnt = utils::div_ceil(file_size as u32, constants::PAGE_SIZE) as usize;
-    
-    for page_idx in 0..page_cnt {
+    let mut exec_file = exec_file.unwrap();
+    let file_size = exec_file.get_size();
+    if file_size.is_err() {
+        return Result::Err(ExecError::OpenFileError(file_size.unwrap_err()));
+    }
+    let file_size = file_size.unwrap();
 
-        let cur_addr = addr + page_idx * constants::PAGE_SIZE as usize;
-        
-        // 申请1页内存
-        memory::malloc_user_page_by_vaddr(&mut cur_pcb.task_struct.vaddr_pool, cur_addr);
-        
-        // 把这个页空间转成一个buffer
-        let buff =  unsafe { core::slice::from_raw_parts_mut(cur_addr as *mut u8, constants::PAGE_SIZE as usize) };
-        
-        // 读取文件到指定的内存空间中
-        let read_res = exec_file.read(buff);
-        if read_res.is_err() {
-            return Result::Err(ExecError::OpenFileError(read_res.unwrap_err()));
+    // 读取ELF文件头
+    let mut elf_header = Elf32Header::empty();
+    self::read_at(&mut exec_file, 0, elf_header.as_buff())?;
+    let check_res = elf_header.check();
+    if check_res.is_err() {
+        return Result::Err(ExecError::ElfError(check_res.unwrap_err()));
+    }
+
+    // 遍历每一个程序头
+    let mut entry_found = false;
+    for ph_idx in 0 .. elf_header.e_phnum as usize {
+        let program_header = self::read_program_header(&mut exec_file, &elf_header, ph_idx)?;
+        if !program_header.is_load() {
+            continue;
+        }
+        // 段只能加载到用户空间（用户栈以下）
+        let check_res = program_header.check_range(constants::USER_STACK_LIMIT_ADDR);
+        if check_res.is_err() {
+            return Result::Err(ExecError::ElfError(check_res.unwrap_err()));
         }
+        // 段的数据不能超出文件的末尾
+        let data_end = (program_header.p_offset as usize).checked_add(program_header.p_filesz as usize);
+        if data_end.is_none() || data_end.unwrap() > file_size {
+            return Result::Err(ExecError::ElfError(elf::ElfError::Truncated));
+        }
+        entry_found = entry_found || program_header.contains(elf_header.e_entry as usize);
+    }
+    // 入口地址必须在加载的段中
+    if !entry_found {
+        return Result::Err(ExecError::ElfError(elf::ElfError::InvalidEntry));
+    }
+    return Result::Ok((exec_file, elf_header));
+}
+
+/**
+ * 读取第ph_idx个程序头
+ */
+#[inline(never)]
+fn read_program_header(exec_file: &mut filesystem::File, elf_header: &Elf32Header, ph_idx: usize) -> Result<Elf32ProgramHeader, ExecError> {
+    let mut program_header = Elf32ProgramHeader::empty();
+    self::read_at(exec_file, elf_header.program_header_off(ph_idx), program_header.as_buff())?;
+    return Result::Ok(program_header);
+}
+
+/**
+ * 加载已经校验过的ELF可执行文件：遍历程序头，把每个PT_LOAD的段加载到p_vaddr
+ * 返回程序的入口地址，以及程序加载的最高地址
+ */
+#[inline(never)]
+fn load(exec_file: &mut filesystem::File, elf_header: &Elf32Header) -> Result<(u32, usize), ExecError> {
+    // 所有段加载到的最高地址
+    let mut image_end = 0;
+    // 遍历每一个程序头
+    for ph_idx in 0 .. elf_header.e_phnum as usize {
+        let program_header = self::read_program_header(exec_file, elf_header, ph_idx)?;
+        // 只加载PT_LOAD类型的段
+        if !program_header.is_load() {
+            continue;
+        }
+        let seg_end = self::load_segment(exec_file, &program_header)?;
+        image_end = image_end.max(seg_end);
+    }
+    return Result::Ok((elf_header.e_entry, image_end));
+}
+
+/**
+ * 把一个段，加载到内存中，返回段的结束地址
+ *    - 给段覆盖的每一页申请内存（已经存在的页，直接复用）
+ *    - 把段所在的内存清零（包括bss）
+ *    - 从文件中读取p_filesz个字节到p_vaddr
+ */
+#[inline(never)]
+fn load_segment(exec_file: &mut filesystem::File, program_header: &Elf32ProgramHeader) -> Result<usize, ExecError> {
+    // 校验过了，这里再检查一次，保证下面的计算不会溢出，也不会写到用户空间以外
+    let seg_end = program_header.check_range(constants::USER_STACK_LIMIT_ADDR);
+    if seg_end.is_err() {
+        return Result::Err(ExecError::ElfError(seg_end.unwrap_err()));
+    }
+    let seg_end = seg_end.unwrap();
+    let seg_vaddr = program_header.p_vaddr as usize;
+    let mem_size = program_header.p_memsz as usize;
+    if mem_size == 0 {
+        return Result::Ok(seg_end);
+    }
+
+    let cur_pcb = thread::current_thread();
+    // 段覆盖的页，起始页和结束页
+    let page_start = seg_vaddr & !(constants::PAGE_SIZE as usize - 1);
+    let page_end = userprog::page_align_up(seg_end);
+    for page_addr in (page_start .. page_end).step_by(constants::PAGE_SIZE as usize) {
+        // 这一页已经有了（比如多个段共用一页），那么就不用再申请了
+        if cur_pcb.task_struct.vaddr_pool.is_set(page_addr) {
+            continue;
+        }
+        // 申请1页内存
+        memory::malloc_user_page_by_vaddr(&mut cur_pcb.task_struct.vaddr_pool, page_addr);
+    }
+
+    // 把这个段的内存清零。多出文件大小的部分，就是bss
+    let seg_buff = unsafe { core::slice::from_raw_parts_mut(seg_vaddr as *mut u8, mem_size) };
+    seg_buff.fill(0);
+
+    // 把文件中这个段的数据，读取到内存中（check_range保证了文件大小不超过内存大小）
+    let file_size = program_header.p_filesz as usize;
+    self::read_at(exec_file, program_header.p_offset, &mut seg_buff[..file_size])?;
+    return Result::Ok(seg_end);
+}
+
+/**
+ * 从文件的off位置开始，读满整个缓冲区
+ */
+#[inline(never)]
+fn read_at(exec_file: &mut filesystem::File, off: u32, buff: &mut [u8]) -> Result<(), ExecError> {
+    let seek_res = exec_file.seek(SeekFrom::Start(off));
+    if seek_res.is_err() {
+        return Result::Err(ExecError::OpenFileError(seek_res.unwrap_err()));
+    }
+    let read_res = exec_file.read(buff);
+    if read_res.is_err() {
+        return Result::Err(ExecError::OpenFileError(read_res.unwrap_err()));
+    }
+    // 文件不完整
+    if read_res.unwrap() < buff.len() {
+        return Result::Err(ExecError::ElfError(elf::ElfError::Truncated));
     }
     return Result::Ok(());
-}
\ No newline at end of file
+}
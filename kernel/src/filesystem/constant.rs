@@ -1,6 +1,6 @@
 use core::mem::size_of;
 
-use os_in_rust_common::{constants, domain::InodeNo};
+use os_in_rust_common::{constants, domain::LbaAddr};
 
 use super::dir_entry::DirEntry;
 
@@ -8,6 +8,31 @@ use super::dir_entry::DirEntry;
  * 文件系统魔数
  */
 pub const FILESYSTEM_MAGIC: u32 = 0x20010217;
+
+/**
+ * 文件系统的版本：inode只有直接块和一级间接块。旧的硬盘镜像是这个版本
+ */
+pub const FILESYSTEM_VERSION_LEGACY: u32 = 0;
+
+/**
+ * 文件系统的版本：inode增加了二级间接块和三级间接块
+ */
+pub const FILESYSTEM_VERSION_DEEP_INDIRECT: u32 = 1;
+
+/**
+ * 文件系统的版本：inode增加了硬链接数
+ */
+pub const FILESYSTEM_VERSION_LINK_COUNT: u32 = 2;
+
+/**
+ * 文件系统的版本：inode增加了权限、属主以及时间戳
+ */
+pub const FILESYSTEM_VERSION_METADATA: u32 = 3;
+
+/**
+ * 当前的文件系统版本。新安装的文件系统是这个版本
+ */
+pub const FILESYSTEM_VERSION_CURRENT: u32 = FILESYSTEM_VERSION_METADATA;
 /**
  * inode直接块的数据扇区数量
  */
@@ -21,6 +46,26 @@ pub const INODE_INDIRECT_DATA_SECS: usize = 1;
  */
 pub const INODE_DATA_SECS: usize = INODE_DIRECT_DATA_SECS + INODE_INDIRECT_DATA_SECS;
 
+/**
+ * 一个间接块里面，LBA地址的数量
+ */
+pub const LBA_PER_BLOCK: usize = constants::DISK_SECTOR_SIZE / size_of::<LbaAddr>();
+
+/**
+ * inode一级间接块 能够索引的数据扇区数量
+ */
+pub const INODE_SINGLE_INDIRECT_BLOCKS: usize = INODE_INDIRECT_DATA_SECS * LBA_PER_BLOCK;
+
+/**
+ * inode二级间接块 能够索引的数据扇区数量
+ */
+pub const INODE_DOUBLE_INDIRECT_BLOCKS: usize = LBA_PER_BLOCK * LBA_PER_BLOCK;
+
+/**
+ * inode三级间接块 能够索引的数据扇区数量
+ */
+pub const INODE_TRIPLE_INDIRECT_BLOCKS: usize = LBA_PER_BLOCK * LBA_PER_BLOCK * LBA_PER_BLOCK;
+
 /**
  * 一个文件系统最大的文件数量（inode数量）
  */
@@ -42,6 +87,11 @@ pub const MAX_OPENED_FILE_IN_SYSTEM: usize = 32;
  */
 pub const MAX_FILE_PATH_LEN: usize = 100;
 
+/**
+ * 解析一个路径的时候，最多跟随的符号链接数量。超过了，认为符号链接出现了循环
+ */
+pub const MAX_SYMLINK_DEPTH: u32 = 8;
+
 
 /**
  * 一个块里面最多有多少个目录项
@@ -137,6 +137,10 @@ pub fn change_dir(task: &mut TaskStruct, path: &str) -> Option<()> {
     let (entry, entry_inode) = dir_entry::search_dir_entry(fs, path)?;
     // 关闭inode
     inode::inode_close(fs, entry_inode);
+    // 只能切换到目录（符号链接已经跟随过了）
+    if entry.file_type as FileType != FileType::Directory {
+        return Option::None;
+    }
     // 取出inode号
     task.cwd_inode = Option::Some(entry.i_no);
 
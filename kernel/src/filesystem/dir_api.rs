@@ -5,7 +5,7 @@ use os_in_rust_common::{constants, cstr_write, cstring_utils, MY_PANIC};
 use crate::memory;
 
 use super::{
-    constant, dir, dir_entry::{self, DirEntry, DirEntrySearchReq}, file_util, fs, inode::{self, OpenedInode}
+    constant, dir, dir_entry::{self, DirEntry, DirEntrySearchReq, FileType}, file_util, fs, inode::{self, OpenedInode}
 };
 
 #[derive(Debug)]
@@ -15,6 +15,7 @@ pub enum DirError {
     ParentDirNotExists,
     AlreadyExists,
     DirectoryNotEmpty,
+    NotADirectory,
 }
 
 #[derive(Debug)]
@@ -279,6 +280,16 @@ fn search_dir_entry(path: &str) -> Result<&'static mut OpenedInode, DirError> {
 #[inline(never)]
 pub fn remove_dir(path: &str) -> Result<(),  DirError> {
     let fs = fs::get_filesystem();
+    // 不跟随符号链接。如果是指向目录的符号链接，不能删除它指向的目录
+    let searched = dir_entry::search_dir_entry_nofollow(fs, path);
+    if searched.is_none() {
+        return Result::Err(DirError::NotFound);
+    }
+    let (entry, entry_inode) = searched.unwrap();
+    inode::inode_close(fs, entry_inode);
+    if entry.file_type as FileType != FileType::Directory {
+        return Result::Err(DirError::NotADirectory);
+    }
     let mut dir_to_remove = self::read_dir(path)?;
     // 如果存在数据，无法删除
     if !dir_to_remove.is_empty() {
@@ -2,9 +2,9 @@ use core::{mem::size_of, slice};
 
 use os_in_rust_common::{constants, cstr_write, cstring_utils, domain::{InodeNo, LbaAddr}, printkln, utils, ASSERT, MY_PANIC};
 
-use crate::{device::Disk, memory};
+use crate::{device::{self, Disk}, memory};
 
-use super::{constant, fs::{self, FileSystem}, inode::{self, Inode, OpenedInode}};
+use super::{constant, fs::{self, FileSystem}, inode::{self, Inode, OpenedInode}, metadata, symlink};
 
 
 /**
@@ -26,6 +26,10 @@ pub enum FileType {
      * 未知
      */
     Unknown,
+    /**
+     * 符号链接。数据块中存放的是目标路径。放在最后，不改变已有类型在硬盘中的值
+     */
+    Symlink,
 }
 
 /**
@@ -87,6 +91,14 @@ impl DirEntry {
         }
         return i == 0;
     }
+
+    /**
+     * 是否是.或者..目录项
+     */
+    pub fn is_dot_entry(&self) -> bool {
+        let name = self.name;
+        name.starts_with(b".\0") || name.starts_with(b"..\0")
+    }
 }
 
 
@@ -98,49 +110,105 @@ pub fn read_dir_entry<'a, 'b>(fs: &'a mut FileSystem, lba: LbaAddr, buff: &'b mu
 }
 
 /**
- * 指定目录项的路径，搜索这个目录项
+ * 指定目录项的路径，搜索这个目录项。路径中的符号链接（包括最后一个）都会跟随
  */
 #[inline(never)]
 pub fn search_dir_entry(filesystem: &mut FileSystem, file_path: &str) -> Option<(DirEntry, &'static mut OpenedInode)> {
+    self::search_path(filesystem, file_path, true)
+}
+
+/**
+ * 指定目录项的路径，搜索这个目录项。如果最后一个目录项是符号链接，返回符号链接本身（中间的符号链接依然会跟随）
+ */
+#[inline(never)]
+pub fn search_dir_entry_nofollow(filesystem: &mut FileSystem, file_path: &str) -> Option<(DirEntry, &'static mut OpenedInode)> {
+    self::search_path(filesystem, file_path, false)
+}
+
+#[inline(never)]
+fn search_path(filesystem: &mut FileSystem, file_path: &str, follow_last: bool) -> Option<(DirEntry, &'static mut OpenedInode)> {
     if file_path.is_empty() {
         return Option::None;
     }
 
     let root_inode_no = filesystem.get_root_inode().i_no;
     // 当前的inode，是根目录的inode
-    let mut cur_inode = inode::inode_open(filesystem, root_inode_no);
-    // 当前的目录项。默认是根目录
-    let mut cur_dir_entry = DirEntry::new(root_inode_no, "/", FileType::Directory);
+    let root_inode = inode::inode_open(filesystem, root_inode_no);
+    // 已经跟随的符号链接数量
+    let mut link_depth = 0;
+    self::walk_path(filesystem, root_inode, file_path, follow_last, &mut link_depth)
+}
 
-    // 如果就是根目录
-    if file_path == "/" {
-        return Option::Some((cur_dir_entry, cur_inode));
-    }
+/**
+ * 从目录start_inode开始，逐级搜索路径file_path。start_inode会被关闭
+ * 遇到符号链接，就读取它的目标路径继续搜索：
+ *  - 绝对路径，从根目录开始搜索
+ *  - 相对路径，从符号链接所在的目录开始搜索
+ * 跟随的符号链接超过MAX_SYMLINK_DEPTH个，认为出现了循环，搜索失败
+ */
+#[inline(never)]
+fn walk_path(fs: &mut FileSystem, start_inode: &'static mut OpenedInode, file_path: &str, follow_last: bool, link_depth: &mut u32) -> Option<(DirEntry, &'static mut OpenedInode)> {
+    let mut cur_inode = start_inode;
+    // 当前的目录项。如果路径中没有任何目录项，就是开始的那个目录
+    let mut cur_dir_entry: Option<DirEntry> = Option::None;
 
     // 把要搜索的路径，分隔
-    let mut file_entry_split = file_path.split("/");
+    let mut file_entry_split = file_path.split("/").filter(|name| !name.is_empty()).peekable();
     while let Option::Some(file_entry_name) = file_entry_split.next() {
-        if file_entry_name.is_empty() {
-            continue;
+        // 只有目录下面，才能继续搜索
+        if cur_dir_entry.is_some() && cur_dir_entry.unwrap().file_type as FileType != FileType::Directory {
+            inode::inode_close(fs, cur_inode);
+            return Option::None;
         }
         // 根据名称搜索目录项
-        let dir_entry = do_search_dir_entry(filesystem, cur_inode, DirEntrySearchReq::build().entry_name(file_entry_name));
-        // 关掉inode
-        inode::inode_close(filesystem, cur_inode);
+        let dir_entry = do_search_dir_entry(fs, cur_inode, DirEntrySearchReq::build().entry_name(file_entry_name));
         // 如果目录项不存在
         if dir_entry.is_none() {
+            inode::inode_close(fs, cur_inode);
             return Option::None;
         }
-
         let dir_entry = dir_entry.unwrap();
-        // 根据inode号，打开
-        cur_inode = inode::inode_open(filesystem, dir_entry.i_no);
 
-        // 搜索下一个目录项
-        cur_dir_entry = dir_entry;
+        // 普通的目录项，根据inode号打开，搜索下一个目录项
+        let is_last = file_entry_split.peek().is_none();
+        if dir_entry.file_type as FileType != FileType::Symlink || (is_last && !follow_last) {
+            inode::inode_close(fs, cur_inode);
+            cur_inode = inode::inode_open(fs, dir_entry.i_no);
+            cur_dir_entry = Option::Some(dir_entry);
+            continue;
+        }
+
+        // 是符号链接，跟随它
+        *link_depth += 1;
+        if *link_depth > constant::MAX_SYMLINK_DEPTH {
+            inode::inode_close(fs, cur_inode);
+            return Option::None;
+        }
+        let target_buf: &mut [u8; constants::DISK_SECTOR_SIZE] = memory::malloc(constants::DISK_SECTOR_SIZE);
+        let link_inode = inode::inode_open(fs, dir_entry.i_no);
+        let target = symlink::read_symlink(fs, link_inode, target_buf);
+        inode::inode_close(fs, link_inode);
+
+        let base_inode = if target.starts_with("/") {
+            inode::inode_open(fs, fs.super_block.root_inode_no)
+        } else {
+            inode::inode_open(fs, cur_inode.i_no)
+        };
+        let resolved = self::walk_path(fs, base_inode, target, true, link_depth);
+        memory::sys_free(target_buf.as_ptr() as usize);
+        inode::inode_close(fs, cur_inode);
+        if resolved.is_none() {
+            return Option::None;
+        }
+        let (resolved_entry, resolved_inode) = resolved.unwrap();
+        cur_inode = resolved_inode;
+        cur_dir_entry = Option::Some(resolved_entry);
+    }
+    if cur_dir_entry.is_none() {
+        return Option::Some((self::current_inode_entry(cur_inode), cur_inode));
     }
     // 返回找到的最后的那个目录项
-    return Option::Some((cur_dir_entry, cur_inode));
+    return Option::Some((cur_dir_entry.unwrap(), cur_inode));
 }
 
 #[derive(Clone, Copy)]
@@ -241,8 +309,9 @@ pub fn do_create_dir_entry(fs: &mut FileSystem, parent_inode: &mut OpenedInode,
     // 从当前分区中，申请1个inode，并且写入硬盘（inode位图）
     let inode_no = fs.inode_pool.apply_inode(1);
 
-    // 创建一个inode
-    let inode = Inode::new(inode_no);
+    // 创建一个inode，初始化类型、权限、时间等元信息
+    let mut inode = Inode::new(inode_no);
+    metadata::init_inode(&mut inode, file_type, device::current_timestamp());
     let opened_inode: &mut OpenedInode = memory::malloc_system(size_of::<OpenedInode>());
     *opened_inode = OpenedInode::new(inode);
 
@@ -377,6 +446,7 @@ pub fn sync_dir_entry(fs: &mut FileSystem, parent_inode: &mut OpenedInode, dir_e
 
     // 增加当前文件的大小
     parent_inode.i_size += size_of::<DirEntry>() as u32;
+    metadata::touch_modified(parent_inode);
     // 如果是直接块找到空闲目录项，那么需要同步inode（直接块的地址放在inode的i_sectors字段中）
     inode::sync_inode(fs, parent_inode);
 
@@ -384,6 +454,34 @@ pub fn sync_dir_entry(fs: &mut FileSystem, parent_inode: &mut OpenedInode, dir_e
     memory::sys_free(buf.as_ptr() as usize);
 }
 
+/**
+ * 在目录dir_inode中，找到符合条件entry_req的目录项，把它指向的inode号改成i_no，并且写回硬盘。返回是否找到
+ */
+#[inline(never)]
+pub fn update_dir_entry_ino(fs: &mut FileSystem, dir_inode: &mut OpenedInode, entry_req: DirEntrySearchReq, i_no: InodeNo) -> bool {
+    inode::load_indirect_data_block(fs, dir_inode);
+    let disk = unsafe { &mut *fs.base_part.from_disk };
+    let buf: &mut [u8; constants::DISK_SECTOR_SIZE] = memory::malloc(constants::DISK_SECTOR_SIZE);
+    let mut found = false;
+    for block_lba in dir_inode.get_data_blocks_ref() {
+        if block_lba.is_empty() {
+            continue;
+        }
+        disk.read_sectors(*block_lba, 1, buf);
+        let dir_entry_list = unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut DirEntry, constants::DISK_SECTOR_SIZE / size_of::<DirEntry>()) };
+        let find = self::find_dir_entry(dir_entry_list, entry_req);
+        if find.is_none() {
+            continue;
+        }
+        dir_entry_list[find.unwrap()].i_no = i_no;
+        disk.write_sector(buf, *block_lba, 1);
+        found = true;
+        break;
+    }
+    memory::sys_free(buf.as_ptr() as usize);
+    found
+}
+
 /**
  * 找到某个inode的上一级目录
  */
@@ -491,6 +589,7 @@ pub fn remove_dir_entry(fs: &mut FileSystem, parent_dir_inode: &mut OpenedInode,
         // 删除目录项
         if self::do_remove_dir_entry(disk, *block_lba, buf, entry_req) {
             parent_dir_inode.i_size -= size_of::<DirEntry>() as u32;
+            metadata::touch_modified(parent_dir_inode);
             return true;
         }
     }
@@ -507,6 +606,7 @@ pub fn remove_dir_entry(fs: &mut FileSystem, parent_dir_inode: &mut OpenedInode,
         // 删除目录项
         if self::do_remove_dir_entry(disk, *block_lba, buf, entry_req) {
             parent_dir_inode.i_size -= size_of::<DirEntry>() as u32;
+            metadata::touch_modified(parent_dir_inode);
             return true;
         }
     }
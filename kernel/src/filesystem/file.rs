@@ -1,12 +1,12 @@
 
 use core::{fmt::Display, mem::size_of};
 
-use os_in_rust_common::{constants, printkln, utils, ASSERT};
+use os_in_rust_common::{constants, utils, ASSERT};
 
 
 use crate::{console_println, memory, thread};
 use super::{
-    constant, dir_entry::{self, DirEntrySearchReq}, file_descriptor::FileDescriptor, file_util, fs::{self, FileSystem}, global_file_table, inode::{self, OpenedInode}, DirEntry, FileType
+    constant, dir_entry::{self, DirEntrySearchReq}, file_descriptor::FileDescriptor, file_util, fs::{self, FileSystem}, global_file_table, inode::{self, OpenedInode}, metadata, DirEntry, FileType
 };
 
 /**
@@ -55,6 +55,10 @@ impl OpenedFile {
         self.inode
     }
 
+    pub fn get_inode_mut(&mut self) -> &mut OpenedInode {
+        self.inode
+    }
+
 }
 
 
@@ -85,8 +89,14 @@ pub enum FileError {
     // 文件
     BadDescriptor,
 
-    // 无法删除一个打开中的文件
-    CouldNotRemoveAnOpenedFile
+    // 文件超过了文件系统支持的最大大小
+    FileTooLarge,
+    // 不能把目录移动到它自己的子目录中
+    MoveIntoItself,
+    // 当前文件系统的版本不支持这个操作
+    Unsupported,
+    // 不是一个符号链接
+    NotASymlink,
 }
 
 // pub fn close_file()
@@ -242,19 +252,24 @@ pub fn write_file(fs: &mut FileSystem, file: &mut OpenedFile, buff: &[u8]) -> us
         // 本次循环写入的字节数量
         let mut bytes_written = constants::DISK_SECTOR_SIZE;
         // 要写入的数据扇区的LBA地址
-        let data_block_lba = &mut file.inode.get_data_blocks()[block_idx];
+        let mut data_block_lba = inode::get_data_block(fs, file.inode, block_idx);
         let mut new_data_block = false;
         // 如果这个数据扇区没有填充过，那么需要申请一个数据块
-        if data_block_lba.is_empty() {
-            *data_block_lba = fs.data_block_pool.apply_block(1);
+        if data_block_lba.is_none() {
+            data_block_lba = inode::apply_data_block(fs, file.inode, block_idx);
             new_data_block = true;
         }
+        // 超过了一个文件最多能有的数据块，不能再写入了
+        if data_block_lba.is_none() {
+            break;
+        }
+        let data_block_lba = data_block_lba.unwrap();
 
         // 如果是第一个扇区，并且开始写入的字节开始偏移量不是整扇区
         if relative_block_idx == 0 && start_bytes_over_sector > 0 {
             if !new_data_block {
                 // 读取出这个扇区
-                disk.read_sectors(*data_block_lba, 1, single_sector_buffer);
+                disk.read_sectors(data_block_lba, 1, single_sector_buffer);
             }
             // 写入的字节数量 = 当前扇区剩余的数量和缓冲区长度的最小值
             bytes_written = start_bytes_left_sector.min(buff.len());
@@ -265,7 +280,7 @@ pub fn write_file(fs: &mut FileSystem, file: &mut OpenedFile, buff: &[u8]) -> us
         } else if block_idx == end_data_block_idx && end_bytes_over_sector > 0 {
             if !new_data_block {
                 // 读取出这个扇区
-                disk.read_sectors(*data_block_lba, 1, single_sector_buffer);
+                disk.read_sectors(data_block_lba, 1, single_sector_buffer);
             }
             bytes_written = end_bytes_over_sector;
             // 如果这是最后一个扇区，同时也是第一个扇区
@@ -288,7 +303,7 @@ pub fn write_file(fs: &mut FileSystem, file: &mut OpenedFile, buff: &[u8]) -> us
             single_sector_buffer.copy_from_slice(&buff[buf_start_byte_idx .. buf_end_byte_idx]);
             bytes_written = single_sector_buffer.len();
         }
-        disk.write_sector(single_sector_buffer, *data_block_lba, 1);
+        disk.write_sector(single_sector_buffer, data_block_lba, 1);
         succeed_bytes += bytes_written;
     }
     // 释放缓冲区
@@ -298,6 +313,7 @@ pub fn write_file(fs: &mut FileSystem, file: &mut OpenedFile, buff: &[u8]) -> us
 
     // 当前文件的数据大小发生变化
     file.inode.i_size = file.inode.i_size.max(file.file_off);
+    metadata::touch_modified(file.inode);
     // 把inode元数据同步到硬盘（inode数组）
     inode::sync_inode(fs, file.inode);
 
@@ -365,19 +381,17 @@ pub fn read_file(fs: &mut FileSystem, file: &mut OpenedFile, buff: &mut [u8]) ->
         
         // 本次循环读取到的字节
         let mut bytes_read = 0; 
-        // 要写入的数据扇区的LBA地址
-        let data_block_lba = &mut file.inode.get_data_blocks()[block_idx];
-
         // 没有字节可以读取了
         if left_bytes <= 0 {
             break;
         }
-        // 如果这个数据扇区没有地址
-        if data_block_lba.is_empty() {
-            continue;
+        // 要读取的数据扇区的LBA地址
+        let data_block_lba = inode::get_data_block(fs, file.inode, block_idx);
+        // 如果这个数据扇区没有地址（比如截断之后又扩大的文件），读取到的都是0
+        if data_block_lba.is_some() {
+            // 读取出这个扇区
+            disk.read_sectors(data_block_lba.unwrap(), 1, single_sector_buffer);
         }
-        // 读取出这个扇区
-        disk.read_sectors(*data_block_lba, 1, single_sector_buffer);
 
         // 如果是第一个扇区，并且开始写入的字节开始偏移量不是整扇区
         if relative_block_idx == 0 && start_bytes_over_sector > 0 {
@@ -410,30 +424,65 @@ pub fn read_file(fs: &mut FileSystem, file: &mut OpenedFile, buff: &mut [u8]) ->
         left_bytes -= bytes_read as i32;
         file.file_off += bytes_read as u32;
     }
+    // 只在内存中更新访问时间，不为了读取单独写一次硬盘。下次同步inode的时候一起保存
+    metadata::touch_accessed(file.inode);
     succeed_bytes
 }
 
 
 /**
- * 删除一个文件
- *   1. 删除这个文件的数据内容（inode）
- *   2. 删除这个文件所在父目录的目录项
+ * 把文件的大小设置为new_len
+ *  - 变小：new_len之后的数据块（包括不再需要的间接块）都释放掉，最后一个数据块中new_len之后的字节清零
+ *  - 变大：不申请数据块，读取没有数据块的部分，得到的都是0
  */
 #[inline(never)]
-pub fn remove_file(fs: &mut FileSystem, parent_inode: &mut OpenedInode, inode_to_remove: &OpenedInode) -> Result<(), FileError> {
-    if inode_to_remove.open_cnts > 1 {
-        return Result::Err(FileError::CouldNotRemoveAnOpenedFile);
+pub fn truncate_file(fs: &mut FileSystem, inode: &mut OpenedInode, new_len: u32) -> Result<(), FileError> {
+    if new_len as usize > inode::max_data_blocks(fs) * constants::DISK_SECTOR_SIZE {
+        return Result::Err(FileError::FileTooLarge);
     }
+    if new_len < inode.i_size {
+        // 需要保留的数据块数量
+        let keep_blocks = utils::div_ceil(new_len, constants::DISK_SECTOR_SIZE as u32) as usize;
+        inode::release_blocks_after(fs, inode, keep_blocks);
+
+        // 最后一个保留的数据块，把new_len之后的字节清零。以后文件变大的时候，读取到的是0
+        let bytes_over_sector = new_len as usize % constants::DISK_SECTOR_SIZE;
+        let last_block_lba = if bytes_over_sector > 0 { inode::get_data_block(fs, inode, keep_blocks - 1) } else { Option::None };
+        if last_block_lba.is_some() {
+            let disk = unsafe { &mut *fs.base_part.from_disk };
+            let last_block_lba = last_block_lba.unwrap();
+            let single_sector_buffer: &mut [u8; constants::DISK_SECTOR_SIZE] = memory::malloc(constants::DISK_SECTOR_SIZE);
+            disk.read_sectors(last_block_lba, 1, single_sector_buffer);
+            single_sector_buffer[bytes_over_sector..].fill(0);
+            disk.write_sector(single_sector_buffer, last_block_lba, 1);
+            memory::sys_free(single_sector_buffer.as_ptr() as usize);
+        }
+    }
+    inode.i_size = new_len;
+    metadata::touch_modified(inode);
+    // 把inode元数据同步到硬盘（inode数组和间接块）
+    inode::sync_inode(fs, inode);
+    Result::Ok(())
+}
 
-    // 1. 删除Inode
-    inode::inode_remove(fs, inode_to_remove);
-
-    // 2. 删除这个文件所在父目录的目录项
-    let delete = dir_entry::remove_dir_entry(fs, parent_inode, DirEntrySearchReq::build().i_no(inode_to_remove.i_no));
+/**
+ * 删除一个文件的目录项（unlink）
+ *   1. 删除这个文件所在父目录中，名为file_name的目录项
+ *   2. 硬链接数 - 1
+ * 硬链接数降为0之后，调用方关闭最后一个打开的inode时（inode_close），才会真正删除inode以及数据
+ */
+#[inline(never)]
+pub fn remove_file(fs: &mut FileSystem, parent_inode: &mut OpenedInode, file_name: &str, inode_to_remove: &mut OpenedInode) -> Result<(), FileError> {
+    // 1. 删除这个文件所在父目录的目录项。同一个目录下，可能有多个目录项指向同一个inode，所以按照名称删除
+    let delete = dir_entry::remove_dir_entry(fs, parent_inode, DirEntrySearchReq::build().entry_name(file_name));
     if !delete {
         return Result::Err(FileError::NotFound);
     }
     // 父目录操作完成后，保存到硬盘
     inode::sync_inode(fs, parent_inode);
+
+    // 2. 少了一个目录项指向这个inode
+    inode_to_remove.i_nlink = inode_to_remove.i_nlink.saturating_sub(1);
+    inode::sync_inode(fs, inode_to_remove);
     return Result::Ok(());
 }
\ No newline at end of file
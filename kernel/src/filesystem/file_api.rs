@@ -1,15 +1,16 @@
 use core::fmt::{Debug};
 
-use os_in_rust_common::{cstr_write, cstring_utils, printkln, ASSERT};
+use os_in_rust_common::{constants, cstr_write, cstring_utils, domain::InodeNo, printkln, ASSERT};
 
-use crate::{filesystem::{constant, file, fs}, thread};
+use crate::{filesystem::{constant, file, fs}, memory, thread};
 
-use super::{dir_entry::{self, DirEntrySearchReq}, file::{FileError, OpenedFile}, file_descriptor::FileDescriptor, file_util, global_file_table, inode};
+use super::{dir_entry::{self, DirEntry, DirEntrySearchReq, FileType}, file::{FileError, OpenedFile}, file_descriptor::FileDescriptor, file_util, global_file_table, inode::{self, OpenedInode}, metadata::{self, Metadata}};
 
 pub struct OpenOptions {
     write: bool, 
     append: bool,
     read: bool,
+    truncate: bool,
     ignore_drop: bool,
 }
 
@@ -21,6 +22,7 @@ impl OpenOptions {
             write: false,
             append: false,
             read: false,
+            truncate: false,
             ignore_drop: false,
         }
     }
@@ -42,6 +44,15 @@ impl OpenOptions {
         self
     }
 
+    /**
+     * 打开的时候，把文件清空（需要可写）
+     */
+    #[inline(never)]
+    pub fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.truncate = truncate;
+        self
+    }
+
     #[inline(never)]
     pub fn ignore_drop(&mut self, ignore: bool) -> &mut Self {
         self.ignore_drop = ignore;
@@ -53,6 +64,9 @@ impl OpenOptions {
         let fd = file::open_file(path, self.append)?;
         let mut file = File::new(fd, path, self.write, self.read);
         file.ignore_drop = self.ignore_drop;
+        if self.truncate {
+            file.set_len(0)?;
+        }
 
         return Result::Ok(file);
     }
@@ -228,6 +242,17 @@ impl File {
         Result::Ok(file::write_file(fs, opened_file, buff))
     }
 
+    /**
+     * 把文件的大小设置为len。变小的部分释放掉，变大的部分读取到的是0
+     */
+    #[inline(never)]
+    pub fn set_len(&mut self, len: u32) -> Result<(), FileError> {
+        if !self.write {
+            return Result::Err(FileError::PermissionDenied);
+        }
+        self::ftruncate(self.fd, len)
+    }
+
     pub fn get_path(&self) -> &str {
         let res = cstring_utils::read_from_bytes(&self.path);
         ASSERT!(res.is_some());
@@ -259,6 +284,57 @@ impl Drop for File {
     }
 }
 
+/**
+ * 把文件描述符fd对应的文件，大小设置为len
+ */
+#[inline(never)]
+pub fn ftruncate(fd: FileDescriptor, len: u32) -> Result<(), FileError> {
+    let opened_file = global_file_table::get_file_by_fd(fd)?;
+    let fs = fs::get_filesystem();
+    file::truncate_file(fs, opened_file.get_inode_mut(), len)
+}
+
+/**
+ * 得到path的元信息。如果path是符号链接，得到它指向的文件的元信息
+ */
+#[inline(never)]
+pub fn stat(path: &str) -> Result<Metadata, FileError> {
+    let fs = fs::get_filesystem();
+    let searched = dir_entry::search_dir_entry(fs, path);
+    self::metadata_of(fs, searched)
+}
+
+/**
+ * 得到path的元信息。如果path是符号链接，得到符号链接本身的元信息
+ */
+#[inline(never)]
+pub fn lstat(path: &str) -> Result<Metadata, FileError> {
+    let fs = fs::get_filesystem();
+    let searched = dir_entry::search_dir_entry_nofollow(fs, path);
+    self::metadata_of(fs, searched)
+}
+
+#[inline(never)]
+fn metadata_of(fs: &mut fs::FileSystem, searched: Option<(DirEntry, &'static mut OpenedInode)>) -> Result<Metadata, FileError> {
+    if searched.is_none() {
+        return Result::Err(FileError::NotFound);
+    }
+    let (entry, entry_inode) = searched.unwrap();
+    let metadata = Metadata::new(entry_inode, entry.file_type);
+    inode::inode_close(fs, entry_inode);
+    Result::Ok(metadata)
+}
+
+/**
+ * 得到文件描述符fd对应的文件的元信息。文件类型从inode的i_mode中得到（旧版本的文件系统，类型未知）
+ */
+#[inline(never)]
+pub fn fstat(fd: FileDescriptor) -> Result<Metadata, FileError> {
+    let opened_file = global_file_table::get_file_by_fd(fd)?;
+    let file_inode = opened_file.get_inode_mut();
+    Result::Ok(Metadata::new(file_inode, metadata::file_type_of(file_inode.i_mode)))
+}
+
 /**
  * 删除文件
  */
@@ -291,14 +367,232 @@ pub fn remove_file(path: &str) -> Result<(), FileError> {
     let cur_file_entry = cur_file_entry.unwrap();
     let cur_file_inode = inode::inode_open(fs, cur_file_entry.i_no);
     
-    // 把这个文件的数据扇区LBA地址都加载出来（间接扇区）
-    inode::load_indirect_data_block(fs, cur_file_inode);
-    // 指定父目录，删除这个文件inode
-    file::remove_file(fs, parent_dir_inode, cur_file_inode)?;
+    // 指定父目录，删除这个文件的目录项
+    let remove_res = file::remove_file(fs, parent_dir_inode, file_name, cur_file_inode);
     
-    // 关闭inode
+    // 关闭inode。如果已经没有目录项指向它，并且其他任务也没有打开它，关闭的时候就会删除inode以及数据
     inode::inode_close(fs, parent_dir_inode);
     inode::inode_close(fs, cur_file_inode);
-    // remove_res
-    return Result::Ok(());
-}
\ No newline at end of file
+    remove_res
+}
+
+/**
+ * 把old_path移动（重命名）为new_path。可以在同一个目录中，也可以跨目录。new_path不能已经存在
+ *  - 移动目录到另一个目录中，会修改这个目录的..目录项，指向新的父目录
+ *  - 不能把目录移动到它自己的子目录中
+ */
+#[inline(never)]
+pub fn rename(old_path: &str, new_path: &str) -> Result<(), FileError> {
+    let old_split = file_util::split_file_path(old_path);
+    let new_split = file_util::split_file_path(new_path);
+    if old_split.is_none() || new_split.is_none() {
+        return Result::Err(FileError::FilePathIllegal);
+    }
+    let (old_dir_path, old_name) = old_split.unwrap();
+    let (new_dir_path, new_name) = new_split.unwrap();
+    if old_name == "." || old_name == ".." || new_name == "." || new_name == ".." || new_name.len() >= constant::MAX_FILE_NAME {
+        return Result::Err(FileError::FilePathIllegal);
+    }
+    let fs = fs::get_filesystem();
+
+    // 原来的父目录
+    let old_parent = dir_entry::search_dir_entry(fs, old_dir_path);
+    if old_parent.is_none() {
+        return Result::Err(FileError::ParentDirNotExists);
+    }
+    let (_, old_parent_inode) = old_parent.unwrap();
+
+    // 新的父目录
+    let new_parent = dir_entry::search_dir_entry(fs, new_dir_path);
+    if new_parent.is_none() {
+        inode::inode_close(fs, old_parent_inode);
+        return Result::Err(FileError::ParentDirNotExists);
+    }
+    let (new_parent_entry, new_parent_inode) = new_parent.unwrap();
+
+    let res = if new_parent_entry.file_type as FileType != FileType::Directory {
+        Result::Err(FileError::ParentDirNotExists)
+    } else {
+        self::do_rename(fs, old_parent_inode, old_name, new_parent_inode, new_name)
+    };
+    inode::inode_close(fs, old_parent_inode);
+    inode::inode_close(fs, new_parent_inode);
+    res
+}
+
+#[inline(never)]
+fn do_rename(fs: &mut fs::FileSystem, old_parent_inode: &mut OpenedInode, old_name: &str, new_parent_inode: &mut OpenedInode, new_name: &str) -> Result<(), FileError> {
+    // 要移动的目录项
+    let entry = dir_entry::do_search_dir_entry(fs, old_parent_inode, DirEntrySearchReq::build().entry_name(old_name));
+    if entry.is_none() {
+        return Result::Err(FileError::NotFound);
+    }
+    let entry = entry.unwrap();
+
+    // 新的名称已经存在
+    let exist_entry = dir_entry::do_search_dir_entry(fs, new_parent_inode, DirEntrySearchReq::build().entry_name(new_name));
+    if exist_entry.is_some() {
+        // 移动到自己，什么都不用做
+        if old_parent_inode.i_no == new_parent_inode.i_no && old_name == new_name {
+            return Result::Ok(());
+        }
+        return Result::Err(FileError::AlreadyExists);
+    }
+
+    // 目录移动到了另一个目录中。新的父目录不能是它自己，或者它的子目录
+    let move_dir = entry.file_type as FileType == FileType::Directory && old_parent_inode.i_no != new_parent_inode.i_no;
+    if move_dir && self::is_in_subtree(fs, new_parent_inode.i_no, entry.i_no) {
+        return Result::Err(FileError::MoveIntoItself);
+    }
+
+    // 先在新的父目录中添加目录项，再从原来的父目录中删除
+    dir_entry::do_create_dir_entry_with_inode(fs, new_parent_inode, entry.i_no, new_name, entry.file_type);
+    dir_entry::remove_dir_entry(fs, old_parent_inode, DirEntrySearchReq::build().entry_name(old_name));
+    inode::sync_inode(fs, old_parent_inode);
+
+    // 目录的..目录项，指向新的父目录
+    if move_dir {
+        let moved_inode = inode::inode_open(fs, entry.i_no);
+        dir_entry::update_dir_entry_ino(fs, moved_inode, DirEntrySearchReq::build().entry_name(".."), new_parent_inode.i_no);
+        inode::inode_close(fs, moved_inode);
+    }
+    Result::Ok(())
+}
+
+/**
+ * 创建硬链接：在new_path添加一个目录项，指向existing_path的inode。new_path不能已经存在
+ *  - 不能给目录创建硬链接
+ *  - 旧版本的文件系统，inode中没有硬链接数，不支持
+ */
+#[inline(never)]
+pub fn link(existing_path: &str, new_path: &str) -> Result<(), FileError> {
+    let fs = fs::get_filesystem();
+    if !fs.super_block.support_link_count() {
+        return Result::Err(FileError::Unsupported);
+    }
+    let new_split = file_util::split_file_path(new_path);
+    if new_split.is_none() {
+        return Result::Err(FileError::FilePathIllegal);
+    }
+    let (new_dir_path, new_name) = new_split.unwrap();
+    if new_name == "." || new_name == ".." || new_name.len() >= constant::MAX_FILE_NAME {
+        return Result::Err(FileError::FilePathIllegal);
+    }
+
+    // 已经存在的文件
+    let existing = dir_entry::search_dir_entry(fs, existing_path);
+    if existing.is_none() {
+        return Result::Err(FileError::NotFound);
+    }
+    let (existing_entry, existing_inode) = existing.unwrap();
+    if existing_entry.file_type as FileType == FileType::Directory {
+        inode::inode_close(fs, existing_inode);
+        return Result::Err(FileError::IsADirectory);
+    }
+
+    // 新的目录项所在的父目录
+    let new_parent = dir_entry::search_dir_entry(fs, new_dir_path);
+    if new_parent.is_none() {
+        inode::inode_close(fs, existing_inode);
+        return Result::Err(FileError::ParentDirNotExists);
+    }
+    let (new_parent_entry, new_parent_inode) = new_parent.unwrap();
+
+    let res = if new_parent_entry.file_type as FileType != FileType::Directory {
+        Result::Err(FileError::ParentDirNotExists)
+    } else if dir_entry::do_search_dir_entry(fs, new_parent_inode, DirEntrySearchReq::build().entry_name(new_name)).is_some() {
+        Result::Err(FileError::AlreadyExists)
+    } else {
+        // 添加目录项，指向同一个inode
+        dir_entry::do_create_dir_entry_with_inode(fs, new_parent_inode, existing_inode.i_no, new_name, existing_entry.file_type);
+        // 多了一个目录项指向这个inode
+        existing_inode.i_nlink += 1;
+        inode::sync_inode(fs, existing_inode);
+        Result::Ok(())
+    };
+    inode::inode_close(fs, existing_inode);
+    inode::inode_close(fs, new_parent_inode);
+    res
+}
+
+/**
+ * 创建符号链接：在link_path创建一个符号链接，内容是target。link_path不能已经存在
+ *  - target不用存在，也可以是相对路径（相对于符号链接所在的目录）
+ */
+#[inline(never)]
+pub fn symlink(target: &str, link_path: &str) -> Result<(), FileError> {
+    if target.is_empty() || target.len() > constant::MAX_FILE_PATH_LEN {
+        return Result::Err(FileError::FilePathIllegal);
+    }
+    let link_split = file_util::split_file_path(link_path);
+    if link_split.is_none() {
+        return Result::Err(FileError::FilePathIllegal);
+    }
+    let (link_dir_path, link_name) = link_split.unwrap();
+    if link_name == "." || link_name == ".." || link_name.len() >= constant::MAX_FILE_NAME {
+        return Result::Err(FileError::FilePathIllegal);
+    }
+    let fs = fs::get_filesystem();
+
+    // 符号链接所在的父目录
+    let parent = dir_entry::search_dir_entry(fs, link_dir_path);
+    if parent.is_none() {
+        return Result::Err(FileError::ParentDirNotExists);
+    }
+    let (parent_entry, parent_inode) = parent.unwrap();
+
+    let res = if parent_entry.file_type as FileType != FileType::Directory {
+        Result::Err(FileError::ParentDirNotExists)
+    } else if dir_entry::do_search_dir_entry(fs, parent_inode, DirEntrySearchReq::build().entry_name(link_name)).is_some() {
+        Result::Err(FileError::AlreadyExists)
+    } else {
+        super::symlink::create_symlink(fs, parent_inode, link_name, target);
+        Result::Ok(())
+    };
+    inode::inode_close(fs, parent_inode);
+    res
+}
+
+/**
+ * 读取符号链接path的目标路径，写入到buf中。返回目标路径的长度（超过buf的部分会被截断）
+ */
+#[inline(never)]
+pub fn read_link(path: &str, buf: &mut [u8]) -> Result<usize, FileError> {
+    let fs = fs::get_filesystem();
+    // 不跟随最后一个符号链接，找到符号链接本身
+    let searched = dir_entry::search_dir_entry_nofollow(fs, path);
+    if searched.is_none() {
+        return Result::Err(FileError::NotFound);
+    }
+    let (entry, link_inode) = searched.unwrap();
+    if entry.file_type as FileType != FileType::Symlink {
+        inode::inode_close(fs, link_inode);
+        return Result::Err(FileError::NotASymlink);
+    }
+    let target_buf: &mut [u8; constants::DISK_SECTOR_SIZE] = memory::malloc(constants::DISK_SECTOR_SIZE);
+    let target = super::symlink::read_symlink(fs, link_inode, target_buf);
+    let target_len = target.len().min(buf.len());
+    buf[..target_len].copy_from_slice(&target.as_bytes()[..target_len]);
+    memory::sys_free(target_buf.as_ptr() as usize);
+    inode::inode_close(fs, link_inode);
+    Result::Ok(target_len)
+}
+
+/**
+ * 目录dir_ino是不是目录ancestor_ino自己，或者在它的子树中。沿着..一直往上找，直到根目录
+ */
+#[inline(never)]
+fn is_in_subtree(fs: &mut fs::FileSystem, dir_ino: InodeNo, ancestor_ino: InodeNo) -> bool {
+    let mut cur_ino = dir_ino;
+    loop {
+        if cur_ino == ancestor_ino {
+            return true;
+        }
+        if cur_ino == fs.super_block.root_inode_no {
+            return false;
+        }
+        let cur_inode = inode::inode_open(fs, cur_ino);
+        cur_ino = dir_entry::parent_entry(cur_inode);
+        inode::inode_close(fs, cur_inode);
+    }
+}
@@ -187,6 +187,13 @@ impl InodePool {
         }
     }
 
+    /**
+     * inode位图的数据
+     */
+    pub fn get_bitmap_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.inode_bitmap.map_ptr, self.inode_bitmap.bits_len() / 8) }
+    }
+
     /**
      * 从inode池中申请一个inode
      */
@@ -217,6 +224,7 @@ impl InodePool {
 
     /**
      * ino号inode所在的inode位图同步到硬盘
+     * 写入的是扇区缓存，由定时刷新或者sync系统调用写回硬盘
      */
     #[inline(never)]
     pub fn sync_inode_pool(&mut self, ino: InodeNo) {
@@ -281,6 +289,13 @@ impl DataBlockPool {
         }
     }
 
+    /**
+     * 块位图的数据
+     */
+    pub fn get_bitmap_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.block_bitmap.map_ptr, self.block_bitmap.bits_len() / 8) }
+    }
+
     /**
      * 在数据块的池子中，申请一个数据块。（会同步到硬盘）
      */
@@ -314,6 +329,7 @@ impl DataBlockPool {
 
     /**
      * 空闲块为block_lba所在的块位图，同步到硬盘
+     * 写入的是扇区缓存，由定时刷新或者sync系统调用写回硬盘
      */
     #[inline(never)]
     pub fn sync_block_pool(&mut self, block_lba: LbaAddr) {
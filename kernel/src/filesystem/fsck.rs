@@ -0,0 +1,599 @@
+use core::{fmt::Display, mem::size_of, slice};
+
+use os_in_rust_common::{bitmap::BitMap, constants, domain::{InodeNo, LbaAddr}, utils, ASSERT};
+
+use crate::{device::Disk, memory};
+
+use super::{constant, dir_entry::{DirEntry, FileType}, fs, inode::Inode, metadata, superblock::SuperBlock};
+
+/**
+ * 文件系统的一致性检查（fsck）
+ * 从根目录开始，遍历所有能够访问到的inode，把每个inode引用的数据块（直接块、间接块）和块位图、inode位图进行对比：
+ *  - 被inode引用，但是位图中是空闲的块或者inode
+ *  - 位图中被占用，但是没有任何inode引用的块（孤儿块），以及访问不到的inode（孤儿inode）
+ *  - 被多个inode（或者同一个inode的多个位置）引用的块
+ *  - 硬链接数和实际的目录项数量不一致的inode
+ * 检查的逻辑不依赖内核的硬盘驱动，宿主机上的fsck程序也可以直接检查硬盘镜像
+ */
+
+/**
+ * 检查过程中，需要的扇区缓冲区数量：3级间接块各1个，目录项数据块1个，inode所在扇区2个
+ */
+const SCRATCH_SECTORS: usize = 6;
+/**
+ * 目录项数据块的缓冲区，在临时空间中的下标
+ */
+const DIR_BLOCK_BUF: usize = 3;
+/**
+ * inode所在扇区的缓冲区，在临时空间中的下标（占用2个扇区）
+ */
+const INODE_BUF: usize = 4;
+
+/**
+ * 报告中最多记录的问题数量。超过了只计数
+ */
+pub const MAX_REPORTED_PROBLEMS: usize = 32;
+
+/**
+ * 检查的文件系统所在的设备。内核中是挂载的分区，宿主机上是硬盘镜像文件
+ */
+pub trait FsckDevice {
+    /**
+     * 从lba_start开始，读取sec_cnt个扇区到buf中
+     */
+    fn read_sectors(&mut self, lba_start: LbaAddr, sec_cnt: usize, buf: &mut [u8]);
+
+    /**
+     * 把buf写入到lba_start开始的sec_cnt个扇区中
+     */
+    fn write_sectors(&mut self, buf: &[u8], lba_start: LbaAddr, sec_cnt: usize);
+
+    /**
+     * 该inode是否正在使用（被打开了）。正在使用的inode，不检查硬链接数，没有目录项也不当作孤儿
+     */
+    fn is_inode_busy(&self, _i_no: InodeNo) -> bool {
+        false
+    }
+}
+
+/**
+ * 检查出来的问题
+ */
+#[derive(Debug, Clone, Copy)]
+pub enum FsckProblem {
+    /**
+     * inode引用的块，不在数据区的范围内
+     */
+    BadBlockAddr { i_no: InodeNo, lba: LbaAddr },
+    /**
+     * 块已经被其他地方引用了
+     */
+    DoublyClaimedBlock { i_no: InodeNo, lba: LbaAddr },
+    /**
+     * inode引用的块，在块位图中是空闲的
+     */
+    UnmarkedBlock { i_no: InodeNo, lba: LbaAddr },
+    /**
+     * 块位图中被占用，但是没有inode引用的块
+     */
+    OrphanBlock { lba: LbaAddr },
+    /**
+     * 目录项指向的inode号，超出了inode的数量
+     */
+    BadDirEntry { dir_i_no: InodeNo, i_no: InodeNo },
+    /**
+     * 目录项指向的inode，在inode位图中是空闲的
+     */
+    UnmarkedInode { i_no: InodeNo },
+    /**
+     * inode位图中被占用，但是从根目录访问不到的inode
+     */
+    OrphanInode { i_no: InodeNo },
+    /**
+     * inode记录的硬链接数，和指向它的目录项数量不一致
+     */
+    WrongLinkCount { i_no: InodeNo, recorded: u32, actual: u32 },
+}
+
+impl FsckProblem {
+    /**
+     * 是否可以修复。被多次引用的块、错误的地址，不知道哪个是对的，只报告不修复
+     */
+    pub fn is_repairable(&self) -> bool {
+        match self {
+            FsckProblem::BadBlockAddr { .. } | FsckProblem::DoublyClaimedBlock { .. } | FsckProblem::BadDirEntry { .. } => false,
+            _ => true,
+        }
+    }
+}
+
+impl Display for FsckProblem {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match *self {
+            FsckProblem::BadBlockAddr { i_no, lba } => write!(f, "inode {}: block {} out of data area", i_no.get_data(), lba.get_lba()),
+            FsckProblem::DoublyClaimedBlock { i_no, lba } => write!(f, "inode {}: block {} already claimed", i_no.get_data(), lba.get_lba()),
+            FsckProblem::UnmarkedBlock { i_no, lba } => write!(f, "inode {}: block {} marked free in block bitmap", i_no.get_data(), lba.get_lba()),
+            FsckProblem::OrphanBlock { lba } => write!(f, "block {} marked used but not owned", lba.get_lba()),
+            FsckProblem::BadDirEntry { dir_i_no, i_no } => write!(f, "inode {}: entry points to invalid inode {}", dir_i_no.get_data(), i_no.get_data()),
+            FsckProblem::UnmarkedInode { i_no } => write!(f, "inode {} marked free in inode bitmap", i_no.get_data()),
+            FsckProblem::OrphanInode { i_no } => write!(f, "inode {} marked used but unreachable", i_no.get_data()),
+            FsckProblem::WrongLinkCount { i_no, recorded, actual } => write!(f, "inode {}: link count {}, should be {}", i_no.get_data(), recorded, actual),
+        }
+    }
+}
+
+/**
+ * 检查的结果
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct FsckReport {
+    /**
+     * 前MAX_REPORTED_PROBLEMS个问题
+     */
+    problems: [Option<FsckProblem>; MAX_REPORTED_PROBLEMS],
+    /**
+     * 问题的数量
+     */
+    pub problem_cnt: u32,
+    /**
+     * 修复了的问题数量
+     */
+    pub repaired_cnt: u32,
+    /**
+     * 从根目录能够访问到的inode数量
+     */
+    pub inode_cnt: u32,
+    /**
+     * 被inode引用的块数量（包括间接块）
+     */
+    pub block_cnt: u32,
+}
+
+impl FsckReport {
+    pub fn new() -> Self {
+        Self {
+            problems: [Option::None; MAX_REPORTED_PROBLEMS],
+            problem_cnt: 0,
+            repaired_cnt: 0,
+            inode_cnt: 0,
+            block_cnt: 0,
+        }
+    }
+
+    /**
+     * 没有任何问题
+     */
+    pub fn is_clean(&self) -> bool {
+        self.problem_cnt == 0
+    }
+
+    /**
+     * 记录下来的问题（最多MAX_REPORTED_PROBLEMS个）
+     */
+    pub fn problems(&self) -> impl Iterator<Item = &FsckProblem> {
+        self.problems.iter().filter_map(|problem| problem.as_ref())
+    }
+
+    fn add(&mut self, problem: FsckProblem, repaired: bool) {
+        if (self.problem_cnt as usize) < MAX_REPORTED_PROBLEMS {
+            self.problems[self.problem_cnt as usize] = Option::Some(problem);
+        }
+        self.problem_cnt += 1;
+        if repaired {
+            self.repaired_cnt += 1;
+        }
+    }
+}
+
+impl Display for FsckReport {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} inodes, {} blocks in use, {} problems, {} repaired", self.inode_cnt, self.block_cnt, self.problem_cnt, self.repaired_cnt)
+    }
+}
+
+/**
+ * 检查需要的临时空间大小。单位：字节
+ *  - 扇区缓冲区
+ *  - 访问到的inode、目录inode、检查过的inode，各一个位图
+ *  - 被引用的块的位图
+ *  - 每个inode的目录项数量（u16）
+ */
+pub fn scratch_size(super_block: &SuperBlock) -> usize {
+    let inode_bytes = utils::div_ceil(super_block.inode_cnt, 8) as usize;
+    let block_bytes = utils::div_ceil(super_block.data_block_secs, 8) as usize;
+    SCRATCH_SECTORS * constants::DISK_SECTOR_SIZE + 3 * inode_bytes + block_bytes + super_block.inode_cnt as usize * size_of::<u16>()
+}
+
+/**
+ * 一次文件系统检查
+ */
+pub struct Fsck<'a, D: FsckDevice> {
+    device: &'a mut D,
+    super_block: &'a SuperBlock,
+    /**
+     * 硬盘中的inode位图、块位图。修复的时候直接修改，最后写回硬盘
+     */
+    inode_bitmap: BitMap,
+    block_bitmap: BitMap,
+    /**
+     * 从根目录访问到的inode
+     */
+    inode_seen: BitMap,
+    /**
+     * 访问到的inode中，哪些是目录
+     */
+    inode_dir: BitMap,
+    /**
+     * 已经检查过数据块的inode
+     */
+    inode_checked: BitMap,
+    /**
+     * 已经被引用的块
+     */
+    block_seen: BitMap,
+    /**
+     * 每个inode的目录项数量
+     */
+    link_cnts: &'a mut [u8],
+    /**
+     * 扇区缓冲区
+     */
+    sectors: &'a mut [u8],
+    repair: bool,
+    report: FsckReport,
+}
+
+impl<'a, D: FsckDevice> Fsck<'a, D> {
+    /**
+     * 构建一次检查
+     *  - inode_bitmap、block_bitmap: 硬盘中的inode位图、块位图（至少是位图占用的整个扇区）
+     *  - scratch: 临时空间，至少scratch_size个字节
+     */
+    pub fn new(device: &'a mut D, super_block: &'a SuperBlock, inode_bitmap: &'a mut [u8], block_bitmap: &'a mut [u8], scratch: &'a mut [u8]) -> Self {
+        let inode_bitmap_bytes = super_block.inode_bitmap_secs as usize * constants::DISK_SECTOR_SIZE;
+        let block_bitmap_bytes = super_block.block_bitmap_secs as usize * constants::DISK_SECTOR_SIZE;
+        ASSERT!(inode_bitmap.len() >= inode_bitmap_bytes);
+        ASSERT!(block_bitmap.len() >= block_bitmap_bytes);
+        ASSERT!(scratch.len() >= self::scratch_size(super_block));
+        unsafe { scratch.as_mut_ptr().write_bytes(0, scratch.len()) };
+
+        let inode_bytes = utils::div_ceil(super_block.inode_cnt, 8) as usize;
+        let block_bytes = utils::div_ceil(super_block.data_block_secs, 8) as usize;
+        let (sectors, left) = scratch.split_at_mut(SCRATCH_SECTORS * constants::DISK_SECTOR_SIZE);
+        let (inode_seen, left) = left.split_at_mut(inode_bytes);
+        let (inode_dir, left) = left.split_at_mut(inode_bytes);
+        let (inode_checked, left) = left.split_at_mut(inode_bytes);
+        let (block_seen, left) = left.split_at_mut(block_bytes);
+        Self {
+            device,
+            super_block,
+            inode_bitmap: BitMap::new(&mut inode_bitmap[..inode_bitmap_bytes]),
+            block_bitmap: BitMap::new(&mut block_bitmap[..block_bitmap_bytes]),
+            inode_seen: BitMap::new(inode_seen),
+            inode_dir: BitMap::new(inode_dir),
+            inode_checked: BitMap::new(inode_checked),
+            block_seen: BitMap::new(block_seen),
+            link_cnts: left,
+            sectors,
+            repair: false,
+            report: FsckReport::new(),
+        }
+    }
+
+    /**
+     * 执行检查。repair为true，修复能够修复的问题
+     */
+    #[inline(never)]
+    pub fn run(&mut self, repair: bool) -> FsckReport {
+        self.repair = repair;
+        // 根目录没有目录项指向它，当作有1个
+        self.reach_inode(self.super_block.root_inode_no, FileType::Directory);
+
+        // 一层一层地检查：每一轮检查上一轮在目录中新发现的inode，直到没有新的inode
+        loop {
+            let mut found = false;
+            for idx in 0 .. self.super_block.inode_cnt as usize {
+                if self.inode_seen.is_set(idx) && !self.inode_checked.is_set(idx) {
+                    self.check_inode(InodeNo::from(idx), self.inode_dir.is_set(idx));
+                    found = true;
+                }
+            }
+            if !found {
+                break;
+            }
+        }
+
+        // 先检查孤儿inode（正在使用的孤儿inode，它的块不能当作孤儿块）
+        self.check_orphan_inodes();
+        self.check_orphan_blocks();
+        self.check_link_counts();
+
+        // 修复了位图，写回硬盘
+        if self.report.repaired_cnt > 0 {
+            let inode_bitmap = self.inode_bitmap.get_bitmap();
+            self.device.write_sectors(inode_bitmap, self.super_block.inode_bitmap_lba, self.super_block.inode_bitmap_secs as usize);
+            let block_bitmap = self.block_bitmap.get_bitmap();
+            self.device.write_sectors(block_bitmap, self.super_block.block_bitmap_lba, self.super_block.block_bitmap_secs as usize);
+        }
+        self.report
+    }
+
+    /**
+     * 发现了一个问题。返回是否需要修复
+     */
+    fn found(&mut self, problem: FsckProblem) -> bool {
+        let repair = self.repair && problem.is_repairable();
+        self.report.add(problem, repair);
+        repair
+    }
+
+    /**
+     * 一个目录项（或者根目录）指向了i_no
+     */
+    fn reach_inode(&mut self, i_no: InodeNo, file_type: FileType) {
+        let idx = usize::from(i_no);
+        let link_cnt = self.link_cnt(idx);
+        self.set_link_cnt(idx, link_cnt.saturating_add(1));
+        if self.inode_seen.is_set(idx) {
+            return;
+        }
+        self.inode_seen.set_bit(idx, true);
+        if file_type == FileType::Directory {
+            self.inode_dir.set_bit(idx, true);
+        }
+        if !self.inode_bitmap.is_set(idx) && self.found(FsckProblem::UnmarkedInode { i_no }) {
+            self.inode_bitmap.set_bit(idx, true);
+        }
+    }
+
+    /**
+     * 检查i_no引用的所有块。如果是目录，检查里面的目录项
+     */
+    #[inline(never)]
+    fn check_inode(&mut self, i_no: InodeNo, is_dir: bool) {
+        self.inode_checked.set_bit(usize::from(i_no), true);
+        self.report.inode_cnt += 1;
+        let inode = self.read_inode(i_no);
+        for block_idx in 0 .. constant::INODE_DIRECT_DATA_SECS {
+            self.check_data_block(i_no, inode.direct_sectors[block_idx], is_dir);
+        }
+        self.check_index_block(i_no, inode.indirect_sector, 1, is_dir);
+        if self.super_block.support_deep_indirect() {
+            self.check_index_block(i_no, inode.double_indirect_sector, 2, is_dir);
+            self.check_index_block(i_no, inode.triple_indirect_sector, 3, is_dir);
+        }
+    }
+
+    /**
+     * i_no引用了levels级间接块table_lba。检查它，以及它索引的块
+     */
+    fn check_index_block(&mut self, i_no: InodeNo, table_lba: LbaAddr, levels: usize, is_dir: bool) {
+        if table_lba.is_empty() || !self.claim_block(i_no, table_lba) {
+            return;
+        }
+        // 每一级间接块，使用自己的缓冲区
+        let buf_off = (levels - 1) * constants::DISK_SECTOR_SIZE;
+        self.device.read_sectors(table_lba, 1, &mut self.sectors[buf_off .. buf_off + constants::DISK_SECTOR_SIZE]);
+        for slot in 0 .. constant::LBA_PER_BLOCK {
+            let lba_off = buf_off + slot * size_of::<LbaAddr>();
+            let lba = LbaAddr::new(u32::from_ne_bytes(self.sectors[lba_off .. lba_off + size_of::<LbaAddr>()].try_into().unwrap()));
+            if levels > 1 {
+                self.check_index_block(i_no, lba, levels - 1, is_dir);
+            } else {
+                self.check_data_block(i_no, lba, is_dir);
+            }
+        }
+    }
+
+    /**
+     * i_no引用了数据块lba
+     */
+    fn check_data_block(&mut self, i_no: InodeNo, lba: LbaAddr, is_dir: bool) {
+        if lba.is_empty() || !self.claim_block(i_no, lba) {
+            return;
+        }
+        if is_dir {
+            self.scan_dir_block(i_no, lba);
+        }
+    }
+
+    /**
+     * i_no引用了块lba。返回这个块是否可以继续检查（地址正确，并且是第一次被引用）
+     */
+    fn claim_block(&mut self, i_no: InodeNo, lba: LbaAddr) -> bool {
+        let data_start = self.super_block.data_lba_start.get_lba();
+        if lba.get_lba() < data_start || lba.get_lba() >= data_start + self.super_block.data_block_secs {
+            self.found(FsckProblem::BadBlockAddr { i_no, lba });
+            return false;
+        }
+        let bit_idx = (lba.get_lba() - data_start) as usize;
+        if self.block_seen.is_set(bit_idx) {
+            self.found(FsckProblem::DoublyClaimedBlock { i_no, lba });
+            return false;
+        }
+        self.block_seen.set_bit(bit_idx, true);
+        self.report.block_cnt += 1;
+        if !self.block_bitmap.is_set(bit_idx) && self.found(FsckProblem::UnmarkedBlock { i_no, lba }) {
+            self.block_bitmap.set_bit(bit_idx, true);
+        }
+        true
+    }
+
+    /**
+     * 检查目录dir_i_no的一个数据块中的目录项
+     */
+    fn scan_dir_block(&mut self, dir_i_no: InodeNo, lba: LbaAddr) {
+        let buf_off = DIR_BLOCK_BUF * constants::DISK_SECTOR_SIZE;
+        self.device.read_sectors(lba, 1, &mut self.sectors[buf_off .. buf_off + constants::DISK_SECTOR_SIZE]);
+        for entry_idx in 0 .. constant::MAX_ENTRY_IN_BLOCK {
+            let entries = unsafe { slice::from_raw_parts(self.sectors[buf_off ..].as_ptr() as *const DirEntry, constant::MAX_ENTRY_IN_BLOCK) };
+            let entry = entries[entry_idx];
+            if entry.is_empty() || entry.file_type as FileType == FileType::Unknown || entry.is_dot_entry() {
+                continue;
+            }
+            let i_no = entry.i_no;
+            if u32::from(i_no) >= self.super_block.inode_cnt {
+                self.found(FsckProblem::BadDirEntry { dir_i_no, i_no });
+                continue;
+            }
+            self.reach_inode(i_no, entry.file_type);
+        }
+    }
+
+    /**
+     * 检查inode位图中被占用，但是访问不到的inode
+     */
+    #[inline(never)]
+    fn check_orphan_inodes(&mut self) {
+        for idx in 0 .. self.super_block.inode_cnt as usize {
+            if !self.inode_bitmap.is_set(idx) || self.inode_seen.is_set(idx) {
+                continue;
+            }
+            let i_no = InodeNo::from(idx);
+            // 已经删除了目录项，但是还打开着的文件。它的块还在使用
+            if self.device.is_inode_busy(i_no) {
+                self.check_inode(i_no, false);
+                continue;
+            }
+            // 释放孤儿inode。它的块没有被引用，接下来当作孤儿块释放
+            if self.found(FsckProblem::OrphanInode { i_no }) {
+                self.inode_bitmap.set_bit(idx, false);
+            }
+        }
+    }
+
+    /**
+     * 检查块位图中被占用，但是没有被引用的块
+     */
+    #[inline(never)]
+    fn check_orphan_blocks(&mut self) {
+        // 块位图后面不对应数据块的位，本来就是1
+        for bit_idx in 0 .. self.super_block.data_block_secs as usize {
+            if !self.block_bitmap.is_set(bit_idx) || self.block_seen.is_set(bit_idx) {
+                continue;
+            }
+            let lba = self.super_block.data_lba_start.add(bit_idx as u32);
+            if self.found(FsckProblem::OrphanBlock { lba }) {
+                self.block_bitmap.set_bit(bit_idx, false);
+            }
+        }
+    }
+
+    /**
+     * 检查访问到的inode的硬链接数。旧版本的文件系统没有硬链接数，不检查
+     */
+    #[inline(never)]
+    fn check_link_counts(&mut self) {
+        if !self.super_block.support_link_count() {
+            return;
+        }
+        for idx in 0 .. self.super_block.inode_cnt as usize {
+            let i_no = InodeNo::from(idx);
+            if !self.inode_seen.is_set(idx) || self.device.is_inode_busy(i_no) {
+                continue;
+            }
+            let mut inode = self.read_inode(i_no);
+            let actual = self.link_cnt(idx) as u32;
+            if inode.i_nlink != actual && self.found(FsckProblem::WrongLinkCount { i_no, recorded: inode.i_nlink, actual }) {
+                inode.i_nlink = actual;
+                self.write_inode(i_no, &inode);
+            }
+        }
+    }
+
+    fn link_cnt(&self, idx: usize) -> u16 {
+        u16::from_ne_bytes([self.link_cnts[idx * 2], self.link_cnts[idx * 2 + 1]])
+    }
+
+    fn set_link_cnt(&mut self, idx: usize, link_cnt: u16) {
+        self.link_cnts[idx * 2 .. idx * 2 + 2].copy_from_slice(&link_cnt.to_ne_bytes());
+    }
+
+    /**
+     * inode所在的扇区LBA地址、扇区内的字节偏移量、占用的扇区数量
+     */
+    fn locate_inode(&self, i_no: InodeNo) -> (LbaAddr, usize, usize) {
+        let inode_size = self.super_block.inode_size();
+        let bytes_start = usize::from(i_no) * inode_size;
+        let sec_start = bytes_start / constants::DISK_SECTOR_SIZE;
+        let sec_end = utils::div_ceil((bytes_start + inode_size) as u32, constants::DISK_SECTOR_SIZE as u32) as usize;
+        (self.super_block.inode_table_lba.add(sec_start as u32), bytes_start % constants::DISK_SECTOR_SIZE, sec_end - sec_start)
+    }
+
+    /**
+     * 从硬盘中读取inode。旧版本的inode比较小，没有的字段和inode::load_inode一样处理
+     */
+    fn read_inode(&mut self, i_no: InodeNo) -> Inode {
+        let (lba, bytes_off, sec_cnt) = self.locate_inode(i_no);
+        let buf_off = INODE_BUF * constants::DISK_SECTOR_SIZE;
+        self.device.read_sectors(lba, sec_cnt, &mut self.sectors[buf_off .. buf_off + sec_cnt * constants::DISK_SECTOR_SIZE]);
+
+        let mut inode = Inode::empty();
+        let inode_size = self.super_block.inode_size();
+        let inode_buf = unsafe { slice::from_raw_parts_mut(&mut inode as *mut _ as *mut u8, inode_size) };
+        inode_buf.copy_from_slice(&self.sectors[buf_off + bytes_off .. buf_off + bytes_off + inode_size]);
+        if !self.super_block.support_link_count() {
+            inode.i_nlink = 1;
+        }
+        if !self.super_block.support_metadata() {
+            inode.i_mode = metadata::LEGACY_PERM;
+        }
+        inode
+    }
+
+    /**
+     * 把inode写回硬盘
+     */
+    fn write_inode(&mut self, i_no: InodeNo, inode: &Inode) {
+        let (lba, bytes_off, sec_cnt) = self.locate_inode(i_no);
+        let buf_off = INODE_BUF * constants::DISK_SECTOR_SIZE;
+        let buf_end = buf_off + sec_cnt * constants::DISK_SECTOR_SIZE;
+        self.device.read_sectors(lba, sec_cnt, &mut self.sectors[buf_off .. buf_end]);
+
+        let inode_size = self.super_block.inode_size();
+        let inode_buf = unsafe { slice::from_raw_parts(inode as *const _ as *const u8, inode_size) };
+        self.sectors[buf_off + bytes_off .. buf_off + bytes_off + inode_size].copy_from_slice(inode_buf);
+        self.device.write_sectors(&self.sectors[buf_off .. buf_end], lba, sec_cnt);
+    }
+}
+
+/**
+ * 挂载的分区
+ */
+struct MountedDevice {
+    disk: &'static mut Disk,
+}
+
+impl FsckDevice for MountedDevice {
+    fn read_sectors(&mut self, lba_start: LbaAddr, sec_cnt: usize, buf: &mut [u8]) {
+        self.disk.read_sectors(lba_start, sec_cnt, buf);
+    }
+
+    fn write_sectors(&mut self, buf: &[u8], lba_start: LbaAddr, sec_cnt: usize) {
+        self.disk.write_sector(buf, lba_start, sec_cnt);
+    }
+
+    fn is_inode_busy(&self, i_no: InodeNo) -> bool {
+        fs::get_filesystem().find_inode(i_no).is_some()
+    }
+}
+
+/**
+ * 检查当前挂载的文件系统。repair为true，修复能够修复的问题
+ * 直接检查、修复内存中的inode位图和块位图，修复之后不需要重新挂载
+ */
+#[inline(never)]
+pub fn check_filesystem(repair: bool) -> FsckReport {
+    let fs = fs::get_filesystem();
+    let super_block = fs.super_block;
+    let scratch_len = self::scratch_size(super_block);
+    let scratch = unsafe { slice::from_raw_parts_mut(memory::sys_malloc(scratch_len) as *mut u8, scratch_len) };
+
+    let mut device = MountedDevice { disk: unsafe { &mut *fs.base_part.from_disk } };
+    let inode_bitmap = fs::get_filesystem().inode_pool.get_bitmap_mut();
+    let block_bitmap = fs::get_filesystem().data_block_pool.get_bitmap_mut();
+    let report = Fsck::new(&mut device, super_block, inode_bitmap, block_bitmap, scratch).run(repair);
+
+    memory::sys_free(scratch.as_ptr() as usize);
+    report
+}
@@ -1,15 +1,16 @@
 use core::{mem::{size_of, size_of_val}, slice};
 
-use os_in_rust_common::{constants, domain::InodeNo, utils, ASSERT};
+use os_in_rust_common::{constants, domain::InodeNo, utils, ASSERT, MY_PANIC};
 
 use crate::device::{self, Partition};
 use crate::memory;
 
-use super::{dir_entry::{self, DirEntry}, fs::{self, FileSystem}, inode::Inode, superblock::SuperBlock};
+use super::{constant, dir_entry::{self, DirEntry, FileType}, fs::{self, FileSystem}, inode::Inode, metadata, superblock::SuperBlock};
 
 
 /**
  * 为所有的分区安装文件系统
+ * 已经有文件系统的分区（比如用宿主机的fsimg工具制作的镜像）不会重新格式化
  */
 #[inline(never)]
 pub fn install_filesystem_for_all_part() {
@@ -19,10 +20,28 @@ pub fn install_filesystem_for_all_part() {
     // 遍历每个分区，安装文件系统
     for part_tag in all_partition.iter() {
         let part = Partition::parse_by_tag(part_tag);
+        if self::has_filesystem(part) {
+            continue;
+        }
         self::install_filesystem(part);
     }
 }
 
+/**
+ * part分区中，是否已经安装了文件系统
+ * 不认识的版本也算已经安装了，不能重新格式化；挂载的时候再拒绝
+ */
+#[inline(never)]
+fn has_filesystem(part: &mut Partition) -> bool {
+    let super_block: &mut SuperBlock = memory::malloc(size_of::<SuperBlock>());
+    let sb_buf = unsafe { slice::from_raw_parts_mut(super_block as *mut _ as *mut u8, size_of::<SuperBlock>()) };
+    let disk = unsafe { &mut *part.from_disk };
+    disk.read_sectors(part.abs_lba_start(1), 1, sb_buf);
+    let valid = super_block.is_valid();
+    memory::sys_free(super_block as *const _ as usize);
+    valid
+}
+
 #[inline(never)]
 pub fn mount_part(part_name: &str) {
     // 找到所有分区
@@ -41,17 +60,21 @@ pub fn mount_part(part_name: &str) {
                 let sb_buf = unsafe { slice::from_raw_parts_mut(super_block as *mut _ as *mut u8, size_of::<SuperBlock>()) };
                 // 读取SuperBlock
                 disk.read_sectors(part.abs_lba_start(1), 1, sb_buf);
+                // 不认识的版本，按照旧的格式读写会破坏数据
+                if !super_block.is_supported() {
+                    MY_PANIC!("failed to mount {}: unsupported filesystem version {}, the newest supported version is {}", part_name, super_block.version, constant::FILESYSTEM_VERSION_CURRENT);
+                }
 
 
                 // inode位图
-                let inode_bitmap_len = super_block.inode_bitmap_lba.get_lba() as usize * constants::DISK_SECTOR_SIZE;
+                let inode_bitmap_len = super_block.inode_bitmap_secs as usize * constants::DISK_SECTOR_SIZE;
                 let inode_bitmap_bits = unsafe { slice::from_raw_parts_mut(memory::sys_malloc(inode_bitmap_len) as *mut u8, inode_bitmap_len) };
                 disk.read_sectors(super_block.inode_bitmap_lba, super_block.inode_bitmap_secs as usize, inode_bitmap_bits);
 
 
-                // 块位图
+                // 块位图。位图的长度（字节数 * 8位）决定了fsck检查、修复的范围，必须是块位图自己的长度
                 let block_bitmap_len = super_block.block_bitmap_secs as usize * constants::DISK_SECTOR_SIZE;
-                let block_bitmap_bits = unsafe { slice::from_raw_parts_mut(memory::sys_malloc(block_bitmap_len) as *mut u8, inode_bitmap_len) };
+                let block_bitmap_bits = unsafe { slice::from_raw_parts_mut(memory::sys_malloc(block_bitmap_len) as *mut u8, block_bitmap_len) };
                 disk.read_sectors(super_block.block_bitmap_lba, super_block.block_bitmap_secs as usize, block_bitmap_bits);
                 
 
@@ -215,6 +238,8 @@ fn install_inode_table(part: &mut Partition, super_block: &SuperBlock, buff: &mu
     let root_inode = &mut inode_table[0];
     root_inode.i_no = InodeNo::new(0);
     root_inode.i_size = super_block.dir_entry_size * 2; // 2个目录：.和..
+    root_inode.i_nlink = 1;
+    metadata::init_inode(root_inode, FileType::Directory, device::current_timestamp());
     // 根目录inode，数据区就是在第一个数据扇区
     root_inode.direct_sectors[0] = super_block.data_lba_start;
 
@@ -5,7 +5,7 @@ use os_in_rust_common::racy_cell::RacyCell;
 
 use crate::{memory, sync::Lock, thread};
 
-use super::{constant, fs::FileSystem};
+use super::{constant, fs::FileSystem, metadata};
 
 
 /**
@@ -35,6 +35,46 @@ pub struct Inode {
      * 该inode数据扇区所在的LBA地址。
      */
     pub indirect_sector: LbaAddr,
+
+    /**
+     * 二级间接块的LBA地址。这个块内，是很多一级间接块的LBA地址
+     */
+    pub double_indirect_sector: LbaAddr,
+
+    /**
+     * 三级间接块的LBA地址。这个块内，是很多二级间接块的LBA地址
+     */
+    pub triple_indirect_sector: LbaAddr,
+
+    /**
+     * 硬链接数。有多少个目录项指向这个inode
+     */
+    pub i_nlink: u32,
+
+    /**
+     * 文件类型以及权限。见metadata模块的MODE_*
+     */
+    pub i_mode: u32,
+    /**
+     * 属主的用户ID
+     */
+    pub i_uid: u32,
+    /**
+     * 属主的组ID
+     */
+    pub i_gid: u32,
+    /**
+     * 创建时间（时间戳，单位：秒）
+     */
+    pub i_ctime: u32,
+    /**
+     * 最后修改时间
+     */
+    pub i_mtime: u32,
+    /**
+     * 最后访问时间
+     */
+    pub i_atime: u32,
 }
 
 impl Inode {
@@ -44,6 +84,15 @@ impl Inode {
             i_size: 0,
             direct_sectors: [LbaAddr::empty(); constant::INODE_DIRECT_DATA_SECS],
             indirect_sector: LbaAddr::empty(),
+            double_indirect_sector: LbaAddr::empty(),
+            triple_indirect_sector: LbaAddr::empty(),
+            i_nlink: 0,
+            i_mode: 0,
+            i_uid: 0,
+            i_gid: 0,
+            i_ctime: 0,
+            i_mtime: 0,
+            i_atime: 0,
         }
     }
     pub fn new(i_no: InodeNo) -> Self {
@@ -52,6 +101,16 @@ impl Inode {
             i_size: 0,
             direct_sectors: [LbaAddr::empty(); constant::INODE_DIRECT_DATA_SECS],
             indirect_sector: LbaAddr::empty(),
+            double_indirect_sector: LbaAddr::empty(),
+            triple_indirect_sector: LbaAddr::empty(),
+            // 新创建的inode，只有一个目录项指向它
+            i_nlink: 1,
+            i_mode: 0,
+            i_uid: 0,
+            i_gid: 0,
+            i_ctime: 0,
+            i_mtime: 0,
+            i_atime: 0,
         }
     }
 
@@ -60,6 +119,15 @@ impl Inode {
         self.i_size = opened_inode.i_size;
         self.direct_sectors.copy_from_slice(opened_inode.get_direct_data_blocks_ref());
         self.indirect_sector = unsafe {*opened_inode.indirect_block_lba.get_mut()};
+        self.double_indirect_sector = opened_inode.double_indirect_lba;
+        self.triple_indirect_sector = opened_inode.triple_indirect_lba;
+        self.i_nlink = opened_inode.i_nlink;
+        self.i_mode = opened_inode.i_mode;
+        self.i_uid = opened_inode.i_uid;
+        self.i_gid = opened_inode.i_gid;
+        self.i_ctime = opened_inode.i_ctime;
+        self.i_mtime = opened_inode.i_mtime;
+        self.i_atime = opened_inode.i_atime;
     }
 
 }
@@ -80,6 +148,27 @@ pub struct OpenedInode {
      */
     pub i_size: u32,
 
+    /**
+     * 硬链接数。降为0，并且没有人打开的时候，才真正删除这个inode
+     */
+    pub i_nlink: u32,
+
+    /**
+     * 文件类型以及权限
+     */
+    pub i_mode: u32,
+    /**
+     * 属主的用户ID、组ID
+     */
+    pub i_uid: u32,
+    pub i_gid: u32,
+    /**
+     * 创建、最后修改、最后访问的时间戳
+     */
+    pub i_ctime: u32,
+    pub i_mtime: u32,
+    pub i_atime: u32,
+
     /**
      * 该inode打开的次数
      */
@@ -106,10 +195,20 @@ pub struct OpenedInode {
      * 间接块的地址（这个块内，就是很多的间接数据块的LBA地址）
      */
     pub indirect_block_lba: RacyCell<LbaAddr>,
+
+    /**
+     * 二级间接块的地址。二级、三级间接块索引的数据块很多，不缓存，使用的时候读取硬盘
+     */
+    pub double_indirect_lba: LbaAddr,
+
+    /**
+     * 三级间接块的地址
+     */
+    pub triple_indirect_lba: LbaAddr,
 }
 impl Display for OpenedInode {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        printk!("(i_no: {}, i_size: {}, open_cnts: {})", self.i_no, self.i_size, self.open_cnts);
+        printk!("(i_no: {}, i_size: {}, i_nlink: {}, open_cnts: {})", self.i_no, self.i_size, self.i_nlink, self.open_cnts);
         Result::Ok(())
     }
 }
@@ -119,11 +218,20 @@ impl OpenedInode {
         let mut inode = Self {
             i_no: base_inode.i_no,
             i_size: base_inode.i_size,
+            i_nlink: base_inode.i_nlink,
+            i_mode: base_inode.i_mode,
+            i_uid: base_inode.i_uid,
+            i_gid: base_inode.i_gid,
+            i_ctime: base_inode.i_ctime,
+            i_mtime: base_inode.i_mtime,
+            i_atime: base_inode.i_atime,
             open_cnts: 0, // 创建出来认为打开0次，放入到了列表里
             write_deny: false,
             tag: LinkedNode::new(),
             lock: Lock::new(),
             indirect_block_lba: RacyCell::new(base_inode.indirect_sector),
+            double_indirect_lba: base_inode.double_indirect_sector,
+            triple_indirect_lba: base_inode.triple_indirect_sector,
             data_block_list: [LbaAddr::empty(); constant::INODE_DIRECT_DATA_SECS + (constant::INODE_INDIRECT_DATA_SECS * constants::DISK_SECTOR_SIZE) / size_of::<LbaAddr>()],
         };
         // 把硬盘中的该inode数据区，复制到缓冲区中
@@ -249,6 +357,11 @@ pub fn inode_close(fs: &mut FileSystem, inode: &mut OpenedInode) {
         fs.remove_inode(inode);
         // 解锁
         inode.lock.unlock();
+        // 已经没有目录项指向这个inode了，最后一次关闭的时候，真正删除它
+        if inode.i_nlink == 0 {
+            self::load_indirect_data_block(fs, inode);
+            self::inode_remove(fs, inode);
+        }
         let cur_task = &mut thread::current_thread().task_struct;
         let pgdir_bak = cur_task.pgdir;
         cur_task.pgdir = ptr::null_mut();
@@ -276,9 +389,20 @@ pub fn load_inode(fs: &FileSystem, i_no: InodeNo) -> Inode {
     // 从硬盘中读取扇区
     disk.read_sectors(inode_location.lba, inode_location.sec_cnt, inode_buf);
 
-    // 根据字节偏移量，找到这个inode数据
-    let target_inode = unsafe { *(inode_buf[inode_location.bytes_off .. ].as_ptr() as *const Inode) };
+    // 根据字节偏移量，找到这个inode数据。旧版本的inode比较小，没有的字段就是空的
+    let mut target_inode = Inode::empty();
+    let inode_size = fs.super_block.inode_size();
+    let target_inode_buf = unsafe { slice::from_raw_parts_mut(&mut target_inode as *mut _ as *mut u8, inode_size) };
+    target_inode_buf.copy_from_slice(&inode_buf[inode_location.bytes_off .. inode_location.bytes_off + inode_size]);
     memory::sys_free(inode_buf.as_ptr() as usize);
+    // 旧版本的inode没有硬链接数，每个inode只会有一个目录项
+    if !fs.super_block.support_link_count() {
+        target_inode.i_nlink = 1;
+    }
+    // 旧版本的inode没有元信息：不知道文件类型，权限默认是rwxr-xr-x，属主是root，时间都是0
+    if !fs.super_block.support_metadata() {
+        target_inode.i_mode = metadata::LEGACY_PERM;
+    }
 
     target_inode
 }
@@ -292,13 +416,15 @@ pub fn locate_inode(fs: &FileSystem, i_no: InodeNo) -> InodeLocation {
     if u32::from(i_no) >  constant::MAX_FILE_PER_FS {
         MY_PANIC!("failed to locate inode({:?}). exceed maximum({})", i_no, constant::MAX_FILE_PER_FS);
     }
+    // 硬盘中一个inode的大小（和文件系统的版本有关）
+    let inode_size = fs.super_block.inode_size();
     // inode所在相对inode数组，开始的字节偏移量
-    let i_idx_start = usize::from(i_no) * size_of::<Inode>();
+    let i_idx_start = usize::from(i_no) * inode_size;
     // 换算成扇区偏移数
     let sec_start = i_idx_start / constants::DISK_SECTOR_SIZE;
 
     // inode所在相对inode数组，结束的字节偏移量
-    let i_idx_end = (usize::from(i_no) + 1) as usize * size_of::<Inode>();
+    let i_idx_end = (usize::from(i_no) + 1) as usize * inode_size;
     // inode结束的偏移量，换算成扇区偏移数
     let sec_end: usize = utils::div_ceil(i_idx_end as u32, constants::DISK_SECTOR_SIZE  as u32).try_into().unwrap();
     InodeLocation {
@@ -329,10 +455,12 @@ pub fn sync_inode(fs: &mut FileSystem, opened_inode: &mut OpenedInode) {
     // 读取出inode所在的扇区
     disk.read_sectors(i_location.lba, i_location.sec_cnt, buf);
 
-    // 硬盘中的inode结构
-    let inode_from_disk = unsafe { &mut *(buf.as_mut_ptr().add(i_location.bytes_off) as *mut Inode) };
-    // 把内存中的inode结构，复制到硬盘的inode结构中
-    inode_from_disk.from(opened_inode);
+    // 把内存中的inode结构，复制到硬盘的inode结构中（只复制该版本的inode有的字段）
+    let mut inode_to_disk = Inode::empty();
+    inode_to_disk.from(opened_inode);
+    let inode_size = fs.super_block.inode_size();
+    let inode_bytes = unsafe { slice::from_raw_parts(&inode_to_disk as *const _ as *const u8, inode_size) };
+    buf[i_location.bytes_off .. i_location.bytes_off + inode_size].copy_from_slice(inode_bytes);
 
     // 把inode写回到硬盘中
     disk.write_sector(buf, i_location.lba, i_location.sec_cnt.try_into().unwrap());
@@ -357,13 +485,14 @@ pub fn sync_inode(fs: &mut FileSystem, opened_inode: &mut OpenedInode) {
 
 /**
  * 申请一个间接块
- *  - 如果间接块已经存在，那也不用申请
+ *  - 如果间接块已经存在，那也不用申请，加载间接块的数据
  */
 #[inline(never)]
 pub fn apply_indirect_data_block(fs: &mut FileSystem, opened_inode: &mut OpenedInode) {
     // 数组最后一个元素，是间接块的LBA地址。这个块里面，是很多的LBA地址
     let indirect_lba = *unsafe { opened_inode.indirect_block_lba.get_mut() };
     if !indirect_lba.is_empty() {
+        self::load_indirect_data_block(fs, opened_inode);
         return;
     }
     opened_inode.indirect_block_lba = RacyCell::new(fs.data_block_pool.apply_block(1));
@@ -394,33 +523,236 @@ pub fn load_indirect_data_block(fs: &mut FileSystem, opened_inode: &mut OpenedIn
 
 
 /**
- * 删除一个inode，以及清除该inode下的数据区
+ * 得到inode第block_idx个数据块的LBA地址。数据块不存在，返回None
+ *  - 直接块和一级间接块，从缓存中读取（一级间接块需要先加载）
+ *  - 二级、三级间接块，读取硬盘中的间接块
  */
 #[inline(never)]
-pub fn inode_remove(fs: &mut FileSystem, inode: &OpenedInode) {
+pub fn get_data_block(fs: &mut FileSystem, opened_inode: &mut OpenedInode, block_idx: usize) -> Option<LbaAddr> {
+    self::map_data_block(fs, opened_inode, block_idx, false)
+}
+
+/**
+ * 得到inode第block_idx个数据块的LBA地址。数据块（以及路径上的间接块）不存在，就申请
+ * 超过了该inode最多能有的数据块，返回None
+ */
+#[inline(never)]
+pub fn apply_data_block(fs: &mut FileSystem, opened_inode: &mut OpenedInode, block_idx: usize) -> Option<LbaAddr> {
+    self::map_data_block(fs, opened_inode, block_idx, true)
+}
+
+/**
+ * 该文件系统中，一个inode最多有多少个数据块
+ */
+pub fn max_data_blocks(fs: &FileSystem) -> usize {
+    let cached_blocks = constant::INODE_DIRECT_DATA_SECS + constant::INODE_SINGLE_INDIRECT_BLOCKS;
+    if !fs.super_block.support_deep_indirect() {
+        return cached_blocks;
+    }
+    cached_blocks + constant::INODE_DOUBLE_INDIRECT_BLOCKS + constant::INODE_TRIPLE_INDIRECT_BLOCKS
+}
+
+#[inline(never)]
+fn map_data_block(fs: &mut FileSystem, opened_inode: &mut OpenedInode, block_idx: usize, create: bool) -> Option<LbaAddr> {
+    if block_idx >= self::max_data_blocks(fs) {
+        return Option::None;
+    }
+    // 直接块和一级间接块，在缓存中
+    let cached_blocks = opened_inode.get_data_blocks_ref().len();
+    if block_idx < cached_blocks {
+        let data_block_lba = &mut opened_inode.get_data_blocks()[block_idx];
+        if data_block_lba.is_empty() {
+            if !create {
+                return Option::None;
+            }
+            *data_block_lba = fs.data_block_pool.apply_block(1);
+        }
+        return Option::Some(*data_block_lba);
+    }
+    // 二级间接块
+    let deep_idx = block_idx - cached_blocks;
+    if deep_idx < constant::INODE_DOUBLE_INDIRECT_BLOCKS {
+        return self::walk_indirect_block(fs, &mut opened_inode.double_indirect_lba, 2, deep_idx, create);
+    }
+    // 三级间接块
+    let deep_idx = deep_idx - constant::INODE_DOUBLE_INDIRECT_BLOCKS;
+    self::walk_indirect_block(fs, &mut opened_inode.triple_indirect_lba, 3, deep_idx, create)
+}
+
+/**
+ * 从多级间接块root_lba开始，逐级找到第idx个数据块
+ *  - levels: 间接块的级数。二级间接块是2，三级间接块是3
+ *  - create: 间接块或者数据块不存在的时候，是否申请
+ */
+#[inline(never)]
+fn walk_indirect_block(fs: &mut FileSystem, root_lba: &mut LbaAddr, levels: u32, idx: usize, create: bool) -> Option<LbaAddr> {
+    if root_lba.is_empty() {
+        if !create {
+            return Option::None;
+        }
+        *root_lba = self::apply_index_block(fs);
+    }
     let disk = unsafe { &mut *fs.base_part.from_disk };
     let buf: &mut [u8; constants::DISK_SECTOR_SIZE] = memory::malloc(constants::DISK_SECTOR_SIZE);
+    // 间接块（一个块）里面全部都是LBA地址
+    let block_lbas = unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut LbaAddr, constant::LBA_PER_BLOCK) };
+
+    let mut table_lba = *root_lba;
+    let mut left_idx = idx;
+    for level in (0 .. levels).rev() {
+        // 这一级间接块中，每一项能够索引的数据块数量
+        let span = constant::LBA_PER_BLOCK.pow(level);
+        let slot = left_idx / span;
+        left_idx %= span;
+
+        disk.read_sectors(table_lba, 1, buf);
+        if block_lbas[slot].is_empty() {
+            if !create {
+                memory::sys_free(buf.as_ptr() as usize);
+                return Option::None;
+            }
+            // 最后一级指向数据块，其他的指向下一级间接块
+            block_lbas[slot] = if level > 0 { self::apply_index_block(fs) } else { fs.data_block_pool.apply_block(1) };
+            disk.write_sector(buf, table_lba, 1);
+        }
+        table_lba = block_lbas[slot];
+    }
+    memory::sys_free(buf.as_ptr() as usize);
+    Option::Some(table_lba)
+}
+
+/**
+ * 申请一个间接块，并且清零（里面的LBA地址都是空的）
+ */
+#[inline(never)]
+fn apply_index_block(fs: &mut FileSystem) -> LbaAddr {
+    let disk = unsafe { &mut *fs.base_part.from_disk };
+    let block_lba = fs.data_block_pool.apply_block(1);
+    let buf: &mut [u8; constants::DISK_SECTOR_SIZE] = memory::malloc(constants::DISK_SECTOR_SIZE);
+    unsafe { buf.as_mut_ptr().write_bytes(0, buf.len()) };
+    disk.write_sector(buf, block_lba, 1);
+    memory::sys_free(buf.as_ptr() as usize);
+    block_lba
+}
+
+/**
+ * 只保留inode的前keep_blocks个数据块，后面的数据块（以及不再需要的间接块）都清零并且释放
+ */
+#[inline(never)]
+pub fn release_blocks_after(fs: &mut FileSystem, opened_inode: &mut OpenedInode, keep_blocks: usize) {
+    // 直接块和一级间接块的数据块
+    self::load_indirect_data_block(fs, opened_inode);
+    let cached_blocks = opened_inode.get_data_blocks_ref().len();
+    for block_idx in keep_blocks.min(cached_blocks) .. cached_blocks {
+        let block_lba = opened_inode.get_data_blocks()[block_idx];
+        self::clear_block(fs, block_lba);
+        opened_inode.get_data_blocks()[block_idx] = LbaAddr::empty();
+    }
+    // 一级间接块里面没有数据块了，释放一级间接块自身
+    if keep_blocks <= constant::INODE_DIRECT_DATA_SECS {
+        let indirect_lba = unsafe { opened_inode.indirect_block_lba.get_mut() };
+        self::clear_block(fs, *indirect_lba);
+        *indirect_lba = LbaAddr::empty();
+    }
+    // 二级、三级间接块
+    let deep_keep = keep_blocks.saturating_sub(cached_blocks);
+    self::truncate_indirect_block(fs, &mut opened_inode.double_indirect_lba, 2, deep_keep);
+    let deep_keep = deep_keep.saturating_sub(constant::INODE_DOUBLE_INDIRECT_BLOCKS);
+    self::truncate_indirect_block(fs, &mut opened_inode.triple_indirect_lba, 3, deep_keep);
+}
+
+/**
+ * levels级间接块table_lba，只保留它索引的前keep个数据块。一个都不保留，间接块自身也释放
+ */
+#[inline(never)]
+fn truncate_indirect_block(fs: &mut FileSystem, table_lba: &mut LbaAddr, levels: u32, keep: usize) {
+    if table_lba.is_empty() {
+        return;
+    }
+    if keep == 0 {
+        self::remove_indirect_block(fs, *table_lba, levels);
+        *table_lba = LbaAddr::empty();
+        return;
+    }
+    let disk = unsafe { &mut *fs.base_part.from_disk };
+    let buf: &mut [u8; constants::DISK_SECTOR_SIZE] = memory::malloc(constants::DISK_SECTOR_SIZE);
+    disk.read_sectors(*table_lba, 1, buf);
+    let block_lbas = unsafe { slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut LbaAddr, constant::LBA_PER_BLOCK) };
+    // 每一项能够索引的数据块数量
+    let span = constant::LBA_PER_BLOCK.pow(levels - 1);
+    for slot in 0 .. block_lbas.len() {
+        let slot_start = slot * span;
+        // 这一项索引的数据块全部保留
+        if slot_start + span <= keep {
+            continue;
+        }
+        let slot_keep = keep.saturating_sub(slot_start);
+        if levels > 1 {
+            self::truncate_indirect_block(fs, &mut block_lbas[slot], levels - 1, slot_keep);
+        } else {
+            self::clear_block(fs, block_lbas[slot]);
+            block_lbas[slot] = LbaAddr::empty();
+        }
+    }
+    disk.write_sector(buf, *table_lba, 1);
+    memory::sys_free(buf.as_ptr() as usize);
+}
 
+/**
+ * 删除一个inode，以及清除该inode下的数据区
+ */
+#[inline(never)]
+pub fn inode_remove(fs: &mut FileSystem, inode: &OpenedInode) {
     // 在inode位图中释放这个inode
     fs.inode_pool.release_inode(inode.i_no);
 
-    // 把该inode下的所有数据区扇区清零
+    // 把该inode下的所有数据区扇区清零，并且释放（直接块和一级间接块的数据块）
     for block_lba in inode.get_data_blocks_ref() {
-        // 清零
-        unsafe { buf.as_mut_ptr().write_bytes(0, buf.len()) };
-        // 写入到硬盘
-        disk.write_sector(buf, *block_lba, 1)
+        self::clear_block(fs, *block_lba);
     }
+    // 一级间接块自身
+    self::clear_block(fs, unsafe { *inode.indirect_block_lba.get_mut() });
+    // 二级、三级间接块，以及它们索引的数据块
+    self::remove_indirect_block(fs, inode.double_indirect_lba, 2);
+    self::remove_indirect_block(fs, inode.triple_indirect_lba, 3);
+}
 
-    // 释放缓冲区
+/**
+ * 释放levels级间接块table_lba，以及它索引的所有间接块和数据块
+ */
+#[inline(never)]
+fn remove_indirect_block(fs: &mut FileSystem, table_lba: LbaAddr, levels: u32) {
+    if table_lba.is_empty() {
+        return;
+    }
+    let disk = unsafe { &mut *fs.base_part.from_disk };
+    let buf: &mut [u8; constants::DISK_SECTOR_SIZE] = memory::malloc(constants::DISK_SECTOR_SIZE);
+    disk.read_sectors(table_lba, 1, buf);
+    let block_lbas = unsafe { slice::from_raw_parts(buf.as_ptr() as *const LbaAddr, constant::LBA_PER_BLOCK) };
+    for block_lba in block_lbas {
+        if levels > 1 {
+            self::remove_indirect_block(fs, *block_lba, levels - 1);
+        } else {
+            self::clear_block(fs, *block_lba);
+        }
+    }
     memory::sys_free(buf.as_ptr() as usize);
+    self::clear_block(fs, table_lba);
+}
 
-    // 释放 该inode的所有数据区
-    for block_lba in inode.get_data_blocks_ref() {
-        if block_lba.is_empty() {
-            continue;
-        }
-        // 释放这个数据区
-        fs.data_block_pool.release_block(*block_lba);
+/**
+ * 把一个块清零，然后释放。空的地址不处理
+ */
+#[inline(never)]
+fn clear_block(fs: &mut FileSystem, block_lba: LbaAddr) {
+    if block_lba.is_empty() {
+        return;
     }
-}
\ No newline at end of file
+    let disk = unsafe { &mut *fs.base_part.from_disk };
+    let buf: &mut [u8; constants::DISK_SECTOR_SIZE] = memory::malloc(constants::DISK_SECTOR_SIZE);
+    unsafe { buf.as_mut_ptr().write_bytes(0, buf.len()) };
+    disk.write_sector(buf, block_lba, 1);
+    memory::sys_free(buf.as_ptr() as usize);
+    // 释放这个数据区
+    fs.data_block_pool.release_block(block_lba);
+}
@@ -0,0 +1,183 @@
+use os_in_rust_common::domain::InodeNo;
+
+use crate::device;
+
+use super::{dir_entry::FileType, inode::{Inode, OpenedInode}};
+
+/**
+ * 文件的元信息：类型、权限、属主以及时间戳
+ * inode的i_mode字段，高位是文件类型，低9位是属主、组、其他人的rwx权限（和Unix一样）
+ */
+
+/**
+ * 文件类型的掩码
+ */
+pub const MODE_TYPE_MASK: u32 = 0o170000;
+/**
+ * 普通文件
+ */
+pub const MODE_REGULAR: u32 = 0o100000;
+/**
+ * 目录
+ */
+pub const MODE_DIRECTORY: u32 = 0o040000;
+/**
+ * 符号链接
+ */
+pub const MODE_SYMLINK: u32 = 0o120000;
+/**
+ * 权限的掩码
+ */
+pub const MODE_PERM_MASK: u32 = 0o777;
+
+/**
+ * 旧版本的inode没有记录权限，默认是rwxr-xr-x
+ */
+pub const LEGACY_PERM: u32 = 0o755;
+
+/**
+ * 还没有用户，所有文件的属主都是root
+ */
+pub const ROOT_UID: u32 = 0;
+pub const ROOT_GID: u32 = 0;
+
+/**
+ * 一个文件的元信息
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Metadata {
+    /**
+     * inode编号
+     */
+    pub i_no: InodeNo,
+    /**
+     * 文件类型
+     */
+    pub file_type: FileType,
+    /**
+     * 文件大小。单位：字节
+     */
+    pub size: u32,
+    /**
+     * 硬链接数
+     */
+    pub nlink: u32,
+    /**
+     * 文件类型以及权限
+     */
+    pub mode: u32,
+    /**
+     * 属主的用户ID、组ID
+     */
+    pub uid: u32,
+    pub gid: u32,
+    /**
+     * 创建、最后修改、最后访问的时间戳（1970-01-01 00:00:00以来的秒数）
+     */
+    pub created: u32,
+    pub modified: u32,
+    pub accessed: u32,
+}
+
+impl Metadata {
+    /**
+     * 根据打开的inode，构建元信息。文件类型以目录项中的为准
+     */
+    pub fn new(inode: &OpenedInode, file_type: FileType) -> Self {
+        Self {
+            i_no: inode.i_no,
+            file_type,
+            size: inode.i_size,
+            nlink: inode.i_nlink,
+            mode: inode.i_mode,
+            uid: inode.i_uid,
+            gid: inode.i_gid,
+            created: inode.i_ctime,
+            modified: inode.i_mtime,
+            accessed: inode.i_atime,
+        }
+    }
+
+    /**
+     * 权限（低9位）
+     */
+    pub fn permissions(&self) -> u32 {
+        self.mode & MODE_PERM_MASK
+    }
+
+    /**
+     * 类似ls -l的权限字符串，比如：drwxr-xr-x
+     */
+    pub fn mode_str<'a>(&self, buf: &'a mut [u8; 10]) -> &'a str {
+        buf[0] = match self.file_type {
+            FileType::Regular => b'-',
+            FileType::Directory => b'd',
+            FileType::Symlink => b'l',
+            FileType::Unknown => b'?',
+        };
+        let perm = self.permissions();
+        let signs = [b'r', b'w', b'x'];
+        for bit in 0..9 {
+            let set = perm & (1 << (8 - bit)) != 0;
+            buf[1 + bit] = if set { signs[bit % 3] } else { b'-' };
+        }
+        core::str::from_utf8(buf).unwrap()
+    }
+}
+
+/**
+ * 新创建的文件，默认的类型以及权限
+ *  - 普通文件：rw-r--r--
+ *  - 目录：rwxr-xr-x
+ *  - 符号链接：rwxrwxrwx
+ */
+pub fn default_mode(file_type: FileType) -> u32 {
+    match file_type {
+        FileType::Regular => MODE_REGULAR | 0o644,
+        FileType::Directory => MODE_DIRECTORY | 0o755,
+        FileType::Symlink => MODE_SYMLINK | 0o777,
+        FileType::Unknown => 0o644,
+    }
+}
+
+/**
+ * 根据i_mode，得到文件类型
+ */
+pub fn file_type_of(mode: u32) -> FileType {
+    match mode & MODE_TYPE_MASK {
+        MODE_REGULAR => FileType::Regular,
+        MODE_DIRECTORY => FileType::Directory,
+        MODE_SYMLINK => FileType::Symlink,
+        _ => FileType::Unknown,
+    }
+}
+
+/**
+ * 初始化新创建的inode的元信息：类型、默认权限、属主以及时间戳
+ */
+pub fn init_inode(inode: &mut Inode, file_type: FileType, now: u32) {
+    inode.i_mode = self::default_mode(file_type);
+    inode.i_uid = ROOT_UID;
+    inode.i_gid = ROOT_GID;
+    inode.i_ctime = now;
+    inode.i_mtime = now;
+    inode.i_atime = now;
+}
+
+/**
+ * 文件内容（目录的目录项）被修改了
+ */
+#[inline(never)]
+pub fn touch_modified(inode: &mut OpenedInode) {
+    let now = device::current_timestamp();
+    inode.i_mtime = now;
+    inode.i_atime = now;
+}
+
+/**
+ * 文件内容被读取了
+ */
+#[inline(never)]
+pub fn touch_accessed(inode: &mut OpenedInode) {
+    inode.i_atime = device::current_timestamp();
+}
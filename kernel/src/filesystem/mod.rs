@@ -1,6 +1,7 @@
-mod constant;
+pub mod constant;
 pub mod superblock;
 pub mod inode;
+pub mod fsck;
 mod dir;
 mod init;
 mod file;
@@ -11,6 +12,8 @@ mod fs;
 mod file_api;
 mod dir_api;
 mod file_util;
+mod symlink;
+pub mod metadata;
 
 pub use fs::get_filesystem;
 
@@ -20,8 +23,10 @@ pub use file_descriptor::StdFileDescriptor;
 pub use file_descriptor::FileDescriptorType;
 
 pub use file::FileError;
+pub use file::OpenedFile;
 pub use file::read_file;
 pub use file::write_file;
+pub use file::truncate_file;
 
 
 
@@ -49,6 +54,20 @@ pub use file_api::File;
 pub use file_api::SeekFrom;
 pub use file_api::OpenOptions;
 pub use file_api::remove_file;
+pub use file_api::ftruncate;
+pub use file_api::rename;
+pub use file_api::link;
+pub use file_api::symlink;
+pub use file_api::read_link;
+pub use file_api::stat;
+pub use file_api::lstat;
+pub use file_api::fstat;
+
+pub use metadata::Metadata;
+
+pub use fsck::FsckReport;
+pub use fsck::FsckProblem;
+pub use fsck::check_filesystem;
 
 
 pub use global_file_table::get_opened_file;
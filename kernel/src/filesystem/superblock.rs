@@ -85,6 +85,11 @@ pub struct SuperBlock {
      * 数据扇区的数量。实际真正可用的数据扇区（根目录所在扇区也算可用的数据扇区）
      */
     pub data_block_secs: u32,
+
+    /**
+     * 文件系统的版本。旧的硬盘镜像没有这个字段（为0），是FILESYSTEM_VERSION_LEGACY
+     */
+    pub version: u32,
 }
 
 impl SuperBlock {
@@ -135,6 +140,69 @@ impl SuperBlock {
             // 空闲块起始LBA地址，跳过前面的所有块
             data_lba_start: LbaAddr::new(block_bitmap_lba + block_bitmap_secs),
             data_block_secs: data_block_secs, // 数据块占用的扇区的数量
+            version: constant::FILESYSTEM_VERSION_CURRENT,
+        }
+    }
+
+    /**
+     * 魔数是否正确。不正确说明这个分区没有安装文件系统
+     */
+    pub fn is_valid(&self) -> bool {
+        self.magic == constant::FILESYSTEM_MAGIC
+    }
+
+    /**
+     * 是否认识这个版本。更新的版本（比如更新的fsimg制作的镜像）不能挂载，也不能重新格式化
+     */
+    pub fn is_supported(&self) -> bool {
+        self.version <= constant::FILESYSTEM_VERSION_CURRENT
+    }
+
+    /**
+     * inode是否有二级、三级间接块
+     */
+    pub fn support_deep_indirect(&self) -> bool {
+        self.version_at_least(constant::FILESYSTEM_VERSION_DEEP_INDIRECT)
+    }
+
+    /**
+     * inode是否记录了硬链接数
+     */
+    pub fn support_link_count(&self) -> bool {
+        self.version_at_least(constant::FILESYSTEM_VERSION_LINK_COUNT)
+    }
+
+    /**
+     * inode是否记录了权限、属主以及时间戳
+     */
+    pub fn support_metadata(&self) -> bool {
+        self.version_at_least(constant::FILESYSTEM_VERSION_METADATA)
+    }
+
+    /**
+     * 版本号不小于version。不认识的版本号，挂载的时候已经拒绝了
+     */
+    fn version_at_least(&self, version: u32) -> bool {
+        self.version >= version
+    }
+
+    /**
+     * 硬盘中，一个inode占用的字节数
+     * 每个版本的inode，都是在上一个版本的inode后面追加字段，旧版本的inode是新版本inode的前半部分：
+     *   - 没有权限、属主以及时间戳（6个u32）
+     *   - 再旧的版本没有硬链接数
+     *   - 最旧的版本也没有二级、三级间接块
+     */
+    pub fn inode_size(&self) -> usize {
+        let metadata_size = 6 * size_of::<u32>();
+        if self.support_metadata() {
+            size_of::<Inode>()
+        } else if self.support_link_count() {
+            size_of::<Inode>() - metadata_size
+        } else if self.support_deep_indirect() {
+            size_of::<Inode>() - metadata_size - size_of::<u32>()
+        } else {
+            size_of::<Inode>() - metadata_size - size_of::<u32>() - 2 * size_of::<LbaAddr>()
         }
     }
 
@@ -0,0 +1,50 @@
+use os_in_rust_common::{constants, domain::InodeNo, ASSERT};
+
+use crate::memory;
+
+use super::{dir_entry::{self, FileType}, fs::FileSystem, inode::{self, OpenedInode}};
+
+/**
+ * 符号链接的结构以及操作
+ * 符号链接是一个FileType::Symlink类型的文件，它的第一个数据块中存放目标路径，i_size是目标路径的长度
+ */
+
+/**
+ * 在parent_inode目录下，创建一个名为link_name的符号链接，指向target
+ */
+#[inline(never)]
+pub fn create_symlink(fs: &mut FileSystem, parent_inode: &mut OpenedInode, link_name: &str, target: &str) -> InodeNo {
+    ASSERT!(target.len() <= constants::DISK_SECTOR_SIZE);
+    let link_i_no = dir_entry::create_dir_entry(fs, parent_inode, link_name, FileType::Symlink);
+    let link_inode = inode::inode_open(fs, link_i_no);
+
+    // 申请一个数据块，存放目标路径
+    let block_lba = inode::apply_data_block(fs, link_inode, 0);
+    ASSERT!(block_lba.is_some());
+    let buf: &mut [u8; constants::DISK_SECTOR_SIZE] = memory::malloc(constants::DISK_SECTOR_SIZE);
+    buf[..target.len()].copy_from_slice(target.as_bytes());
+    let disk = unsafe { &mut *fs.base_part.from_disk };
+    disk.write_sector(buf, block_lba.unwrap(), 1);
+    memory::sys_free(buf.as_ptr() as usize);
+
+    link_inode.i_size = target.len() as u32;
+    inode::sync_inode(fs, link_inode);
+    inode::inode_close(fs, link_inode);
+
+    link_i_no
+}
+
+/**
+ * 读取符号链接link_inode的目标路径，放到buf中
+ */
+#[inline(never)]
+pub fn read_symlink<'a>(fs: &mut FileSystem, link_inode: &mut OpenedInode, buf: &'a mut [u8; constants::DISK_SECTOR_SIZE]) -> &'a str {
+    let block_lba = inode::get_data_block(fs, link_inode, 0);
+    if block_lba.is_none() {
+        return "";
+    }
+    let disk = unsafe { &mut *fs.base_part.from_disk };
+    disk.read_sectors(block_lba.unwrap(), 1, buf);
+    let target_len = (link_inode.i_size as usize).min(buf.len());
+    core::str::from_utf8(&buf[..target_len]).unwrap_or("")
+}
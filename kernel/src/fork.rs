@@ -2,7 +2,7 @@ use core::{mem, ptr};
 use core::mem::size_of;
 
 use os_in_rust_common::{cstr_write, printk, MY_PANIC};
-use os_in_rust_common::{constants, linked_list::LinkedNode, paging::PageTable, printkln, ASSERT};
+use os_in_rust_common::{linked_list::LinkedNode, paging::PageTable, printkln, ASSERT};
 
 use crate::filesystem::FileDescriptorType;
 use crate::process;
@@ -33,11 +33,18 @@ pub fn fork() -> Pid {
     // 拷贝 虚拟地址池。每个TaskStruct有一个虚拟地址池（堆空间）
     self::vaddr_pool_copy(&cur_pcb.task_struct, &mut sub_pcb.task_struct);
     thread::check_task_stack("failed to fork, copy vaddr pool error");
+
+    // 拷贝 虚拟内存区域。子进程有自己的一份VMA列表
+    sub_pcb.task_struct.vma_list = cur_pcb.task_struct.vma_list.fork();
+
+    // 子进程继承信号的动作，但是不继承待处理的信号
+    sub_pcb.task_struct.pending_signals = 0;
+    sub_pcb.task_struct.stop_reported = false;
     
-    // 拷贝 堆内存（该任务的页表映射了的所有内存）
+    // 共享 堆内存（该任务的页表映射了的所有内存），写时复制
     let to_task_dir_table = unsafe { &mut *(sub_pcb.task_struct.pgdir) };
-    self::heap_memory_copy(to_task_dir_table);
-    thread::check_task_stack("failed to fork, copy heap memory error");
+    self::heap_memory_share(to_task_dir_table);
+    thread::check_task_stack("failed to fork, share heap memory error");
     
     printkln!();
 
@@ -50,7 +57,7 @@ pub fn fork() -> Pid {
     ASSERT!(!thread::get_all_thread().contains(&sub_pcb.task_struct.all_tag));
     thread::append_all_thread(&mut sub_pcb.task_struct);
 
-    ASSERT!(!thread::get_ready_thread().contains(&sub_pcb.task_struct.general_tag));
+    ASSERT!(!thread::in_ready_thread(&sub_pcb.task_struct));
     thread::append_read_thread(&mut sub_pcb.task_struct);
 
 
@@ -104,16 +111,17 @@ fn vaddr_pool_copy(from_task: &TaskStruct, to_task: &mut TaskStruct) {
 }
 
 /**
- * 堆内存拷贝
+ * 堆内存共享（写时复制）
+ * 父子进程共享同一份物理页，并且都设置为只读。等到某个进程写入的时候，才在缺页中断中复制一份
  */
 #[inline(never)]
-fn heap_memory_copy(to_task_dir_table: &mut PageTable) {
+fn heap_memory_share(to_task_dir_table: &mut PageTable) {
     let from_task = &thread::current_thread().task_struct;
 
-    // 要拷贝的任务的虚拟地址池
+    // 要共享的任务的虚拟地址池
     let from_task_addr_pool = &from_task.vaddr_pool;
 
-    // 作为复制页表的入参，默认是None
+    // 作为共享页表的入参，默认是None
     let mut page_table_req = Option::None;
     // 遍历 虚拟地址池，里面所有被set过的地址
     for (vaddr, set) in from_task_addr_pool.iter_valid() {
@@ -121,11 +129,13 @@ fn heap_memory_copy(to_task_dir_table: &mut PageTable) {
         if !set {
             continue;
         }
-        // 这个地址指向页的数据
-        let page_data = unsafe { core::slice::from_raw_parts(vaddr as *const u8, constants::PAGE_SIZE as usize) };
+        // 这个地址没有映射物理页，那么不需要
+        if !memory::page_util::addr_to_pde(vaddr).present() || !memory::page_util::addr_to_pte(vaddr).present() {
+            continue;
+        }
         
-        // 把这个页的数据，拷贝到另一个任务的页目录表中。得到操作的页表，用于下次循环
-        let page_table  = memory::copy_single_user_page(page_data, to_task_dir_table, page_table_req);
+        // 把这个页共享到另一个任务的页目录表中。得到操作的页表，用于下次循环
+        let page_table  = memory::share_single_user_page(vaddr, to_task_dir_table, page_table_req);
 
         // 把得到的页表，作为下次循环的参数
         page_table_req = Option::Some(unsafe { &mut *(page_table as *mut PageTable) });
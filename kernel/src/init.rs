@@ -1,4 +1,4 @@
-use os_in_rust_common::{bios_mem::{ARDSType, AddressRangeDescriptorStructure}, context::BootContext, instruction, printkln, ASSERT};
+use os_in_rust_common::{bios_mem::{ARDSType, AddressRangeDescriptorStructure}, context::BootContext, instruction, printkln, reg_cr0::{self, CR0}, ASSERT};
 
 use crate::{device, filesystem, interrupt, memory, process, sys_call, thread, thread_management, tss};
 
@@ -37,6 +37,11 @@ pub fn init_all(boot_info: &BootContext) {
     thread_management::thread_init();
     thread::check_task_stack("failed to init thread");
 
+    // 用户物理页的引用计数（写时复制）
+    memory::frame_ref_init();
+    // 打开写保护。内核写入只读的用户页（写时复制的页）也要触发缺页异常
+    reg_cr0::set_on(CR0::WP);
+
     // 加载TSS
     tss::tss_init();
 
@@ -46,10 +51,17 @@ pub fn init_all(boot_info: &BootContext) {
     sys_call::init();
     thread::check_task_stack("overflow after syscall init");
 
+    // 读取开机时间
+    device::rtc_init();
+
     // 初始化硬盘ATA通道
     device::ata_init();
     thread::check_task_stack("overflow after ata init");
 
+    // 初始化扇区缓存
+    device::block_cache_init();
+    thread::check_task_stack("overflow after block cache init");
+
     // 给每个分区，安装文件系统
     filesystem::install_filesystem_for_all_part();
     thread::check_task_stack("overflow after fs init");
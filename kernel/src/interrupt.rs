@@ -1,9 +1,9 @@
 
 use core::{arch::asm, ptr::{self, addr_of}};
 
-use os_in_rust_common::{idt::{self, InterruptStackFrame, InterruptTypeEnum}, pic, pit, port::Port, sd::SegmentDPL, ASSERT, MY_PANIC};
+use os_in_rust_common::{idt::{self, InterruptStackFrame, InterruptTypeEnum}, pic, pit, port::Port, printkln, reg_cr2, sd::SegmentDPL, ASSERT, MY_PANIC};
 
-use crate::{device::{self, ChannelIrqNoEnum, StatusRegister}, keyboard::{self, ScanCodeCombinator}, pid_allocator::Pid, scheduler, sys_call::{self, HandlerType}, thread};
+use crate::{device::{self, ChannelIrqNoEnum, StatusRegister}, keyboard::{self, ScanCodeCombinator}, memory, pid_allocator::Pid, scheduler, signal, sys_call::{self, HandlerType}, thread, timer, userprog};
 
 /**
  * exceptions and codes: <https://wiki.osdev.org/Exceptions>
@@ -83,9 +83,38 @@ fn double_fault_handler(frame: InterruptStackFrame, error_code: u32) {
     todo!()
 }
 
+/**
+ * 缺页异常
+ * 错误码的结构：<https://wiki.osdev.org/Exceptions#Page_Fault>
+ *    - 第0位：1表示页存在（权限问题），0表示页不存在
+ *    - 第1位：1表示写操作引发的
+ *    - 第2位：1表示用户态引发的
+ */
 #[cfg(all(not(test), target_arch = "x86"))]
 extern "x86-interrupt" fn page_fault_handler(frame: InterruptStackFrame, error_code: u32) {
-    MY_PANIC!("page fault, code:0x{:x}. eip: 0x{:x}, cs:0x{:x}, eflags:0x{:x}, sp: 0x{:x}, ss:{:x}", error_code, frame.ip as u32, frame.cs as u32, frame.eflags as u32, frame.sp as u32, frame.ss as u32);
+    // 引发缺页的地址
+    let fault_addr = reg_cr2::load_cr2() as usize;
+    // 写入一个存在的页，可能是写时复制的页
+    if error_code & 0x1 == 0x1 && error_code & 0x2 == 0x2 {
+        if memory::copy_on_write(fault_addr) {
+            return;
+        }
+    }
+    // 访问一个不存在的页，可能是堆或者栈，按需分配
+    if error_code & 0x1 == 0 {
+        if memory::demand_page(fault_addr, error_code & 0x2 == 0x2) {
+            return;
+        }
+    }
+    // 用户态的进程访问了非法的地址，只结束这个进程
+    // 内核态引发的（比如系统调用），可能持有锁，不能在这里结束进程。系统调用访问用户的地址之前，需要先校验
+    let cur_task = &thread::current_thread().task_struct;
+    if !cur_task.pgdir.is_null() && error_code & 0x4 == 0x4 {
+        printkln!("segmentation fault, pid:{}, addr:0x{:x}, code:0x{:x}, eip:0x{:x}", cur_task.pid.get_data(), fault_addr, error_code, frame.ip as u32);
+        userprog::exit_by_signal(signal::SIGSEGV);
+        return;
+    }
+    MY_PANIC!("page fault, addr:0x{:x}, code:0x{:x}. eip: 0x{:x}, cs:0x{:x}, eflags:0x{:x}, sp: 0x{:x}, ss:{:x}", fault_addr, error_code, frame.ip as u32, frame.cs as u32, frame.eflags as u32, frame.sp as u32, frame.ss as u32);
 }
 #[cfg(all(not(target_arch = "x86")))]
 fn page_fault_handler(frame: InterruptStackFrame, error_code: u32) {
@@ -129,9 +158,18 @@ pub extern "x86-interrupt" fn timer_handler(frame: InterruptStackFrame) {
 
     pic::send_end_of_interrupt();
 
+    // 全局tick数加一，唤醒睡眠到期的任务
+    timer::tick();
+
     // 检查任务的调度。时间片耗尽则调度
     scheduler::check_task_schedule();
 
+    // 从用户态进入的中断，返回之前处理待处理的信号（比如死循环的进程收到了Ctrl+C）
+    // CPU压入的上下文，刚好在PCB页的最高处，也就是中断栈的最后几个字段
+    if frame.cs as u32 & 0x3 == 0x3 {
+        signal::do_signal(&mut thread::current_thread().interrupt_stack);
+    }
+
     // 中断退出
     pop_intr_stack();
 }
@@ -333,6 +371,15 @@ fn pop_intr_stack() {
 #[no_mangle]
 #[cfg(all(not(test), target_arch = "x86"))]
 pub fn intr_exit() {
+    // 返回用户态之前，处理待处理的信号。此时栈顶就是中断栈
+    unsafe {
+        asm!(
+            "push esp",
+            "call do_signal",
+            "add esp, 4",
+            clobber_abi("C"),
+        )
+    }
     // 恢复手动入栈的上下文
     pop_intr_stack();
     // 在使用iret恢复CPU入栈的上下文
@@ -1,6 +1,6 @@
 use os_in_rust_common::{printkln, racy_cell::RacyCell};
 
-use crate::{ascii::AsciiKey, blocking_queue::{ArrayBlockingQueue, BlockingQueue}, scancode::{Key, KeyCode, ScanCodeType}};
+use crate::{ascii::AsciiKey, blocking_queue::{ArrayBlockingQueue, BlockingQueue}, scancode::{Key, KeyCode, ScanCodeType}, signal};
 
 
 
@@ -13,6 +13,10 @@ struct KeyBoard {
      * 键盘的shift是不是处于按着
      */
     shift_down: bool,
+    /**
+     * 键盘的ctrl是不是处于按着
+     */
+    ctrl_down: bool,
     /**
      * 此刻键盘按下的键
      */
@@ -23,6 +27,7 @@ impl KeyBoard {
         Self { 
             caps_lock: false,
             shift_down: false,
+            ctrl_down: false,
             key: KeyCode::empty(),
         }
     }
@@ -46,10 +51,21 @@ impl KeyBoard {
                 self.shift_down = false;
             }
         }
+        // ctrl键
+        if keycode.key == Key::LeftCtrl || keycode.key == Key::RightCtrl {
+            self.ctrl_down = keycode.code_type == ScanCodeType::MakeCode;
+        }
         // 赋值
         self.key = keycode;
     }
 
+    /**
+     * 当前是不是按着ctrl，按下了key
+     */
+    pub fn is_ctrl_with(&self, key: Key) -> bool {
+        self.ctrl_down && self.key.key == key && self.key.code_type == ScanCodeType::MakeCode
+    }
+
     /**
      * 获取当前键盘键入键对应的ascii码
      */
@@ -186,7 +202,20 @@ pub fn scan_code_handler(scan_code: u8) {
         if keycode.code_type == ScanCodeType::BreakCode {
             return;
         }
-        
+
+        // ctrl + c，给前台进程组发送SIGINT。放入一个ETX，唤醒正在等待键盘输入的进程
+        if keyboard.is_ctrl_with(Key::C) {
+            signal::kill_foreground(signal::SIGINT);
+            get_keycode_queue().put(AsciiKey::ETX);
+            return;
+        }
+        // ctrl + z，给前台进程组发送SIGTSTP。同样放入一个SUB，唤醒正在等待键盘输入的进程
+        if keyboard.is_ctrl_with(Key::Z) {
+            signal::kill_foreground(signal::SIGTSTP);
+            get_keycode_queue().put(AsciiKey::SUB);
+            return;
+        }
+
         // 把键入的ascii码，放入队列中
         get_keycode_queue().put(keyboard.get_ascii());
     });
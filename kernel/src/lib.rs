@@ -10,6 +10,8 @@ pub mod interrupt;
 pub mod init;
 pub mod thread_management;
 pub mod scheduler;
+pub mod timer;
+pub mod signal;
 pub mod sync;
 pub mod mutex;
 pub mod console;
@@ -29,7 +31,7 @@ pub mod filesystem;
 pub mod fork;
 pub mod shell;
 pub mod exec;
-pub mod program_loader;
+pub mod elf;
 mod common;
 pub mod userprog;
 pub mod pipe;
\ No newline at end of file
@@ -8,9 +8,7 @@
 
 
 use core::panic::PanicInfo;
-use kernel::{init, program_loader, thread_management};
-use os_in_rust_common::domain::LbaAddr;
-use os_in_rust_common::constants;
+use kernel::{init, timer};
 use os_in_rust_common::{context::BootContext, printkln};
 
 
@@ -23,14 +21,9 @@ pub extern "C" fn _start(boot_info: &BootContext) {
     init::init_all(boot_info);
 
 
-    // 读取并且写入用户进程
-    program_loader::sync_program(LbaAddr::new(250), 100 * constants::DISK_SECTOR_SIZE, "/cat");
-    program_loader::sync_program(LbaAddr::new(350), 50 * constants::DISK_SECTOR_SIZE, "/grep");
-    program_loader::sync_program(LbaAddr::new(400), 10 * constants::DISK_SECTOR_SIZE, "/echo");
-    program_loader::sync_program(LbaAddr::new(410), 1443, "/main.rs");
-
+    // 主线程没有别的事情了，睡眠，不占用CPU
     loop {
-        thread_management::thread_yield();
+        timer::sleep_ms(1000);
     }
 }
 
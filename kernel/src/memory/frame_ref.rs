@@ -0,0 +1,92 @@
+use core::mem::size_of;
+
+use os_in_rust_common::{constants, instruction, racy_cell::RacyCell, utils};
+
+use super::{memory_management, memory_poll};
+
+/**
+ * ************************************************************
+ * *       本文件是用户物理页的引用计数（用于写时复制）
+ * ************************************************************
+ *
+ * 用户物理内存池的每一页，对应一个u32的计数，表示有多少个进程映射了这一页：
+ *   - 0：只有一个进程在使用（没有被共享过）
+ *   - n(n>=2)：有n个进程共享这一页
+ *
+ * 每个进程至少占用一页内核内存，共享一页的进程数量远远小于u32的上限，计数不会溢出
+ */
+static FRAME_REF_LIST: RacyCell<Option<&'static mut [u32]>> = RacyCell::new(Option::None);
+
+/**
+ * 初始化引用计数表。需要在内存池和线程初始化之后调用
+ */
+#[inline(never)]
+pub fn frame_ref_init() {
+    // 用户物理内存池有多少页
+    let frame_cnt = memory_poll::get_user_mem_pool().bitmap.bits_len();
+    let page_cnt = utils::div_ceil((frame_cnt * size_of::<u32>()) as u32, constants::PAGE_SIZE) as usize;
+    // 申请的内核页已经被清零了
+    let addr = memory_management::malloc_kernel_page(page_cnt);
+    let ref_list = unsafe { core::slice::from_raw_parts_mut(addr as *mut u32, frame_cnt) };
+    *unsafe { FRAME_REF_LIST.get_mut() } = Option::Some(ref_list);
+}
+
+/**
+ * 物理地址对应的计数。不是用户物理页，返回None
+ */
+#[inline(never)]
+fn get_ref_mut(phy_addr: usize) -> Option<&'static mut u32> {
+    let ref_list = unsafe { FRAME_REF_LIST.get_mut() }.as_mut()?;
+    let user_mem_pool = memory_poll::get_user_mem_pool();
+    if !user_mem_pool.in_pool(phy_addr) {
+        return Option::None;
+    }
+    let idx = (phy_addr - user_mem_pool.addr_start) / user_mem_pool.granularity;
+    ref_list.get_mut(idx)
+}
+
+/**
+ * 这个物理页，被多少个进程映射了
+ */
+#[inline(never)]
+pub fn owner_cnt(phy_addr: usize) -> u32 {
+    let ref_cnt = self::get_ref_mut(phy_addr);
+    if ref_cnt.is_none() {
+        return 1;
+    }
+    let ref_cnt = *ref_cnt.unwrap();
+    if ref_cnt == 0 { 1 } else { ref_cnt }
+}
+
+/**
+ * 这个物理页，多一个进程共享
+ */
+#[inline(never)]
+pub fn inc_ref(phy_addr: usize) {
+    let old_status = instruction::disable_interrupt();
+    let ref_cnt = self::get_ref_mut(phy_addr);
+    if ref_cnt.is_some() {
+        let ref_cnt = ref_cnt.unwrap();
+        *ref_cnt = if *ref_cnt == 0 { 2 } else { *ref_cnt + 1 };
+    }
+    instruction::set_interrupt(old_status);
+}
+
+/**
+ * 这个物理页，少一个进程使用。返回剩余使用的进程数量（0表示可以回收这一页了）
+ */
+#[inline(never)]
+pub fn dec_ref(phy_addr: usize) -> u32 {
+    let old_status = instruction::disable_interrupt();
+    let ref_cnt = self::get_ref_mut(phy_addr);
+    let left = if ref_cnt.is_none() {
+        0
+    } else {
+        let ref_cnt = ref_cnt.unwrap();
+        // 只剩下一个进程在用了，那么计数清零
+        *ref_cnt = if *ref_cnt <= 1 { 0 } else { *ref_cnt - 1 };
+        *ref_cnt
+    };
+    instruction::set_interrupt(old_status);
+    left
+}
@@ -6,7 +6,7 @@ use os_in_rust_common::{constants, pool::MemPool, ASSERT, MY_PANIC};
 
 use crate::memory::page_util;
 
-use super::mem_block::MemBlock;
+use super::{frame_ref, mem_block::MemBlock};
 
 
 
@@ -103,8 +103,8 @@ pub fn free_page(addr_pool: &mut MemPool, mem_pool: &mut MemPool, vaddr_start: u
             MY_PANIC!("phy addr: 0x{:x} not in memory pool", phy_addr);
         }
 
-        // 把物理地址放回池子中
-        if phy_free {
+        // 把物理地址放回池子中。如果还有其他进程共享这一页（写时复制），那么不释放
+        if phy_free && frame_ref::dec_ref(phy_addr) == 0 {
             mem_pool.restore(phy_addr);
         }
 
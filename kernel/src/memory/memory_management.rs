@@ -1,11 +1,11 @@
 
 use core::ptr;
 
-use os_in_rust_common::{constants, paging::{PageTable, PageTableEntry}, pool::MemPool, racy_cell::RacyCell, ASSERT, MY_PANIC};
+use os_in_rust_common::{constants, instruction, paging::{PageTable, PageTableEntry}, pool::MemPool, racy_cell::RacyCell, ASSERT, MY_PANIC};
 
-use crate::{memory::page_util, sync::Lock, thread::{self, TaskStruct}};
+use crate::{filesystem, memory::page_util, sync::Lock, thread::{self, TaskStruct}};
 
-use super::{mem_block, memory_allocation, memory_deallocation, memory_poll};
+use super::{frame_ref, mem_block, memory_allocation, memory_deallocation, memory_poll, vma::{Vma, VmaBacking}};
 
 
 
@@ -120,20 +120,6 @@ pub fn malloc_kernel_page(page_cnt: usize) -> usize {
     vaddr
 }
 
-#[inline(never)]
-pub fn malloc_user_page(task: &mut TaskStruct, page_cnt: usize) -> usize {
-    thread::check_task_stack("failed to malloc user page memory");
-    unsafe { USER_MEM_POOL_LOCK.get_mut().lock() };
-    let vaddr = memory_allocation::malloc_page(&mut task.vaddr_pool, memory_poll::get_user_mem_pool(), page_cnt);
-    
-    // 清空申请到的内存空间
-    let page_data = unsafe { core::slice::from_raw_parts_mut(vaddr as *mut u8, page_cnt * constants::PAGE_SIZE as usize) };
-    unsafe { page_data.as_mut_ptr().write_bytes(0, page_data.len()) };
-    
-    unsafe { USER_MEM_POOL_LOCK.get_mut().unlock() };
-    vaddr
-}
-
 #[inline(never)]
 pub fn free_user_page(task: &mut TaskStruct, vaddr: usize, page_cnt: usize, phy_free: bool) {
     unsafe { USER_MEM_POOL_LOCK.get_mut().lock() };
@@ -206,8 +192,10 @@ pub fn free_by_addr_pool(vaddr_pool: &MemPool) {
             }
             // 得到这个页表项中记录的物理地址
             let phy_addr = pte.get_phy_addr();
-            // 用户内存池释放
-            memory_poll::get_user_mem_pool().restore(phy_addr.try_into().unwrap());
+            // 用户内存池释放。如果还有其他进程共享这一页，那么不释放
+            if frame_ref::dec_ref(phy_addr as usize) == 0 {
+                memory_poll::get_user_mem_pool().restore(phy_addr.try_into().unwrap());
+            }
             pte.set_present(false);
         }
 
@@ -218,37 +206,35 @@ pub fn free_by_addr_pool(vaddr_pool: &MemPool) {
 }
 
 /**
- * 已知空间page_data，把这块数据复制一份，并且让to_dir_table页目录表可以访问到
- *  @param page_data: 需要复制的数据（当前程序可以访问，经过了当前任务页表的映射）
- *  @param to_dir_table: 数据要拷贝到的页目录表（此时该地址，是当前任务可以访问的虚拟地址），这是其他任务的页目录表
- *  @param page_table: 该数据拷贝到页目录表后，所在的页表（因为可能页表不存在的话就需要申请空间，因此可以为空）（当前任务可访问的虚拟地址）
+ * 已知当前任务的虚拟地址vaddr，把这一页以写时复制的方式，共享给to_dir_table页目录表
+ *    - 当前任务的页表项，去掉R/W位，设置写时复制的标记
+ *    - 另一个任务的页表项，指向同一个物理页（同样只读、写时复制）
+ *    - 这个物理页的引用计数加1
+ *  @param vaddr: 需要共享的页的虚拟地址（当前任务页表已经映射）
+ *  @param to_dir_table: 要共享到的页目录表（此时该地址，是当前任务可以访问的虚拟地址），这是其他任务的页目录表
+ *  @param page_table: 该页共享到页目录表后，所在的页表（因为可能页表不存在的话就需要申请空间，因此可以为空）（当前任务可访问的虚拟地址）
  *
  */
 #[inline(never)]
-pub fn copy_single_user_page<'a>(page_data: &[u8], to_dir_table: &mut PageTable, to_page_table: Option<&'a mut PageTable>) -> &'a mut PageTable {
-    
-    // // 为什么这样就可以？
-    // let page_table = if to_page_table.is_some() {
-    //     to_page_table.unwrap()
-    // } else {
-    //     unsafe { &mut *(self::malloc_kernel_page(1) as *mut PageTable) }
-    // };
-    // return page_table;
-
-    ASSERT!(page_data.len() == constants::PAGE_SIZE as usize);
-    let cur_task = &mut thread::current_thread().task_struct;
-
-    /*** 1. 把这一页的数据，先复制一份出来  */
-    // 申请一块用户空间，用于存放我们要复制的数据
-    let new_page_data_addr = self::malloc_user_page(cur_task, 1);
-    let new_page_data = unsafe { core::slice::from_raw_parts_mut(new_page_data_addr as *mut u8, constants::PAGE_SIZE as usize) };
-    // 那一页的数据，复制到新的空间中
-    new_page_data.copy_from_slice(page_data);
+pub fn share_single_user_page<'a>(vaddr: usize, to_dir_table: &mut PageTable, to_page_table: Option<&'a mut PageTable>) -> &'a mut PageTable {
+    ASSERT!(vaddr % constants::PAGE_SIZE as usize == 0);
+
+    /*** 1. 当前任务的这一页，改成只读、写时复制  */
+    let pte = page_util::addr_to_pte(vaddr);
+    ASSERT!(pte.present());
+    if pte.writable() {
+        pte.set_writable(false);
+        pte.set_cow(true);
+        // 刷新TLB缓存，否则还是可以写
+        instruction::invalidate_page(vaddr);
+    }
+    // 这一页多了一个进程共享
+    frame_ref::inc_ref(pte.get_phy_addr() as usize);
 
 
-    // /**** 2. 填充页表，页表指向数据页的物理地址  */
-    let pde_idx = page_util::locate_pde(page_data.as_ptr() as usize);
-    let pte_idx = page_util::locate_pte(page_data.as_ptr() as usize);
+    // /**** 2. 填充页表，页表指向同一个物理页  */
+    let pde_idx = page_util::locate_pde(vaddr);
+    let pte_idx = page_util::locate_pte(vaddr);
 
     // 页目录项，指向的是页表。看看页目录项有没有值
     // 如果页目录项有值，那么我们是不知道页表的虚拟地址的（只有知道虚拟地址才可以操作），因此需要使用入参的页表地址
@@ -264,8 +250,8 @@ pub fn copy_single_user_page<'a>(page_data: &[u8], to_dir_table: &mut PageTable,
         unsafe { &mut *(self::malloc_kernel_page(1) as *mut PageTable) }
     };
 
-    // 填充页表。页表项指向物理页的物理地址
-    page_table.set_entry(pte_idx, PageTableEntry::new_default(page_util::get_phy_from_virtual_addr(new_page_data_addr)));
+    // 填充页表。页表项跟当前任务的一模一样
+    page_table.set_entry(pte_idx, *pte);
 
 
     /**** 3. 填充页目录表，页目录表该项指向页表的物理地址 *****/
@@ -274,8 +260,213 @@ pub fn copy_single_user_page<'a>(page_data: &[u8], to_dir_table: &mut PageTable,
         to_dir_table.set_entry(pde_idx, PageTableEntry::new_default(page_util::get_phy_from_virtual_addr(page_table as *const _ as usize)));
     }
 
-    /**** 4. 释放申请的内存（不要释放物理地址，只是释放该内存空间跟当前任务的链接关系） */
-    self::free_user_page(cur_task, new_page_data_addr, 1, false);
-
     page_table
 }
+
+/**
+ * 处理写时复制的缺页。返回是否处理成功（不是写时复制的页，返回false）
+ *    - 如果这一页只有当前任务在用了，那么直接恢复可写
+ *    - 否则，复制一份新的物理页，当前任务的页表项指向新的物理页
+ */
+#[inline(never)]
+pub fn copy_on_write(fault_vaddr: usize) -> bool {
+    let page_vaddr = fault_vaddr & !(constants::PAGE_SIZE as usize - 1);
+    let pde = page_util::addr_to_pde(page_vaddr);
+    if !pde.present() {
+        return false;
+    }
+    let pte = page_util::addr_to_pte(page_vaddr);
+    if !pte.present() || !pte.is_cow() {
+        return false;
+    }
+    let phy_addr = pte.get_phy_addr() as usize;
+
+    // 只剩下当前任务在用这一页，直接恢复可写即可
+    if frame_ref::owner_cnt(phy_addr) <= 1 {
+        pte.set_cow(false);
+        pte.set_writable(true);
+        instruction::invalidate_page(page_vaddr);
+        return true;
+    }
+
+    // 先把这一页的数据，复制到一个内核页中
+    let buff_addr = self::malloc_kernel_page(1);
+    let buff = unsafe { core::slice::from_raw_parts_mut(buff_addr as *mut u8, constants::PAGE_SIZE as usize) };
+    let page_data = unsafe { core::slice::from_raw_parts_mut(page_vaddr as *mut u8, constants::PAGE_SIZE as usize) };
+    buff.copy_from_slice(page_data);
+
+    // 申请一个新的物理页
+    unsafe { USER_MEM_POOL_LOCK.get_mut().lock() };
+    let new_phy_addr = memory_poll::get_user_mem_pool().apply_one();
+    unsafe { USER_MEM_POOL_LOCK.get_mut().unlock() };
+    if new_phy_addr.is_err() {
+        MY_PANIC!("failed to copy on write, user memory exhausted. vaddr:0x{:x}", fault_vaddr);
+    }
+    // 原本的物理页，少了一个进程使用
+    frame_ref::dec_ref(phy_addr);
+
+    // 页表项，指向新的物理页（可写）
+    *pte = PageTableEntry::new_default(new_phy_addr.unwrap());
+    instruction::invalidate_page(page_vaddr);
+
+    // 把数据复制回来
+    page_data.copy_from_slice(buff);
+    self::free_kernel_page(buff_addr, 1, true);
+    true
+}
+
+/**
+ * 按需分配（缺页的时候才分配物理页）。返回是否处理成功（访问的地址不合法，返回false）
+ *    - mmap映射的区域（VMA），根据VMA的权限和数据来源填充
+ *    - 地址池已经分配了、但是还没有映射物理页的地址（堆）
+ *    - 栈顶下方、栈最大范围之内的地址（栈增长）
+ * 分配的物理页都会清零
+ */
+#[inline(never)]
+pub fn demand_page(fault_vaddr: usize, write: bool) -> bool {
+    let cur_task = &mut thread::current_thread().task_struct;
+    // 内核线程，没有按需分配
+    if cur_task.pgdir.is_null() {
+        return false;
+    }
+    let page_vaddr = fault_vaddr & !(constants::PAGE_SIZE as usize - 1);
+    // 这一页已经映射了，那么不是缺页（权限问题）
+    if page_util::addr_to_pde(page_vaddr).present() && page_util::addr_to_pte(page_vaddr).present() {
+        return false;
+    }
+
+    // mmap映射的区域，由VMA决定怎么填充
+    let vma = cur_task.vma_list.find(page_vaddr).copied();
+    if vma.is_some() {
+        return self::fault_in_vma(&mut cur_task.vaddr_pool, &vma.unwrap(), page_vaddr, write);
+    }
+
+    if cur_task.vaddr_pool.is_set(page_vaddr) {
+        // 地址池已经分配了，只是还没有物理页
+        unsafe { USER_MEM_POOL_LOCK.get_mut().lock() };
+        memory_allocation::malloc_phy_by_vaddr(page_vaddr, memory_poll::get_user_mem_pool());
+        unsafe { USER_MEM_POOL_LOCK.get_mut().unlock() };
+    } else if page_vaddr >= constants::USER_STACK_LIMIT_ADDR && page_vaddr < constants::USER_STACK_TOP_ADDR {
+        // 栈向下增长
+        self::malloc_user_page_by_vaddr(&mut cur_task.vaddr_pool, page_vaddr);
+    } else {
+        return false;
+    }
+
+    // 清空申请到的内存空间
+    let page_data = unsafe { core::slice::from_raw_parts_mut(page_vaddr as *mut u8, constants::PAGE_SIZE as usize) };
+    unsafe { page_data.as_mut_ptr().write_bytes(0, page_data.len()) };
+    true
+}
+
+/**
+ * 填充VMA中的一页
+ *    - 写入一个不可写的VMA，返回false
+ *    - 匿名映射：清零
+ *    - 文件映射：清零后，从文件对应的偏移量读取一页（超过文件末尾的部分保持0）
+ *    - 不可写的VMA，页表项去掉R/W位
+ */
+#[inline(never)]
+fn fault_in_vma(vaddr_pool: &mut MemPool, vma: &Vma, page_vaddr: usize, write: bool) -> bool {
+    if write && !vma.writable() {
+        return false;
+    }
+    // mmap的时候，已经占用了虚拟地址池
+    ASSERT!(vaddr_pool.is_set(page_vaddr));
+    unsafe { USER_MEM_POOL_LOCK.get_mut().lock() };
+    memory_allocation::malloc_phy_by_vaddr(page_vaddr, memory_poll::get_user_mem_pool());
+    unsafe { USER_MEM_POOL_LOCK.get_mut().unlock() };
+
+    let page_data = unsafe { core::slice::from_raw_parts_mut(page_vaddr as *mut u8, constants::PAGE_SIZE as usize) };
+    unsafe { page_data.as_mut_ptr().write_bytes(0, page_data.len()) };
+
+    if let VmaBacking::File { i_no, offset } = vma.backing {
+        let fs = filesystem::get_filesystem();
+        // mmap的时候打开了这个inode，VMA还在，inode就一直是打开的
+        let opened_inode = fs.find_inode(i_no);
+        ASSERT!(opened_inode.is_some());
+        let opened_inode = opened_inode.unwrap();
+        let file_off = offset + (page_vaddr - vma.start) as u32;
+        if file_off < opened_inode.i_size {
+            let read_len = (opened_inode.i_size - file_off).min(constants::PAGE_SIZE) as usize;
+            let mut opened_file = filesystem::OpenedFile::new(opened_inode, false);
+            opened_file.set_file_off(file_off);
+            // 读取硬盘需要等待硬盘的中断，因此要打开中断
+            let old_status = instruction::enable_interrupt();
+            filesystem::read_file(fs, &mut opened_file, &mut page_data[..read_len]);
+            instruction::set_interrupt(old_status);
+        }
+    }
+
+    if !vma.writable() {
+        page_util::addr_to_pte(page_vaddr).set_writable(false);
+        instruction::invalidate_page(page_vaddr);
+    }
+    true
+}
+
+/**
+ * 系统调用访问用户进程传入的地址之前，校验[addr, addr + len)是不是可以访问。返回false，系统调用应该失败，而不是在内核态缺页
+ *    - 地址范围不能溢出
+ *    - 内核空间的地址（比如内核中的shell传入的静态数据），已经映射了，直接放行
+ *    - 用户空间的每一页，要么已经映射了（写入的话，要可写或者写时复制），要么可以按需分配
+ */
+#[inline(never)]
+pub fn check_user_range(addr: usize, len: usize, write: bool) -> bool {
+    let cur_task = &thread::current_thread().task_struct;
+    // 内核线程，没有用户空间
+    if cur_task.pgdir.is_null() || len == 0 {
+        return true;
+    }
+    let end = addr.checked_add(len);
+    if end.is_none() {
+        return false;
+    }
+    let end = end.unwrap();
+    let page_start = addr & !(constants::PAGE_SIZE as usize - 1);
+    for page_vaddr in (page_start .. end).step_by(constants::PAGE_SIZE as usize) {
+        if page_vaddr >= constants::KERNEL_ADDR_START {
+            continue;
+        }
+        if page_util::addr_to_pde(page_vaddr).present() && page_util::addr_to_pte(page_vaddr).present() {
+            let pte = page_util::addr_to_pte(page_vaddr);
+            if write && !pte.writable() && !pte.is_cow() {
+                return false;
+            }
+            continue;
+        }
+        // 还没有映射，和demand_page的判断一致
+        let vma = cur_task.vma_list.find(page_vaddr);
+        if vma.is_some() {
+            if write && !vma.unwrap().writable() {
+                return false;
+            }
+            continue;
+        }
+        let in_stack = page_vaddr >= constants::USER_STACK_LIMIT_ADDR && page_vaddr < constants::USER_STACK_TOP_ADDR;
+        if !in_stack && !cur_task.vaddr_pool.is_set(page_vaddr) {
+            return false;
+        }
+    }
+    true
+}
+
+/**
+ * 取消一页用户空间的映射，虚拟地址还给地址池
+ *    - 这一页映射了物理页的话，没有其他进程共享，就释放物理页
+ *    - 还没有映射（还没有发生过缺页）的话，只需要归还虚拟地址
+ */
+#[inline(never)]
+pub fn unmap_user_page(vaddr_pool: &mut MemPool, vaddr: usize) {
+    ASSERT!(vaddr % constants::PAGE_SIZE as usize == 0);
+    if page_util::addr_to_pde(vaddr).present() && page_util::addr_to_pte(vaddr).present() {
+        let phy_addr = page_util::addr_to_pte(vaddr).get_phy_addr() as usize;
+        if frame_ref::dec_ref(phy_addr) == 0 {
+            unsafe { USER_MEM_POOL_LOCK.get_mut().lock() };
+            memory_poll::get_user_mem_pool().restore(phy_addr);
+            unsafe { USER_MEM_POOL_LOCK.get_mut().unlock() };
+        }
+        page_util::unset_pte(vaddr);
+    }
+    vaddr_pool.restore(vaddr);
+}
@@ -3,6 +3,8 @@ mod memory_deallocation;
 mod mem_block;
 mod memory_poll;
 mod memory_management;
+mod frame_ref;
+mod vma;
 pub mod page_util;
 
 // 初始化内存池
@@ -20,7 +22,20 @@ pub use memory_management::free_by_addr_pool;
 // 释放内存
 pub use memory_management::sys_free;
 
-pub use memory_management::copy_single_user_page;
+pub use memory_management::share_single_user_page;
+pub use memory_management::copy_on_write;
+pub use memory_management::demand_page;
+pub use memory_management::check_user_range;
+pub use memory_management::unmap_user_page;
+
+pub use frame_ref::frame_ref_init;
+
+pub use vma::Vma;
+pub use vma::VmaList;
+pub use vma::VmaBacking;
+pub use vma::PROT_READ;
+pub use vma::PROT_WRITE;
+pub use vma::PROT_EXEC;
 
 
 pub use mem_block::MemBlockAllocator;
@@ -0,0 +1,293 @@
+use core::mem::size_of;
+
+use os_in_rust_common::{constants, domain::InodeNo, ASSERT};
+
+use crate::filesystem::{self, inode};
+
+use super::memory_management;
+
+/**
+ * ************************************************************
+ * *       本文件是进程的虚拟内存区域（VMA）
+ * ************************************************************
+ *
+ * 每个用户进程有一个VMA列表，记录通过mmap映射的每一段虚拟地址：
+ *   - 起始地址和长度（按页对齐）
+ *   - 访问权限（读、写、执行）
+ *   - 背后的数据来源（匿名内存，或者某个文件的某个偏移量）
+ * 缺页的时候，根据VMA决定怎么填充这一页；fork和exit的时候，根据VMA决定复制、释放什么
+ */
+
+/**
+ * 可读
+ */
+pub const PROT_READ: u8 = 0x1;
+/**
+ * 可写
+ */
+pub const PROT_WRITE: u8 = 0x2;
+/**
+ * 可执行
+ */
+pub const PROT_EXEC: u8 = 0x4;
+
+/**
+ * VMA背后的数据来源
+ */
+#[derive(Debug, Clone, Copy)]
+pub enum VmaBacking {
+    /**
+     * 匿名内存，缺页的时候清零
+     */
+    Anonymous,
+    /**
+     * 文件映射，缺页的时候从文件的offset处读取
+     */
+    File {
+        i_no: InodeNo,
+        offset: u32,
+    },
+}
+
+/**
+ * 一段虚拟内存区域：[start, end)
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct Vma {
+    pub start: usize,
+    pub end: usize,
+    pub prot: u8,
+    pub backing: VmaBacking,
+}
+
+impl Vma {
+    #[inline(never)]
+    pub fn new(start: usize, len: usize, prot: u8, backing: VmaBacking) -> Self {
+        ASSERT!(start % constants::PAGE_SIZE as usize == 0);
+        ASSERT!(len % constants::PAGE_SIZE as usize == 0);
+        Self {
+            start,
+            end: start + len,
+            prot,
+            backing,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn contains(&self, addr: usize) -> bool {
+        addr >= self.start && addr < self.end
+    }
+
+    pub fn writable(&self) -> bool {
+        self.prot & PROT_WRITE == PROT_WRITE
+    }
+
+    /**
+     * 这个区域，跟[start, end)是否有交集
+     */
+    pub fn overlaps(&self, start: usize, end: usize) -> bool {
+        self.start < end && start < self.end
+    }
+
+    /**
+     * 截取这个区域中[start, end)的部分。文件映射的偏移量跟着移动
+     */
+    #[inline(never)]
+    fn slice(&self, start: usize, end: usize) -> Self {
+        ASSERT!(start >= self.start && end <= self.end && start < end);
+        let backing = match self.backing {
+            VmaBacking::Anonymous => VmaBacking::Anonymous,
+            VmaBacking::File { i_no, offset } => VmaBacking::File { i_no, offset: offset + (start - self.start) as u32 },
+        };
+        Self {
+            start,
+            end,
+            prot: self.prot,
+            backing,
+        }
+    }
+
+    /**
+     * 文件映射的VMA，多一个引用，把文件的inode再打开一次
+     */
+    #[inline(never)]
+    fn open_backing(&self) {
+        if let VmaBacking::File { i_no, .. } = self.backing {
+            inode::inode_open(filesystem::get_filesystem(), i_no);
+        }
+    }
+
+    /**
+     * 文件映射的VMA，少一个引用，把文件的inode关闭一次
+     */
+    #[inline(never)]
+    fn close_backing(&self) {
+        if let VmaBacking::File { i_no, .. } = self.backing {
+            let fs = filesystem::get_filesystem();
+            let opened_inode = fs.find_inode(i_no);
+            ASSERT!(opened_inode.is_some());
+            inode::inode_close(fs, opened_inode.unwrap());
+        }
+    }
+}
+
+/**
+ * 一个进程的VMA列表。列表本身放在一个单独的内核页中，避免PCB变大
+ * 全0的VmaList（比如内核线程），就是一个空的列表
+ */
+#[derive(Debug)]
+pub struct VmaList {
+    vmas: *mut Option<Vma>,
+    cap: usize,
+}
+
+impl VmaList {
+    /**
+     * 申请一页内核空间，作为VMA列表
+     */
+    #[inline(never)]
+    pub fn new() -> Self {
+        let addr = memory_management::malloc_kernel_page(1);
+        let cap = constants::PAGE_SIZE as usize / size_of::<Option<Vma>>();
+        let vmas = addr as *mut Option<Vma>;
+        for idx in 0 .. cap {
+            unsafe { vmas.add(idx).write(Option::None) };
+        }
+        Self { vmas, cap }
+    }
+
+    /**
+     * 用已有的空间作为VMA列表（比如宿主机上的测试）。这个列表不能release
+     */
+    pub fn from_slots(slots: &'static mut [Option<Vma>]) -> Self {
+        slots.fill(Option::None);
+        Self { vmas: slots.as_mut_ptr(), cap: slots.len() }
+    }
+
+    fn as_slice(&self) -> &[Option<Vma>] {
+        if self.vmas.is_null() {
+            return &[];
+        }
+        unsafe { core::slice::from_raw_parts(self.vmas, self.cap) }
+    }
+
+    fn as_slice_mut(&mut self) -> &mut [Option<Vma>] {
+        if self.vmas.is_null() {
+            return &mut [];
+        }
+        unsafe { core::slice::from_raw_parts_mut(self.vmas, self.cap) }
+    }
+
+    /**
+     * 遍历所有的VMA
+     */
+    pub fn iter(&self) -> impl Iterator<Item = &Vma> {
+        self.as_slice().iter().filter_map(|vma| vma.as_ref())
+    }
+
+    /**
+     * 还能不能再放入cnt个VMA
+     */
+    pub fn has_room(&self, cnt: usize) -> bool {
+        self.as_slice().iter().filter(|vma| vma.is_none()).count() >= cnt
+    }
+
+    /**
+     * 找到地址addr所在的VMA
+     */
+    #[inline(never)]
+    pub fn find(&self, addr: usize) -> Option<&Vma> {
+        self.iter().find(|vma| vma.contains(addr))
+    }
+
+    /**
+     * 放入一个VMA。列表满了，返回false
+     */
+    #[inline(never)]
+    pub fn add(&mut self, vma: Vma) -> bool {
+        let slot = self.as_slice_mut().iter_mut().find(|slot| slot.is_none());
+        if slot.is_none() {
+            return false;
+        }
+        *slot.unwrap() = Option::Some(vma);
+        true
+    }
+
+    /**
+     * 把[start, end)从VMA列表中移除。跨过这个范围的VMA会被截断或者拆分成两段
+     * 调用前需要确认列表有足够的空位（拆分最多多出一个VMA）
+     */
+    #[inline(never)]
+    pub fn remove_range(&mut self, start: usize, end: usize) {
+        for idx in 0 .. self.cap {
+            let slot = &mut self.as_slice_mut()[idx];
+            if slot.is_none() || !slot.unwrap().overlaps(start, end) {
+                continue;
+            }
+            let vma = slot.take().unwrap();
+            // 保留前面没有被移除的部分
+            let head = if vma.start < start { Option::Some(vma.slice(vma.start, start)) } else { Option::None };
+            // 保留后面没有被移除的部分
+            let tail = if vma.end > end { Option::Some(vma.slice(end, vma.end)) } else { Option::None };
+            match (head, tail) {
+                (Option::Some(head), Option::Some(tail)) => {
+                    *slot = Option::Some(head);
+                    // 拆成了两段，文件多了一个引用
+                    tail.open_backing();
+                    ASSERT!(self.add(tail));
+                },
+                (Option::Some(remain), Option::None) | (Option::None, Option::Some(remain)) => {
+                    *slot = Option::Some(remain);
+                },
+                (Option::None, Option::None) => {
+                    vma.close_backing();
+                },
+            }
+        }
+    }
+
+    /**
+     * 复制一个VMA列表（fork的时候用）。文件映射的文件，都要再打开一次
+     */
+    #[inline(never)]
+    pub fn fork(&self) -> Self {
+        let mut to = Self::new();
+        if self.vmas.is_null() {
+            return to;
+        }
+        to.as_slice_mut().copy_from_slice(self.as_slice());
+        for vma in to.iter() {
+            vma.open_backing();
+        }
+        to
+    }
+
+    /**
+     * 清空所有的VMA，关闭所有映射的文件
+     */
+    #[inline(never)]
+    pub fn clear(&mut self) {
+        for slot in self.as_slice_mut() {
+            if slot.is_some() {
+                slot.take().unwrap().close_backing();
+            }
+        }
+    }
+
+    /**
+     * 清空VMA列表，并且释放列表自身所在的内核页
+     */
+    #[inline(never)]
+    pub fn release(&mut self) {
+        if self.vmas.is_null() {
+            return;
+        }
+        self.clear();
+        memory_management::free_kernel_page(self.vmas as usize, 1, true);
+        self.vmas = core::ptr::null_mut();
+        self.cap = 0;
+    }
+}
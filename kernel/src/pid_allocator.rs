@@ -3,7 +3,7 @@ use os_in_rust_common::{bitmap::{self, BitMap}, pool::MemPool, ASSERT};
 use crate::{mutex::Mutex, println};
 
 #[derive(Clone, Copy)]
-#[derive(PartialEq)]
+#[derive(PartialEq, Debug)]
 pub struct Pid {
     data: u8
 }
@@ -5,6 +5,8 @@ mod pipe_holder;
 pub use pipe::pipe;
 pub use pipe::set_producer;
 pub use pipe::set_consumer;
+pub use pipe::set_error_producer;
+pub use pipe::write_end;
 pub use pipe::release_pipe;
 pub use pipe_container::PipeContainer;
 pub use pipe_container::get_pipe_by_fd;
@@ -39,6 +39,38 @@ pub fn set_producer(pipe_fd: FileDescriptor) -> Result<(), PipeError> {
 }
 
 
+/**
+ * 把标准错误重定向到fd。fd可以是管道、文件，也可以是标准输出（2>&1）
+ */
+#[inline(never)]
+pub fn set_error_producer(fd: FileDescriptor) -> Result<(), PipeError> {
+    // 如果是管道，那么需要设置生产者
+    let pipe = pipe_container::get_pipe_by_fd(fd);
+    if pipe.is_some() {
+        let pipe = pipe.unwrap();
+        let producer = &thread::current_thread().task_struct;
+        pipe.set_producer(producer);
+    }
+
+    // 把该进程的标准错误，重定向到fd
+    filesystem::redirect_file_descriptor(FileDescriptor::new(StdFileDescriptor::StdErrorNo as usize), fd);
+    return Result::Ok(());
+}
+
+/**
+ * 管道写入结束。不用等到生产者退出，消费者读取完剩下的数据之后就不再阻塞
+ */
+#[inline(never)]
+pub fn write_end(pipe_fd: FileDescriptor) -> Result<(), PipeError> {
+    let pipe = pipe_container::get_pipe_by_fd(pipe_fd);
+    if pipe.is_none() {
+        return Result::Err(PipeError::PipeNotExist);
+    }
+    pipe.unwrap().write_end();
+    return Result::Ok(());
+}
+
+
 #[inline(never)]
 pub fn release_pipe(fd: FileDescriptor) {
     pipe_container::release_pipe(fd);
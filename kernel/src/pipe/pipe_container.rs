@@ -104,14 +104,14 @@ impl <'a, T: Copy + Sized> PipeContainer<'a, T> {
     }
 
     /**
-     * 读取该管道的数据，读取一个元素出来。阻塞操作
+     * 读取该管道的数据，读取一个元素出来。阻塞操作，收到信号会提前返回已经读取的数量
      */
     #[inline(never)]
     pub fn read(&mut self, buff: &mut [T]) -> usize {
         for (idx, ele) in buff.iter_mut().enumerate() {
-            // 从队列里面取数据（阻塞）
-            let data = self.queue.take();
-            // 如果为空了，那么这个队列彻底空了
+            // 从队列里面取数据（阻塞，可以被信号打断）
+            let data = self.queue.take_interruptible();
+            // 如果为空了，那么这个队列彻底空了（或者被信号打断了）
             if data.is_none() {
                 return idx;
             }
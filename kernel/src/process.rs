@@ -1,7 +1,19 @@
 
-use os_in_rust_common::{constants, instruction, paging::{PageTable, PageTableEntry}};
+use os_in_rust_common::{constants, instruction, paging::{PageTable, PageTableEntry}, racy_cell::RacyCell};
 
-use crate::{interrupt, memory::{self, page_util}, pid_allocator, println, shell, sys_call::{self}, thread::{self, ThreadArg}, thread_management};
+use crate::{interrupt, memory::{self, page_util}, pid_allocator::{self, Pid}, shell, sys_call::{self}, thread::{self, ThreadArg}, thread_management};
+
+/**
+ * init进程的pid。孤儿进程会过继给init进程
+ */
+static INIT_PID: RacyCell<Option<Pid>> = RacyCell::new(Option::None);
+
+/**
+ * init进程的pid。init进程还没有创建的时候，返回None
+ */
+pub fn get_init_pid() -> Option<Pid> {
+    *unsafe { INIT_PID.get_mut() }
+}
 
 /**
  * 用户进程的实现
@@ -47,7 +59,7 @@ pub fn create_page_dir() -> *mut PageTable {
 
 
 #[inline(never)]
-pub fn process_execute(process_name: &'static str, func: extern "C" fn()) {
+pub fn process_execute(process_name: &'static str, func: extern "C" fn()) -> Pid {
     // 申请1页空间
     let pcb_page_addr = memory::malloc_kernel_page(1);
     // 强转
@@ -58,6 +70,9 @@ pub fn process_execute(process_name: &'static str, func: extern "C" fn()) {
     // 设置用户地址池
     pcb_page.task_struct.vaddr_pool = thread_management::apply_user_addr_pool();
 
+    // 设置虚拟内存区域列表
+    pcb_page.task_struct.vma_list = memory::VmaList::new();
+
     // 设置线程栈
     pcb_page.init_thread_stack(start_process, func as u32);
 
@@ -79,6 +94,7 @@ pub fn process_execute(process_name: &'static str, func: extern "C" fn()) {
     // println!("pcb_page:{}", pcb_page);
     instruction::set_interrupt(old_status);
 
+    pcb_page.task_struct.pid
 }
 
 /**
@@ -90,18 +106,13 @@ extern "C" fn init_process() {
     // 发起系统调用，fork
     let fork_res = sys_call::fork();
     match fork_res {
-        sys_call::ForkResult::Parent(child_pid) => {
-            // println!("i'm father, my pid is {}, my child pid is {}", sys_call::get_pid().get_data(), child_pid.get_data());
+        sys_call::ForkResult::Parent(_) => {
+            // 回收所有的子进程，包括过继过来的孤儿进程
             loop {
-                sys_call::thread_yield();
+                if let sys_call::WaitResult::NoChild = sys_call::waitpid(sys_call::WaitTarget::Any, 0) {
+                    sys_call::thread_yield();
+                }
             }
-            // let wait_res = sys_call::wait();
-            // if wait_res.is_none() {
-            //     println!("child process exit with no result, child pid:{}", child_pid.get_data());
-            //     return;
-            // }
-            // let (cpid, exit_status) = wait_res.unwrap();
-            // println!("child process exit with {:?}, child pid:{}", exit_status, cpid.get_data());
         },
         sys_call::ForkResult::Child => {
             // println!("im child, my pid is {}", sys_call::get_pid().get_data());
@@ -117,5 +128,6 @@ extern "C" fn init_process() {
 pub fn init() {
     instruction::disable_interrupt();
     // 执行init进程
-    self::process_execute("init", init_process);
+    let init_pid = self::process_execute("init", init_process);
+    *unsafe { INIT_PID.get_mut() } = Option::Some(init_pid);
 }
\ No newline at end of file
@@ -122,6 +122,21 @@ static MAKE_CODE_ASCII_MAPPING: [(Key, AsciiKey, AsciiKey); constants::KEYBOARD_
     (Key::CapsLock, AsciiKey::DC4, AsciiKey::DC4),
 ];
 
+/**
+ * 扩展键（通码以0xE0开头）和字符的映射
+ */
+static EXTEND_KEY_ASCII_MAPPING: [(Key, AsciiKey); 9] = [
+    (Key::RightAlt, AsciiKey::DC2),
+    (Key::RightCtrl, AsciiKey::DC1),
+    (Key::Home, AsciiKey::HOME),
+    (Key::Up, AsciiKey::UP),
+    (Key::Left, AsciiKey::LEFT),
+    (Key::Right, AsciiKey::RIGHT),
+    (Key::End, AsciiKey::END),
+    (Key::Down, AsciiKey::DOWN),
+    (Key::Delete, AsciiKey::DEL),
+];
+
 /**
  * 目前适配的所有的键。value是这个键的通码
  */
@@ -188,6 +203,13 @@ pub enum Key {
     CapsLock = 0x3A,
     RightAlt = 0xE038,
     RightCtrl = 0xE01D,
+    Home = 0xE047,
+    Up = 0xE048,
+    Left = 0xE04B,
+    Right = 0xE04D,
+    End = 0xE04F,
+    Down = 0xE050,
+    Delete = 0xE053,
 }
 
 /**
@@ -274,26 +296,12 @@ impl KeyCode {
             let (key, low_char, high_char) = MAKE_CODE_ASCII_MAPPING[make_code as usize];
             // 构建键码信息
             return Option::Some(KeyCode::new(scan_code, key, code_type, low_char, high_char));
-        } else {
-            // 特殊判断
-            if make_code == Key::RightAlt as u16 {
-                return Option::Some(KeyCode::new(
-                    scan_code,
-                    Key::RightAlt,
-                    code_type,
-                    AsciiKey::DC2,
-                    AsciiKey::DC2,
-                ));
-            // 特殊判断
-            } else if make_code == Key::RightCtrl as u16 {
-                return Option::Some(KeyCode::new(
-                    scan_code,
-                    Key::RightCtrl,
-                    code_type,
-                    AsciiKey::DC1,
-                    AsciiKey::DC1,
-                ));
-            }
+        }
+        // 扩展键
+        let extend_key = EXTEND_KEY_ASCII_MAPPING.iter().find(|(key, _)| *key as u16 == make_code);
+        if extend_key.is_some() {
+            let (key, char) = *extend_key.unwrap();
+            return Option::Some(KeyCode::new(scan_code, key, code_type, char, char));
         }
 
         Option::None
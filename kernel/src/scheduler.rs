@@ -1,9 +1,14 @@
 use core::arch::asm;
 
-use os_in_rust_common::{instruction, printkln, ASSERT};
+use os_in_rust_common::{constants, instruction, printkln, racy_cell::RacyCell, ASSERT};
 
 use crate::{console_println, thread::{self, TaskStatus, TaskStruct}};
 
+/**
+ * 距离上一次老化（把所有任务提升到第0级），过去了多少个tick
+ */
+static AGING_ELAPSED_TICKS: RacyCell<u32> = RacyCell::new(0);
+
 
 /**
  * 阻塞某一个线程。（一般阻塞操作都是线程阻塞自身）
@@ -18,8 +23,8 @@ use crate::{console_println, thread::{self, TaskStatus, TaskStruct}};
  */
 #[inline(never)]
 pub fn block_thread(task: &mut TaskStruct, task_status: TaskStatus) {
-    // 只能是这三种状态之一
-    let allow_status = [TaskStatus::TaskBlocked, TaskStatus::TaskHanging, TaskStatus::TaskWaiting];
+    // 只能是这几种状态之一
+    let allow_status = [TaskStatus::TaskBlocked, TaskStatus::TaskHanging, TaskStatus::TaskWaiting, TaskStatus::TaskStopped];
     ASSERT!(allow_status.contains(&task_status));
     
     // 关闭中断
@@ -46,6 +51,14 @@ pub fn check_task_schedule() {
     // 该进程运行的tick数+1
     task_struct.elapsed_ticks += 1;
 
+    // 定期老化，防止低级别的任务饿死
+    let aging_elapsed_ticks = unsafe { AGING_ELAPSED_TICKS.get_mut() };
+    *aging_elapsed_ticks += 1;
+    if *aging_elapsed_ticks >= constants::MLFQ_AGING_TICKS {
+        *aging_elapsed_ticks = 0;
+        self::age_all_thread();
+    }
+
     // 如果剩余的时间片还有，那就减少
     if task_struct.left_ticks > 0 {
         task_struct.left_ticks -= 1;
@@ -69,7 +82,9 @@ pub fn schedule() {
     // 原本该线程处于正在运行，那么说明是时间中断，定时切换的
     if cur_task.task_status == TaskStatus::TaskRunning {
         // 确保不在就绪队列中
-        ASSERT!(!thread::get_ready_thread().contains(&cur_task.general_tag));
+        ASSERT!(!thread::in_ready_thread(cur_task));
+        // 用完了整个时间片，降一级
+        cur_task.demote();
         // 把当前线程加入到就绪队列
         thread::append_read_thread(cur_task);
         // 重置剩余的ticks
@@ -81,14 +96,14 @@ pub fn schedule() {
 
     }
     // 如果没有就绪任务，那么就执行idle线程
-    if thread::get_ready_thread().is_empty() {
+    if thread::is_ready_thread_empty() {
         let idle_thread = thread::get_idle_thread();
         thread::wake_thread(idle_thread);
     }
-    ASSERT!(!thread::get_ready_thread().is_empty());
-    let pcb_ready_tag = thread::get_ready_thread().pop();
-    // 找到那个要运行的task
-    let task_to_run = unsafe { &mut *(TaskStruct::parse_by_general_tag(pcb_ready_tag)) };
+    // 找到那个要运行的task（优先级最高的队列中的第一个）
+    let task_to_run = thread::pop_ready_thread();
+    ASSERT!(task_to_run.is_some());
+    let task_to_run = task_to_run.unwrap();
     
     task_to_run.set_status(TaskStatus::TaskRunning);
 
@@ -108,6 +123,40 @@ pub fn schedule() {
 
 }
 
+/**
+ * 老化。把所有任务都提升到第0级
+ *    - 就绪的任务，从原来的队列移到第0级的队列
+ *    - 其他任务（运行中、阻塞中），只修改级别
+ */
+#[inline(never)]
+fn age_all_thread() {
+    for task_tag in thread::get_all_thread().iter() {
+        let task = unsafe { &mut *TaskStruct::parse_by_all_tag(&*task_tag) };
+        if task.mlfq_level == 0 {
+            continue;
+        }
+        if task.task_status == TaskStatus::TaskReady {
+            thread::remove_from_ready_thread(task);
+            task.mlfq_level = 0;
+            thread::append_read_thread(task);
+        } else {
+            task.mlfq_level = 0;
+        }
+    }
+}
+
+/**
+ * 调整当前任务的基础优先级（nice）。increment为正数，优先级降低；为负数，优先级升高
+ * 返回调整后的优先级
+ */
+#[inline(never)]
+pub fn nice(increment: i32) -> u8 {
+    let cur_task = &mut thread::current_thread().task_struct;
+    let new_priority = (cur_task.priority as i32 - increment).clamp(constants::TASK_MIN_PRIORITY as i32, constants::TASK_MAX_PRIORITY as i32);
+    cur_task.priority = new_priority as u8;
+    cur_task.priority
+}
+
 /**
  * 切换任务（该函数不可用。该方法已经使用纯汇编来实现）
  * - cur_task: 当前任务
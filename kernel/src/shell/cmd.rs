@@ -10,8 +10,42 @@ pub enum Cmd<'a> {
     Rmdir,
     Touch,
     Rm,
+    Mv,
+    Ln,
+    Sync,
+    Fsck,
+    Jobs,
+    Fg,
+    Bg,
+    History,
+    Sh,
     Custom(&'a str)
 }
+
+/**
+ * 内置命令的名称，以及对应的命令
+ */
+const BUILTIN_CMDS: [(&str, Cmd<'static>); 18] = [
+    ("pwd", Cmd::Pwd),
+    ("cd", Cmd::Cd),
+    ("ps", Cmd::Ps),
+    ("ls", Cmd::Ls),
+    ("clear", Cmd::Clear),
+    ("mkdir", Cmd::Mkdir),
+    ("rmdir", Cmd::Rmdir),
+    ("touch", Cmd::Touch),
+    ("rm", Cmd::Rm),
+    ("mv", Cmd::Mv),
+    ("ln", Cmd::Ln),
+    ("sync", Cmd::Sync),
+    ("fsck", Cmd::Fsck),
+    ("jobs", Cmd::Jobs),
+    ("fg", Cmd::Fg),
+    ("bg", Cmd::Bg),
+    ("history", Cmd::History),
+    ("sh", Cmd::Sh),
+];
+
 impl <'a> Cmd<'a> {
     // pub fn get_name(&self) -> &str {
     //     match self {
@@ -22,19 +56,19 @@ impl <'a> Cmd<'a> {
     //     }
     // }
     pub fn get_by_name(name: &'a str) -> Self {
-        match name {
-            "pwd" => Self::Pwd,
-            "cd" => Self::Cd,
-            "ps" => Self::Ps,
-            "ls" => Self::Ls,
-            "clear" => Self::Clear,
-            "mkdir" => Self::Mkdir,
-            "rmdir" => Self::Rmdir,
-            "touch" => Self::Touch,
-            "rm" => Self::Rm,
-            _ => Cmd::Custom(name),
+        let builtin = BUILTIN_CMDS.iter().find(|(builtin_name, _)| *builtin_name == name);
+        match builtin {
+            Option::Some((_, cmd)) => *cmd,
+            Option::None => Cmd::Custom(name),
         }
     }
+
+    /**
+     * 所有内置命令的名称
+     */
+    pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+        BUILTIN_CMDS.iter().map(|(name, _)| *name)
+    }
 }
 
 
@@ -1,24 +1,45 @@
 use crate::{common::exec_dto::ExecParam, pipe, sys_call};
 
 use super::shell_util;
-use crate::println;
+use crate::eprintln;
 
+/**
+ * 执行用户程序时，传递的默认环境变量
+ */
+const DEFAULT_ENVS: [&str; 2] = ["PATH=/", "HOME=/"];
+
+/**
+ * 最多传递多少个环境变量
+ */
+const MAX_ENV_CNT: usize = 8;
+
+/**
+ * 执行用户程序。extra_envs（命令前面的NAME=value）追加在默认环境变量的后面
+ */
 #[inline(never)]
-pub fn custom_cmd(cwd: &str, cmd: &str, param: Option<&str>, buff: &mut [u8]) {
+pub fn custom_cmd(cwd: &str, cmd: &str, args: &[&str], extra_envs: &[&str], buff: &mut [u8]) {
     let cmd_path = shell_util::get_abs_path(cwd, cmd, buff);
     if cmd_path.is_err() {
-        println!("failed to get abs path, cwd:{}, cmd:{}, error:{:?}", cwd, cmd, cmd_path.unwrap_err());
+        eprintln!("failed to get abs path, cwd:{}, cmd:{}, error:{:?}", cwd, cmd, cmd_path.unwrap_err());
         return;
     }
     
     let cmd_path = cmd_path.unwrap();
 
-    let exec_param = ExecParam::new(cmd_path, param);
+    let mut envs = [""; MAX_ENV_CNT];
+    let mut env_cnt = 0;
+    // 多余的环境变量忽略
+    for env in DEFAULT_ENVS.iter().chain(extra_envs.iter()).take(MAX_ENV_CNT) {
+        envs[env_cnt] = env;
+        env_cnt += 1;
+    }
+
+    let exec_param = ExecParam::new(cmd_path, args, &envs[.. env_cnt]);
     
     // 执行exec系统调用
     let exe_res = sys_call::exec(&exec_param);
     if exe_res.is_err() {
-        println!("failed to exec {}, error:{:?}", cmd, exe_res.unwrap_err());
+        eprintln!("failed to exec {}, error:{:?}", cmd, exe_res.unwrap_err());
         return;
     }
 }
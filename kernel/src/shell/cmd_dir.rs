@@ -73,6 +73,10 @@ pub fn rmdir(cwd: &str, param: Option<&str>, buf: &mut [u8]) {
                     println!("dir not empty: {}", dir_path);
                     return;
                 },
+                filesystem::DirError::NotADirectory => {
+                    println!("not a directory: {}", dir_path);
+                    return;
+                },
             }
         },
     }
@@ -1,136 +1,209 @@
-use crate::{filesystem::{FileDescriptor, FileError, StdFileDescriptor}, pipe::{self, PipeError}, println, shell::shell_util::PathError, sys_call};
+use os_in_rust_common::racy_cell::RacyCell;
 
-use super::{cmd_executor, shell_util};
+use crate::{eprintln, filesystem::{FileDescriptor, FileError, StdFileDescriptor}, pid_allocator::Pid, sys_call, userprog::TaskExitStatus};
 
+use super::{cmd::Cmd, cmd_cd, cmd_executor, job, parser::{self, CommandLine, Connector, ParseError, Pipeline, RedirectKind, SimpleCmd}, script, shell::Shell, shell_util, variable};
 
 /**
- * 根据shell的输出，解析和分配命令的执行
+ * 语法错误的退出码
+ */
+const SYNTAX_ERROR: TaskExitStatus = 2;
+
+/**
+ * 一个命令前面最多多少个变量赋值
+ */
+const MAX_ASSIGN_CNT: usize = 6;
+
+static COMMAND_LINE: RacyCell<CommandLine> = RacyCell::new(CommandLine::new());
+
+/**
+ * 解析之后的命令行。比较大，不放在栈上
+ */
+fn get_command_line() -> &'static mut CommandLine {
+    unsafe { COMMAND_LINE.get_mut() }
+}
+
+/**
+ * 根据shell的输入，解析和分配命令的执行
  */
 #[inline(never)]
-pub fn dispatch_cmd(cwd: &str, input: &str, buf: &mut [u8]) {
-    if input.trim().is_empty() {
-        return;
+pub fn dispatch_cmd<const PATH_LEN: usize, const INPUT_LEN: usize>(shell: &mut Shell<PATH_LEN, INPUT_LEN>, buf: &mut [u8]) {
+    let res = self::get_command_line().parse(shell.get_input().trim(), variable::get_vars());
+    self::execute_line(shell, res, buf);
+}
+
+/**
+ * 解析和执行一行命令（比如脚本中的一行），返回退出码
+ */
+#[inline(never)]
+pub fn dispatch_line<const PATH_LEN: usize, const INPUT_LEN: usize>(shell: &mut Shell<PATH_LEN, INPUT_LEN>, input: &str, buf: &mut [u8]) -> TaskExitStatus {
+    let res = self::get_command_line().parse(input.trim(), variable::get_vars());
+    self::execute_line(shell, res, buf)
+}
+
+/**
+ * 执行解析好的命令行。命令行中的管道依次执行：&&后面的管道，上一个管道成功才执行；||后面的管道，上一个管道失败才执行
+ * 每个管道的退出码，记录到$?中。返回最后的退出码
+ */
+#[inline(never)]
+fn execute_line<const PATH_LEN: usize, const INPUT_LEN: usize>(shell: &mut Shell<PATH_LEN, INPUT_LEN>, res: Result<(), ParseError>, buf: &mut [u8]) -> TaskExitStatus {
+    if res.is_err() {
+        eprintln!("syntax error: {:?}", res.unwrap_err());
+        variable::get_vars().set_status(SYNTAX_ERROR);
+        return SYNTAX_ERROR;
     }
-    unsafe { buf.as_mut_ptr().write_bytes(0, buf.len()) };
-    let input = input.trim();
+    let line: &CommandLine = self::get_command_line();
+    for pipeline in line.pipelines() {
+        let last_status = variable::get_vars().get_status();
+        match pipeline.get_connector() {
+            Connector::And if last_status != 0 => continue,
+            Connector::Or if last_status == 0 => continue,
+            _ => {},
+        }
+        let status = self::run_pipeline(shell, line, pipeline, buf);
+        variable::get_vars().set_status(status);
+    }
+    variable::get_vars().get_status()
+}
 
-    // 先把shell输入，按照重定向符(>和>>)分隔
-    let redirection = self::split_redirection(cwd, input, buf);
-    if redirection.is_err() {
-        println!("failed to exec:{}, error:{:?}", input, redirection.unwrap_err());
-        return;
+/**
+ * 执行一个管道，返回退出码
+ */
+#[inline(never)]
+fn run_pipeline<const PATH_LEN: usize, const INPUT_LEN: usize>(shell: &mut Shell<PATH_LEN, INPUT_LEN>, line: &CommandLine, pipeline: &Pipeline, buf: &mut [u8]) -> TaskExitStatus {
+    unsafe { buf.as_mut_ptr().write_bytes(0, buf.len()) };
+    let cmds = line.get_cmds(pipeline);
+    let background = pipeline.is_background();
+    // 单独的一个命令，有些需要在shell进程自己执行
+    if cmds.len() == 1 && !background {
+        let status = self::run_in_shell(shell, line, &cmds[0], buf);
+        if status.is_some() {
+            return status.unwrap();
+        }
     }
-    let (input, mut file) = redirection.unwrap();
 
-    // 把参数，按照管道分隔
-    let cmd_split = input.split("|");
-    let cmd_cnt = cmd_split.filter(|s|!s.trim().is_empty()).count();
+    let cmd_cnt = cmds.len();
     // 管道的数量 = 命令的数量 - 1
-    let pipe_cnt = cmd_cnt - 1;
-    // 没有pipe，直接执行命令
-    if pipe_cnt == 0 {
+    let pipes = if cmd_cnt > 1 { Option::Some(self::batch_create_pipe(cmd_cnt - 1)) } else { Option::None };
+    // 所有命令在同一个进程组中，组长是第一个命令的进程（执行脚本的时候，是脚本的进程组）
+    let mut job_pgid: Option<Pid> = job::new_job_pgid();
+    let mut last_pid: Option<Pid> = Option::None;
+    // 遍历每个管道隔开的命令
+    for (idx, cmd) in cmds.iter().enumerate() {
         let fork_res = sys_call::fork();
-        match fork_res {
-            sys_call::ForkResult::Parent(child_id) => {
-                // 阻塞等待子进程退出
-                let wait_res = sys_call::wait();
-                if wait_res.is_none() {
-                    println!("child process does not exit");
-                }
-                let (chpid, exit_status) = wait_res.unwrap();
-                println!("child process exit");
-                // println!("child process exit. cur pid:{}, child pid:{}, child status:{:?}", sys_call::get_pid().get_data(), chpid.get_data(), exit_status);
-            },
-            sys_call::ForkResult::Child => {
-                let (cmd, param) = shell_util::parse_cmd(input);
-                // 如果最终结果要重定向到某个文件，那么把打印到屏幕的内容写入到文件
-                if file.is_some() {
-                    let file = file.as_mut();
-                    sys_call::set_producer(file.unwrap().get_fd());
-                }
-                cmd_executor::execute_cmd(cwd, cmd, param, buf);
-                // 关闭文件
-                if file.is_some() {
-                    let file = file.as_mut();
-                    file.unwrap().close();
-                }
-                sys_call::exit(0);
-            },
+        // 如果是父进程，继续下一个循环
+        if let sys_call::ForkResult::Parent(child_id) = fork_res {
+            let _ = sys_call::setpgid(Option::Some(child_id), job_pgid);
+            if job_pgid.is_none() {
+                job_pgid = Option::Some(child_id);
+            }
+            last_pid = Option::Some(child_id);
+            continue;
         }
-        return;
-    }
-    // 批量创建管道
-    let pipes = self::batch_create_pipe(pipe_cnt);
-
-    let cmd_split = input.split("|");
-    let cmd_iterator = cmd_split
-        // 取出空白
-        .map(|s|s.trim())
-        // 过滤掉空的命令
-        .filter(|s|!s.is_empty());
-    // 遍历每个管道隔开的命令
-    for (idx, cmd) in cmd_iterator.enumerate() {
-            let cmd = cmd.trim();
-                // 解析单个命令
-            let (cmd, param) = shell_util::parse_cmd(cmd);
-            
-            let fork_res = sys_call::fork();
-            // 如果是父进程，继续下一个循环
-            if let sys_call::ForkResult::Parent(_) = fork_res {
-                continue;
+        // 如果是子进程，那么就执行命令
+        job::enter_job(job_pgid, background);
+        if pipes.is_some() {
+            let pipes = pipes.as_ref().unwrap();
+            // 不是第一个命令，标准输入改为从上一个管道输入
+            if idx > 0 {
+                sys_call::set_consumer(pipes[idx - 1]);
             }
-            // 如果是子进程，那么就执行命令
-
-            // 如果是第一个循环，那么输出的文件描述符重定向
-            if idx == 0 {
-                let cur_pipe = pipes[idx];
-                // 标准输出 重定向到管道的写入
-                sys_call::set_producer(cur_pipe);
-            // 如果是最后一个循环
-            } else if idx == cmd_cnt - 1 {
-                let last_pipe = pipes[idx - 1];
-                // 标准输入，改为从管道输入
-                sys_call::set_consumer(last_pipe);
-                // 如果最终结果要重定向到某个文件，那么把打印到屏幕的内容写入到文件
-                if file.is_some() {
-                    let file = file.as_ref().unwrap();
-                    sys_call::set_producer(file.get_fd());
-                }
-            } else {
-                let cur_fd = pipes[idx];
-                let last_fd = pipes[idx - 1];
-                // 标准输出 重定向到管道的写入
-                sys_call::set_producer(cur_fd);
-                // 标准输入，改为从管道输入
-                sys_call::set_consumer(last_fd);
+            // 不是最后一个命令，标准输出重定向到管道的写入
+            if idx < cmd_cnt - 1 {
+                sys_call::set_producer(pipes[idx]);
             }
+        }
+        let status = self::exec_simple_cmd(shell, line, cmd, buf);
+        // 执行完命令，系统调用退出
+        sys_call::exit(status);
+    }
 
+    // 等待整个作业（或者放到后台），作业结束之后释放所有管道
+    job::launch(job_pgid.unwrap(), last_pid.unwrap(), cmd_cnt, line.get_text(pipeline), pipes, background)
+}
 
-            // 开始执行命令
-            cmd_executor::execute_cmd(cwd, cmd, param, buf);
-            
-            // 关闭文件
-            if idx == cmd_cnt - 1 && file.is_some() {
-                let file = file.as_mut();
-                file.unwrap().close();
+/**
+ * 需要在shell进程自己执行的命令：变量赋值、cd、作业控制。返回退出码；不是这些命令，返回None
+ */
+#[inline(never)]
+fn run_in_shell<const PATH_LEN: usize, const INPUT_LEN: usize>(shell: &mut Shell<PATH_LEN, INPUT_LEN>, line: &CommandLine, cmd: &SimpleCmd, buf: &mut [u8]) -> Option<TaskExitStatus> {
+    // 没有命令，只有变量赋值（以及重定向）。重定向的文件只打开（创建、清空），不会重定向shell自己
+    if line.get_words(cmd).next().is_none() {
+        for (kind, target) in line.get_redirects(cmd) {
+            if kind == RedirectKind::HereString || kind == RedirectKind::ErrorToOutput {
+                continue;
             }
-
-            // 执行完命令，系统调用退出
-            sys_call::exit(0);
-    };
-
-    // 父进程在这里统一循环等待
-    for _ in 0..cmd_cnt {
-        sys_call::wait();
+            let file = self::open_redirect(shell.get_cwd(), kind, target, buf);
+            if file.is_err() {
+                return Option::Some(1);
+            }
+            file.unwrap().close();
+        }
+        for assign in line.get_assigns(cmd) {
+            let (name, value) = assign.split_once('=').unwrap();
+            if !variable::get_vars().set(name, value) {
+                eprintln!("failed to set variable {}", name);
+                return Option::Some(1);
+            }
+        }
+        return Option::Some(0);
     }
-
-    // 释放所有管道
-    for pipe_fd in pipes.iter() {
-        sys_call::release_pipe(*pipe_fd)
+    let (cmd, args) = line.get_cmd(cmd);
+    match cmd {
+        Cmd::Cd => {
+            let param = args.first();
+            let path = cmd_cd::cd(shell.get_cwd(), param, buf);
+            if path.is_none() {
+                eprintln!("cd {} error, not exist", param.unwrap_or(""));
+                return Option::Some(1);
+            }
+            shell.set_cwd(path.unwrap());
+            Option::Some(0)
+        },
+        // 作业控制，需要在shell进程自己执行
+        Cmd::Jobs => {
+            job::jobs();
+            Option::Some(0)
+        },
+        Cmd::Fg => {
+            Option::Some(job::fg(args.first()))
+        },
+        Cmd::Bg => {
+            job::bg(args.first());
+            Option::Some(0)
+        },
+        _ => Option::None,
     }
+}
 
-    // 释放内存
-    sys_call::free(pipes.as_ptr());
+/**
+ * 在fork出来的子进程中，执行一个命令。返回退出码
+ * sh 脚本 参数...，或者第一行是#!的文件，在这个子进程中执行脚本
+ */
+#[inline(never)]
+fn exec_simple_cmd<const PATH_LEN: usize, const INPUT_LEN: usize>(shell: &mut Shell<PATH_LEN, INPUT_LEN>, line: &CommandLine, cmd: &SimpleCmd, buf: &mut [u8]) -> TaskExitStatus {
+    let mut files: [Option<sys_call::File>; parser::MAX_REDIRECT_CNT] = core::array::from_fn(|_| Option::None);
+    if !self::apply_redirects(shell.get_cwd(), line, cmd, &mut files, buf) {
+        return 1;
+    }
+    // 命令前面的变量赋值，作为环境变量传给用户程序
+    let mut envs = [""; MAX_ASSIGN_CNT];
+    let mut env_cnt = 0;
+    for assign in line.get_assigns(cmd).take(MAX_ASSIGN_CNT) {
+        envs[env_cnt] = assign;
+        env_cnt += 1;
+    }
+    let (cmd, args) = line.get_cmd(cmd);
+    let status = match cmd {
+        Cmd::Sh => script::sh(shell, args.as_slice(), buf),
+        Cmd::Custom(name) if script::is_script(shell.get_cwd(), name, buf) => script::run_script(shell, name, args.as_slice(), buf),
+        _ => cmd_executor::execute_cmd(shell.get_cwd(), cmd, &args, &envs[.. env_cnt], buf),
+    };
+    // 关闭文件
+    for file in files.iter_mut().filter(|file| file.is_some()) {
+        file.as_mut().unwrap().close();
+    }
+    status
 }
 
 #[inline(never)]
@@ -148,38 +221,86 @@ fn batch_create_pipe(pipe_cnt: usize) -> &'static mut [FileDescriptor] {
 }
 
 /**
- * 把输入的命令，根据>和>>解析，因为如果使用了>或者>>说明是要把结果重定向到某个文件中
+ * 按照出现的顺序，设置命令的重定向（在fork出来的子进程中）。打开的文件放入files，命令执行完之后关闭
+ * 顺序是有意义的：cmd > f 2>&1，标准错误也写入f；cmd 2>&1 > f，标准错误写到原来的标准输出
  */
 #[inline(never)]
-fn split_redirection<'a>(cwd: &str, input: &'a str, buf: &mut [u8]) -> Result<(&'a str, Option<sys_call::File>), FileError> {
-    // 如果输入不包含重定向符号，那么不处理
-    if !input.contains(">") {
-        return Result::Ok((input, Option::None));
-    }
-    // 如果包含的是>>，那么就是追加写
-    if input.contains(">>") {
-        let (command, file_name) = input.split_once(">>").unwrap();
-        let file_name = file_name.trim();
-        let file_name = shell_util::get_abs_path(cwd, file_name, buf);
-        if file_name.is_err() {
-            println!("failed to parse file_name, error:{:?}", file_name.unwrap_err());
-            return Result::Err(FileError::FilePathIllegal);
+fn apply_redirects(cwd: &str, line: &CommandLine, cmd: &SimpleCmd, files: &mut [Option<sys_call::File>; parser::MAX_REDIRECT_CNT], buf: &mut [u8]) -> bool {
+    for (idx, (kind, target)) in line.get_redirects(cmd).enumerate() {
+        match kind {
+            // 标准错误，改为写到当前的标准输出
+            RedirectKind::ErrorToOutput => {
+                sys_call::set_error_producer(FileDescriptor::new(StdFileDescriptor::StdOutputNo as usize));
+                continue;
+            },
+            RedirectKind::HereString => {
+                if !self::here_string(target) {
+                    return false;
+                }
+                continue;
+            },
+            _ => {},
+        }
+        let file = self::open_redirect(cwd, kind, target, buf);
+        if file.is_err() {
+            return false;
+        }
+        let file = file.unwrap();
+        let fd = file.get_fd();
+        match kind {
+            // 标准输入，改为从文件读取
+            RedirectKind::Input => sys_call::set_consumer(fd),
+            // 标准错误，写入到文件
+            RedirectKind::ErrorTruncate | RedirectKind::ErrorAppend => sys_call::set_error_producer(fd),
+            // 把打印到屏幕的内容写入到文件
+            _ => sys_call::set_producer(fd),
         }
-        let file_name = file_name.unwrap();
-        let file = sys_call::OpenOptions::new().append(true).read(true).open(file_name)?;
-        return Result::Ok((command, Option::Some(file)));
+        files[idx] = Option::Some(file);
     }
+    true
+}
 
+/**
+ * <<<：把内容（加上换行）写入一个新的管道，作为标准输入。写完之后结束管道，命令读取完内容就读到了结尾
+ */
+#[inline(never)]
+fn here_string(text: &str) -> bool {
+    // 管道的大小：内容、换行，以及结束管道占用的一个位置
+    let pipe = sys_call::pipe(text.len() + 2);
+    if pipe.is_err() {
+        eprintln!("failed to create pipe, error:{:?}", pipe.unwrap_err());
+        return false;
+    }
+    let pipe = pipe.unwrap();
+    sys_call::write(pipe, text.as_bytes());
+    sys_call::write(pipe, "\n".as_bytes());
+    sys_call::pipe_write_end(pipe);
+    sys_call::set_consumer(pipe);
+    true
+}
 
-    // 如果包含的是>符号，那么就是从头写
-    let (command, file_name) = input.split_once(">").unwrap();
-    let file_name = file_name.trim();
-    let file_name = shell_util::get_abs_path(cwd, file_name, buf);
-    if file_name.is_err() {
-        println!("failed to parse file_name, error:{:?}", file_name.unwrap_err());
+/**
+ * 打开重定向的文件。<要求文件存在；>、2>清空文件，从头写；>>、2>>追加写
+ */
+#[inline(never)]
+fn open_redirect(cwd: &str, kind: RedirectKind, file_name: &str, buf: &mut [u8]) -> Result<sys_call::File, FileError> {
+    let file_path = shell_util::get_abs_path(cwd, file_name, buf);
+    if file_path.is_err() {
+        eprintln!("failed to parse file_name, error:{:?}", file_path.unwrap_err());
         return Result::Err(FileError::FilePathIllegal);
     }
-    let file_name = file_name.unwrap();
-    let file = sys_call::OpenOptions::new().write(true).open(file_name)?;
-    return Result::Ok((command, Option::Some(file)));
-}
\ No newline at end of file
+    let file_path = file_path.unwrap();
+    let mut options = sys_call::OpenOptions::new();
+    match kind {
+        RedirectKind::Input => options.read(true),
+        RedirectKind::Append | RedirectKind::ErrorAppend => options.append(true).write(true),
+        _ => options.write(true).truncate(true),
+    };
+    let opened = options.open(file_path);
+    if opened.is_err() {
+        let err = opened.err().unwrap();
+        eprintln!("failed to open {}, error:{:?}", file_name, err);
+        return Result::Err(err);
+    }
+    opened
+}
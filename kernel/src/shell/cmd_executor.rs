@@ -1,13 +1,25 @@
-use super::{cmd_custom, cmd_dir, cmd_file};
-use super::{cmd::Cmd, cmd_cd, cmd_ls, cmd_ps};
+use super::shell_util::CmdArgs;
+use super::{cmd_custom, cmd_dir, cmd_file, history, job};
+use super::{cmd::Cmd, cmd_cd, cmd_fsck, cmd_ls, cmd_ps};
 
 use crate::{print, println};
 use crate::sys_call;
+use crate::userprog::TaskExitStatus;
 
+/**
+ * 用户程序执行失败（找不到、不能执行）的退出码
+ */
+pub const CMD_NOT_FOUND: TaskExitStatus = 127;
+
+/**
+ * 执行一个命令，返回退出码。envs是额外传递给用户程序的环境变量
+ */
 #[inline(never)]
-pub fn execute_cmd(cwd: &str, cmd: Cmd, param: Option<&str>, buf: &mut [u8]) {
+pub fn execute_cmd(cwd: &str, cmd: Cmd, args: &CmdArgs, envs: &[&str], buf: &mut [u8]) -> TaskExitStatus {
     // 清空缓冲区
     unsafe { buf.as_mut_ptr().write_bytes(0, buf.len()) };
+    // 内置命令只用到第一个参数
+    let param = args.first();
     match cmd { 
         Cmd::Pwd => {
             print!("{}", cwd);
@@ -45,8 +57,39 @@ pub fn execute_cmd(cwd: &str, cmd: Cmd, param: Option<&str>, buf: &mut [u8]) {
         Cmd::Rm => {
             cmd_file::remove_file(cwd, param, buf);
         },
+        // 移动（重命名）文件或者目录
+        Cmd::Mv => {
+            cmd_file::mv(cwd, args.as_slice(), buf);
+        },
+        // 创建硬链接
+        Cmd::Ln => {
+            cmd_file::ln(cwd, args.as_slice(), buf);
+        },
+        // 把缓存写回硬盘
+        Cmd::Sync => {
+            sys_call::sync();
+        },
+        // 检查文件系统
+        Cmd::Fsck => {
+            cmd_fsck::fsck(args.as_slice());
+        },
+        // 列出作业
+        Cmd::Jobs => {
+            job::jobs();
+        },
+        // fg、bg只能由shell进程自己执行；sh在执行命令的子进程中由cmd_dispatcher执行
+        Cmd::Fg | Cmd::Bg | Cmd::Sh => {
+            println!("{:?} can only run in the shell", cmd);
+        },
+        // 列出历史命令
+        Cmd::History => {
+            history::history();
+        },
         Cmd::Custom(cmd) => {
-            cmd_custom::custom_cmd(cwd, cmd, param, buf);
+            cmd_custom::custom_cmd(cwd, cmd, args.as_slice(), envs, buf);
+            // exec成功不会返回。返回了，说明执行失败
+            return CMD_NOT_FOUND;
         },
     };
+    0
 }
\ No newline at end of file
@@ -1,4 +1,6 @@
-use crate::{filesystem, println, sys_call};
+use os_in_rust_common::{cstr_write, cstring_utils};
+
+use crate::{filesystem::FileError, println, sys_call};
 
 use super::shell_util;
 
@@ -38,4 +40,108 @@ pub fn remove_file(cwd: &str, param: Option<&str>, buff: &mut [u8]) {
     if remove_res.is_err() {
         println!("failed to remove {}, error:{:?}", file_name, remove_res.unwrap_err());
     }
-}
\ No newline at end of file
+}
+
+/**
+ * mv命令。mv 原路径 新路径
+ * 如果新路径是一个已经存在的目录，那么移动到这个目录下面
+ */
+#[inline(never)]
+pub fn mv(cwd: &str, args: &[&str], buff: &mut [u8]) {
+    if args.len() < 2 || args[0].is_empty() || args[1].is_empty() {
+        println!("usage: mv source target");
+        return;
+    }
+    let old_path = shell_util::get_abs_path(cwd, args[0], buff);
+    if old_path.is_err() {
+        println!("failed to move {}, error:{:?}", args[0], old_path.unwrap_err());
+        return;
+    }
+    let old_path = old_path.unwrap();
+
+    let new_path_buf: &mut [u8; 100] = sys_call::malloc(100);
+    let new_path = shell_util::get_abs_path(cwd, args[1], new_path_buf);
+    if new_path.is_err() {
+        println!("failed to move {}, error:{:?}", args[0], new_path.unwrap_err());
+        sys_call::free(new_path_buf.as_ptr());
+        return;
+    }
+    let new_path = new_path.unwrap();
+    let mut res = sys_call::rename(old_path, new_path);
+
+    // 新路径已经存在，如果是目录，就移动到这个目录下面
+    if let Result::Err(FileError::AlreadyExists) = res {
+        let (_, file_name) = old_path.rsplit_once("/").unwrap_or(("", old_path));
+        let new_dir_path = new_path.trim_end_matches("/");
+        // 新目录的路径 + / + 文件名 + \0
+        let into_dir_len = new_dir_path.len() + file_name.len() + 2;
+        let into_dir_buf = unsafe { core::slice::from_raw_parts_mut(sys_call::malloc::<u8>(into_dir_len) as *mut u8, into_dir_len) };
+        cstr_write!(into_dir_buf, "{}/{}", new_dir_path, file_name);
+        let into_dir_path = cstring_utils::read_from_bytes(into_dir_buf).unwrap_or("");
+        if sys_call::rename(old_path, into_dir_path).is_ok() {
+            res = Result::Ok(());
+        }
+        sys_call::free(into_dir_buf.as_ptr());
+    }
+    if res.is_err() {
+        println!("failed to move {} to {}, error:{:?}", args[0], args[1], res.unwrap_err());
+    }
+    sys_call::free(new_path_buf.as_ptr());
+}
+
+/**
+ * ln命令。ln 已存在的文件 链接路径
+ * 创建一个硬链接，两个路径指向同一个文件
+ *  - ln -s 目标路径 链接路径：创建一个符号链接
+ */
+#[inline(never)]
+pub fn ln(cwd: &str, args: &[&str], buff: &mut [u8]) {
+    if args.len() > 0 && args[0] == "-s" {
+        self::symlink(cwd, &args[1..], buff);
+        return;
+    }
+    if args.len() < 2 || args[0].is_empty() || args[1].is_empty() {
+        println!("usage: ln [-s] target link_name");
+        return;
+    }
+    let existing_path = shell_util::get_abs_path(cwd, args[0], buff);
+    if existing_path.is_err() {
+        println!("failed to link {}, error:{:?}", args[0], existing_path.unwrap_err());
+        return;
+    }
+    let existing_path = existing_path.unwrap();
+
+    let new_path_buf: &mut [u8; 100] = sys_call::malloc(100);
+    let new_path = shell_util::get_abs_path(cwd, args[1], new_path_buf);
+    if new_path.is_err() {
+        println!("failed to link {}, error:{:?}", args[1], new_path.unwrap_err());
+        sys_call::free(new_path_buf.as_ptr());
+        return;
+    }
+    let res = sys_call::link(existing_path, new_path.unwrap());
+    if res.is_err() {
+        println!("failed to link {} to {}, error:{:?}", args[1], args[0], res.unwrap_err());
+    }
+    sys_call::free(new_path_buf.as_ptr());
+}
+
+/**
+ * ln -s 目标路径 链接路径
+ * 目标路径原样保存，如果是相对路径，是相对于符号链接所在的目录
+ */
+#[inline(never)]
+fn symlink(cwd: &str, args: &[&str], buff: &mut [u8]) {
+    if args.len() < 2 || args[0].is_empty() || args[1].is_empty() {
+        println!("usage: ln -s target link_name");
+        return;
+    }
+    let link_path = shell_util::get_abs_path(cwd, args[1], buff);
+    if link_path.is_err() {
+        println!("failed to link {}, error:{:?}", args[1], link_path.unwrap_err());
+        return;
+    }
+    let res = sys_call::symlink(args[0], link_path.unwrap());
+    if res.is_err() {
+        println!("failed to link {} to {}, error:{:?}", args[1], args[0], res.unwrap_err());
+    }
+}
@@ -0,0 +1,24 @@
+use crate::println;
+use crate::sys_call;
+
+/**
+ * fsck命令的效果
+ *  - fsck：检查当前挂载的文件系统
+ *  - fsck -r：检查，并且修复能够修复的问题
+ */
+#[inline(never)]
+pub fn fsck(args: &[&str]) {
+    let repair = args.len() > 0 && args[0] == "-r";
+    if args.len() > 0 && !repair {
+        println!("usage: fsck [-r]");
+        return;
+    }
+    let report = sys_call::fsck(repair);
+    for problem in report.problems() {
+        println!("{}", problem);
+    }
+    if report.problem_cnt as usize > report.problems().count() {
+        println!("... {} more problems", report.problem_cnt as usize - report.problems().count());
+    }
+    println!("{}", report);
+}
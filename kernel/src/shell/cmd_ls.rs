@@ -1,4 +1,4 @@
-use os_in_rust_common::{cstr_write, cstring_utils, ASSERT};
+use os_in_rust_common::{cstr_write, cstring_utils, time::DateTime, ASSERT};
 
 use crate::filesystem::{self, inode};
 use crate::shell::shell_util;
@@ -22,6 +22,14 @@ pub fn ls(cwd: &str, param: Option<&str>, buff: &mut [u8]) {
             if entry_name.starts_with(".") {
                 continue;
             }
+            // 符号链接，展示：名称 -> 目标路径
+            if dir_entry.file_type as filesystem::FileType == filesystem::FileType::Symlink {
+                let target_buf: &mut [u8; 100] = sys_call::malloc(100);
+                let target = self::read_link_target(shell_util::get_abs_path(cwd, entry_name, buff).unwrap(), target_buf);
+                print!("{} -> {} ", entry_name, target);
+                sys_call::free(target_buf.as_ptr());
+                continue;
+            }
             print!("{} ", entry_name);
         }
         println!();
@@ -36,7 +44,7 @@ pub fn ls(cwd: &str, param: Option<&str>, buff: &mut [u8]) {
     }
 
     println!("total: {}", dir.get_file_size());
-    println!("file_type inode_no file_size file_name");
+    println!("mode       links inode_no file_size modified            file_name");
     
     // 如果是-l参数
     for dir_entry in dir.iter() {
@@ -47,49 +55,44 @@ pub fn ls(cwd: &str, param: Option<&str>, buff: &mut [u8]) {
         if entry_name.starts_with(".") {
             continue;
         }
-        
-        let file_type = &(dir_entry.file_type as filesystem::FileType);
-        let file_type_sign = self::get_file_type_sign(file_type);
-        let file_inode = dir_entry.i_no;
 
-        let file_size = self::get_file_size(shell_util::get_abs_path(cwd, entry_name, buff).unwrap());
+        let entry_path = shell_util::get_abs_path(cwd, entry_name, buff).unwrap();
+        // 不跟随符号链接，展示符号链接本身的元信息
+        let metadata = sys_call::lstat(entry_path);
+        if metadata.is_err() {
+            println!("failed to stat {}, error:{:?}", entry_name, metadata.unwrap_err());
+            continue;
+        }
+        let metadata = metadata.unwrap();
+        let mut mode_buf = [0u8; 10];
+        let mode = metadata.mode_str(&mut mode_buf);
+        let modified = DateTime::from_timestamp(metadata.modified);
+
+        // 符号链接，展示：名称 -> 目标路径
+        if metadata.file_type == filesystem::FileType::Symlink {
+            let target_buf: &mut [u8; 100] = sys_call::malloc(100);
+            let target = self::read_link_target(entry_path, target_buf);
+            println!("{} {:>5} {:>8} {:>9} {} {} -> {}", mode, metadata.nlink, metadata.i_no.get_data(), metadata.size, modified, entry_name, target);
+            sys_call::free(target_buf.as_ptr());
+            continue;
+        }
 
-        println!("{:^9} {:^8} {:^9} {:^9}", file_type_sign, file_inode.get_data(), file_size, dir_entry.get_name());
+        println!("{} {:>5} {:>8} {:>9} {} {}", mode, metadata.nlink, metadata.i_no.get_data(), metadata.size, modified, entry_name);
     }
     return;
 }
 
 
 /**
- * 得到文件类型的标识：
- *  - 普通文件：使用"-"标识
- *  - 目录文件：使用"d"标识
- * 
+ * 读取符号链接的目标路径
  */
-fn get_file_type_sign(ft: &filesystem::FileType) -> &str {
-    match ft {
-        filesystem::FileType::Regular => "-",
-        filesystem::FileType::Directory => "d",
-        filesystem::FileType::Unknown => "*",
+#[inline(never)]
+fn read_link_target<'a>(link_path: &str, buff: &'a mut [u8]) -> &'a str {
+    let target = sys_call::read_link(link_path, buff);
+    if target.is_err() {
+        println!("{:?}", target.unwrap_err());
+        return "";
     }
+    target.unwrap()
 }
 
-
-/**
- * 得到文件的大小
- */
-#[inline(never)]
-fn get_file_size(file_path: &str) -> usize {
-    let file = sys_call::File::open(file_path);
-    if file.is_err() {
-        println!("{:?}", file.unwrap_err());
-        return 0;
-    }
-    let file = file.unwrap();
-    let file_size = file.get_size();
-    if file_size.is_err() {
-        println!("{:?}", file_size.unwrap_err());
-        return 0;
-    }
-    file_size.unwrap()
-}
\ No newline at end of file
@@ -0,0 +1,232 @@
+use os_in_rust_common::racy_cell::RacyCell;
+
+use crate::{filesystem::{constant, FileType}, sys_call};
+
+use super::{cmd::Cmd, shell_util};
+
+/**
+ * shell的Tab补全
+ *  - 命令名称（一行的第一个单词，或者|、;、&&、||、&后面的第一个单词）：补全内置命令，以及当前目录和根目录下的文件
+ *  - 参数：把已经输入的部分当作路径，补全所在目录中的文件名
+ *  - 只有一个候选，直接补全（目录后面加/，其他加空格）；有多个候选，补全它们的公共前缀；公共前缀也补全不了，列出所有候选
+ */
+
+/**
+ * 最多记录多少个候选
+ */
+const MAX_CANDIDATE_CNT: usize = 32;
+
+#[derive(Clone, Copy)]
+struct Candidate {
+    name: [u8; constant::MAX_FILE_NAME],
+    len: usize,
+    is_dir: bool,
+}
+
+impl Candidate {
+    const fn empty() -> Self {
+        Self {
+            name: [0; constant::MAX_FILE_NAME],
+            len: 0,
+            is_dir: false,
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        unsafe { core::str::from_utf8_unchecked(&self.name[.. self.len]) }
+    }
+}
+
+/**
+ * 补全的候选列表
+ */
+pub struct Candidates {
+    list: [Candidate; MAX_CANDIDATE_CNT],
+    cnt: usize,
+}
+
+impl Candidates {
+    pub const fn new() -> Self {
+        Self {
+            list: [Candidate::empty(); MAX_CANDIDATE_CNT],
+            cnt: 0,
+        }
+    }
+
+    /**
+     * 添加一个候选。重复的、太长的名称不添加；满了之后不再添加
+     */
+    #[inline(never)]
+    pub fn add(&mut self, name: &str, is_dir: bool) {
+        if self.cnt >= MAX_CANDIDATE_CNT || name.len() > constant::MAX_FILE_NAME {
+            return;
+        }
+        if self.iter().any(|(exist_name, _)| exist_name == name) {
+            return;
+        }
+        let candidate = &mut self.list[self.cnt];
+        candidate.name[.. name.len()].copy_from_slice(name.as_bytes());
+        candidate.len = name.len();
+        candidate.is_dir = is_dir;
+        self.cnt += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        self.cnt
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cnt == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.cnt = 0;
+    }
+
+    /**
+     * 所有的候选：（名称，是否是目录）
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.list[.. self.cnt].iter().map(|candidate| (candidate.get_name(), candidate.is_dir))
+    }
+
+    /**
+     * 所有候选的最长公共前缀
+     */
+    #[inline(never)]
+    pub fn common_prefix(&self) -> &str {
+        if self.cnt == 0 {
+            return "";
+        }
+        let first = self.list[0].get_name();
+        let mut len = first.len();
+        for (name, _) in self.iter().skip(1) {
+            len = first.bytes().zip(name.bytes()).take(len).take_while(|(a, b)| a == b).count();
+        }
+        &first[.. len]
+    }
+}
+
+/**
+ * 补全的结果
+ */
+#[derive(Debug, PartialEq)]
+pub enum Completion<'a> {
+    /**
+     * 没有候选
+     */
+    None,
+    /**
+     * 在光标处插入的内容，以及插入之后再追加的字符（/或者空格）
+     */
+    Insert(&'a str, Option<char>),
+    /**
+     * 有多个候选，并且没有可以补全的公共前缀，需要列出所有候选
+     */
+    List,
+}
+
+/**
+ * 光标处正在输入的单词。返回（单词的起始下标，是否是命令名称）
+ */
+#[inline(never)]
+pub fn current_word(line: &str, cursor: usize) -> (usize, bool) {
+    let before = &line[.. cursor];
+    let start = before.rfind(|ch| ch == ' ' || ch == '|' || ch == ';' || ch == '&').map(|idx| idx + 1).unwrap_or(0);
+    let previous = before[.. start].trim_end();
+    (start, previous.is_empty() || previous.ends_with(|ch| ch == '|' || ch == ';' || ch == '&'))
+}
+
+/**
+ * 把路径拆分成（目录部分，文件名部分）。例如：/usr/lo => (/usr/, lo)
+ */
+pub fn split_word(word: &str) -> (&str, &str) {
+    match word.rfind('/') {
+        Option::Some(idx) => (&word[.. idx + 1], &word[idx + 1 ..]),
+        Option::None => ("", word),
+    }
+}
+
+/**
+ * 已经输入了prefix，根据候选列表计算补全的结果
+ */
+#[inline(never)]
+pub fn complete<'a>(prefix: &str, candidates: &'a Candidates) -> Completion<'a> {
+    if candidates.is_empty() {
+        return Completion::None;
+    }
+    if candidates.len() == 1 {
+        let (name, is_dir) = candidates.iter().next().unwrap();
+        return Completion::Insert(&name[prefix.len() ..], Option::Some(if is_dir { '/' } else { ' ' }));
+    }
+    let common_prefix = candidates.common_prefix();
+    if common_prefix.len() > prefix.len() {
+        return Completion::Insert(&common_prefix[prefix.len() ..], Option::None);
+    }
+    Completion::List
+}
+
+static CANDIDATES: RacyCell<Candidates> = RacyCell::new(Candidates::new());
+
+/**
+ * 候选列表。比较大，不放在栈上
+ */
+pub fn get_candidates() -> &'static mut Candidates {
+    unsafe { CANDIDATES.get_mut() }
+}
+
+/**
+ * 找出以prefix开头的命令名称：内置命令，以及当前目录和根目录下的文件
+ */
+#[inline(never)]
+pub fn collect_commands(cwd: &str, prefix: &str, candidates: &mut Candidates) {
+    for name in Cmd::builtin_names() {
+        if name.starts_with(prefix) {
+            candidates.add(name, false);
+        }
+    }
+    self::collect_entries(cwd, prefix, true, candidates);
+    self::collect_entries("/", prefix, true, candidates);
+}
+
+/**
+ * 已经输入的参数是word，找出它所在目录中，以文件名部分开头的目录项。返回文件名部分
+ */
+#[inline(never)]
+pub fn collect_paths<'a>(cwd: &str, word: &'a str, candidates: &mut Candidates) -> &'a str {
+    let (dir, prefix) = self::split_word(word);
+    if dir.is_empty() {
+        self::collect_entries(cwd, prefix, false, candidates);
+        return prefix;
+    }
+    let mut buf = [0u8; constant::MAX_FILE_PATH_LEN];
+    let dir_path = shell_util::get_abs_path(cwd, dir, &mut buf);
+    if dir_path.is_ok() {
+        self::collect_entries(dir_path.unwrap(), prefix, false, candidates);
+    }
+    prefix
+}
+
+/**
+ * 目录dir_path中，以prefix开头的目录项。only_file：只要文件（补全命令的时候）
+ */
+#[inline(never)]
+fn collect_entries(dir_path: &str, prefix: &str, only_file: bool, candidates: &mut Candidates) {
+    let dir = sys_call::read_dir(dir_path);
+    if dir.is_err() {
+        return;
+    }
+    let mut dir = dir.unwrap();
+    for entry in dir.iter() {
+        let name = entry.get_name();
+        // .和..不补全；隐藏文件只有输入了.才补全
+        if entry.is_dot_entry() || !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+            continue;
+        }
+        let is_dir = entry.file_type as FileType == FileType::Directory;
+        if only_file && is_dir {
+            continue;
+        }
+        candidates.add(name, is_dir);
+    }
+}
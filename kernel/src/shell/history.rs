@@ -0,0 +1,189 @@
+use os_in_rust_common::racy_cell::RacyCell;
+
+use crate::println;
+
+/**
+ * shell的命令历史
+ *  - 执行过的命令，放入一个环形缓冲区，满了之后覆盖最旧的命令
+ *  - 上下键在历史命令中浏览。开始浏览之前正在输入的内容会保存下来，浏览到最新的命令之后再按下键，恢复这些内容
+ *  - history命令列出所有的历史命令
+ */
+
+/**
+ * 最多记录多少条历史命令
+ */
+const HISTORY_CNT: usize = 16;
+
+/**
+ * 一条历史命令的最大长度
+ */
+const HISTORY_LEN: usize = 100;
+
+pub struct History<const CNT: usize, const LEN: usize> {
+    /**
+     * 环形缓冲区
+     */
+    lines: [[u8; LEN]; CNT],
+    /**
+     * 每条命令的长度
+     */
+    lens: [usize; CNT],
+    /**
+     * 最旧的一条命令，在缓冲区中的下标
+     */
+    start: usize,
+    /**
+     * 记录的命令数量
+     */
+    cnt: usize,
+    /**
+     * 一共记录过多少条命令（包括被覆盖的）。用于给命令编号
+     */
+    total: usize,
+    /**
+     * 正在浏览第几条命令（0是最旧的）。等于cnt，说明没有在浏览
+     */
+    browsing: usize,
+    /**
+     * 开始浏览之前，正在输入的内容
+     */
+    draft: [u8; LEN],
+    draft_len: usize,
+}
+
+impl <const CNT: usize, const LEN: usize> History<CNT, LEN> {
+    pub const fn new() -> Self {
+        Self {
+            lines: [[0; LEN]; CNT],
+            lens: [0; CNT],
+            start: 0,
+            cnt: 0,
+            total: 0,
+            browsing: 0,
+            draft: [0; LEN],
+            draft_len: 0,
+        }
+    }
+
+    /**
+     * 记录一条命令。空命令、以及和上一条一样的命令不记录
+     */
+    #[inline(never)]
+    pub fn push(&mut self, line: &str) {
+        let line = line.trim();
+        self.browsing = self.cnt;
+        if line.is_empty() || self.get(self.cnt.wrapping_sub(1)) == Option::Some(line) {
+            return;
+        }
+        let idx = if self.cnt < CNT {
+            self.cnt += 1;
+            (self.start + self.cnt - 1) % CNT
+        } else {
+            // 满了，覆盖最旧的命令
+            let idx = self.start;
+            self.start = (self.start + 1) % CNT;
+            idx
+        };
+        let len = Self::copy_line(&mut self.lines[idx], line);
+        self.lens[idx] = len;
+        self.total += 1;
+        self.browsing = self.cnt;
+    }
+
+    /**
+     * 第idx条命令（0是最旧的）
+     */
+    #[inline(never)]
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        if idx >= self.cnt {
+            return Option::None;
+        }
+        let idx = (self.start + idx) % CNT;
+        Option::Some(unsafe { core::str::from_utf8_unchecked(&self.lines[idx][.. self.lens[idx]]) })
+    }
+
+    pub fn len(&self) -> usize {
+        self.cnt
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cnt == 0
+    }
+
+    /**
+     * 第idx条命令的编号（从1开始，被覆盖的命令也占用编号）
+     */
+    pub fn number_of(&self, idx: usize) -> usize {
+        self.total - self.cnt + idx + 1
+    }
+
+    /**
+     * 上键：浏览上一条命令。current是当前正在输入的内容。已经是最旧的一条了，返回None
+     */
+    #[inline(never)]
+    pub fn prev(&mut self, current: &str) -> Option<&str> {
+        if self.browsing == 0 {
+            return Option::None;
+        }
+        // 刚开始浏览，保存正在输入的内容
+        if self.browsing == self.cnt {
+            self.draft_len = Self::copy_line(&mut self.draft, current);
+        }
+        self.browsing -= 1;
+        self.get(self.browsing)
+    }
+
+    /**
+     * 下键：浏览下一条命令。浏览到最新的命令之后，返回开始浏览之前输入的内容。没有在浏览，返回None
+     */
+    #[inline(never)]
+    pub fn next(&mut self) -> Option<&str> {
+        if self.browsing >= self.cnt {
+            return Option::None;
+        }
+        self.browsing += 1;
+        if self.browsing == self.cnt {
+            return Option::Some(unsafe { core::str::from_utf8_unchecked(&self.draft[.. self.draft_len]) });
+        }
+        self.get(self.browsing)
+    }
+
+    /**
+     * 结束浏览（比如开始输入新的一行）
+     */
+    pub fn reset_browsing(&mut self) {
+        self.browsing = self.cnt;
+    }
+
+    /**
+     * 把line复制到buf中，超出的部分丢弃（在字符边界截断）。返回复制的长度
+     */
+    fn copy_line(buf: &mut [u8; LEN], line: &str) -> usize {
+        let mut len = line.len().min(LEN);
+        while !line.is_char_boundary(len) {
+            len -= 1;
+        }
+        buf[.. len].copy_from_slice(&line.as_bytes()[.. len]);
+        len
+    }
+}
+
+static HISTORY: RacyCell<History<HISTORY_CNT, HISTORY_LEN>> = RacyCell::new(History::new());
+
+/**
+ * shell的命令历史
+ */
+pub fn get_history() -> &'static mut History<HISTORY_CNT, HISTORY_LEN> {
+    unsafe { HISTORY.get_mut() }
+}
+
+/**
+ * history命令：列出所有的历史命令
+ */
+#[inline(never)]
+pub fn history() {
+    let history = self::get_history();
+    for idx in 0 .. history.len() {
+        println!("{:>5}  {}", history.number_of(idx), history.get(idx).unwrap());
+    }
+}
@@ -0,0 +1,397 @@
+use os_in_rust_common::{cstr_write, cstring_utils, racy_cell::RacyCell};
+
+use crate::{eprintln, filesystem::FileDescriptor, pid_allocator::Pid, println, signal, sys_call::{self, SigAction, WaitResult, WaitTarget}, userprog::TaskExitStatus};
+
+/**
+ * ************************************************************
+ * *       本文件是shell的作业控制
+ * ************************************************************
+ *
+ * shell执行的每一条命令（包括管道连接的多个命令）是一个作业，作业中的进程在同一个进程组中：
+ *   - 前台作业：shell把它的进程组设为前台进程组，等待它结束或者停止（Ctrl+Z）
+ *   - 后台作业（命令以&结尾）：shell不等待，在每次打印提示符之前，检查它有没有结束
+ *   - jobs列出所有的作业，fg把作业放到前台继续运行，bg让停止的作业在后台继续运行
+ */
+
+/**
+ * 最多同时存在多少个作业
+ */
+const MAX_JOB_CNT: usize = 10;
+
+/**
+ * 作业记录的命令的最大长度
+ */
+const JOB_CMD_LEN: usize = 50;
+
+#[derive(Clone, Copy, PartialEq)]
+enum JobState {
+    Running,
+    Stopped,
+}
+
+impl JobState {
+    fn get_name(&self) -> &str {
+        match self {
+            JobState::Running => "Running",
+            JobState::Stopped => "Stopped",
+        }
+    }
+}
+
+struct Job {
+    /**
+     * 作业的编号，从1开始
+     */
+    id: usize,
+    /**
+     * 作业的进程组
+     */
+    pgid: Pid,
+    state: JobState,
+    /**
+     * 还没有退出的进程数量
+     */
+    alive_cnt: usize,
+    /**
+     * 作业的最后一个进程。作业的退出码就是它的退出码
+     */
+    last_pid: Pid,
+    /**
+     * 作业的退出码
+     */
+    status: TaskExitStatus,
+    /**
+     * 作业的命令
+     */
+    cmd: [u8; JOB_CMD_LEN],
+    /**
+     * 作业用到的管道。作业结束之后释放
+     */
+    pipes: Option<&'static mut [FileDescriptor]>,
+}
+
+impl Job {
+    fn get_cmd(&self) -> &str {
+        cstring_utils::read_from_bytes(&self.cmd).unwrap_or("")
+    }
+}
+
+const EMPTY_JOB: Option<Job> = Option::None;
+
+/**
+ * 所有的作业
+ */
+static JOBS: RacyCell<[Option<Job>; MAX_JOB_CNT]> = RacyCell::new([EMPTY_JOB; MAX_JOB_CNT]);
+
+/**
+ * shell自己的进程组
+ */
+static SHELL_PGID: RacyCell<Option<Pid>> = RacyCell::new(Option::None);
+
+/**
+ * 是否进行作业控制。执行脚本的子进程不进行作业控制，脚本中的命令都在脚本的进程组中
+ */
+static JOB_CONTROL: RacyCell<bool> = RacyCell::new(true);
+
+fn get_jobs() -> &'static mut [Option<Job>; MAX_JOB_CNT] {
+    unsafe { JOBS.get_mut() }
+}
+
+/**
+ * shell启动的时候调用。shell新建一个会话，成为前台进程组；并且不会被键盘的Ctrl+C、Ctrl+Z停止
+ */
+#[inline(never)]
+pub fn init() {
+    let _ = sys_call::setsid();
+    let _ = sys_call::signal(signal::SIGINT, SigAction::Ignore);
+    let _ = sys_call::signal(signal::SIGTSTP, SigAction::Ignore);
+    let _ = sys_call::signal(signal::SIGTTIN, SigAction::Ignore);
+    let shell_pgid = sys_call::getpgid(Option::None).ok();
+    *unsafe { SHELL_PGID.get_mut() } = shell_pgid;
+    sys_call::set_foreground(shell_pgid);
+}
+
+/**
+ * 执行脚本的子进程调用。关闭作业控制：之后的命令加入脚本自己的进程组，等待命令结束之后，前台进程组还是脚本自己
+ */
+#[inline(never)]
+pub fn disable_job_control() {
+    *unsafe { JOB_CONTROL.get_mut() } = false;
+    *unsafe { SHELL_PGID.get_mut() } = sys_call::getpgid(Option::None).ok();
+}
+
+/**
+ * 新作业的进程组。有作业控制，返回None（以第一个进程为组长新建）；没有作业控制，就是shell自己的进程组
+ */
+pub fn new_job_pgid() -> Option<Pid> {
+    if *unsafe { JOB_CONTROL.get_mut() } {
+        return Option::None;
+    }
+    *unsafe { SHELL_PGID.get_mut() }
+}
+
+/**
+ * fork出来的子进程调用。加入作业的进程组（pgid为None，以自己为组长新建），恢复被shell忽略的信号
+ */
+#[inline(never)]
+pub fn enter_job(pgid: Option<Pid>, background: bool) {
+    let _ = sys_call::setpgid(Option::None, pgid);
+    // 前台作业，自己也设置一次前台进程组，避免在shell设置之前读取键盘
+    if !background {
+        sys_call::set_foreground(sys_call::getpgid(Option::None).ok());
+    }
+    let _ = sys_call::signal(signal::SIGINT, SigAction::Default);
+    let _ = sys_call::signal(signal::SIGTSTP, SigAction::Default);
+    let _ = sys_call::signal(signal::SIGTTIN, SigAction::Default);
+}
+
+/**
+ * shell fork出一个作业的所有进程之后调用。返回作业的退出码
+ *   - pgid: 作业的进程组
+ *   - last_pid: 作业的最后一个进程
+ *   - proc_cnt: 作业的进程数量
+ *   - pipes: 作业用到的管道，作业结束之后释放
+ *   - background: 是否是后台作业。前台作业会一直等到结束或者停止；后台作业的退出码是0
+ */
+#[inline(never)]
+pub fn launch(pgid: Pid, last_pid: Pid, proc_cnt: usize, cmd: &str, pipes: Option<&'static mut [FileDescriptor]>, background: bool) -> TaskExitStatus {
+    let jobs = self::get_jobs();
+    let slot = jobs.iter().position(|job| job.is_none());
+    if slot.is_none() {
+        eprintln!("too many jobs");
+        // 没有位置记录，只能当作前台作业等待结束
+        let status = self::wait_untracked(pgid, last_pid, proc_cnt);
+        self::release_pipes(pipes);
+        return status;
+    }
+    let slot = slot.unwrap();
+    let id = jobs.iter().filter_map(|job| job.as_ref()).map(|job| job.id).max().unwrap_or(0) + 1;
+    let mut job = Job {
+        id,
+        pgid,
+        state: JobState::Running,
+        alive_cnt: proc_cnt,
+        last_pid,
+        status: 0,
+        cmd: [0; JOB_CMD_LEN],
+        pipes,
+    };
+    cstr_write!(&mut job.cmd, "{}", self::truncate(cmd, JOB_CMD_LEN - 1));
+    jobs[slot] = Option::Some(job);
+
+    if background {
+        println!("[{}] {}", id, pgid.get_data());
+        return 0;
+    }
+    self::wait_foreground(slot)
+}
+
+/**
+ * 检查所有的作业，回收已经退出的进程，打印已经结束、停止的作业。每次打印提示符之前调用
+ */
+#[inline(never)]
+pub fn reap_jobs() {
+    let jobs = self::get_jobs();
+    for slot in 0 .. jobs.len() {
+        if jobs[slot].is_none() {
+            continue;
+        }
+        let job = jobs[slot].as_mut().unwrap();
+        loop {
+            match sys_call::waitpid(WaitTarget::Group(job.pgid), sys_call::WNOHANG | sys_call::WUNTRACED) {
+                WaitResult::Exited(_, _) => {
+                    job.alive_cnt = job.alive_cnt.saturating_sub(1);
+                },
+                WaitResult::Stopped(_) => {
+                    job.state = JobState::Stopped;
+                    println!("[{}]  Stopped    {}", job.id, job.get_cmd());
+                },
+                WaitResult::Running | WaitResult::Interrupted => break,
+                WaitResult::NoChild => {
+                    job.alive_cnt = 0;
+                    break;
+                },
+            }
+        }
+        if job.alive_cnt == 0 {
+            println!("[{}]  Done       {}", job.id, job.get_cmd());
+            self::finish_job(slot);
+        }
+    }
+}
+
+/**
+ * jobs命令。列出所有的作业
+ */
+#[inline(never)]
+pub fn jobs() {
+    for job in self::get_jobs().iter().filter_map(|job| job.as_ref()) {
+        println!("[{}]  {:<10} {}", job.id, job.state.get_name(), job.get_cmd());
+    }
+}
+
+/**
+ * fg命令。把作业（默认最近的一个）放到前台继续运行，并且等待它。返回作业的退出码
+ */
+#[inline(never)]
+pub fn fg(param: Option<&str>) -> TaskExitStatus {
+    let slot = self::find_job(param);
+    if slot.is_none() {
+        eprintln!("fg: no such job");
+        return 1;
+    }
+    let slot = slot.unwrap();
+    let job = self::get_jobs()[slot].as_mut().unwrap();
+    println!("{}", job.get_cmd());
+    job.state = JobState::Running;
+    sys_call::set_foreground(Option::Some(job.pgid));
+    let _ = sys_call::kill_group(job.pgid, signal::SIGCONT);
+    self::wait_foreground(slot)
+}
+
+/**
+ * bg命令。让停止的作业（默认最近的一个）在后台继续运行
+ */
+#[inline(never)]
+pub fn bg(param: Option<&str>) {
+    let slot = self::find_job(param);
+    if slot.is_none() {
+        eprintln!("bg: no such job");
+        return;
+    }
+    let job = self::get_jobs()[slot.unwrap()].as_mut().unwrap();
+    if job.state == JobState::Running {
+        eprintln!("bg: job {} already in background", job.id);
+        return;
+    }
+    job.state = JobState::Running;
+    println!("[{}] {} &", job.id, job.get_cmd());
+    let _ = sys_call::kill_group(job.pgid, signal::SIGCONT);
+}
+
+/**
+ * 把作业放在前台，等待它结束或者停止。之后shell回到前台。返回作业的退出码，停止的作业是128 + SIGTSTP
+ */
+#[inline(never)]
+fn wait_foreground(slot: usize) -> TaskExitStatus {
+    let job = self::get_jobs()[slot].as_mut().unwrap();
+    sys_call::set_foreground(Option::Some(job.pgid));
+    let mut finished = false;
+    let mut status = 0;
+    loop {
+        match sys_call::waitpid(WaitTarget::Group(job.pgid), sys_call::WUNTRACED) {
+            WaitResult::Exited(pid, child_status) => {
+                if pid == job.last_pid {
+                    job.status = child_status.exit_code();
+                }
+                job.alive_cnt = job.alive_cnt.saturating_sub(1);
+                if job.alive_cnt == 0 {
+                    finished = true;
+                    break;
+                }
+            },
+            WaitResult::Stopped(_) => {
+                job.state = JobState::Stopped;
+                status = signal::exit_status_of(signal::SIGTSTP);
+                println!();
+                println!("[{}]  Stopped    {}", job.id, job.get_cmd());
+                break;
+            },
+            WaitResult::Running | WaitResult::NoChild => {
+                finished = true;
+                break;
+            },
+            // 被信号打断，继续等待
+            WaitResult::Interrupted => {},
+        }
+    }
+    sys_call::set_foreground(*unsafe { SHELL_PGID.get_mut() });
+    if finished {
+        status = job.status;
+        self::finish_job(slot);
+    }
+    status
+}
+
+/**
+ * 没有记录在作业表中的作业，等待所有进程退出。返回最后一个进程的退出码
+ */
+#[inline(never)]
+fn wait_untracked(pgid: Pid, last_pid: Pid, proc_cnt: usize) -> TaskExitStatus {
+    sys_call::set_foreground(Option::Some(pgid));
+    let mut status = 0;
+    let mut exited_cnt = 0;
+    while exited_cnt < proc_cnt {
+        match sys_call::waitpid(WaitTarget::Group(pgid), 0) {
+            WaitResult::Exited(pid, child_status) => {
+                if pid == last_pid {
+                    status = child_status.exit_code();
+                }
+                exited_cnt += 1;
+            },
+            WaitResult::NoChild => break,
+            // 被信号打断，继续等待
+            _ => {},
+        }
+    }
+    sys_call::set_foreground(*unsafe { SHELL_PGID.get_mut() });
+    status
+}
+
+/**
+ * 作业结束了，释放管道，从作业表中移除
+ */
+#[inline(never)]
+fn finish_job(slot: usize) {
+    let job = self::get_jobs()[slot].take();
+    if job.is_some() {
+        self::release_pipes(job.unwrap().pipes);
+    }
+}
+
+#[inline(never)]
+fn release_pipes(pipes: Option<&'static mut [FileDescriptor]>) {
+    if pipes.is_none() {
+        return;
+    }
+    let pipes = pipes.unwrap();
+    for pipe_fd in pipes.iter() {
+        sys_call::release_pipe(*pipe_fd);
+    }
+    sys_call::free(pipes.as_ptr());
+}
+
+/**
+ * 根据参数（%n或者n）找到作业。没有参数，找编号最大的作业
+ */
+#[inline(never)]
+fn find_job(param: Option<&str>) -> Option<usize> {
+    let jobs = self::get_jobs();
+    if param.is_none() {
+        return jobs.iter().enumerate()
+            .filter(|(_, job)| job.is_some())
+            .max_by_key(|(_, job)| job.as_ref().unwrap().id)
+            .map(|(slot, _)| slot);
+    }
+    let param = param.unwrap();
+    let id = param.strip_prefix("%").unwrap_or(param).parse::<usize>();
+    if id.is_err() {
+        return Option::None;
+    }
+    let id = id.unwrap();
+    jobs.iter().position(|job| job.is_some() && job.as_ref().unwrap().id == id)
+}
+
+/**
+ * 截取字符串的前len个字节（不会截断一个字符）
+ */
+fn truncate(s: &str, len: usize) -> &str {
+    if s.len() <= len {
+        return s;
+    }
+    let mut end = len;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
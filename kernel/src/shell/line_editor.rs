@@ -0,0 +1,158 @@
+/**
+ * 行编辑器：shell正在输入的一行命令，以及光标的位置
+ * 只负责修改缓冲区，不负责显示。显示由调用方根据返回值来做
+ * 键盘输入的都是ascii字符，所以一个字节就是一个字符
+ */
+pub struct LineEditor<const N: usize> {
+    /**
+     * 输入的内容
+     */
+    buf: [u8; N],
+    /**
+     * 输入的长度
+     */
+    len: usize,
+    /**
+     * 光标的位置：下一个输入的字符，插入到这个下标。取值范围是[0, len]
+     */
+    cursor: usize,
+}
+
+impl <const N: usize> LineEditor<N> {
+    pub const fn new(buf: [u8; N]) -> Self {
+        Self {
+            buf,
+            len: 0,
+            cursor: 0,
+        }
+    }
+
+    /**
+     * 当前输入的一整行
+     */
+    #[inline(never)]
+    pub fn get_line(&self) -> &str {
+        // 只会插入ascii字符
+        unsafe { core::str::from_utf8_unchecked(&self.buf[.. self.len]) }
+    }
+
+    /**
+     * 光标后面的内容
+     */
+    #[inline(never)]
+    pub fn get_tail(&self) -> &str {
+        unsafe { core::str::from_utf8_unchecked(&self.buf[self.cursor .. self.len]) }
+    }
+
+    pub fn get_cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /**
+     * 在光标处插入一个字符，光标后面的内容后移。缓冲区满了（或者不是ascii字符），返回false
+     */
+    #[inline(never)]
+    pub fn insert(&mut self, byte: u8) -> bool {
+        if self.len >= N || !byte.is_ascii() {
+            return false;
+        }
+        self.buf.copy_within(self.cursor .. self.len, self.cursor + 1);
+        self.buf[self.cursor] = byte;
+        self.len += 1;
+        self.cursor += 1;
+        true
+    }
+
+    /**
+     * 删除光标前面的一个字符（退格键）
+     */
+    #[inline(never)]
+    pub fn backspace(&mut self) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+        self.buf.copy_within(self.cursor .. self.len, self.cursor - 1);
+        self.len -= 1;
+        self.cursor -= 1;
+        true
+    }
+
+    /**
+     * 删除光标处的一个字符（Delete键）
+     */
+    #[inline(never)]
+    pub fn delete(&mut self) -> bool {
+        if self.cursor == self.len {
+            return false;
+        }
+        self.buf.copy_within(self.cursor + 1 .. self.len, self.cursor);
+        self.len -= 1;
+        true
+    }
+
+    /**
+     * 光标左移一个字符
+     */
+    pub fn move_left(&mut self) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+        self.cursor -= 1;
+        true
+    }
+
+    /**
+     * 光标右移一个字符
+     */
+    pub fn move_right(&mut self) -> bool {
+        if self.cursor == self.len {
+            return false;
+        }
+        self.cursor += 1;
+        true
+    }
+
+    /**
+     * 光标移动到行首。返回移动了多少个字符
+     */
+    pub fn move_home(&mut self) -> usize {
+        let moved = self.cursor;
+        self.cursor = 0;
+        moved
+    }
+
+    /**
+     * 光标移动到行尾。返回移动了多少个字符
+     */
+    pub fn move_end(&mut self) -> usize {
+        let moved = self.len - self.cursor;
+        self.cursor = self.len;
+        moved
+    }
+
+    /**
+     * 把整行替换成line（比如从历史记录中取出的命令），光标在行尾。超出缓冲区的部分丢弃
+     */
+    #[inline(never)]
+    pub fn set_line(&mut self, line: &str) {
+        let bytes = line.as_bytes();
+        let len = bytes.len().min(N);
+        self.buf[.. len].copy_from_slice(&bytes[.. len]);
+        self.len = len;
+        self.cursor = len;
+    }
+
+    #[inline(never)]
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.cursor = 0;
+    }
+}
@@ -1,15 +1,23 @@
 mod my_shell;
 mod shell;
+pub mod line_editor;
+pub mod history;
+pub mod completion;
+pub mod parser;
+pub mod variable;
+pub mod script;
 pub mod shell_util;
 mod cmd;
 mod cmd_cd;
 mod cmd_ls;
 mod cmd_ps;
+mod cmd_fsck;
 mod cmd_dir;
 mod cmd_custom;
 mod cmd_executor;
 mod cmd_dispatcher;
 mod cmd_file;
+mod job;
 
 pub use my_shell::shell_start;
 pub use shell::Shell;
\ No newline at end of file
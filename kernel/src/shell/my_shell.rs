@@ -1,9 +1,9 @@
 
-use os_in_rust_common::{racy_cell::RacyCell, MY_PANIC};
+use os_in_rust_common::{racy_cell::RacyCell, vga, MY_PANIC};
 
 use crate::{ascii::AsciiKey, print, println, scancode::{Key, ScanCodeType}, sys_call::{self}};
 
-use super::{cmd::Cmd, cmd_cd, cmd_dispatcher, shell::Shell};
+use super::{cmd_dispatcher, completion::{self, Completion}, history, job, script, shell::Shell};
 
 
 const PATH_LEN: usize = 100;
@@ -43,18 +43,116 @@ fn print_prompt(shell: &mut Shell<PATH_LEN, INPUT_LEN>)
     print!("[imcgr@localhost {}]$ ", shell.get_cwd());
 }
 
+/**
+ * 屏幕上的光标左移cnt个字符（不擦除）
+ */
+fn cursor_left(cnt: usize) {
+    for _ in 0 .. cnt {
+        print!("{}", vga::CURSOR_LEFT as char);
+    }
+}
+
+/**
+ * 把正在输入的一整行，替换成line（比如上下键浏览历史命令）
+ */
+#[inline(never)]
+fn replace_line(shell: &mut Shell<PATH_LEN, INPUT_LEN>, line: &str) {
+    let editor = shell.get_editor();
+    // 先把光标移动到行尾，再逐个擦除
+    print!("{}", editor.get_tail());
+    for _ in 0 .. editor.len() {
+        print!("{}", 0x8 as char);
+    }
+    editor.set_line(line);
+    print!("{}", editor.get_line());
+}
+
+/**
+ * 在光标处插入text，并且把光标后面的内容重新打印
+ */
+#[inline(never)]
+fn insert_text(shell: &mut Shell<PATH_LEN, INPUT_LEN>, text: &str) {
+    let editor = shell.get_editor();
+    let mut inserted = 0;
+    for byte in text.bytes() {
+        if !editor.insert(byte) {
+            break;
+        }
+        inserted += 1;
+    }
+    let tail = editor.get_tail();
+    print!("{}{}", &text[.. inserted], tail);
+    self::cursor_left(tail.len());
+}
+
+/**
+ * Tab补全光标处的单词
+ */
+#[inline(never)]
+fn complete(shell: &mut Shell<PATH_LEN, INPUT_LEN>) {
+    let cursor = shell.get_editor().get_cursor();
+    let candidates = completion::get_candidates();
+    candidates.clear();
+
+    let line = shell.get_input();
+    let (word_start, is_cmd) = completion::current_word(line, cursor);
+    let word = &line[word_start .. cursor];
+    // 命令名称里面有/，也是按照路径补全
+    let prefix_len = if is_cmd && !word.contains('/') {
+        completion::collect_commands(shell.get_cwd(), word, candidates);
+        word.len()
+    } else {
+        completion::collect_paths(shell.get_cwd(), word, candidates).len()
+    };
+
+    match completion::complete(&word[word.len() - prefix_len ..], candidates) {
+        Completion::None => {},
+        Completion::Insert(text, suffix) => {
+            self::insert_text(shell, text);
+            if suffix.is_some() {
+                let mut char_buf = [0u8; 4];
+                self::insert_text(shell, suffix.unwrap().encode_utf8(&mut char_buf));
+            }
+        },
+        // 列出所有候选，然后重新打印提示符和输入的内容
+        Completion::List => {
+            println!();
+            for (name, is_dir) in candidates.iter() {
+                print!("{}{}  ", name, if is_dir { "/" } else { "" });
+            }
+            println!();
+            self::print_prompt(shell);
+            let editor = shell.get_editor();
+            print!("{}", editor.get_line());
+            self::cursor_left(editor.len() - editor.get_cursor());
+        },
+    }
+}
+
 /**
  * 读取line
  */
 #[inline(never)]
 fn read_line(shell: &mut Shell<PATH_LEN, INPUT_LEN>) -> &str {
     shell.clear_input();
+    history::get_history().reset_browsing();
     self::set_capital(false);
     loop {
         let ascii_key = sys_call::read_key();
 
         // 如果是回车键，直接本次命令输入结束
         if ascii_key == AsciiKey::CR {
+            // 光标移动到行尾，后面的输出不会覆盖这一行
+            let editor = shell.get_editor();
+            print!("{}", editor.get_tail());
+            editor.move_end();
+            break;
+        }
+
+        // ctrl + c，放弃当前输入的这一行
+        if ascii_key == AsciiKey::ETX {
+            println!("^C");
+            shell.clear_input();
             break;
         }
 
@@ -67,74 +165,109 @@ fn read_line(shell: &mut Shell<PATH_LEN, INPUT_LEN>) -> &str {
 
         // ctrl + u，清除当前行
         if self::get_last_key() == AsciiKey::DC1 && ascii_key == AsciiKey::u {
-            // 清空缓冲区
-            let cmd = shell.get_input();
-            for _ in  0..cmd.len() {
-                print!("{}", 0x8 as char);
-                shell.pop_last_input();
-            }
+            self::replace_line(shell, "");
             continue;
         }
         // 最后一个key
         self::set_last_key(ascii_key);
 
-        let key_char = ascii_key as u8 as char;
-
-        // 如果是退格键，需要删除缓冲区里一个元素
-        if ascii_key == AsciiKey::BS {
-            shell.pop_last_input();
-            print!("{}", key_char);
-            continue;
+        let editor = shell.get_editor();
+        match ascii_key {
+            // 光标左移
+            AsciiKey::LEFT => {
+                if editor.move_left() {
+                    self::cursor_left(1);
+                }
+            },
+            // 光标右移：把光标处的字符再打印一遍
+            AsciiKey::RIGHT => {
+                let tail = editor.get_tail();
+                if !tail.is_empty() {
+                    print!("{}", &tail[.. 1]);
+                    editor.move_right();
+                }
+            },
+            AsciiKey::HOME => {
+                let moved = editor.move_home();
+                self::cursor_left(moved);
+            },
+            AsciiKey::END => {
+                print!("{}", editor.get_tail());
+                editor.move_end();
+            },
+            // 上一条历史命令
+            AsciiKey::UP => {
+                let line = history::get_history().prev(shell.get_input());
+                if line.is_some() {
+                    self::replace_line(shell, line.unwrap());
+                }
+            },
+            // 下一条历史命令
+            AsciiKey::DOWN => {
+                let line = history::get_history().next();
+                if line.is_some() {
+                    self::replace_line(shell, line.unwrap());
+                }
+            },
+            // Tab补全
+            AsciiKey::TAB => {
+                self::complete(shell);
+            },
+            // 退格键：删除光标前面的字符，后面的内容前移
+            AsciiKey::BS => {
+                if editor.backspace() {
+                    self::cursor_left(1);
+                    let tail = editor.get_tail();
+                    print!("{} ", tail);
+                    self::cursor_left(tail.len() + 1);
+                }
+            },
+            // Delete键：删除光标处的字符
+            AsciiKey::DEL => {
+                if editor.delete() {
+                    let tail = editor.get_tail();
+                    print!("{} ", tail);
+                    self::cursor_left(tail.len() + 1);
+                }
+            },
+            _ => {
+                let key_char = ascii_key as u8 as char;
+                // 控制字符，不接收
+                if key_char.is_ascii_control() {
+                    continue;
+                }
+                // 其他的键，插入到光标处，并且把光标后面的内容重新打印
+                let mut char_buf = [0u8; 4];
+                self::insert_text(shell, key_char.encode_utf8(&mut char_buf));
+            },
         }
-
-        // 控制字符，不接收
-        if key_char.is_ascii_control() {
-            continue;
-        }
-        // 其他的键，放入命令队列中
-        shell.append_input(key_char);
-        // 并且打印出来
-        print!("{}", key_char);
     }
     shell.get_input()
 }
 
-#[inline(never)]
-fn exec_cmd(shell: &mut Shell<PATH_LEN, INPUT_LEN>, buf: &mut [u8]) {
-    let cmd = shell.get_cmd();
-    // 如果是更换目录
-    if cmd.is_some() {
-        let (cmd, param) = cmd.unwrap();
-        if cmd == Cmd::Cd {
-            let path = cmd_cd::cd(shell.get_cwd(), param, buf);
-            if path.is_none() {
-                println!("cd {} error, not exist", param.unwrap());
-                return;
-            }
-            shell.set_cwd(path.unwrap());
-            return;
-        }
-    }
-    cmd_dispatcher::dispatch_cmd(shell.get_cwd(), shell.get_input(), buf);
-
-}
-
-
 #[inline(never)]
 pub fn shell_start() {
     // println!("shell start, shell:{}", "/");
     // 默认shell是根目录
     let shell = unsafe { SHELL.get_mut() };
     shell.set_cwd("/");
+    // shell自己是一个会话，并且在前台
+    job::init();
     let buf: &mut [u8; 100] = sys_call::malloc(100);
+    // 执行启动脚本
+    script::run_profile(shell, buf);
     loop {
+        // 检查后台作业有没有结束
+        job::reap_jobs();
         // 打印提示
         self::print_prompt(shell);
         let input = self::read_line(shell);
         if input.trim().is_empty() {
             continue;
         }
-        self::exec_cmd(shell, buf);
+        // 记录到历史命令中
+        history::get_history().push(input);
+        cmd_dispatcher::dispatch_cmd(shell, buf);
         println!();
     }
 }
\ No newline at end of file
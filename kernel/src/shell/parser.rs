@@ -0,0 +1,757 @@
+use crate::userprog::TaskExitStatus;
+
+use super::{cmd::Cmd, shell_util::CmdArgs, variable::{self, ShellVars}};
+
+/**
+ * ************************************************************
+ * *       本文件是shell命令行的解析
+ * ************************************************************
+ *
+ * 一行命令先拆分成单词和操作符，再组成一个命令列表：
+ *   - 单词：空白分隔。'...'中的内容原样保留；"..."中可以用\转义"、\、$，并且展开变量；引号外面用\转义任意字符
+ *   - 变量：$NAME、${NAME}展开成变量的值，$?展开成上一条命令的退出码，$0..$9是位置参数，$#是参数的个数。展开之后不会再按照空白拆分成多个单词
+ *   - 注释：单词开头的#，一直到行尾都是注释
+ *   - 操作符：|（管道）、;（顺序执行）、&&（成功才执行）、||（失败才执行）、&（后台执行）
+ *   - 重定向：>、>>（标准输出）、<（标准输入）、2>、2>>（标准错误）、2>&1（标准错误写到标准输出）、<<< 单词（把单词作为标准输入）
+ *
+ * 解析的结果：
+ *   命令列表 = 管道 (;|&&||||&) 管道 ...
+ *   管道    = 命令 | 命令 ...
+ *   命令    = [NAME=value ...] 单词 ... [重定向 ...]
+ *
+ * 所有的内容都放在固定大小的数组中，单词用下标引用，不需要分配内存
+ */
+
+/**
+ * 一行命令的最大长度
+ */
+pub const MAX_LINE_LEN: usize = 128;
+
+/**
+ * 所有单词（去掉引号、展开变量之后）的总长度
+ */
+const WORD_BUF_LEN: usize = 256;
+
+/**
+ * 一行最多多少个单词（不包括重定向的文件名）
+ */
+const MAX_WORD_CNT: usize = 32;
+
+/**
+ * 一个命令最多多少个重定向
+ */
+pub const MAX_REDIRECT_CNT: usize = 4;
+
+/**
+ * 一行最多多少个命令
+ */
+const MAX_SIMPLE_CMD_CNT: usize = 8;
+
+/**
+ * 一行最多多少个管道
+ */
+const MAX_PIPELINE_CNT: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParseError {
+    /**
+     * 引号没有闭合
+     */
+    UnterminatedQuote,
+    /**
+     * ${没有对应的}，或者变量名不合法
+     */
+    BadSubstitution,
+    /**
+     * 出现在不该出现的地方的操作符
+     */
+    UnexpectedToken(&'static str),
+    /**
+     * 命令行不完整，比如以|、&&结尾，或者>后面没有文件名
+     */
+    UnexpectedEnd,
+    LineTooLong,
+    TooManyWords,
+    TooManyCommands,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Pipe,
+    And,
+    Or,
+    Semicolon,
+    Background,
+    Redirect(RedirectKind),
+}
+
+impl Operator {
+    fn get_name(&self) -> &'static str {
+        match self {
+            Operator::Pipe => "|",
+            Operator::And => "&&",
+            Operator::Or => "||",
+            Operator::Semicolon => ";",
+            Operator::Background => "&",
+            Operator::Redirect(kind) => kind.get_name(),
+        }
+    }
+}
+
+/**
+ * 一段内容在缓冲区中的范围[start, end)
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Span {
+    start: usize,
+    end: usize,
+}
+
+impl Span {
+    const fn empty() -> Self {
+        Self { start: 0, end: 0 }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Token {
+    /**
+     * 单词在单词缓冲区中的范围；以及是否可以作为变量赋值（=前面是合法的变量名，并且没有引号、转义）
+     */
+    Word(Span, bool),
+    Op(Operator),
+}
+
+/**
+ * 管道和前一个管道的关系
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Connector {
+    /**
+     * 第一个管道，或者前面是;、&。总是执行
+     */
+    Always,
+    /**
+     * 前面是&&。上一个管道成功（退出码是0）才执行
+     */
+    And,
+    /**
+     * 前面是||。上一个管道失败才执行
+     */
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RedirectKind {
+    /**
+     * >：标准输出写入文件，清空文件，从头写
+     */
+    Truncate,
+    /**
+     * >>：标准输出追加写入文件
+     */
+    Append,
+    /**
+     * <：从文件读取标准输入
+     */
+    Input,
+    /**
+     * <<<：把后面的单词（加上换行）作为标准输入
+     */
+    HereString,
+    /**
+     * 2>：标准错误写入文件，清空文件
+     */
+    ErrorTruncate,
+    /**
+     * 2>>：标准错误追加写入文件
+     */
+    ErrorAppend,
+    /**
+     * 2>&1：标准错误写到标准输出（当时的标准输出）。没有文件名
+     */
+    ErrorToOutput,
+}
+
+impl RedirectKind {
+    fn get_name(&self) -> &'static str {
+        match self {
+            RedirectKind::Truncate => ">",
+            RedirectKind::Append => ">>",
+            RedirectKind::Input => "<",
+            RedirectKind::HereString => "<<<",
+            RedirectKind::ErrorTruncate => "2>",
+            RedirectKind::ErrorAppend => "2>>",
+            RedirectKind::ErrorToOutput => "2>&1",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Redirect {
+    kind: RedirectKind,
+    target: Span,
+}
+
+/**
+ * 一个命令。单词在单词列表中是连续的，前assign_cnt个是变量赋值
+ */
+#[derive(Clone, Copy)]
+pub struct SimpleCmd {
+    word_start: usize,
+    word_cnt: usize,
+    assign_cnt: usize,
+    redirects: [Redirect; MAX_REDIRECT_CNT],
+    redirect_cnt: usize,
+}
+
+impl SimpleCmd {
+    const fn empty() -> Self {
+        Self {
+            word_start: 0,
+            word_cnt: 0,
+            assign_cnt: 0,
+            redirects: [Redirect { kind: RedirectKind::Truncate, target: Span::empty() }; MAX_REDIRECT_CNT],
+            redirect_cnt: 0,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.word_cnt == 0 && self.redirect_cnt == 0
+    }
+}
+
+/**
+ * 管道连接的一个或者多个命令
+ */
+#[derive(Clone, Copy)]
+pub struct Pipeline {
+    cmd_start: usize,
+    cmd_cnt: usize,
+    connector: Connector,
+    background: bool,
+    /**
+     * 管道在命令行中的原始内容（用于作业的名称）
+     */
+    text: Span,
+}
+
+impl Pipeline {
+    const fn empty() -> Self {
+        Self {
+            cmd_start: 0,
+            cmd_cnt: 0,
+            connector: Connector::Always,
+            background: false,
+            text: Span::empty(),
+        }
+    }
+
+    pub fn get_connector(&self) -> Connector {
+        self.connector
+    }
+
+    pub fn is_background(&self) -> bool {
+        self.background
+    }
+
+    pub fn len(&self) -> usize {
+        self.cmd_cnt
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cmd_cnt == 0
+    }
+}
+
+/**
+ * 解析之后的一行命令
+ */
+pub struct CommandLine {
+    /**
+     * 原始的命令行
+     */
+    line: [u8; MAX_LINE_LEN],
+    /**
+     * 所有单词的内容
+     */
+    word_buf: [u8; WORD_BUF_LEN],
+    word_buf_len: usize,
+    words: [Span; MAX_WORD_CNT],
+    word_cnt: usize,
+    cmds: [SimpleCmd; MAX_SIMPLE_CMD_CNT],
+    cmd_cnt: usize,
+    pipelines: [Pipeline; MAX_PIPELINE_CNT],
+    pipeline_cnt: usize,
+}
+
+impl CommandLine {
+    pub const fn new() -> Self {
+        Self {
+            line: [0; MAX_LINE_LEN],
+            word_buf: [0; WORD_BUF_LEN],
+            word_buf_len: 0,
+            words: [Span::empty(); MAX_WORD_CNT],
+            word_cnt: 0,
+            cmds: [SimpleCmd::empty(); MAX_SIMPLE_CMD_CNT],
+            cmd_cnt: 0,
+            pipelines: [Pipeline::empty(); MAX_PIPELINE_CNT],
+            pipeline_cnt: 0,
+        }
+    }
+
+    /**
+     * 解析一行命令。变量从vars中取值。空行解析出来没有任何管道
+     */
+    #[inline(never)]
+    pub fn parse(&mut self, input: &str, vars: &ShellVars) -> Result<(), ParseError> {
+        self.word_buf_len = 0;
+        self.word_cnt = 0;
+        self.cmd_cnt = 0;
+        self.pipeline_cnt = 0;
+        if input.len() > MAX_LINE_LEN {
+            return Result::Err(ParseError::LineTooLong);
+        }
+        self.line[.. input.len()].copy_from_slice(input.as_bytes());
+
+        let mut lexer = Lexer { input: input.as_bytes(), pos: 0, vars };
+        // 当前的命令、管道是否已经开始
+        let mut cmd_open = false;
+        let mut pipeline_open = false;
+        // 下一个管道和前面的关系
+        let mut connector = Connector::Always;
+        // 操作符（|、&&、||）后面，必须跟着一个命令
+        let mut need_cmd = false;
+        // >、>>后面，必须跟着文件名
+        let mut pending_redirect: Option<RedirectKind> = Option::None;
+        loop {
+            let token_start = lexer.skip_blank();
+            let token = lexer.next_token(self)?;
+            if token.is_none() {
+                break;
+            }
+            match token.unwrap() {
+                Token::Word(span, assignable) => {
+                    if !pipeline_open {
+                        self.open_pipeline(connector, token_start)?;
+                        pipeline_open = true;
+                    }
+                    if !cmd_open {
+                        self.open_cmd()?;
+                        cmd_open = true;
+                    }
+                    need_cmd = false;
+                    if pending_redirect.is_some() {
+                        self.add_redirect(pending_redirect.unwrap(), span)?;
+                        pending_redirect = Option::None;
+                    } else {
+                        if self.word_cnt >= MAX_WORD_CNT {
+                            return Result::Err(ParseError::TooManyWords);
+                        }
+                        // 命令名称前面的NAME=value是变量赋值
+                        let cmd = &mut self.cmds[self.cmd_cnt - 1];
+                        if assignable && cmd.word_cnt == cmd.assign_cnt {
+                            cmd.assign_cnt += 1;
+                        }
+                        cmd.word_cnt += 1;
+                        self.words[self.word_cnt] = span;
+                        self.word_cnt += 1;
+                    }
+                },
+                Token::Op(op) => {
+                    if pending_redirect.is_some() {
+                        return Result::Err(ParseError::UnexpectedToken(op.get_name()));
+                    }
+                    if let Operator::Redirect(kind) = op {
+                        if !pipeline_open {
+                            self.open_pipeline(connector, token_start)?;
+                            pipeline_open = true;
+                        }
+                        if !cmd_open {
+                            self.open_cmd()?;
+                            cmd_open = true;
+                        }
+                        // 2>&1没有文件名，其他的重定向等待下一个单词
+                        if kind == RedirectKind::ErrorToOutput {
+                            self.add_redirect(kind, Span::empty())?;
+                        } else {
+                            pending_redirect = Option::Some(kind);
+                        }
+                        continue;
+                    }
+                    // 其他的操作符，前面必须有一个命令
+                    if !cmd_open || self.cmds[self.cmd_cnt - 1].is_empty() {
+                        return Result::Err(ParseError::UnexpectedToken(op.get_name()));
+                    }
+                    cmd_open = false;
+                    self.pipelines[self.pipeline_cnt - 1].text.end = token_start;
+                    match op {
+                        Operator::Pipe => {
+                            need_cmd = true;
+                            // 管道还没有结束，text的结尾等到下一个操作符再确定
+                            continue;
+                        },
+                        Operator::And | Operator::Or => {
+                            need_cmd = true;
+                            connector = if op == Operator::And { Connector::And } else { Connector::Or };
+                        },
+                        Operator::Background => {
+                            self.pipelines[self.pipeline_cnt - 1].background = true;
+                            connector = Connector::Always;
+                        },
+                        _ => {
+                            connector = Connector::Always;
+                        },
+                    }
+                    pipeline_open = false;
+                },
+            }
+        }
+        if pending_redirect.is_some() || need_cmd {
+            return Result::Err(ParseError::UnexpectedEnd);
+        }
+        if pipeline_open {
+            self.pipelines[self.pipeline_cnt - 1].text.end = input.trim_end().len();
+        }
+        Result::Ok(())
+    }
+
+    fn open_pipeline(&mut self, connector: Connector, text_start: usize) -> Result<(), ParseError> {
+        if self.pipeline_cnt >= MAX_PIPELINE_CNT {
+            return Result::Err(ParseError::TooManyCommands);
+        }
+        self.pipelines[self.pipeline_cnt] = Pipeline {
+            cmd_start: self.cmd_cnt,
+            cmd_cnt: 0,
+            connector,
+            background: false,
+            text: Span { start: text_start, end: text_start },
+        };
+        self.pipeline_cnt += 1;
+        Result::Ok(())
+    }
+
+    fn add_redirect(&mut self, kind: RedirectKind, target: Span) -> Result<(), ParseError> {
+        let cmd = &mut self.cmds[self.cmd_cnt - 1];
+        if cmd.redirect_cnt >= MAX_REDIRECT_CNT {
+            return Result::Err(ParseError::TooManyWords);
+        }
+        cmd.redirects[cmd.redirect_cnt] = Redirect { kind, target };
+        cmd.redirect_cnt += 1;
+        Result::Ok(())
+    }
+
+    fn open_cmd(&mut self) -> Result<(), ParseError> {
+        if self.cmd_cnt >= MAX_SIMPLE_CMD_CNT {
+            return Result::Err(ParseError::TooManyCommands);
+        }
+        let mut cmd = SimpleCmd::empty();
+        cmd.word_start = self.word_cnt;
+        self.cmds[self.cmd_cnt] = cmd;
+        self.cmd_cnt += 1;
+        self.pipelines[self.pipeline_cnt - 1].cmd_cnt += 1;
+        Result::Ok(())
+    }
+
+    /**
+     * 往单词缓冲区追加一个字节
+     */
+    fn push_byte(&mut self, byte: u8) -> Result<(), ParseError> {
+        if self.word_buf_len >= WORD_BUF_LEN {
+            return Result::Err(ParseError::LineTooLong);
+        }
+        self.word_buf[self.word_buf_len] = byte;
+        self.word_buf_len += 1;
+        Result::Ok(())
+    }
+
+    fn push_str(&mut self, s: &str) -> Result<(), ParseError> {
+        for byte in s.bytes() {
+            self.push_byte(byte)?;
+        }
+        Result::Ok(())
+    }
+
+    fn get_str(&self, span: Span) -> &str {
+        unsafe { core::str::from_utf8_unchecked(&self.word_buf[span.start .. span.end]) }
+    }
+
+    /**
+     * 所有的管道，按照出现的顺序
+     */
+    pub fn pipelines(&self) -> &[Pipeline] {
+        &self.pipelines[.. self.pipeline_cnt]
+    }
+
+    /**
+     * 管道中的所有命令
+     */
+    pub fn get_cmds(&self, pipeline: &Pipeline) -> &[SimpleCmd] {
+        &self.cmds[pipeline.cmd_start .. pipeline.cmd_start + pipeline.cmd_cnt]
+    }
+
+    /**
+     * 管道在命令行中的原始内容
+     */
+    pub fn get_text(&self, pipeline: &Pipeline) -> &str {
+        unsafe { core::str::from_utf8_unchecked(&self.line[pipeline.text.start .. pipeline.text.end]) }.trim_end()
+    }
+
+    /**
+     * 命令的单词（不包括变量赋值）。第一个是命令名称
+     */
+    pub fn get_words<'a>(&'a self, cmd: &SimpleCmd) -> impl Iterator<Item = &'a str> {
+        let start = cmd.word_start + cmd.assign_cnt;
+        self.words[start .. cmd.word_start + cmd.word_cnt].iter().map(|span| self.get_str(*span))
+    }
+
+    /**
+     * 命令前面的变量赋值，格式是NAME=value
+     */
+    pub fn get_assigns<'a>(&'a self, cmd: &SimpleCmd) -> impl Iterator<Item = &'a str> {
+        self.words[cmd.word_start .. cmd.word_start + cmd.assign_cnt].iter().map(|span| self.get_str(*span))
+    }
+
+    /**
+     * 命令的重定向，按照出现的顺序：（类型，文件名）。2>&1的文件名是空的；<<<的是要输入的内容
+     */
+    pub fn get_redirects<'a>(&'a self, cmd: &'a SimpleCmd) -> impl Iterator<Item = (RedirectKind, &'a str)> {
+        cmd.redirects[.. cmd.redirect_cnt].iter().map(|redirect| (redirect.kind, self.get_str(redirect.target)))
+    }
+
+    /**
+     * 命令以及它的参数。参数太多了，多余的忽略
+     */
+    #[inline(never)]
+    pub fn get_cmd<'a>(&'a self, cmd: &SimpleCmd) -> (Cmd<'a>, CmdArgs<'a>) {
+        let mut words = self.get_words(cmd);
+        let cmd = Cmd::get_by_name(words.next().unwrap_or(""));
+        let mut args = CmdArgs::new();
+        for arg in words {
+            if !args.push(arg) {
+                break;
+            }
+        }
+        (cmd, args)
+    }
+}
+
+/**
+ * 把命令行拆分成单词和操作符
+ */
+struct Lexer<'a> {
+    input: &'a [u8],
+    pos: usize,
+    vars: &'a ShellVars,
+}
+
+impl <'a> Lexer<'a> {
+    /**
+     * 跳过空白，返回下一个字符的下标
+     */
+    fn skip_blank(&mut self) -> usize {
+        while self.pos < self.input.len() && self.input[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        self.pos
+    }
+
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.input.get(self.pos + offset).copied()
+    }
+
+    fn starts_with(&self, prefix: &[u8]) -> bool {
+        self.input[self.pos ..].starts_with(prefix)
+    }
+
+    /**
+     * 下一个单词或者操作符。单词的内容写入到out的单词缓冲区。没有了，返回None
+     */
+    #[inline(never)]
+    fn next_token(&mut self, out: &mut CommandLine) -> Result<Option<Token>, ParseError> {
+        loop {
+            self.skip_blank();
+            let byte = self.peek(0);
+            if byte.is_none() {
+                return Result::Ok(Option::None);
+            }
+            let byte = byte.unwrap();
+            // 注释，忽略后面所有的内容
+            if byte == b'#' {
+                self.pos = self.input.len();
+                return Result::Ok(Option::None);
+            }
+            let next = self.peek(1);
+            let op = match byte {
+                b'|' if next == Option::Some(b'|') => Option::Some((Operator::Or, 2)),
+                b'|' => Option::Some((Operator::Pipe, 1)),
+                b'&' if next == Option::Some(b'&') => Option::Some((Operator::And, 2)),
+                b'&' => Option::Some((Operator::Background, 1)),
+                b';' => Option::Some((Operator::Semicolon, 1)),
+                b'>' if next == Option::Some(b'>') => Option::Some((Operator::Redirect(RedirectKind::Append), 2)),
+                b'>' => Option::Some((Operator::Redirect(RedirectKind::Truncate), 1)),
+                b'<' if self.starts_with(b"<<<") => Option::Some((Operator::Redirect(RedirectKind::HereString), 3)),
+                b'<' => Option::Some((Operator::Redirect(RedirectKind::Input), 1)),
+                // 2>只有在单词的开头才是重定向，比如echo a2>f中的2是单词的一部分
+                b'2' if self.starts_with(b"2>&1") => Option::Some((Operator::Redirect(RedirectKind::ErrorToOutput), 4)),
+                b'2' if self.starts_with(b"2>>") => Option::Some((Operator::Redirect(RedirectKind::ErrorAppend), 3)),
+                b'2' if next == Option::Some(b'>') => Option::Some((Operator::Redirect(RedirectKind::ErrorTruncate), 2)),
+                _ => Option::None,
+            };
+            if op.is_some() {
+                let (op, len) = op.unwrap();
+                self.pos += len;
+                return Result::Ok(Option::Some(Token::Op(op)));
+            }
+            let word = self.read_word(out)?;
+            // 没有引号的单词，变量展开之后是空的，忽略
+            if word.is_some() {
+                return Result::Ok(word);
+            }
+        }
+    }
+
+    #[inline(never)]
+    fn read_word(&mut self, out: &mut CommandLine) -> Result<Option<Token>, ParseError> {
+        let start = out.word_buf_len;
+        let raw_start = self.pos;
+        let mut quoted = false;
+        while self.pos < self.input.len() {
+            let byte = self.input[self.pos];
+            if byte.is_ascii_whitespace() || b"|&;<>".contains(&byte) {
+                break;
+            }
+            match byte {
+                b'\'' => {
+                    quoted = true;
+                    let len = self.input[self.pos + 1 ..].iter().position(|&b| b == b'\'');
+                    if len.is_none() {
+                        return Result::Err(ParseError::UnterminatedQuote);
+                    }
+                    let len = len.unwrap();
+                    for idx in self.pos + 1 .. self.pos + 1 + len {
+                        out.push_byte(self.input[idx])?;
+                    }
+                    self.pos += len + 2;
+                },
+                b'"' => {
+                    quoted = true;
+                    self.pos += 1;
+                    self.read_double_quoted(out)?;
+                },
+                b'\\' => {
+                    quoted = true;
+                    // 行尾的\，忽略
+                    if self.pos + 1 < self.input.len() {
+                        out.push_byte(self.input[self.pos + 1])?;
+                    }
+                    self.pos += 2;
+                },
+                b'$' => {
+                    self.expand(out)?;
+                },
+                _ => {
+                    out.push_byte(byte)?;
+                    self.pos += 1;
+                },
+            }
+        }
+        let span = Span { start, end: out.word_buf_len };
+        if !quoted && span.start == span.end {
+            return Result::Ok(Option::None);
+        }
+        // 原始内容=前面是合法的变量名，才是变量赋值（引号、转义、$都不是变量名中的字符）
+        let raw = unsafe { core::str::from_utf8_unchecked(&self.input[raw_start .. self.pos.min(self.input.len())]) };
+        let assignable = raw.split_once('=').map(|(name, _)| variable::is_valid_name(name)).unwrap_or(false);
+        Result::Ok(Option::Some(Token::Word(span, assignable)))
+    }
+
+    /**
+     * 读取双引号中的内容，pos在左引号的后面
+     */
+    fn read_double_quoted(&mut self, out: &mut CommandLine) -> Result<(), ParseError> {
+        loop {
+            let byte = self.peek(0);
+            if byte.is_none() {
+                return Result::Err(ParseError::UnterminatedQuote);
+            }
+            match byte.unwrap() {
+                b'"' => {
+                    self.pos += 1;
+                    return Result::Ok(());
+                },
+                // 双引号中，\只转义"、\、$，其他的\原样保留
+                b'\\' if matches!(self.peek(1), Option::Some(b'"' | b'\\' | b'$')) => {
+                    out.push_byte(self.peek(1).unwrap())?;
+                    self.pos += 2;
+                },
+                b'$' => {
+                    self.expand(out)?;
+                },
+                byte => {
+                    out.push_byte(byte)?;
+                    self.pos += 1;
+                },
+            }
+        }
+    }
+
+    /**
+     * 展开变量，pos在$上。$后面不是变量名，$原样保留
+     */
+    #[inline(never)]
+    fn expand(&mut self, out: &mut CommandLine) -> Result<(), ParseError> {
+        let next = self.peek(1);
+        if next == Option::Some(b'?') {
+            self.pos += 2;
+            return self::push_status(out, self.vars.get_status());
+        }
+        // 参数的个数
+        if next == Option::Some(b'#') {
+            self.pos += 2;
+            return self::push_status(out, self.vars.get_param_cnt() as TaskExitStatus);
+        }
+        // 位置参数只有一位数字，$10是$1后面跟着0
+        if next.is_some() && next.unwrap().is_ascii_digit() {
+            self.pos += 2;
+            return out.push_str(self.vars.get_param((next.unwrap() - b'0') as usize).unwrap_or(""));
+        }
+        let name = if next == Option::Some(b'{') {
+            let len = self.input[self.pos + 2 ..].iter().position(|&b| b == b'}');
+            if len.is_none() {
+                return Result::Err(ParseError::BadSubstitution);
+            }
+            let len = len.unwrap();
+            let name = &self.input[self.pos + 2 .. self.pos + 2 + len];
+            self.pos += len + 3;
+            name
+        } else if next.is_some() && (next.unwrap().is_ascii_alphabetic() || next.unwrap() == b'_') {
+            let len = self.input[self.pos + 1 ..].iter().take_while(|&&b| b.is_ascii_alphanumeric() || b == b'_').count();
+            let name = &self.input[self.pos + 1 .. self.pos + 1 + len];
+            self.pos += len + 1;
+            name
+        } else {
+            self.pos += 1;
+            return out.push_byte(b'$');
+        };
+        let name = unsafe { core::str::from_utf8_unchecked(name) };
+        if !variable::is_valid_name(name) {
+            return Result::Err(ParseError::BadSubstitution);
+        }
+        // 没有设置的变量，展开成空
+        out.push_str(self.vars.get(name).unwrap_or(""))
+    }
+}
+
+/**
+ * 把退出码（或者参数的个数）按照十进制写入单词缓冲区
+ */
+fn push_status(out: &mut CommandLine, status: TaskExitStatus) -> Result<(), ParseError> {
+    if status >= 100 {
+        out.push_byte(b'0' + status / 100)?;
+    }
+    if status >= 10 {
+        out.push_byte(b'0' + status / 10 % 10)?;
+    }
+    out.push_byte(b'0' + status % 10)
+}
@@ -0,0 +1,484 @@
+use os_in_rust_common::racy_cell::RacyCell;
+
+use crate::{eprintln, sys_call, userprog::TaskExitStatus};
+
+use super::{cmd_dispatcher, cmd_executor, job, parser::CommandLine, shell::Shell, shell_util, variable::{self, ShellVars}};
+
+/**
+ * ************************************************************
+ * *       本文件是shell脚本的执行
+ * ************************************************************
+ *
+ * 脚本按行执行，每一行和交互输入的一行一样，交给cmd_dispatcher解析和执行。另外支持：
+ *   - #开头的行是注释（包括第一行的#!）
+ *   - if 命令; then ... [else ...] fi：命令的退出码是0，执行then的部分，否则执行else的部分
+ *   - while 命令; do ... done：命令的退出码是0，就一直循环
+ *   - for 变量 in 单词 ...; do ... done：依次把每个单词赋值给变量，执行循环体
+ *   - then、do也可以单独一行；else、fi、done必须单独一行
+ *   - $0是脚本的路径，$1..$9是脚本的参数，$#是参数的个数
+ *
+ * 执行脚本：
+ *   - sh 脚本 参数...，或者执行第一行是#!的文件：在执行命令的子进程中执行脚本，脚本中的命令和脚本在同一个进程组
+ *   - shell启动的时候，如果存在/etc/profile，在shell进程自己执行（可以设置变量、切换目录）
+ */
+
+/**
+ * 脚本最多多少条语句（不包括空行、注释）
+ */
+pub const MAX_STMT_CNT: usize = 64;
+
+/**
+ * if、while、for最多嵌套多少层
+ */
+const MAX_DEPTH: usize = 8;
+
+/**
+ * 脚本文件的最大大小
+ */
+const MAX_SCRIPT_SIZE: usize = 4096;
+
+/**
+ * shell启动的时候执行的脚本
+ */
+pub const PROFILE_PATH: &str = "/etc/profile";
+
+/**
+ * 脚本语法错误的退出码
+ */
+const SCRIPT_ERROR: TaskExitStatus = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScriptError {
+    /**
+     * 语句太多
+     */
+    TooManyStmts,
+    /**
+     * 第几行（从1开始）：嵌套太深
+     */
+    TooDeep(usize),
+    /**
+     * 第几行：没有对应的if、while、for的else、fi、done
+     */
+    Unexpected(usize),
+    /**
+     * 第几行的if、while、for没有结束
+     */
+    Unterminated(usize),
+    /**
+     * 第几行：if、while没有条件，或者for的格式不对
+     */
+    BadSyntax(usize),
+}
+
+/**
+ * 一条语句
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Stmt<'a> {
+    /**
+     * 普通的一行命令
+     */
+    Cmd(&'a str),
+    /**
+     * if的条件（一行命令）
+     */
+    If(&'a str),
+    Else,
+    Fi,
+    /**
+     * while的条件（一行命令）
+     */
+    While(&'a str),
+    /**
+     * for的变量名，以及in后面的单词（还没有展开）
+     */
+    For(&'a str, &'a str),
+    Done,
+}
+
+/**
+ * 解析好的脚本
+ */
+pub struct Script<'a> {
+    stmts: [Stmt<'a>; MAX_STMT_CNT],
+    /**
+     * 块的跳转：if → 对应的else（没有else就是fi）；else → fi；while、for → done
+     */
+    jumps: [usize; MAX_STMT_CNT],
+    cnt: usize,
+}
+
+/**
+ * 脚本执行的环境
+ */
+pub trait ScriptEnv {
+    /**
+     * 执行一行命令，返回退出码
+     */
+    fn run(&mut self, line: &str) -> TaskExitStatus;
+
+    /**
+     * shell的变量表。用于展开for的单词，以及设置for的变量
+     */
+    fn get_vars(&mut self) -> &mut ShellVars;
+}
+
+impl <'a> Script<'a> {
+    /**
+     * 解析脚本，检查if、while、for是否配对
+     */
+    #[inline(never)]
+    pub fn parse(text: &'a str) -> Result<Self, ScriptError> {
+        let mut script = Self {
+            stmts: [Stmt::Done; MAX_STMT_CNT],
+            jumps: [0; MAX_STMT_CNT],
+            cnt: 0,
+        };
+        // 还没有结束的块：（语句的下标，所在的行号）
+        let mut blocks = [(0usize, 0usize); MAX_DEPTH];
+        let mut depth = 0;
+        for (line_no, line) in text.lines().enumerate() {
+            let line_no = line_no + 1;
+            let line = line.trim();
+            // 空行、注释，以及单独一行的then、do
+            if line.is_empty() || line.starts_with('#') || line == "then" || line == "do" {
+                continue;
+            }
+            let stmt = Self::parse_stmt(line);
+            if stmt.is_none() {
+                return Result::Err(ScriptError::BadSyntax(line_no));
+            }
+            let stmt = stmt.unwrap();
+            if script.cnt >= MAX_STMT_CNT {
+                return Result::Err(ScriptError::TooManyStmts);
+            }
+            let idx = script.cnt;
+            let top = if depth > 0 { Option::Some(script.stmts[blocks[depth - 1].0]) } else { Option::None };
+            match stmt {
+                Stmt::If(_) | Stmt::While(_) | Stmt::For(_, _) => {
+                    if depth >= MAX_DEPTH {
+                        return Result::Err(ScriptError::TooDeep(line_no));
+                    }
+                    blocks[depth] = (idx, line_no);
+                    depth += 1;
+                },
+                // else只能跟在if的后面，之后等待的是fi
+                Stmt::Else => {
+                    if !matches!(top, Option::Some(Stmt::If(_))) {
+                        return Result::Err(ScriptError::Unexpected(line_no));
+                    }
+                    script.jumps[blocks[depth - 1].0] = idx;
+                    blocks[depth - 1].0 = idx;
+                },
+                Stmt::Fi => {
+                    if !matches!(top, Option::Some(Stmt::If(_) | Stmt::Else)) {
+                        return Result::Err(ScriptError::Unexpected(line_no));
+                    }
+                    script.jumps[blocks[depth - 1].0] = idx;
+                    depth -= 1;
+                },
+                Stmt::Done => {
+                    if !matches!(top, Option::Some(Stmt::While(_) | Stmt::For(_, _))) {
+                        return Result::Err(ScriptError::Unexpected(line_no));
+                    }
+                    script.jumps[blocks[depth - 1].0] = idx;
+                    depth -= 1;
+                },
+                Stmt::Cmd(_) => {},
+            }
+            script.stmts[idx] = stmt;
+            script.cnt += 1;
+        }
+        if depth > 0 {
+            return Result::Err(ScriptError::Unterminated(blocks[depth - 1].1));
+        }
+        Result::Ok(script)
+    }
+
+    /**
+     * 解析一行（已经去掉了首尾的空白）。格式不对，返回None
+     */
+    fn parse_stmt(line: &'a str) -> Option<Stmt<'a>> {
+        // 关键字后面是空白或者;，比如if; then中的if
+        let keyword_len = line.find(|ch: char| ch.is_ascii_whitespace() || ch == ';').unwrap_or(line.len());
+        let (keyword, rest) = (&line[.. keyword_len], line[keyword_len ..].trim());
+        match keyword {
+            "if" => {
+                let cond = Self::strip_keyword(rest, "then");
+                if cond.is_empty() { Option::None } else { Option::Some(Stmt::If(cond)) }
+            },
+            "while" => {
+                let cond = Self::strip_keyword(rest, "do");
+                if cond.is_empty() { Option::None } else { Option::Some(Stmt::While(cond)) }
+            },
+            // for 变量 in 单词 ...
+            "for" => {
+                let rest = Self::strip_keyword(rest, "do");
+                let (var, list) = rest.split_once(|ch: char| ch.is_ascii_whitespace())?;
+                let list = list.trim_start();
+                let list = if list == "in" { "" } else { list.strip_prefix("in ")? };
+                if !variable::is_valid_name(var) {
+                    return Option::None;
+                }
+                Option::Some(Stmt::For(var, list.trim()))
+            },
+            "else" if rest.is_empty() => Option::Some(Stmt::Else),
+            "fi" if rest.is_empty() => Option::Some(Stmt::Fi),
+            "done" if rest.is_empty() => Option::Some(Stmt::Done),
+            _ => Option::Some(Stmt::Cmd(line)),
+        }
+    }
+
+    /**
+     * 去掉行尾的; then（或者; do）
+     */
+    fn strip_keyword(rest: &'a str, keyword: &str) -> &'a str {
+        let prefix = rest.strip_suffix(keyword).map(|prefix| prefix.trim_end());
+        match prefix.and_then(|prefix| prefix.strip_suffix(';')) {
+            Option::Some(cond) => cond.trim_end(),
+            Option::None => rest,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cnt
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cnt == 0
+    }
+
+    /**
+     * 执行整个脚本，返回最后一条命令的退出码。words用于展开for的单词
+     */
+    #[inline(never)]
+    pub fn execute(&self, env: &mut impl ScriptEnv, words: &mut CommandLine) -> TaskExitStatus {
+        self.exec_range(env, words, 0, self.cnt)
+    }
+
+    /**
+     * 执行下标在[start, end)之间的语句
+     */
+    #[inline(never)]
+    fn exec_range(&self, env: &mut impl ScriptEnv, words: &mut CommandLine, start: usize, end: usize) -> TaskExitStatus {
+        let mut status = 0;
+        let mut idx = start;
+        while idx < end {
+            match self.stmts[idx] {
+                Stmt::Cmd(line) => {
+                    status = env.run(line);
+                    idx += 1;
+                },
+                Stmt::If(cond) => {
+                    let else_or_fi = self.jumps[idx];
+                    let fi = if self.stmts[else_or_fi] == Stmt::Else { self.jumps[else_or_fi] } else { else_or_fi };
+                    status = 0;
+                    if env.run(cond) == 0 {
+                        status = self.exec_range(env, words, idx + 1, else_or_fi);
+                    } else if else_or_fi != fi {
+                        status = self.exec_range(env, words, else_or_fi + 1, fi);
+                    }
+                    idx = fi + 1;
+                },
+                Stmt::While(cond) => {
+                    let done = self.jumps[idx];
+                    status = 0;
+                    while env.run(cond) == 0 {
+                        status = self.exec_range(env, words, idx + 1, done);
+                    }
+                    idx = done + 1;
+                },
+                Stmt::For(var, list) => {
+                    let done = self.jumps[idx];
+                    status = 0;
+                    let mut nth = 0;
+                    while self::next_for_word(env.get_vars(), words, var, list, nth) {
+                        status = self.exec_range(env, words, idx + 1, done);
+                        nth += 1;
+                    }
+                    idx = done + 1;
+                },
+                // 块的结束，直接跳过
+                Stmt::Else | Stmt::Fi | Stmt::Done => {
+                    idx += 1;
+                },
+            }
+        }
+        status
+    }
+}
+
+/**
+ * 展开for的单词，把第nth个单词赋值给变量var。没有第nth个单词（或者赋值失败），返回false
+ * 每次循环都重新展开，循环体中的命令会覆盖words
+ */
+#[inline(never)]
+fn next_for_word(vars: &mut ShellVars, words: &mut CommandLine, var: &str, list: &str, nth: usize) -> bool {
+    if words.parse(list, vars).is_err() {
+        return false;
+    }
+    let pipeline = words.pipelines().first();
+    if pipeline.is_none() {
+        return false;
+    }
+    let cmd = words.get_cmds(pipeline.unwrap()).first();
+    if cmd.is_none() {
+        return false;
+    }
+    let cmd = cmd.unwrap();
+    // 看起来像NAME=value的单词，解析成了变量赋值，也是for的单词
+    let word = words.get_assigns(cmd).chain(words.get_words(cmd)).nth(nth);
+    if word.is_none() {
+        return false;
+    }
+    vars.set(var, word.unwrap())
+}
+
+/**
+ * 在shell中执行脚本
+ */
+struct ShellEnv<'a, const PATH_LEN: usize, const INPUT_LEN: usize> {
+    shell: &'a mut Shell<PATH_LEN, INPUT_LEN>,
+    buf: &'a mut [u8],
+}
+
+impl <'a, const PATH_LEN: usize, const INPUT_LEN: usize> ScriptEnv for ShellEnv<'a, PATH_LEN, INPUT_LEN> {
+    fn run(&mut self, line: &str) -> TaskExitStatus {
+        cmd_dispatcher::dispatch_line(self.shell, line, self.buf)
+    }
+
+    fn get_vars(&mut self) -> &mut ShellVars {
+        variable::get_vars()
+    }
+}
+
+static FOR_WORDS: RacyCell<CommandLine> = RacyCell::new(CommandLine::new());
+
+/**
+ * 展开for单词用的命令行。比较大，不放在栈上
+ */
+fn get_for_words() -> &'static mut CommandLine {
+    unsafe { FOR_WORDS.get_mut() }
+}
+
+/**
+ * sh命令：sh 脚本 参数...。在执行命令的子进程中调用
+ */
+#[inline(never)]
+pub fn sh<const PATH_LEN: usize, const INPUT_LEN: usize>(shell: &mut Shell<PATH_LEN, INPUT_LEN>, args: &[&str], buf: &mut [u8]) -> TaskExitStatus {
+    if args.is_empty() {
+        eprintln!("usage: sh script [arg ...]");
+        return SCRIPT_ERROR;
+    }
+    self::run_script(shell, args[0], &args[1 ..], buf)
+}
+
+/**
+ * 执行脚本文件。在执行命令的子进程中调用，脚本中的命令不再进行作业控制
+ */
+#[inline(never)]
+pub fn run_script<const PATH_LEN: usize, const INPUT_LEN: usize>(shell: &mut Shell<PATH_LEN, INPUT_LEN>, path: &str, args: &[&str], buf: &mut [u8]) -> TaskExitStatus {
+    job::disable_job_control();
+    self::run_file(shell, path, args, buf)
+}
+
+/**
+ * shell启动的时候，在shell进程中执行/etc/profile。文件不存在，什么都不做
+ */
+#[inline(never)]
+pub fn run_profile<const PATH_LEN: usize, const INPUT_LEN: usize>(shell: &mut Shell<PATH_LEN, INPUT_LEN>, buf: &mut [u8]) {
+    if sys_call::File::open(PROFILE_PATH).is_err() {
+        return;
+    }
+    self::run_file(shell, PROFILE_PATH, &[], buf);
+    // 交互的shell没有位置参数
+    variable::get_vars().set_params("", &[]);
+}
+
+/**
+ * 文件的第一行是不是以#!开头
+ */
+#[inline(never)]
+pub fn is_script(cwd: &str, file_name: &str, buf: &mut [u8]) -> bool {
+    let file_path = shell_util::get_abs_path(cwd, file_name, buf);
+    if file_path.is_err() {
+        return false;
+    }
+    let file = sys_call::File::open(file_path.unwrap());
+    if file.is_err() {
+        return false;
+    }
+    let mut head = [0u8; 2];
+    let file = file.unwrap();
+    file.read(&mut head) == head.len() && &head == b"#!"
+}
+
+/**
+ * 读取并执行脚本文件，返回最后一条命令的退出码
+ */
+#[inline(never)]
+fn run_file<const PATH_LEN: usize, const INPUT_LEN: usize>(shell: &mut Shell<PATH_LEN, INPUT_LEN>, path: &str, args: &[&str], buf: &mut [u8]) -> TaskExitStatus {
+    let text_buf: &mut [u8; MAX_SCRIPT_SIZE] = sys_call::malloc(MAX_SCRIPT_SIZE);
+    let status = self::read_and_execute(shell, path, args, text_buf, buf);
+    sys_call::free(text_buf.as_ptr());
+    status
+}
+
+#[inline(never)]
+fn read_and_execute<const PATH_LEN: usize, const INPUT_LEN: usize>(shell: &mut Shell<PATH_LEN, INPUT_LEN>, path: &str, args: &[&str], text_buf: &mut [u8], buf: &mut [u8]) -> TaskExitStatus {
+    let text = self::read_file(shell.get_cwd(), path, text_buf, buf);
+    if text.is_none() {
+        return cmd_executor::CMD_NOT_FOUND;
+    }
+    let script = Script::parse(text.unwrap());
+    if script.is_err() {
+        eprintln!("sh: {}: {:?}", path, script.err().unwrap());
+        return SCRIPT_ERROR;
+    }
+    // 参数引用的是命令行中的单词，执行脚本中的命令之前先复制下来
+    variable::get_vars().set_params(path, args);
+    let mut env = ShellEnv { shell, buf };
+    script.unwrap().execute(&mut env, self::get_for_words())
+}
+
+/**
+ * 把脚本文件读取到text_buf中。文件不存在、太大、不是文本，返回None
+ */
+#[inline(never)]
+fn read_file<'a>(cwd: &str, path: &str, text_buf: &'a mut [u8], buf: &mut [u8]) -> Option<&'a str> {
+    let file_path = shell_util::get_abs_path(cwd, path, buf);
+    if file_path.is_err() {
+        eprintln!("sh: {}: illegal path", path);
+        return Option::None;
+    }
+    let file = sys_call::File::open(file_path.unwrap());
+    if file.is_err() {
+        eprintln!("sh: {}: {:?}", path, file.err().unwrap());
+        return Option::None;
+    }
+    let file = file.unwrap();
+    let mut len = 0;
+    loop {
+        // 缓冲区满了，还能读到内容，说明文件太大
+        if len == text_buf.len() {
+            let mut extra = [0u8; 1];
+            if file.read(&mut extra) > 0 {
+                eprintln!("sh: {}: script too large, limit {} bytes", path, MAX_SCRIPT_SIZE);
+                return Option::None;
+            }
+            break;
+        }
+        let read_len = file.read(&mut text_buf[len ..]);
+        if read_len == 0 {
+            break;
+        }
+        len += read_len;
+    }
+    let text = core::str::from_utf8(&text_buf[.. len]);
+    if text.is_err() {
+        eprintln!("sh: {}: not a text file", path);
+        return Option::None;
+    }
+    text.ok()
+}
@@ -1,6 +1,6 @@
-use os_in_rust_common::{array_deque::ArrayDeque, cstr_write, cstring_utils, ASSERT, MY_PANIC};
+use os_in_rust_common::{cstr_write, cstring_utils, ASSERT};
 
-use super::{cmd, shell_util};
+use super::line_editor::LineEditor;
 
 /**
  * 构造一个shell对象
@@ -13,14 +13,14 @@ pub struct Shell<const PATH_LEN: usize, const CMD_LEN: usize> {
     /**
      * shell存放命令的命令行
      */
-    input: ArrayDeque<u8, CMD_LEN>,
+    input: LineEditor<CMD_LEN>,
 }
 
 impl <const PATH_LEN: usize, const CMD_LEN: usize> Shell<PATH_LEN, CMD_LEN> {
     pub const fn new(cwd: [u8; PATH_LEN], input: [u8; CMD_LEN]) -> Self {
         Self {
             cwd,
-            input: ArrayDeque::new(input),
+            input: LineEditor::new(input),
         }
     }
     /**
@@ -42,53 +42,22 @@ impl <const PATH_LEN: usize, const CMD_LEN: usize> Shell<PATH_LEN, CMD_LEN> {
     }
 
     /**
-     * 在当前命令里追加一个字符
+     * 获取当前的命令
      */
     #[inline(never)]
-    pub fn append_input(&mut self, char: char) {
-        let mut arr = [0; 5];
-        let bytes = char.encode_utf8(&mut arr).as_bytes();
-        for byte in bytes {
-            self.input.append(*byte);
-        }
+    pub fn get_input(&self) -> &str {
+        self.input.get_line()
     }
 
     /**
-     * 踢出命令的最后一个字符
+     * 命令行的编辑器（光标移动、在中间插入等）
      */
-    
-    pub fn pop_last_input(&mut self) {
-        self.input.pop_last();
-    }
-
-    /**
-     * 获取当前的命令
-     */
-    #[inline(never)]
-    pub fn get_input(&self) -> &str {
-        let cmd = core::str::from_utf8(&self.input.get_array());
-        if cmd.is_err() {
-            MY_PANIC!("[sys error] failed to get cmd. cmd:{}", cmd.err().unwrap());
-        }
-        cmd.unwrap()
+    pub fn get_editor(&mut self) -> &mut LineEditor<CMD_LEN> {
+        &mut self.input
     }
 
     #[inline(never)]
     pub fn clear_input(&mut self) {
         self.input.clear();
     }
-
-    /**
-     * 从shell的输入中，解析出命令以及该命令需要的参数
-     * 例如，输入是 ls -alh -s
-     * 解析出来的是 Option::Some(Cmd::Ls, "-alh -s")
-     */
-    #[inline(never)]
-    pub fn get_cmd(&self) -> Option<(cmd::Cmd, Option<&str>)> {
-        let input = self.get_input().trim();
-        if input.is_empty() {
-            return Option::None;
-        }
-        Option::Some(shell_util::parse_cmd(input))
-    }
 }
\ No newline at end of file
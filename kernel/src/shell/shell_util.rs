@@ -94,18 +94,79 @@ pub fn get_abs_path<'a>(cwd: &str, input_path: &str, buff: &'a mut [u8]) -> Resu
 
 
 /**
- * 根据输入，解析命令和参数
+ * 一个命令最多的参数个数
+ */
+pub const MAX_CMD_ARGS: usize = 10;
+
+/**
+ * 命令的参数列表（不包含命令本身）
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct CmdArgs<'a> {
+    args: [&'a str; MAX_CMD_ARGS],
+    len: usize,
+}
+
+impl <'a> CmdArgs<'a> {
+    pub const fn new() -> Self {
+        Self {
+            args: [""; MAX_CMD_ARGS],
+            len: 0,
+        }
+    }
+
+    /**
+     * 追加一个参数。参数满了，返回false
+     */
+    #[inline(never)]
+    pub fn push(&mut self, arg: &'a str) -> bool {
+        if self.len >= MAX_CMD_ARGS {
+            return false;
+        }
+        self.args[self.len] = arg;
+        self.len += 1;
+        true
+    }
+
+    /**
+     * 第一个参数。内置命令大多只需要一个参数
+     */
+    #[inline(never)]
+    pub fn first(&self) -> Option<&'a str> {
+        if self.len == 0 {
+            return Option::None;
+        }
+        Option::Some(self.args[0])
+    }
+
+    #[inline(never)]
+    pub fn as_slice(&self) -> &[&'a str] {
+        &self.args[..self.len]
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/**
+ * 根据输入，解析命令和参数。参数按照空白分隔
  */
 #[inline(never)]
-pub fn parse_cmd(input: &str) -> (cmd::Cmd, Option<&str>) {
-    let input_split = input.split_once(" ");
-    if input_split.is_none() {
-        let cmd = cmd::Cmd::get_by_name(input);
-        return (cmd, Option::None);
+pub fn parse_cmd(input: &str) -> (cmd::Cmd, CmdArgs) {
+    let mut input_split = input.split_whitespace();
+    let cmd = cmd::Cmd::get_by_name(input_split.next().unwrap_or(""));
+    let mut args = CmdArgs::new();
+    for arg in input_split {
+        // 参数太多了，多余的忽略
+        if !args.push(arg) {
+            break;
+        }
     }
-    let (cmd, argv) = input_split.unwrap();
-    let cmd = cmd::Cmd::get_by_name(cmd.trim());
-    let param = if argv.trim().is_empty() {Option::None} else {Option::Some(argv.trim())};
-    (cmd, param)
+    (cmd, args)
 }
 
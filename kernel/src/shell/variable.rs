@@ -0,0 +1,208 @@
+use os_in_rust_common::racy_cell::RacyCell;
+
+use crate::userprog::TaskExitStatus;
+
+/**
+ * shell的变量表
+ *  - NAME=value 设置变量，$NAME、${NAME} 引用变量
+ *  - $? 是上一条命令的退出码
+ *  - $0..$9 是位置参数（执行脚本时，脚本的路径和参数），$# 是参数的个数（不包括$0）
+ */
+
+/**
+ * 最多记录多少个变量
+ */
+const MAX_VAR_CNT: usize = 16;
+
+/**
+ * 位置参数的数量：$0..$9
+ */
+const MAX_PARAM_CNT: usize = 10;
+
+/**
+ * 变量名的最大长度
+ */
+pub const MAX_NAME_LEN: usize = 16;
+
+/**
+ * 变量值的最大长度
+ */
+pub const MAX_VALUE_LEN: usize = 64;
+
+#[derive(Clone, Copy)]
+struct Variable {
+    name: [u8; MAX_NAME_LEN],
+    name_len: usize,
+    value: [u8; MAX_VALUE_LEN],
+    value_len: usize,
+}
+
+impl Variable {
+    const fn empty() -> Self {
+        Self {
+            name: [0; MAX_NAME_LEN],
+            name_len: 0,
+            value: [0; MAX_VALUE_LEN],
+            value_len: 0,
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        unsafe { core::str::from_utf8_unchecked(&self.name[.. self.name_len]) }
+    }
+
+    fn get_value(&self) -> &str {
+        unsafe { core::str::from_utf8_unchecked(&self.value[.. self.value_len]) }
+    }
+
+    /**
+     * 设置变量的值，超出的部分丢弃（在字符边界截断）
+     */
+    fn set_value(&mut self, value: &str) {
+        let mut len = value.len().min(MAX_VALUE_LEN);
+        while !value.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.value[.. len].copy_from_slice(&value.as_bytes()[.. len]);
+        self.value_len = len;
+    }
+}
+
+pub struct ShellVars {
+    vars: [Variable; MAX_VAR_CNT],
+    cnt: usize,
+    /**
+     * 上一条命令的退出码（$?）
+     */
+    status: TaskExitStatus,
+    /**
+     * 位置参数，只用到了值
+     */
+    params: [Variable; MAX_PARAM_CNT],
+    param_cnt: usize,
+}
+
+impl ShellVars {
+    pub const fn new() -> Self {
+        Self {
+            vars: [Variable::empty(); MAX_VAR_CNT],
+            cnt: 0,
+            status: 0,
+            params: [Variable::empty(); MAX_PARAM_CNT],
+            param_cnt: 0,
+        }
+    }
+
+    /**
+     * 变量的值。没有这个变量，返回None
+     */
+    #[inline(never)]
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.iter().find(|(var_name, _)| *var_name == name).map(|(_, value)| value)
+    }
+
+    /**
+     * 设置变量的值。变量名不合法、名称或者值太长、变量表满了，返回false
+     */
+    #[inline(never)]
+    pub fn set(&mut self, name: &str, value: &str) -> bool {
+        if !self::is_valid_name(name) || name.len() > MAX_NAME_LEN || value.len() > MAX_VALUE_LEN {
+            return false;
+        }
+        let idx = self.vars[.. self.cnt].iter().position(|var| var.get_name() == name);
+        let idx = match idx {
+            Option::Some(idx) => idx,
+            Option::None => {
+                if self.cnt >= MAX_VAR_CNT {
+                    return false;
+                }
+                self.cnt += 1;
+                self.cnt - 1
+            },
+        };
+        let var = &mut self.vars[idx];
+        var.name[.. name.len()].copy_from_slice(name.as_bytes());
+        var.name_len = name.len();
+        var.set_value(value);
+        true
+    }
+
+    /**
+     * 删除变量。最后一个变量移动到被删除的位置
+     */
+    #[inline(never)]
+    pub fn unset(&mut self, name: &str) {
+        let idx = self.vars[.. self.cnt].iter().position(|var| var.get_name() == name);
+        if idx.is_none() {
+            return;
+        }
+        self.vars[idx.unwrap()] = self.vars[self.cnt - 1];
+        self.cnt -= 1;
+    }
+
+    /**
+     * 所有的变量：（名称，值）
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.vars[.. self.cnt].iter().map(|var| (var.get_name(), var.get_value()))
+    }
+
+    pub fn get_status(&self) -> TaskExitStatus {
+        self.status
+    }
+
+    pub fn set_status(&mut self, status: TaskExitStatus) {
+        self.status = status;
+    }
+
+    /**
+     * 设置位置参数：$0是name，$1..$9依次是args。超出的参数丢弃，太长的参数截断
+     */
+    #[inline(never)]
+    pub fn set_params(&mut self, name: &str, args: &[&str]) {
+        self.params[0].set_value(name);
+        self.param_cnt = 1;
+        for arg in args.iter().take(MAX_PARAM_CNT - 1) {
+            self.params[self.param_cnt].set_value(arg);
+            self.param_cnt += 1;
+        }
+    }
+
+    /**
+     * 第idx个位置参数（$0是脚本的路径）。没有这个参数，返回None
+     */
+    pub fn get_param(&self, idx: usize) -> Option<&str> {
+        if idx >= self.param_cnt {
+            return Option::None;
+        }
+        Option::Some(self.params[idx].get_value())
+    }
+
+    /**
+     * 参数的个数（$#），不包括$0
+     */
+    pub fn get_param_cnt(&self) -> usize {
+        self.param_cnt.saturating_sub(1)
+    }
+}
+
+/**
+ * 变量名：字母或者下划线开头，后面是字母、数字、下划线
+ */
+pub fn is_valid_name(name: &str) -> bool {
+    let mut bytes = name.bytes();
+    match bytes.next() {
+        Option::Some(first) if first.is_ascii_alphabetic() || first == b'_' => {},
+        _ => return false,
+    }
+    bytes.all(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
+}
+
+static VARS: RacyCell<ShellVars> = RacyCell::new(ShellVars::new());
+
+/**
+ * shell的变量表
+ */
+pub fn get_vars() -> &'static mut ShellVars {
+    unsafe { VARS.get_mut() }
+}
@@ -0,0 +1,487 @@
+use core::ptr;
+
+use os_in_rust_common::{instruction, racy_cell::RacyCell, ASSERT};
+
+use crate::{memory, pid_allocator::Pid, scheduler, thread::{self, InterruptStack, TaskStatus, TaskStruct}, timer, userprog::{self, TaskExitStatus}};
+
+/**
+ * ************************************************************
+ * *       本文件是进程的信号
+ * ************************************************************
+ *
+ * 每个任务有一个待处理信号的位图（pending_signals），第n位表示第n号信号：
+ *   - 发送信号（kill系统调用、键盘的Ctrl+C、Ctrl+Z），只是把对应的位置为1
+ *   - 睡眠中的任务会被提前唤醒；等待子进程、读取管道和键盘的任务，收到不会被忽略的信号，也会被唤醒
+ *   - 任务从中断返回用户态之前（intr_exit、时钟中断），检查待处理的信号，执行对应的动作
+ *   - SIGCONT比较特殊，发送的时候就让停止的任务继续运行
+ * 信号的动作有三种：默认动作（结束进程、停止进程，或者忽略）、忽略、用户注册的处理函数
+ *
+ * 键盘属于前台进程组：Ctrl+C、Ctrl+Z发送给前台进程组，后台进程组读取键盘会被停止
+ *
+ * 用户的处理函数，不是内核直接调用的，而是通过用户程序提供的跳板函数（trampoline）：
+ *   - 内核在用户栈上依次压入：被打断的eip、处理函数的地址、信号编号，然后让任务回到用户态的跳板函数
+ *   - 跳板函数保存所有的寄存器和eflags，调用处理函数，恢复寄存器和eflags，最后ret回到被打断的地方
+ */
+
+/**
+ * 信号的编号
+ */
+pub type SignalNo = u32;
+
+/**
+ * 信号的数量（编号0不是信号，只用来检查进程是否存在）
+ */
+pub const SIGNAL_CNT: usize = 32;
+
+/**
+ * 终端挂断
+ */
+pub const SIGHUP: SignalNo = 1;
+/**
+ * 键盘中断（Ctrl+C）
+ */
+pub const SIGINT: SignalNo = 2;
+/**
+ * 键盘退出
+ */
+pub const SIGQUIT: SignalNo = 3;
+/**
+ * 强制结束。不能忽略，也不能注册处理函数
+ */
+pub const SIGKILL: SignalNo = 9;
+/**
+ * 用户自定义的信号1
+ */
+pub const SIGUSR1: SignalNo = 10;
+/**
+ * 访问非法的内存地址
+ */
+pub const SIGSEGV: SignalNo = 11;
+/**
+ * 用户自定义的信号2
+ */
+pub const SIGUSR2: SignalNo = 12;
+/**
+ * 写一个没有读者的管道
+ */
+pub const SIGPIPE: SignalNo = 13;
+/**
+ * 定时器到期
+ */
+pub const SIGALRM: SignalNo = 14;
+/**
+ * 请求结束
+ */
+pub const SIGTERM: SignalNo = 15;
+/**
+ * 子进程结束。默认忽略
+ */
+pub const SIGCHLD: SignalNo = 17;
+/**
+ * 让停止的进程继续运行。默认忽略
+ */
+pub const SIGCONT: SignalNo = 18;
+/**
+ * 停止进程。不能忽略，也不能注册处理函数
+ */
+pub const SIGSTOP: SignalNo = 19;
+/**
+ * 键盘停止（Ctrl+Z）
+ */
+pub const SIGTSTP: SignalNo = 20;
+/**
+ * 后台进程读取键盘输入
+ */
+pub const SIGTTIN: SignalNo = 21;
+
+/**
+ * 所有停止信号的位图
+ */
+const STOP_SIGNAL_MASK: u32 = (1 << SIGSTOP) | (1 << SIGTSTP) | (1 << SIGTTIN);
+
+/**
+ * 在任务的信号动作表中，表示默认动作
+ */
+const SIG_DFL: u32 = 0;
+/**
+ * 在任务的信号动作表中，表示忽略
+ */
+const SIG_IGN: u32 = 1;
+
+/**
+ * 收到信号后的动作
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SigAction {
+    /**
+     * 默认动作。大部分信号是结束进程；停止信号是停止进程；SIGCHLD、SIGCONT是忽略
+     */
+    Default,
+    /**
+     * 忽略这个信号
+     */
+    Ignore,
+    /**
+     * 用户注册的处理函数
+     *   - handler: 处理函数的地址，函数签名是 extern "C" fn(SignalNo)
+     *   - trampoline: 跳板函数的地址。由用户程序的运行时（rrt）提供
+     */
+    Handler {
+        handler: u32,
+        trampoline: u32,
+    },
+}
+
+#[derive(Debug)]
+pub enum SignalError {
+    Init,
+    /**
+     * 信号编号不合法
+     */
+    InvalidSignal,
+    /**
+     * 找不到这个进程
+     */
+    NoSuchProcess,
+    /**
+     * 不允许的操作。比如给内核线程发信号、修改SIGKILL和SIGSTOP的动作
+     */
+    PermissionDenied,
+}
+
+/**
+ * 前台进程组。键盘的输入、Ctrl+C和Ctrl+Z，都属于前台进程组
+ */
+static FOREGROUND_PGID: RacyCell<Option<Pid>> = RacyCell::new(Option::None);
+
+/**
+ * 是否是合法的信号编号
+ */
+pub fn is_valid(sig: SignalNo) -> bool {
+    sig > 0 && (sig as usize) < SIGNAL_CNT
+}
+
+/**
+ * 该信号的默认动作，是不是结束进程
+ */
+pub fn terminate_by_default(sig: SignalNo) -> bool {
+    sig != SIGCHLD && sig != SIGCONT && !self::stop_by_default(sig)
+}
+
+/**
+ * 该信号的默认动作，是不是停止进程
+ */
+pub fn stop_by_default(sig: SignalNo) -> bool {
+    STOP_SIGNAL_MASK & (1 << sig) != 0
+}
+
+/**
+ * 被信号结束的进程，退出码是128 + 信号编号
+ */
+pub fn exit_status_of(sig: SignalNo) -> TaskExitStatus {
+    (128 + sig) as TaskExitStatus
+}
+
+/**
+ * 设置前台进程组
+ */
+#[inline(never)]
+pub fn set_foreground(pgid: Option<Pid>) {
+    let old_status = instruction::disable_interrupt();
+    *unsafe { FOREGROUND_PGID.get_mut() } = pgid;
+    instruction::set_interrupt(old_status);
+}
+
+/**
+ * 当前的前台进程组
+ */
+#[inline(never)]
+pub fn get_foreground() -> Option<Pid> {
+    *unsafe { FOREGROUND_PGID.get_mut() }
+}
+
+/**
+ * 任务是不是在前台进程组中。没有设置前台进程组的时候，所有任务都是前台
+ */
+#[inline(never)]
+pub fn is_foreground(task: &TaskStruct) -> bool {
+    let foreground = self::get_foreground();
+    foreground.is_none() || foreground.unwrap() == task.pgid
+}
+
+/**
+ * 任务收到信号sig，是不是需要处理（被忽略的信号不需要）
+ */
+pub fn is_deliverable(task: &TaskStruct, sig: SignalNo) -> bool {
+    match self::get_action(task, sig) {
+        SigAction::Ignore => false,
+        SigAction::Default => self::terminate_by_default(sig) || self::stop_by_default(sig),
+        SigAction::Handler { .. } => true,
+    }
+}
+
+/**
+ * 任务有没有需要处理的待处理信号。有的话，阻塞中的任务应该提前返回
+ */
+#[inline(never)]
+pub fn has_deliverable(task: &TaskStruct) -> bool {
+    (1 .. SIGNAL_CNT as SignalNo).any(|sig| task.pending_signals & (1 << sig) != 0 && self::is_deliverable(task, sig))
+}
+
+/**
+ * 给某个任务发送信号：待处理信号的位置为1。如果任务在睡眠，提前唤醒它，让它尽快处理信号
+ * 等待子进程、或者可以被打断地阻塞（读取管道、键盘）的任务，信号需要处理，也提前唤醒
+ *   - SIGCONT：丢弃还没处理的停止信号，停止的任务继续运行
+ *   - 停止信号：丢弃还没处理的SIGCONT
+ *   - SIGKILL：停止的任务也要继续运行，才能结束
+ */
+#[inline(never)]
+pub fn send_signal(task: &mut TaskStruct, sig: SignalNo) {
+    ASSERT!(self::is_valid(sig));
+    let old_status = instruction::disable_interrupt();
+    task.pending_signals |= 1 << sig;
+    if sig == SIGCONT {
+        task.pending_signals &= !STOP_SIGNAL_MASK;
+    }
+    if self::stop_by_default(sig) {
+        task.pending_signals &= !(1 << SIGCONT);
+    }
+    if (sig == SIGCONT || sig == SIGKILL) && task.task_status == TaskStatus::TaskStopped {
+        thread::wake_thread(task);
+    }
+    timer::cancel_sleep(task);
+    if self::is_deliverable(task, sig) {
+        self::interrupt_blocked(task);
+    }
+    instruction::set_interrupt(old_status);
+}
+
+/**
+ * 唤醒正在等待子进程，或者可以被打断地阻塞的任务
+ */
+#[inline(never)]
+fn interrupt_blocked(task: &mut TaskStruct) {
+    if task.task_status == TaskStatus::TaskWaiting {
+        thread::wake_thread(task);
+        return;
+    }
+    if task.task_status != TaskStatus::TaskBlocked || task.interruptible_list.is_null() {
+        return;
+    }
+    // 已经被唤醒了（还没有运行），不在等待队列中了
+    let wait_list = unsafe { &mut *task.interruptible_list };
+    if !wait_list.contains(&task.general_tag) {
+        return;
+    }
+    wait_list.remove(&task.general_tag);
+    task.interruptible_list = ptr::null_mut();
+    thread::wake_thread(task);
+}
+
+/**
+ * 当前任务收到停止信号，停止运行，直到收到SIGCONT（或者SIGKILL）
+ * 如果父进程正在等待子进程，唤醒父进程
+ */
+#[inline(never)]
+pub fn stop_current() {
+    let cur_task = &mut thread::current_thread().task_struct;
+    let old_status = instruction::disable_interrupt();
+    cur_task.stop_reported = false;
+    let parent_task = cur_task.find_parent();
+    if parent_task.is_some() {
+        let parent_task = parent_task.unwrap();
+        if parent_task.task_status == TaskStatus::TaskWaiting {
+            thread::wake_thread(parent_task);
+        }
+    }
+    scheduler::block_thread(cur_task, TaskStatus::TaskStopped);
+    instruction::set_interrupt(old_status);
+}
+
+/**
+ * kill系统调用。给pid对应的进程发送信号sig
+ *   - sig为0，只检查进程是否存在
+ */
+#[inline(never)]
+pub fn kill(pid: Pid, sig: SignalNo) -> Result<(), SignalError> {
+    if sig != 0 && !self::is_valid(sig) {
+        return Result::Err(SignalError::InvalidSignal);
+    }
+    let task = thread::find_alive_task(pid);
+    if task.is_none() {
+        return Result::Err(SignalError::NoSuchProcess);
+    }
+    let task = task.unwrap();
+    // 内核线程不处理信号
+    if task.pgdir.is_null() {
+        return Result::Err(SignalError::PermissionDenied);
+    }
+    if sig != 0 {
+        self::send_signal(task, sig);
+    }
+    Result::Ok(())
+}
+
+/**
+ * 给进程组pgid中的所有进程发送信号sig
+ */
+#[inline(never)]
+pub fn kill_group(pgid: Pid, sig: SignalNo) -> Result<(), SignalError> {
+    if sig != 0 && !self::is_valid(sig) {
+        return Result::Err(SignalError::InvalidSignal);
+    }
+    let mut found = false;
+    for tag in thread::get_all_thread().iter() {
+        let task = unsafe { &mut *TaskStruct::parse_by_all_tag(&*tag) };
+        if task.pgdir.is_null() || task.task_status == TaskStatus::TaskHanging || task.pgid != pgid {
+            continue;
+        }
+        found = true;
+        if sig != 0 {
+            self::send_signal(task, sig);
+        }
+    }
+    if !found {
+        return Result::Err(SignalError::NoSuchProcess);
+    }
+    Result::Ok(())
+}
+
+/**
+ * 给前台进程组发送信号。键盘中断的时候调用
+ */
+#[inline(never)]
+pub fn kill_foreground(sig: SignalNo) {
+    let foreground = self::get_foreground();
+    if foreground.is_none() {
+        return;
+    }
+    let _ = self::kill_group(foreground.unwrap(), sig);
+}
+
+/**
+ * 修改当前任务收到信号sig之后的动作。返回原来的动作
+ */
+#[inline(never)]
+pub fn set_action(sig: SignalNo, action: SigAction) -> Result<SigAction, SignalError> {
+    if !self::is_valid(sig) {
+        return Result::Err(SignalError::InvalidSignal);
+    }
+    // SIGKILL和SIGSTOP只能执行默认动作
+    if sig == SIGKILL || sig == SIGSTOP {
+        return Result::Err(SignalError::PermissionDenied);
+    }
+    let cur_task = &mut thread::current_thread().task_struct;
+    let old_action = self::get_action(cur_task, sig);
+    cur_task.signal_actions[sig as usize] = match action {
+        SigAction::Default => SIG_DFL,
+        SigAction::Ignore => SIG_IGN,
+        SigAction::Handler { handler, trampoline } => {
+            cur_task.signal_trampoline = trampoline;
+            handler
+        },
+    };
+    Result::Ok(old_action)
+}
+
+/**
+ * 任务收到信号sig之后的动作
+ */
+#[inline(never)]
+pub fn get_action(task: &TaskStruct, sig: SignalNo) -> SigAction {
+    match task.signal_actions[sig as usize] {
+        SIG_DFL => SigAction::Default,
+        SIG_IGN => SigAction::Ignore,
+        handler => SigAction::Handler { handler, trampoline: task.signal_trampoline },
+    }
+}
+
+/**
+ * 所有信号恢复默认动作（exec的时候，旧程序的处理函数已经不存在了）
+ */
+#[inline(never)]
+pub fn reset_actions(task: &mut TaskStruct) {
+    task.signal_actions = [SIG_DFL; SIGNAL_CNT];
+    task.signal_trampoline = 0;
+}
+
+/**
+ * 取出一个编号最小的待处理信号，并且从待处理位图中清除
+ */
+#[inline(never)]
+pub fn take_pending(task: &mut TaskStruct) -> Option<SignalNo> {
+    let old_status = instruction::disable_interrupt();
+    let pending = task.pending_signals;
+    let sig = if pending == 0 {
+        Option::None
+    } else {
+        let sig = pending.trailing_zeros();
+        task.pending_signals &= !(1 << sig);
+        Option::Some(sig)
+    };
+    instruction::set_interrupt(old_status);
+    sig
+}
+
+/**
+ * 中断返回用户态之前，处理当前任务待处理的信号
+ *   - intr_stack: 即将恢复的中断栈。只会读写其中CPU压入的部分（eip、cs、esp等）
+ *
+ * **注意，这个函数是给汇编程序调用的，请不要随意修改函数名称**
+ */
+#[no_mangle]
+#[inline(never)]
+pub extern "C" fn do_signal(intr_stack: &mut InterruptStack) {
+    let cur_task = &mut thread::current_thread().task_struct;
+    // 只有返回用户态的用户进程，才处理信号
+    if cur_task.pgdir.is_null() || !intr_stack.from_user() {
+        return;
+    }
+    loop {
+        let sig = self::take_pending(cur_task);
+        if sig.is_none() {
+            return;
+        }
+        let sig = sig.unwrap();
+        match self::get_action(cur_task, sig) {
+            SigAction::Ignore => continue,
+            SigAction::Default => {
+                // 停止，直到收到SIGCONT。继续运行之后，接着处理剩下的信号
+                if self::stop_by_default(sig) {
+                    self::stop_current();
+                    continue;
+                }
+                if !self::terminate_by_default(sig) {
+                    continue;
+                }
+                // 进程退出之后，不会再被调度，也就不会再回到这里
+                userprog::exit_by_signal(sig);
+                return;
+            },
+            SigAction::Handler { handler, trampoline } => {
+                self::setup_handler_frame(intr_stack, sig, handler, trampoline);
+                // 一次只进入一个处理函数，剩下的信号等下一次返回用户态再处理
+                return;
+            },
+        }
+    }
+}
+
+/**
+ * 在用户栈上构建调用处理函数的栈帧，并且让任务回到用户态之后，从跳板函数开始执行
+ * 用户栈从高到低依次是：被打断的eip、处理函数的地址、信号编号
+ */
+#[inline(never)]
+fn setup_handler_frame(intr_stack: &mut InterruptStack, sig: SignalNo, handler: u32, trampoline: u32) {
+    let user_esp = intr_stack.get_esp().wrapping_sub(3 * 4);
+    // 用户栈的页不存在，会在这里缺页，按需分配。用户栈不能访问，结束进程（返回用户态之前，没有持有锁）
+    if !memory::check_user_range(user_esp as usize, 3 * 4, true) {
+        userprog::exit_by_signal(SIGSEGV);
+        return;
+    }
+    let frame = unsafe { core::slice::from_raw_parts_mut(user_esp as *mut u32, 3) };
+    frame[0] = sig;
+    frame[1] = handler;
+    frame[2] = intr_stack.get_eip();
+    intr_stack.set_user_context(trampoline, user_esp);
+}
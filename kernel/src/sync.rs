@@ -3,7 +3,7 @@ use core::ptr;
 
 use os_in_rust_common::{instruction, linked_list::LinkedList, printkln, ASSERT, MY_PANIC};
 
-use crate::{scheduler::{self}, thread::{self, TaskStruct}};
+use crate::{scheduler::{self}, signal, thread::{self, TaskStruct}};
 
 /**
  * 定义一个信号量
@@ -57,6 +57,32 @@ impl Semaphore {
         instruction::set_interrupt(old_status);
     }
 
+    /**
+     * 信号量减少操作，阻塞的时候可以被信号打断。**阻塞操作**
+     * 返回false：收到了需要处理的信号，没有拿到信号量
+     */
+    #[inline(never)]
+    pub fn down_interruptible(&mut self) -> bool {
+        let old_status = instruction::disable_interrupt();
+        while self.value <= 0 {
+            let current_thread = &mut thread::current_thread().task_struct;
+            if signal::has_deliverable(current_thread) {
+                instruction::set_interrupt(old_status);
+                return false;
+            }
+            ASSERT!(!self.waiters.contains(&current_thread.general_tag));
+            // 把自己放入该信号量的等待队列。发送信号的时候，从这个队列中移除，并且唤醒
+            self.waiters.append(&mut current_thread.general_tag);
+            current_thread.interruptible_list = &mut self.waiters;
+            scheduler::block_thread(current_thread, thread::TaskStatus::TaskBlocked);
+            current_thread.interruptible_list = ptr::null_mut();
+        }
+        // 把信号量减一
+        self.value -= 1;
+        instruction::set_interrupt(old_status);
+        true
+    }
+
     /**
      * 是信号量增加。会唤醒等待的线程（并不会立马执行唤醒的线程，而是只是加到就绪队列）
      */
@@ -9,6 +9,7 @@ pub struct OpenOptions {
     write: bool, 
     append: bool,
     read: bool,
+    truncate: bool,
 }
 
 impl OpenOptions {
@@ -18,6 +19,7 @@ impl OpenOptions {
             write: false,
             append: false,
             read: false,
+            truncate: false,
         }
     }
 
@@ -38,9 +40,19 @@ impl OpenOptions {
         self
     }
 
+    /**
+     * 打开的时候，把文件清空
+     */
+    #[inline(never)]
+    pub fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.truncate = truncate;
+        self
+    }
+
     #[inline(never)]
     pub fn open(&self, path: &str) -> Result<File, filesystem::FileError> {
-        let req = OpenFileDto::new(path, self.append);
+        let mut req = OpenFileDto::new(path, self.append);
+        req.truncate = self.truncate;
         Result::Ok(File::new(sys_call_proxy::open_file(&req)?))
     }
 
@@ -105,6 +117,22 @@ impl File {
         sys_call_proxy::write(self.file.get_file_descriptor(), buff)
     }
 
+    /**
+     * 把文件的大小设置为len
+     */
+    #[inline(never)]
+    pub fn set_len(&mut self, len: u32) -> Result<(), filesystem::FileError> {
+        sys_call_proxy::ftruncate(self.file.get_file_descriptor(), len)
+    }
+
+    /**
+     * 得到当前文件的元信息
+     */
+    #[inline(never)]
+    pub fn metadata(&self) -> Result<filesystem::Metadata, filesystem::FileError> {
+        sys_call_proxy::fstat(self.file.get_file_descriptor())
+    }
+
     pub fn get_path(&self) -> &str {
         self.file.get_path()
     }
@@ -0,0 +1,32 @@
+use crate::{common::mmap_dto::MmapParam, userprog::MmapError};
+
+use super::{sys_call_proxy, File};
+
+/**
+ * 映射一段匿名内存。返回映射的起始地址
+ *  - len: 映射的长度（单位字节），向上取整到页
+ *  - prot: 访问权限。PROT_READ、PROT_WRITE、PROT_EXEC的组合
+ */
+#[inline(never)]
+pub fn mmap(len: usize, prot: u8) -> Result<usize, MmapError> {
+    let param = MmapParam::new(len, prot, Option::None, 0);
+    sys_call_proxy::mmap(&param)
+}
+
+/**
+ * 把文件从offset开始的len个字节，只读地映射到内存中。返回映射的起始地址
+ *  - offset: 需要按页对齐
+ */
+#[inline(never)]
+pub fn mmap_file(file: &File, len: usize, offset: u32) -> Result<usize, MmapError> {
+    let param = MmapParam::new(len, crate::memory::PROT_READ, Option::Some(file.get_fd()), offset);
+    sys_call_proxy::mmap(&param)
+}
+
+/**
+ * 取消[addr, addr + len)范围的映射
+ */
+#[inline(never)]
+pub fn munmap(addr: usize, len: usize) -> Result<(), MmapError> {
+    sys_call_proxy::munmap(addr, len)
+}
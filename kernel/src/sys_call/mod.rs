@@ -3,6 +3,7 @@ mod sys_call_api;
 mod sys_call_proxy;
 mod dir_api;
 mod file_api;
+mod mmap_api;
 mod writer;
 
 
@@ -20,6 +21,7 @@ pub use sys_call_proxy::ForkResult;
 pub use sys_call_proxy::thread_yield;
 pub use sys_call_proxy::clear_screen;
 pub use writer::sys_print;
+pub use writer::sys_eprint;
 pub use sys_call_proxy::exec;
 pub use sys_call_proxy::exit;
 pub use sys_call_proxy::wait;
@@ -30,8 +32,30 @@ pub use sys_call_proxy::pipe;
 pub use sys_call_proxy::release_pipe;
 pub use sys_call_proxy::set_consumer;
 pub use sys_call_proxy::set_producer;
+pub use sys_call_proxy::set_error_producer;
+pub use sys_call_proxy::pipe_write_end;
+pub use sys_call_proxy::brk;
+pub use sys_call_proxy::nice;
+pub use sys_call_proxy::sleep;
+pub use sys_call_proxy::uptime;
+pub use sys_call_proxy::kill;
+pub use sys_call_proxy::signal;
+pub use sys_call_proxy::set_foreground;
+pub use sys_call_proxy::kill_group;
+pub use sys_call_proxy::setpgid;
+pub use sys_call_proxy::getpgid;
+pub use sys_call_proxy::setsid;
+pub use sys_call_proxy::waitpid;
+pub use crate::userprog::WaitResult;
+pub use crate::userprog::WaitTarget;
+pub use crate::userprog::ChildStatus;
+pub use crate::userprog::WNOHANG;
+pub use crate::userprog::WUNTRACED;
+pub use crate::userprog::PgrpError;
 pub use crate::println;
 pub use crate::print;
+pub use crate::eprintln;
+pub use crate::eprint;
 
 
 pub use dir_api::create_dir;
@@ -43,3 +67,27 @@ pub use dir_api::remove_dir;
 pub use file_api::File;
 pub use file_api::OpenOptions;
 pub use file_api::remove_file;
+pub use sys_call_proxy::ftruncate;
+pub use sys_call_proxy::rename;
+pub use sys_call_proxy::link;
+pub use sys_call_proxy::symlink;
+pub use sys_call_proxy::read_link;
+pub use sys_call_proxy::stat;
+pub use sys_call_proxy::lstat;
+pub use sys_call_proxy::fstat;
+pub use sys_call_proxy::sync;
+pub use sys_call_proxy::fsck;
+
+
+pub use mmap_api::mmap;
+pub use mmap_api::mmap_file;
+pub use mmap_api::munmap;
+pub use crate::userprog::MmapError;
+pub use crate::memory::PROT_READ;
+pub use crate::memory::PROT_WRITE;
+pub use crate::memory::PROT_EXEC;
+
+
+pub use crate::signal::SigAction;
+pub use crate::signal::SignalError;
+pub use crate::signal::SignalNo;
@@ -184,6 +184,123 @@ pub enum SystemCallNo {
      * 设置管道的消费者
      */
     SetConsumer,
+
+    /**
+     * 映射一段虚拟内存（匿名或者文件）
+     */
+    Mmap,
+
+    /**
+     * 取消映射一段虚拟内存
+     */
+    Munmap,
+
+    /**
+     * 调整堆的结束地址（program break）
+     */
+    Brk,
+
+    /**
+     * 调整当前任务的优先级
+     */
+    Nice,
+
+    /**
+     * 当前任务睡眠一段时间（毫秒）
+     */
+    Sleep,
+
+    /**
+     * 系统启动以来经过的时间（毫秒）
+     */
+    Uptime,
+
+    /**
+     * 给某个进程发送信号
+     */
+    Kill,
+
+    /**
+     * 设置收到某个信号之后的动作
+     */
+    Signal,
+
+    /**
+     * 设置前台进程组（接收键盘的输入、Ctrl+C、Ctrl+Z）
+     */
+    SetForeground,
+
+    /**
+     * 设置进程所在的进程组
+     */
+    SetPgid,
+
+    /**
+     * 获取进程所在的进程组
+     */
+    GetPgid,
+
+    /**
+     * 新建一个会话
+     */
+    SetSid,
+
+    /**
+     * 等待指定的子进程（某个pid、任意一个、某个进程组）结束或者停止
+     */
+    WaitPid,
+
+    /**
+     * 截断文件（设置文件的大小）
+     */
+    Ftruncate,
+
+    /**
+     * 移动（重命名）文件或者目录
+     */
+    Rename,
+    /**
+     * 创建硬链接
+     */
+    Link,
+    /**
+     * 创建符号链接
+     */
+    Symlink,
+    /**
+     * 读取符号链接的目标路径
+     */
+    Readlink,
+    /**
+     * 根据路径得到文件的元信息（跟随符号链接）
+     */
+    Stat,
+    /**
+     * 根据路径得到文件的元信息（不跟随符号链接）
+     */
+    Lstat,
+    /**
+     * 根据文件描述符得到文件的元信息
+     */
+    Fstat,
+    /**
+     * 把缓存中修改过的扇区，写回硬盘
+     */
+    Sync,
+    /**
+     * 检查（以及修复）当前挂载的文件系统
+     */
+    Fsck,
+
+    /**
+     * 设置标准错误的输出（管道或者文件）
+     */
+    SetErrorProducer,
+
+    /**
+     * 管道写入结束。消费者读取完剩下的数据之后，不再阻塞
+     */
+    PipeWriteEnd,
 }
 
 /**
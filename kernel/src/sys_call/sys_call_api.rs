@@ -1,8 +1,8 @@
-use core::{fmt, mem::{size_of, take}, str, task};
+use core::{fmt, mem::{size_of, size_of_val, take}, str, task};
 
 use os_in_rust_common::{printkln, vga::{self}, ASSERT, MY_PANIC};
 
-use crate::{ascii::AsciiKey, blocking_queue::BlockingQueue, common::{cwd_dto::CwdDto, exec_dto::ExecParam, open_file_dto::OpenFileDto}, console, console_print, exec, filesystem::{self, DirError, FileDescriptor, FileDescriptorType, StdFileDescriptor}, fork, keyboard, memory, pid_allocator::Pid, pipe::{self, PipeError, PipeReader, PipeWriter}, scancode::KeyCode, thread, thread_management, userprog::{self, TaskExitStatus}};
+use crate::{ascii::AsciiKey, common::{cwd_dto::CwdDto, exec_dto::ExecParam, mmap_dto::MmapParam, open_file_dto::OpenFileDto, path_pair_dto::PathPairDto, read_link_dto::ReadLinkDto}, console, console_print, device, exec, filesystem::{self, DirError, FileDescriptor, FileDescriptorType, StdFileDescriptor}, fork, keyboard, memory, pid_allocator::Pid, pipe::{self, PipeError, PipeReader, PipeWriter}, scancode::KeyCode, scheduler, signal::{self, SigAction, SignalError}, thread, thread_management, timer, userprog::{self, TaskExitStatus}};
 use super::sys_call::{self, HandlerType, SystemCallNo};
 
 /**
@@ -100,6 +100,158 @@ pub fn init() {
     
     // 设置消费者
     sys_call::register_handler(SystemCallNo::SetConsumer, HandlerType::OneParam(set_consumer));
+
+    // 映射虚拟内存
+    sys_call::register_handler(SystemCallNo::Mmap, HandlerType::TwoParams(mmap));
+
+    // 取消映射虚拟内存
+    sys_call::register_handler(SystemCallNo::Munmap, HandlerType::ThreeParams(munmap));
+
+    // 调整堆的大小
+    sys_call::register_handler(SystemCallNo::Brk, HandlerType::OneParam(brk));
+
+    // 调整优先级
+    sys_call::register_handler(SystemCallNo::Nice, HandlerType::OneParam(nice));
+
+    // 睡眠
+    sys_call::register_handler(SystemCallNo::Sleep, HandlerType::OneParam(sleep));
+
+    // 系统运行的时间
+    sys_call::register_handler(SystemCallNo::Uptime, HandlerType::OneParam(uptime));
+
+    // 发送信号
+    sys_call::register_handler(SystemCallNo::Kill, HandlerType::ThreeParams(kill));
+
+    // 设置信号的动作
+    sys_call::register_handler(SystemCallNo::Signal, HandlerType::ThreeParams(signal));
+
+    // 设置前台进程组
+    sys_call::register_handler(SystemCallNo::SetForeground, HandlerType::OneParam(set_foreground));
+
+    // 设置进程组
+    sys_call::register_handler(SystemCallNo::SetPgid, HandlerType::ThreeParams(setpgid));
+
+    // 获取进程组
+    sys_call::register_handler(SystemCallNo::GetPgid, HandlerType::TwoParams(getpgid));
+
+    // 新建会话
+    sys_call::register_handler(SystemCallNo::SetSid, HandlerType::OneParam(setsid));
+
+    // 等待子进程
+    sys_call::register_handler(SystemCallNo::WaitPid, HandlerType::ThreeParams(wait_pid));
+
+    // 截断文件
+    sys_call::register_handler(SystemCallNo::Ftruncate, HandlerType::ThreeParams(ftruncate));
+
+    // 移动文件
+    sys_call::register_handler(SystemCallNo::Rename, HandlerType::TwoParams(rename));
+
+    // 创建硬链接
+    sys_call::register_handler(SystemCallNo::Link, HandlerType::TwoParams(link));
+
+    // 创建符号链接
+    sys_call::register_handler(SystemCallNo::Symlink, HandlerType::TwoParams(symlink));
+
+    // 读取符号链接
+    sys_call::register_handler(SystemCallNo::Readlink, HandlerType::TwoParams(read_link));
+
+    // 文件的元信息
+    sys_call::register_handler(SystemCallNo::Stat, HandlerType::ThreeParams(stat));
+    sys_call::register_handler(SystemCallNo::Lstat, HandlerType::ThreeParams(lstat));
+    sys_call::register_handler(SystemCallNo::Fstat, HandlerType::TwoParams(fstat));
+
+    // 把缓存写回硬盘
+    sys_call::register_handler(SystemCallNo::Sync, HandlerType::NoneParam(sync));
+
+    // 检查文件系统
+    sys_call::register_handler(SystemCallNo::Fsck, HandlerType::TwoParams(fsck));
+
+    // 设置标准错误的输出
+    sys_call::register_handler(SystemCallNo::SetErrorProducer, HandlerType::OneParam(set_error_producer));
+
+    // 管道写入结束
+    sys_call::register_handler(SystemCallNo::PipeWriteEnd, HandlerType::OneParam(pipe_write_end));
+}
+
+/**
+ * 用户进程传入的地址不能访问，系统调用直接失败
+ */
+macro_rules! try_user {
+    ($res:expr) => {
+        match $res {
+            Option::Some(val) => val,
+            Option::None => return self::bad_address(),
+        }
+    };
+}
+
+/**
+ * 用户进程传入了不能访问的地址：给当前进程发送SIGSEGV，系统调用直接返回
+ * 不能等到内核态缺页的时候再结束进程，那时候可能持有文件系统、缓存或者内存池的锁。返回用户态的时候处理信号，不持有任何锁
+ */
+#[inline(never)]
+fn bad_address() -> u32 {
+    signal::send_signal(&mut thread::current_thread().task_struct, signal::SIGSEGV);
+    0
+}
+
+/**
+ * 用户进程传入的一个T类型的数据（只读）
+ */
+fn user_ref<T>(addr: u32) -> Option<&'static T> {
+    if !memory::check_user_range(addr as usize, size_of::<T>(), false) {
+        return Option::None;
+    }
+    Option::Some(unsafe { &*(addr as *const T) })
+}
+
+/**
+ * 用户进程传入的一个T类型的数据（内核要写入）
+ */
+fn user_mut<T>(addr: u32) -> Option<&'static mut T> {
+    if !memory::check_user_range(addr as usize, size_of::<T>(), true) {
+        return Option::None;
+    }
+    Option::Some(unsafe { &mut *(addr as *mut T) })
+}
+
+/**
+ * 用户进程传入的缓冲区
+ */
+fn user_slice(addr: u32, len: u32, write: bool) -> Option<&'static mut [u8]> {
+    if !memory::check_user_range(addr as usize, len as usize, write) {
+        return Option::None;
+    }
+    Option::Some(unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, len as usize) })
+}
+
+/**
+ * 用户进程传入的字符串。不是合法的UTF-8，也当作不能访问
+ */
+fn user_str(addr: u32, len: u32) -> Option<&'static str> {
+    let bytes = self::user_slice(addr, len, false)?;
+    str::from_utf8(bytes).ok()
+}
+
+/**
+ * 请求参数中的缓冲区，内核可以读取
+ */
+fn readable(buff: &[u8]) -> bool {
+    memory::check_user_range(buff.as_ptr() as usize, buff.len(), false)
+}
+
+/**
+ * 请求参数中的缓冲区，内核可以写入
+ */
+fn writable(buff: &[u8]) -> bool {
+    memory::check_user_range(buff.as_ptr() as usize, buff.len(), true)
+}
+
+/**
+ * 请求参数中的字符串数组（比如exec的参数），数组和每个字符串都可以读取
+ */
+fn readable_strs(strs: &[&str]) -> bool {
+    memory::check_user_range(strs.as_ptr() as usize, size_of_val(strs), false) && strs.iter().all(|s| self::readable(s.as_bytes()))
 }
 
 /**
@@ -116,8 +268,8 @@ fn get_pid() -> u32 {
  */
 #[inline(never)]
 fn write(fd_addr: u32, addr: u32, len: u32) -> u32 {
-    let fd  = unsafe { *(fd_addr as *const FileDescriptor) };
-    let buf = unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, len.try_into().unwrap()) };
+    let fd  = *try_user!(self::user_ref::<FileDescriptor>(fd_addr));
+    let buf = try_user!(self::user_slice(addr, len, false));
 
     // 根据文件描述符找到
     let task_file_descriptor = filesystem::get_task_file_descriptor(fd);
@@ -128,8 +280,9 @@ fn write(fd_addr: u32, addr: u32, len: u32) -> u32 {
 
     // 如果是控制台
     if task_file_descriptor.get_fd_type() == FileDescriptorType::Console {
-        // 如果是标准输出，那么就打印到控制台
-        if filesystem::StdFileDescriptor::StdOutputNo as usize == task_file_descriptor.get_global_idx() {
+        // 如果是标准输出、标准错误，那么就打印到控制台
+        let global_idx = task_file_descriptor.get_global_idx();
+        if filesystem::StdFileDescriptor::StdOutputNo as usize == global_idx || filesystem::StdFileDescriptor::StdErrorNo as usize == global_idx {
             let str_res = str::from_utf8(buf);
             ASSERT!(str_res.is_ok());
             let string = str_res.unwrap();
@@ -164,8 +317,8 @@ fn write(fd_addr: u32, addr: u32, len: u32) -> u32 {
  */
 #[inline(never)]
 fn read(fd_addr: u32, buf: u32, len: u32) -> u32 {
-    let fd  = unsafe { *(fd_addr as *const FileDescriptor) };
-    let buf = unsafe { core::slice::from_raw_parts_mut(buf as *mut u8, len.try_into().unwrap()) };
+    let fd  = *try_user!(self::user_ref::<FileDescriptor>(fd_addr));
+    let buf = try_user!(self::user_slice(buf, len, true));
     let task_file_descriptor = filesystem::get_task_file_descriptor(fd);
     ASSERT!(task_file_descriptor.is_some());
     let task_file_descriptor = task_file_descriptor.unwrap();
@@ -174,14 +327,18 @@ fn read(fd_addr: u32, buf: u32, len: u32) -> u32 {
     if task_file_descriptor.get_fd_type() == FileDescriptorType::Console {
         // 如果是标准输入
         if filesystem::StdFileDescriptor::StdInputNo as usize == fd.get_value() {
+            // 键盘属于前台进程组。后台进程读取键盘，停止运行，直到被放到前台
+            if !self::wait_for_foreground() {
+                return 0;
+            }
             let key_buff = unsafe { core::slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut AsciiKey, buf.len() / size_of::<AsciiKey>()) };
             let mut idx = 0;
             // 键盘队列
             let keyboard_queue = keyboard::get_keycode_queue();
             while idx < key_buff.len() {
-                // 逐个取出输入的键，直到满了
-                let ascii_key = keyboard_queue.take();
-                // 如果队列里空了
+                // 逐个取出输入的键，直到满了（可以被信号打断，比如Ctrl+C）
+                let ascii_key = keyboard_queue.take_interruptible();
+                // 如果队列里空了，或者被信号打断了
                 if ascii_key.is_none() {
                     break;
                 }
@@ -221,6 +378,21 @@ fn read(fd_addr: u32, buf: u32, len: u32) -> u32 {
 }
 
 
+/**
+ * 等待当前进程成为前台进程组。返回false，表示没法等待（忽略或者处理了SIGTTIN，或者有其他信号要处理）
+ */
+#[inline(never)]
+fn wait_for_foreground() -> bool {
+    let cur_task = &mut thread::current_thread().task_struct;
+    while !signal::is_foreground(cur_task) {
+        if signal::get_action(cur_task, signal::SIGTTIN) != SigAction::Default || cur_task.pending_signals != 0 {
+            return false;
+        }
+        signal::stop_current();
+    }
+    true
+}
+
 /**
  * 申请bytes大小的内存空间
  */
@@ -259,6 +431,7 @@ fn thread_yield() -> u32 {
 #[inline(never)]
 fn clear_screen() -> u32 {
     vga::clear_all();
+    vga::sync_cursor();
     0
 }
 
@@ -267,10 +440,9 @@ fn clear_screen() -> u32 {
  */
 #[inline(never)]
 fn read_dir(addr: u32, len: u32, dir_addr: u32) -> u32 {
-    let dir = dir_addr as *mut Result<filesystem::ReadDir, DirError>;
-    let dir_path = unsafe { core::str::from_utf8(core::slice::from_raw_parts(addr as *const u8, len.try_into().unwrap())) };
-    ASSERT!(dir_path.is_ok());
-    unsafe { *dir = filesystem::read_dir(dir_path.unwrap()) };
+    let dir = try_user!(self::user_mut::<Result<filesystem::ReadDir, DirError>>(dir_addr));
+    let dir_path = try_user!(self::user_str(addr, len));
+    *dir = filesystem::read_dir(dir_path);
     0
 }
 
@@ -279,51 +451,48 @@ fn read_dir(addr: u32, len: u32, dir_addr: u32) -> u32 {
  */
 #[inline(never)]
 fn remove_dir(addr: u32, len: u32, res_addr: u32) -> u32 {
-    let res = res_addr as *mut Result<(), DirError>;
-    let dir_path = unsafe { core::str::from_utf8(core::slice::from_raw_parts(addr as *const u8, len.try_into().unwrap())) };
-    ASSERT!(dir_path.is_ok());
-    unsafe { *res = filesystem::remove_dir(dir_path.unwrap()) };
+    let res = try_user!(self::user_mut::<Result<(), DirError>>(res_addr));
+    let dir_path = try_user!(self::user_str(addr, len));
+    *res = filesystem::remove_dir(dir_path);
     0
 }
 
 #[inline(never)]
 fn create_dir(addr: u32, len: u32, res_addr: u32) -> u32 {
-    let res = res_addr as *mut Result<(), DirError>;
-    let dir_path = unsafe { core::str::from_utf8(core::slice::from_raw_parts(addr as *const u8, len.try_into().unwrap())) };
-    ASSERT!(dir_path.is_ok());
-    unsafe { *res = filesystem::create_dir(dir_path.unwrap()) };
+    let res = try_user!(self::user_mut::<Result<(), DirError>>(res_addr));
+    let dir_path = try_user!(self::user_str(addr, len));
+    *res = filesystem::create_dir(dir_path);
     0
 }
 
 #[inline(never)]
 fn create_dir_all(addr: u32, len: u32, res_addr: u32) -> u32 {
-    let res = res_addr as *mut Result<(), DirError>;
-    let dir_path = unsafe { core::str::from_utf8(core::slice::from_raw_parts(addr as *const u8, len.try_into().unwrap())) };
-    ASSERT!(dir_path.is_ok());
-    unsafe { *res = filesystem::create_dir_all(dir_path.unwrap()) };
+    let res = try_user!(self::user_mut::<Result<(), DirError>>(res_addr));
+    let dir_path = try_user!(self::user_str(addr, len));
+    *res = filesystem::create_dir_all(dir_path);
     0
 }
 
 
 #[inline(never)]
 fn dir_iter(dir_addr: u32, res_addr: u32) -> u32 {
-    let dir = unsafe { &mut *(dir_addr as *mut filesystem::ReadDir) };
-    let res = unsafe { &mut *(res_addr as *mut Option<filesystem::ReadDirIterator>) };
+    let dir = try_user!(self::user_mut::<filesystem::ReadDir>(dir_addr));
+    let res = try_user!(self::user_mut::<Option<filesystem::ReadDirIterator>>(res_addr));
     *res = Option::Some(dir.iter_ignore_drop());
     0
 }
 
 #[inline(never)]
 fn dir_iter_next(iter_addr: u32, res_addr: u32) -> u32 {
-    let iter = unsafe { &mut *(iter_addr as *mut filesystem::ReadDirIterator) };
-    let res = unsafe { &mut *(res_addr as *mut Option<&filesystem::DirEntry>) };
+    let iter = try_user!(self::user_mut::<filesystem::ReadDirIterator>(iter_addr));
+    let res = try_user!(self::user_mut::<Option<&filesystem::DirEntry>>(res_addr));
     *res = iter.next();
     0
 }
 
 #[inline(never)]
 fn dir_iter_drop(iter_addr: u32) -> u32 {
-    let iter = unsafe { &mut *(iter_addr as *mut filesystem::ReadDirIterator) };
+    let iter = try_user!(self::user_mut::<filesystem::ReadDirIterator>(iter_addr));
     // 手动触发drop
     iter.drop();
     0
@@ -332,25 +501,27 @@ fn dir_iter_drop(iter_addr: u32) -> u32 {
 
 #[inline(never)]
 fn create_file(addr: u32, len: u32, res_addr: u32) -> u32 {
-    let res = unsafe {&mut *(res_addr as *mut Result<filesystem::File, filesystem::FileError>)};
-    let dir_path = unsafe { core::str::from_utf8(core::slice::from_raw_parts(addr as *const u8, len.try_into().unwrap())) };
-    ASSERT!(dir_path.is_ok());
-    *res = filesystem::File::create_ignore_drop(dir_path.unwrap());
+    let res = try_user!(self::user_mut::<Result<filesystem::File, filesystem::FileError>>(res_addr));
+    let dir_path = try_user!(self::user_str(addr, len));
+    *res = filesystem::File::create_ignore_drop(dir_path);
     0
 }
 
 #[inline(never)]
 fn open_file(req_addr: u32, res_addr: u32) -> u32 {
-    let res = unsafe {&mut *(res_addr as *mut Result<filesystem::File, filesystem::FileError>)};
-    let req = unsafe { &*(req_addr as *const OpenFileDto) };
-    *res = filesystem::OpenOptions::new().read(true).write(true).append(req.append).ignore_drop(true).open(req.file_path);
+    let res = try_user!(self::user_mut::<Result<filesystem::File, filesystem::FileError>>(res_addr));
+    let req = try_user!(self::user_ref::<OpenFileDto>(req_addr));
+    if !self::readable(req.file_path.as_bytes()) {
+        return self::bad_address();
+    }
+    *res = filesystem::OpenOptions::new().read(true).write(true).append(req.append).truncate(req.truncate).ignore_drop(true).open(req.file_path);
     0
 }
 
 #[inline(never)]
 fn file_size(file_addr: u32, res_addr: u32) -> u32 {
-    let file = unsafe {&*(file_addr as *const filesystem::File)};
-    let res = unsafe {&mut *(res_addr as *mut Result<usize, filesystem::FileError>)};
+    let file = try_user!(self::user_ref::<filesystem::File>(file_addr));
+    let res = try_user!(self::user_mut::<Result<usize, filesystem::FileError>>(res_addr));
     *res = file.get_size();
     0
 }
@@ -358,9 +529,9 @@ fn file_size(file_addr: u32, res_addr: u32) -> u32 {
 
 #[inline(never)]
 fn seek_file(file_addr: u32, seek_addr: u32, res_addr: u32) -> u32 {
-    let file = unsafe {&mut *(file_addr as *mut filesystem::File)};
-    let res = unsafe { &mut *(res_addr as *mut Result<(), filesystem::FileError>) };
-    let seek_from = unsafe { & *(seek_addr as *const filesystem::SeekFrom) };
+    let file = try_user!(self::user_mut::<filesystem::File>(file_addr));
+    let res = try_user!(self::user_mut::<Result<(), filesystem::FileError>>(res_addr));
+    let seek_from = try_user!(self::user_ref::<filesystem::SeekFrom>(seek_addr));
     *res = file.seek(*seek_from);
     0
 }
@@ -368,28 +539,119 @@ fn seek_file(file_addr: u32, seek_addr: u32, res_addr: u32) -> u32 {
 
 #[inline(never)]
 fn close_file(file_addr: u32, res_addr: u32) -> u32 {
-    let file = unsafe {&mut *(file_addr as *mut filesystem::File)};
-    let res = unsafe {&mut *(res_addr as *mut Result<(), filesystem::FileError>)};
+    let file = try_user!(self::user_mut::<filesystem::File>(file_addr));
+    let res = try_user!(self::user_mut::<Result<(), filesystem::FileError>>(res_addr));
     *res = file.close();
     0
 }
 
 
+#[inline(never)]
+fn ftruncate(fd_addr: u32, len: u32, res_addr: u32) -> u32 {
+    let fd  = *try_user!(self::user_ref::<FileDescriptor>(fd_addr));
+    let res = try_user!(self::user_mut::<Result<(), filesystem::FileError>>(res_addr));
+    *res = filesystem::ftruncate(fd, len);
+    0
+}
+
+#[inline(never)]
+fn rename(req_addr: u32, res_addr: u32) -> u32 {
+    let req = try_user!(self::user_ref::<PathPairDto>(req_addr));
+    if !self::readable(req.old_path.as_bytes()) || !self::readable(req.new_path.as_bytes()) {
+        return self::bad_address();
+    }
+    let res = try_user!(self::user_mut::<Result<(), filesystem::FileError>>(res_addr));
+    *res = filesystem::rename(req.old_path, req.new_path);
+    0
+}
+
+#[inline(never)]
+fn link(req_addr: u32, res_addr: u32) -> u32 {
+    let req = try_user!(self::user_ref::<PathPairDto>(req_addr));
+    if !self::readable(req.old_path.as_bytes()) || !self::readable(req.new_path.as_bytes()) {
+        return self::bad_address();
+    }
+    let res = try_user!(self::user_mut::<Result<(), filesystem::FileError>>(res_addr));
+    *res = filesystem::link(req.old_path, req.new_path);
+    0
+}
+
+#[inline(never)]
+fn symlink(req_addr: u32, res_addr: u32) -> u32 {
+    let req = try_user!(self::user_ref::<PathPairDto>(req_addr));
+    if !self::readable(req.old_path.as_bytes()) || !self::readable(req.new_path.as_bytes()) {
+        return self::bad_address();
+    }
+    let res = try_user!(self::user_mut::<Result<(), filesystem::FileError>>(res_addr));
+    *res = filesystem::symlink(req.old_path, req.new_path);
+    0
+}
+
+#[inline(never)]
+fn read_link(req_addr: u32, res_addr: u32) -> u32 {
+    let req = try_user!(self::user_mut::<ReadLinkDto>(req_addr));
+    if !self::readable(req.path.as_bytes()) || !self::writable(req.buff) {
+        return self::bad_address();
+    }
+    let res = try_user!(self::user_mut::<Result<usize, filesystem::FileError>>(res_addr));
+    *res = filesystem::read_link(req.path, req.buff);
+    0
+}
+
+#[inline(never)]
+fn stat(addr: u32, len: u32, res_addr: u32) -> u32 {
+    let res = try_user!(self::user_mut::<Result<filesystem::Metadata, filesystem::FileError>>(res_addr));
+    let path = try_user!(self::user_str(addr, len));
+    *res = filesystem::stat(path);
+    0
+}
+
+#[inline(never)]
+fn lstat(addr: u32, len: u32, res_addr: u32) -> u32 {
+    let res = try_user!(self::user_mut::<Result<filesystem::Metadata, filesystem::FileError>>(res_addr));
+    let path = try_user!(self::user_str(addr, len));
+    *res = filesystem::lstat(path);
+    0
+}
+
+#[inline(never)]
+fn fstat(fd_addr: u32, res_addr: u32) -> u32 {
+    let fd  = *try_user!(self::user_ref::<FileDescriptor>(fd_addr));
+    let res = try_user!(self::user_mut::<Result<filesystem::Metadata, filesystem::FileError>>(res_addr));
+    *res = filesystem::fstat(fd);
+    0
+}
+
+#[inline(never)]
+fn sync() -> u32 {
+    device::sync_block_cache();
+    0
+}
+
+#[inline(never)]
+fn fsck(repair: u32, res_addr: u32) -> u32 {
+    let res = try_user!(self::user_mut::<filesystem::FsckReport>(res_addr));
+    *res = filesystem::check_filesystem(repair != 0);
+    0
+}
+
 #[inline(never)]
 fn remove_file(addr: u32, len: u32, res_addr: u32) -> u32 {
-    let res = unsafe {&mut *(res_addr as *mut Result<(), filesystem::FileError>)};
-    let dir_path = unsafe { core::str::from_utf8(core::slice::from_raw_parts(addr as *const u8, len.try_into().unwrap())) };
-    ASSERT!(dir_path.is_ok());
-    *res = filesystem::remove_file(dir_path.unwrap());
+    let res = try_user!(self::user_mut::<Result<(), filesystem::FileError>>(res_addr));
+    let dir_path = try_user!(self::user_str(addr, len));
+    *res = filesystem::remove_file(dir_path);
     0
 }
 
 
 #[inline(never)]
 fn exec(param_addr: u32, res_addr: u32) -> u32 {
-    let param = unsafe { &*(param_addr as *const ExecParam) };
+    let param = try_user!(self::user_ref::<ExecParam>(param_addr));
+    if !self::readable(param.get_file_path().as_bytes()) || !self::readable_strs(param.get_args()) || !self::readable_strs(param.get_envs()) {
+        return self::bad_address();
+    }
 
-    let res = unsafe {&mut *(res_addr as *mut Result<(), exec::ExecError>)};
+    let res = try_user!(self::user_mut::<Result<(), exec::ExecError>>(res_addr));
     *res = exec::execv(param);
 
     return 0;
@@ -403,14 +665,17 @@ fn exit(status: u32) -> u32 {
 
 #[inline(never)]
 fn wait(res_addr: u32) -> u32 {
-    let res = unsafe { &mut *(res_addr as *mut Option<(Pid, Option<TaskExitStatus>)>) };
+    let res = try_user!(self::user_mut::<Option<(Pid, Option<TaskExitStatus>)>>(res_addr));
     *res = userprog::wait();
     0
 }
 
 #[inline(never)]
 fn get_cwd(dto_addr: u32) -> u32 {
-    let cwd_dto = unsafe { &mut *(dto_addr as *mut CwdDto) };
+    let cwd_dto = try_user!(self::user_mut::<CwdDto>(dto_addr));
+    if !self::writable(cwd_dto.buff) {
+        return self::bad_address();
+    }
     let cur_task = &thread::current_thread().task_struct;
     cwd_dto.str = filesystem::get_cwd(cur_task, cwd_dto.buff);
     return 0;
@@ -418,8 +683,8 @@ fn get_cwd(dto_addr: u32) -> u32 {
 
 #[inline(never)]
 fn change_dir(path_addr: u32, path_len: u32, res_addr: u32) -> u32 {
-    let path = unsafe { core::str::from_utf8(core::slice::from_raw_parts(path_addr as *const u8, path_len.try_into().unwrap())) }.unwrap();
-    let res = unsafe { &mut *(res_addr as *mut Option<()>) };
+    let path = try_user!(self::user_str(path_addr, path_len));
+    let res = try_user!(self::user_mut::<Option<()>>(res_addr));
     let cur_task = &mut thread::current_thread().task_struct;
     *res = filesystem::change_dir(cur_task, path);
     0
@@ -427,29 +692,152 @@ fn change_dir(path_addr: u32, path_len: u32, res_addr: u32) -> u32 {
 
 #[inline(never)]
 fn pipe_create(size: u32, res_addr: u32) -> u32 {
-    let res = unsafe { &mut *(res_addr as *mut Result<FileDescriptor, PipeError>) };
+    let res = try_user!(self::user_mut::<Result<FileDescriptor, PipeError>>(res_addr));
     *res = pipe::pipe(size as usize);
     0
 }
 
 #[inline(never)]
 fn pipe_end(fd_addr: u32) -> u32 {
-    let fd = unsafe { *(fd_addr as *const FileDescriptor) };
+    let fd = *try_user!(self::user_ref::<FileDescriptor>(fd_addr));
     pipe::release_pipe(fd);
     0
 }
 
 #[inline(never)]
 fn set_producer(pipe_fd_addr: u32) -> u32 {
-    let pipe_fd = unsafe { *(pipe_fd_addr as *const FileDescriptor) };
+    let pipe_fd = *try_user!(self::user_ref::<FileDescriptor>(pipe_fd_addr));
     let _ = pipe::set_producer(pipe_fd);
     0
 }
 
 #[inline(never)]
 fn set_consumer(pipe_fd_addr: u32) -> u32 {
-    let pipe_fd = unsafe { *(pipe_fd_addr as *const FileDescriptor) };
+    let pipe_fd = *try_user!(self::user_ref::<FileDescriptor>(pipe_fd_addr));
     let _ = pipe::set_consumer(pipe_fd);
     0
 }
 
+#[inline(never)]
+fn set_error_producer(fd_addr: u32) -> u32 {
+    let fd = *try_user!(self::user_ref::<FileDescriptor>(fd_addr));
+    let _ = pipe::set_error_producer(fd);
+    0
+}
+
+#[inline(never)]
+fn pipe_write_end(pipe_fd_addr: u32) -> u32 {
+    let pipe_fd = *try_user!(self::user_ref::<FileDescriptor>(pipe_fd_addr));
+    let _ = pipe::write_end(pipe_fd);
+    0
+}
+
+#[inline(never)]
+fn mmap(param_addr: u32, res_addr: u32) -> u32 {
+    let param = try_user!(self::user_ref::<MmapParam>(param_addr));
+    let res = try_user!(self::user_mut::<Result<usize, userprog::MmapError>>(res_addr));
+    *res = userprog::mmap(param);
+    0
+}
+
+#[inline(never)]
+fn sleep(ms: u32) -> u32 {
+    timer::sleep_ms(ms);
+    0
+}
+
+/**
+ * pid为负数，表示给进程组-pid发送信号
+ */
+#[inline(never)]
+fn kill(pid: u32, sig: u32, res_addr: u32) -> u32 {
+    let res = try_user!(self::user_mut::<Result<(), SignalError>>(res_addr));
+    let pid = pid as i32;
+    *res = if pid < 0 {
+        signal::kill_group(Pid::new((-pid) as u8), sig)
+    } else {
+        signal::kill(Pid::new(pid as u8), sig)
+    };
+    0
+}
+
+#[inline(never)]
+fn signal(sig: u32, action_addr: u32, res_addr: u32) -> u32 {
+    let action = *try_user!(self::user_ref::<SigAction>(action_addr));
+    let res = try_user!(self::user_mut::<Result<SigAction, SignalError>>(res_addr));
+    *res = signal::set_action(sig, action);
+    0
+}
+
+/**
+ * pgid为0，表示没有前台进程组
+ */
+#[inline(never)]
+fn set_foreground(pgid: u32) -> u32 {
+    let pgid = if pgid == 0 { Option::None } else { Option::Some(Pid::new(pgid as u8)) };
+    signal::set_foreground(pgid);
+    0
+}
+
+/**
+ * pid和pgid为0，分别表示当前进程、以pid为组长新建进程组
+ */
+#[inline(never)]
+fn setpgid(pid: u32, pgid: u32, res_addr: u32) -> u32 {
+    let pid = if pid == 0 { Option::None } else { Option::Some(Pid::new(pid as u8)) };
+    let pgid = if pgid == 0 { Option::None } else { Option::Some(Pid::new(pgid as u8)) };
+    let res = try_user!(self::user_mut::<Result<(), userprog::PgrpError>>(res_addr));
+    *res = userprog::setpgid(pid, pgid);
+    0
+}
+
+/**
+ * pid为0，表示当前进程
+ */
+#[inline(never)]
+fn getpgid(pid: u32, res_addr: u32) -> u32 {
+    let pid = if pid == 0 { Option::None } else { Option::Some(Pid::new(pid as u8)) };
+    let res = try_user!(self::user_mut::<Result<Pid, userprog::PgrpError>>(res_addr));
+    *res = userprog::getpgid(pid);
+    0
+}
+
+#[inline(never)]
+fn setsid(res_addr: u32) -> u32 {
+    let res = try_user!(self::user_mut::<Result<Pid, userprog::PgrpError>>(res_addr));
+    *res = userprog::setsid();
+    0
+}
+
+#[inline(never)]
+fn wait_pid(target_addr: u32, options: u32, res_addr: u32) -> u32 {
+    let target = *try_user!(self::user_ref::<userprog::WaitTarget>(target_addr));
+    let res = try_user!(self::user_mut::<userprog::WaitResult>(res_addr));
+    *res = userprog::wait_pid(target, options);
+    0
+}
+
+#[inline(never)]
+fn uptime(res_addr: u32) -> u32 {
+    let res = try_user!(self::user_mut::<u64>(res_addr));
+    *res = timer::uptime_ms();
+    0
+}
+
+#[inline(never)]
+fn nice(increment: u32) -> u32 {
+    scheduler::nice(increment as i32) as u32
+}
+
+#[inline(never)]
+fn brk(new_end: u32) -> u32 {
+    userprog::brk(new_end as usize) as u32
+}
+
+#[inline(never)]
+fn munmap(addr: u32, len: u32, res_addr: u32) -> u32 {
+    let res = try_user!(self::user_mut::<Result<(), userprog::MmapError>>(res_addr));
+    *res = userprog::munmap(addr as usize, len as usize);
+    0
+}
+
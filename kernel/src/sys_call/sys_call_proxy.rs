@@ -6,12 +6,16 @@ use core::mem::size_of_val;
 use crate::ascii::AsciiKey;
 use crate::common::cwd_dto::CwdDto;
 use crate::common::exec_dto::ExecParam;
+use crate::common::mmap_dto::MmapParam;
 use crate::common::open_file_dto::OpenFileDto;
+use crate::common::path_pair_dto::PathPairDto;
+use crate::common::read_link_dto::ReadLinkDto;
 use crate::exec;
 use crate::filesystem::{self, FileDescriptor, SeekFrom, StdFileDescriptor};
 use crate::pid_allocator::Pid;
 use crate::pipe::PipeError;
-use crate::userprog::TaskExitStatus;
+use crate::signal::{SigAction, SignalError, SignalNo};
+use crate::userprog::{MmapError, PgrpError, TaskExitStatus, WaitResult, WaitTarget};
 
 use super::sys_call::SystemCallNo;
 
@@ -183,6 +187,105 @@ pub fn file_size(file: &filesystem::File) -> Result<usize, filesystem::FileError
     res
 }
 
+#[inline(never)]
+pub fn ftruncate(fd: FileDescriptor, len: u32) -> Result<(), filesystem::FileError> {
+    let mut res: Result<(), filesystem::FileError> = Result::Err(filesystem::FileError::NotFound);
+    self::do_sys_call(SystemCallNo::Ftruncate, Option::Some(&fd as *const _ as u32), Option::Some(len), Option::Some(&mut res as *mut _ as u32));
+    res
+}
+
+#[inline(never)]
+pub fn rename(old_path: &str, new_path: &str) -> Result<(), filesystem::FileError> {
+    let req = PathPairDto::new(old_path, new_path);
+    let mut res: Result<(), filesystem::FileError> = Result::Err(filesystem::FileError::NotFound);
+    self::do_sys_call(SystemCallNo::Rename, Option::Some(&req as *const _ as u32), Option::Some(&mut res as *mut _ as u32), Option::None);
+    res
+}
+
+/**
+ * 创建硬链接new_path，指向existing_path的文件
+ */
+#[inline(never)]
+pub fn link(existing_path: &str, new_path: &str) -> Result<(), filesystem::FileError> {
+    let req = PathPairDto::new(existing_path, new_path);
+    let mut res: Result<(), filesystem::FileError> = Result::Err(filesystem::FileError::NotFound);
+    self::do_sys_call(SystemCallNo::Link, Option::Some(&req as *const _ as u32), Option::Some(&mut res as *mut _ as u32), Option::None);
+    res
+}
+
+/**
+ * 创建符号链接link_path，内容是target
+ */
+#[inline(never)]
+pub fn symlink(target: &str, link_path: &str) -> Result<(), filesystem::FileError> {
+    let req = PathPairDto::new(target, link_path);
+    let mut res: Result<(), filesystem::FileError> = Result::Err(filesystem::FileError::NotFound);
+    self::do_sys_call(SystemCallNo::Symlink, Option::Some(&req as *const _ as u32), Option::Some(&mut res as *mut _ as u32), Option::None);
+    res
+}
+
+/**
+ * 读取符号链接path的目标路径，放到buff中
+ */
+#[inline(never)]
+pub fn read_link<'a>(path: &str, buff: &'a mut [u8]) -> Result<&'a str, filesystem::FileError> {
+    let mut res: Result<usize, filesystem::FileError> = Result::Err(filesystem::FileError::NotFound);
+    let mut req = ReadLinkDto::new(path, &mut *buff);
+    self::do_sys_call(SystemCallNo::Readlink, Option::Some(&mut req as *mut _ as u32), Option::Some(&mut res as *mut _ as u32), Option::None);
+    let target_len = res?;
+    let buff: &'a [u8] = buff;
+    let target = core::str::from_utf8(&buff[..target_len]);
+    if target.is_err() {
+        return Result::Err(filesystem::FileError::Uncategorized);
+    }
+    Result::Ok(target.unwrap())
+}
+
+/**
+ * 得到path的元信息。如果path是符号链接，得到它指向的文件的元信息
+ */
+#[inline(never)]
+pub fn stat(path: &str) -> Result<filesystem::Metadata, filesystem::FileError> {
+    let mut res: Result<filesystem::Metadata, filesystem::FileError> = Result::Err(filesystem::FileError::NotFound);
+    self::do_sys_call(SystemCallNo::Stat, Option::Some(path.as_ptr() as u32), Option::Some(path.len() as u32), Option::Some(&mut res as *mut _ as u32));
+    res
+}
+
+/**
+ * 得到path的元信息。如果path是符号链接，得到符号链接本身的元信息
+ */
+#[inline(never)]
+pub fn lstat(path: &str) -> Result<filesystem::Metadata, filesystem::FileError> {
+    let mut res: Result<filesystem::Metadata, filesystem::FileError> = Result::Err(filesystem::FileError::NotFound);
+    self::do_sys_call(SystemCallNo::Lstat, Option::Some(path.as_ptr() as u32), Option::Some(path.len() as u32), Option::Some(&mut res as *mut _ as u32));
+    res
+}
+
+#[inline(never)]
+pub fn fstat(fd: FileDescriptor) -> Result<filesystem::Metadata, filesystem::FileError> {
+    let mut res: Result<filesystem::Metadata, filesystem::FileError> = Result::Err(filesystem::FileError::NotFound);
+    self::do_sys_call(SystemCallNo::Fstat, Option::Some(&fd as *const _ as u32), Option::Some(&mut res as *mut _ as u32), Option::None);
+    res
+}
+
+/**
+ * 把缓存中修改过的扇区，写回硬盘
+ */
+#[inline(never)]
+pub fn sync() {
+    self::do_sys_call(SystemCallNo::Sync, Option::None, Option::None, Option::None);
+}
+
+/**
+ * 检查当前挂载的文件系统。repair为true，修复能够修复的问题
+ */
+#[inline(never)]
+pub fn fsck(repair: bool) -> filesystem::FsckReport {
+    let mut report = filesystem::FsckReport::new();
+    self::do_sys_call(SystemCallNo::Fsck, Option::Some(repair as u32), Option::Some(&mut report as *mut _ as u32), Option::None);
+    report
+}
+
 #[inline(never)]
 pub fn remove_file(path: &str) -> Result<(), filesystem::FileError> {
     let mut res: Result<(), filesystem::FileError> = Result::Err(filesystem::FileError::NotFound);
@@ -249,11 +352,159 @@ pub fn set_consumer(pipe_fd: FileDescriptor) {
 }
 
 
+#[inline(never)]
+pub fn mmap(param: &MmapParam) -> Result<usize, MmapError> {
+    let mut res: Result<usize, MmapError> = Result::Err(MmapError::Init);
+    self::do_sys_call(SystemCallNo::Mmap, Option::Some(param as *const _ as u32), Option::Some(&mut res as *mut _ as u32), Option::None);
+    res
+}
+
+#[inline(never)]
+pub fn munmap(addr: usize, len: usize) -> Result<(), MmapError> {
+    let mut res: Result<(), MmapError> = Result::Err(MmapError::Init);
+    self::do_sys_call(SystemCallNo::Munmap, Option::Some(addr as u32), Option::Some(len as u32), Option::Some(&mut res as *mut _ as u32));
+    res
+}
+
+/**
+ * 把堆的结束地址调整到new_end，返回调整后的结束地址。new_end为0，只查询当前的结束地址
+ */
+#[inline(never)]
+pub fn brk(new_end: usize) -> usize {
+    self::do_sys_call(SystemCallNo::Brk, Option::Some(new_end as u32), Option::None, Option::None) as usize
+}
+
+/**
+ * 调整当前任务的优先级。increment为正数，优先级降低；为负数，优先级升高。返回调整后的优先级
+ */
+#[inline(never)]
+pub fn nice(increment: i32) -> u8 {
+    self::do_sys_call(SystemCallNo::Nice, Option::Some(increment as u32), Option::None, Option::None) as u8
+}
+
+/**
+ * 当前任务睡眠ms毫秒
+ */
+#[inline(never)]
+pub fn sleep(ms: u32) {
+    self::do_sys_call(SystemCallNo::Sleep, Option::Some(ms), Option::None, Option::None);
+}
+
+/**
+ * 系统启动以来经过的毫秒数
+ */
+#[inline(never)]
+pub fn uptime() -> u64 {
+    let mut res: u64 = 0;
+    self::do_sys_call(SystemCallNo::Uptime, Option::Some(&mut res as *mut _ as u32), Option::None, Option::None);
+    res
+}
+
+/**
+ * 给pid对应的进程发送信号sig。sig为0，只检查进程是否存在
+ */
+#[inline(never)]
+pub fn kill(pid: Pid, sig: SignalNo) -> Result<(), SignalError> {
+    let mut res: Result<(), SignalError> = Result::Err(SignalError::Init);
+    self::do_sys_call(SystemCallNo::Kill, Option::Some(pid.get_data() as u32), Option::Some(sig), Option::Some(&mut res as *mut _ as u32));
+    res
+}
+
+/**
+ * 设置收到信号sig之后的动作，返回原来的动作
+ */
+#[inline(never)]
+pub fn signal(sig: SignalNo, action: SigAction) -> Result<SigAction, SignalError> {
+    let mut res: Result<SigAction, SignalError> = Result::Err(SignalError::Init);
+    self::do_sys_call(SystemCallNo::Signal, Option::Some(sig), Option::Some(&action as *const _ as u32), Option::Some(&mut res as *mut _ as u32));
+    res
+}
+
+/**
+ * 给进程组pgid中的所有进程发送信号sig
+ */
+#[inline(never)]
+pub fn kill_group(pgid: Pid, sig: SignalNo) -> Result<(), SignalError> {
+    let mut res: Result<(), SignalError> = Result::Err(SignalError::Init);
+    self::do_sys_call(SystemCallNo::Kill, Option::Some((-(pgid.get_data() as i32)) as u32), Option::Some(sig), Option::Some(&mut res as *mut _ as u32));
+    res
+}
+
+/**
+ * 设置前台进程组。键盘的输入、Ctrl+C、Ctrl+Z，都属于前台进程组
+ */
+#[inline(never)]
+pub fn set_foreground(pgid: Option<Pid>) {
+    let pgid = if pgid.is_none() { 0 } else { pgid.unwrap().get_data() as u32 };
+    self::do_sys_call(SystemCallNo::SetForeground, Option::Some(pgid), Option::None, Option::None);
+}
+
+/**
+ * 把进程pid放入进程组pgid
+ *   - pid: None表示当前进程
+ *   - pgid: None表示以pid为组长，新建一个进程组
+ */
+#[inline(never)]
+pub fn setpgid(pid: Option<Pid>, pgid: Option<Pid>) -> Result<(), PgrpError> {
+    let pid = if pid.is_none() { 0 } else { pid.unwrap().get_data() as u32 };
+    let pgid = if pgid.is_none() { 0 } else { pgid.unwrap().get_data() as u32 };
+    let mut res: Result<(), PgrpError> = Result::Err(PgrpError::Init);
+    self::do_sys_call(SystemCallNo::SetPgid, Option::Some(pid), Option::Some(pgid), Option::Some(&mut res as *mut _ as u32));
+    res
+}
+
+/**
+ * 进程pid所在的进程组。pid为None表示当前进程
+ */
+#[inline(never)]
+pub fn getpgid(pid: Option<Pid>) -> Result<Pid, PgrpError> {
+    let pid = if pid.is_none() { 0 } else { pid.unwrap().get_data() as u32 };
+    let mut res: Result<Pid, PgrpError> = Result::Err(PgrpError::Init);
+    self::do_sys_call(SystemCallNo::GetPgid, Option::Some(pid), Option::Some(&mut res as *mut _ as u32), Option::None);
+    res
+}
+
+/**
+ * 新建一个会话，当前进程成为会话首进程和进程组组长。返回会话id
+ */
+#[inline(never)]
+pub fn setsid() -> Result<Pid, PgrpError> {
+    let mut res: Result<Pid, PgrpError> = Result::Err(PgrpError::Init);
+    self::do_sys_call(SystemCallNo::SetSid, Option::Some(&mut res as *mut _ as u32), Option::None, Option::None);
+    res
+}
+
+/**
+ * 等待符合target的子进程结束或者停止。options是WNOHANG、WUNTRACED的组合
+ */
+#[inline(never)]
+pub fn waitpid(target: WaitTarget, options: u32) -> WaitResult {
+    let mut res = WaitResult::NoChild;
+    self::do_sys_call(SystemCallNo::WaitPid, Option::Some(&target as *const _ as u32), Option::Some(options), Option::Some(&mut res as *mut _ as u32));
+    res
+}
+
 #[inline(never)]
 pub fn set_producer(pipe_fd: FileDescriptor) {
     self::do_sys_call(SystemCallNo::SetProducer, Option::Some(&pipe_fd as *const _ as u32), Option::None, Option::None);
 }
 
+/**
+ * 把标准错误重定向到fd（管道、文件，或者标准输出）
+ */
+#[inline(never)]
+pub fn set_error_producer(fd: FileDescriptor) {
+    self::do_sys_call(SystemCallNo::SetErrorProducer, Option::Some(&fd as *const _ as u32), Option::None, Option::None);
+}
+
+/**
+ * 管道不会再写入数据了。消费者读取完剩下的数据，读取返回0
+ */
+#[inline(never)]
+pub fn pipe_write_end(pipe_fd: FileDescriptor) {
+    self::do_sys_call(SystemCallNo::PipeWriteEnd, Option::Some(&pipe_fd as *const _ as u32), Option::None, Option::None);
+}
+
 /**
  * 发起系统调用
  * eax: 系统调用号
@@ -12,6 +12,11 @@ use super::sys_call_proxy;
  */
 static CONSOLE_WRITER: RacyCell<FileWriter> = RacyCell::new(FileWriter::new(FileDescriptor::new(StdFileDescriptor::StdOutputNo as usize)));
 
+/**
+ * 标准错误的writer。没有重定向的时候，也是写入到控制台
+ */
+static ERROR_WRITER: RacyCell<FileWriter> = RacyCell::new(FileWriter::new(FileDescriptor::new(StdFileDescriptor::StdErrorNo as usize)));
+
 /**
  * 系统调用 print!
  */
@@ -29,12 +34,35 @@ macro_rules! println {
     ($($arg:tt)*) => ($crate::print!("{}\n", format_args!($($arg)*)));
 }
 
+/**
+ * 系统调用 eprint!，写入到标准错误
+ */
+#[macro_export]
+macro_rules! eprint {
+    ($($arg:tt)*) => ($crate::sys_call::sys_eprint(format_args!($($arg)*)));
+}
+
+/**
+ * 系统调用 eprintln!
+ */
+#[macro_export]
+macro_rules! eprintln {
+    () => ($crate::eprint!("\n"));
+    ($($arg:tt)*) => ($crate::eprint!("{}\n", format_args!($($arg)*)));
+}
+
 #[no_mangle]
 pub fn sys_print(args: fmt::Arguments) {
     let writer = unsafe { CONSOLE_WRITER.get_mut() };
     writer.write_fmt(args).unwrap();
 }
 
+#[no_mangle]
+pub fn sys_eprint(args: fmt::Arguments) {
+    let writer = unsafe { ERROR_WRITER.get_mut() };
+    writer.write_fmt(args).unwrap();
+}
+
 pub struct FileWriter {
     fd: FileDescriptor,
 }
@@ -2,7 +2,7 @@ use core::{arch::asm, fmt::{write, Display}, mem::size_of, ptr};
 
 use os_in_rust_common::{constants, cstr_write, cstring_utils, domain::InodeNo, elem2entry, instruction::{self, enable_interrupt}, linked_list::{LinkedList, LinkedNode, LinkedNodeIterator}, paging::{self, PageTable}, pool::MemPool, printkln, racy_cell::RacyCell, reg_cr3::{self, CR3}, reg_eflags::{self, EFlags, FlagEnum}, selector::SegmentSelector, utils, ASSERT, MY_PANIC};
 
-use crate::{console_println, filesystem::TaskFileDescriptorTable, interrupt, memory::{page_util, MemBlockAllocator}, pid_allocator::Pid, tss, userprog::TaskExitStatus};
+use crate::{console_println, filesystem::TaskFileDescriptorTable, interrupt, memory::{page_util, MemBlockAllocator, VmaList}, pid_allocator::Pid, signal, tss, userprog::TaskExitStatus};
 
 
 /**
@@ -11,9 +11,14 @@ use crate::{console_println, filesystem::TaskFileDescriptorTable, interrupt, mem
 static ALL_THREAD_LIST: RacyCell<LinkedList> = RacyCell::new(LinkedList::new());
 
 /**
- * 就绪的进程List
+ * 空的链表，用来初始化就绪队列
  */
-static READY_THREAD_LIST: RacyCell<LinkedList> = RacyCell::new(LinkedList::new());
+const EMPTY_THREAD_LIST: LinkedList = LinkedList::new();
+
+/**
+ * 就绪的进程List。多级反馈队列，每一级一个List，下标越小优先级越高
+ */
+static READY_THREAD_LIST: RacyCell<[LinkedList; constants::MLFQ_LEVEL_CNT]> = RacyCell::new([EMPTY_THREAD_LIST; constants::MLFQ_LEVEL_CNT]);
 
 static IDLE_THREAD: RacyCell<Option<&mut TaskStruct>> = RacyCell::new(Option::None);
 
@@ -43,19 +48,69 @@ pub fn remove_from_all_thread(thread_to_remove: &TaskStruct) {
     self::get_all_thread().remove(&thread_to_remove.all_tag);
 }
 
+/**
+ * 第level级的就绪队列
+ */
 #[inline(never)]
-pub fn get_ready_thread() -> &'static mut LinkedList{
-    unsafe { READY_THREAD_LIST.get_mut() }
+pub fn get_ready_thread(level: usize) -> &'static mut LinkedList{
+    unsafe { &mut READY_THREAD_LIST.get_mut()[level] }
 }
 
+/**
+ * 加入就绪队列。放入该任务当前所在级别的队列
+ */
 #[inline(never)]
 pub fn append_read_thread(thread: &mut TaskStruct) {
-    get_ready_thread().append(&mut thread.general_tag);
+    get_ready_thread(thread.mlfq_level as usize).append(&mut thread.general_tag);
 }
 
 #[inline(never)]
 pub fn remove_from_ready_thread(thread: &TaskStruct) {
-    self::get_ready_thread().remove(&thread.general_tag)
+    for level in 0 .. constants::MLFQ_LEVEL_CNT {
+        self::get_ready_thread(level).remove(&thread.general_tag);
+    }
+}
+
+/**
+ * 该任务是否在就绪队列中
+ */
+#[inline(never)]
+pub fn in_ready_thread(thread: &TaskStruct) -> bool {
+    (0 .. constants::MLFQ_LEVEL_CNT).any(|level| self::get_ready_thread(level).contains(&thread.general_tag))
+}
+
+/**
+ * 所有级别的就绪队列，是否都是空的
+ */
+#[inline(never)]
+pub fn is_ready_thread_empty() -> bool {
+    (0 .. constants::MLFQ_LEVEL_CNT).all(|level| self::get_ready_thread(level).is_empty())
+}
+
+/**
+ * 从优先级最高的、非空的就绪队列中，取出第一个任务
+ */
+#[inline(never)]
+pub fn pop_ready_thread() -> Option<&'static mut TaskStruct> {
+    for level in 0 .. constants::MLFQ_LEVEL_CNT {
+        let ready_list = self::get_ready_thread(level);
+        if ready_list.is_empty() {
+            continue;
+        }
+        let pcb_ready_tag = ready_list.pop();
+        return Option::Some(unsafe { &mut *(TaskStruct::parse_by_general_tag(pcb_ready_tag)) });
+    }
+    Option::None
+}
+
+/**
+ * 根据pid，找到还没有退出的任务
+ */
+#[inline(never)]
+pub fn find_alive_task(pid: Pid) -> Option<&'static mut TaskStruct> {
+    self::get_all_thread().iter()
+        .map(|tag| unsafe { &mut *TaskStruct::parse_by_all_tag(&*tag) })
+        .find(|task| task.pid == pid && task.task_status != TaskStatus::TaskHanging && task.task_status != TaskStatus::TaskDied)
 }
 
 #[inline(never)]
@@ -243,6 +298,14 @@ pub struct TaskStruct {
      * 父任务的pid
      */
     pub parent_pid: Option<Pid>,
+    /**
+     * 所在的进程组id。进程组的组长，pgid就是自己的pid
+     */
+    pub pgid: Pid,
+    /**
+     * 所在的会话id。会话的首进程，sid就是自己的pid
+     */
+    pub sid: Pid,
     /**
      * PCB内核栈地址
      */
@@ -260,6 +323,11 @@ pub struct TaskStruct {
      */
     pub priority: u8,
 
+    /**
+     * 在多级反馈队列中，当前所处的级别。0级优先级最高
+     */
+    pub mlfq_level: u8,
+
     /**
      * 当前进程/线程还剩的滴答数量
      */
@@ -268,6 +336,15 @@ pub struct TaskStruct {
      * 该任务一共执行了多久
      */
     pub elapsed_ticks: u8,
+
+    /**
+     * 睡眠的任务，在第几个tick醒来
+     */
+    pub wake_tick: u64,
+    /**
+     * 可以被信号打断的阻塞（比如读取管道）：任务所在的等待队列。没有这样阻塞，是空指针
+     */
+    pub interruptible_list: *mut LinkedList,
     /**
      * 该PCB使用的页表地址
      */
@@ -295,6 +372,21 @@ pub struct TaskStruct {
      */
     pub vaddr_pool: MemPool,
 
+    /**
+     * 该进程通过mmap映射的虚拟内存区域
+     */
+    pub vma_list: VmaList,
+
+    /**
+     * 堆（brk）的起始地址，也就是程序加载的最高地址（按页对齐）。0表示该进程没有brk堆
+     */
+    pub heap_start: usize,
+
+    /**
+     * 堆（brk）当前的结束地址（program break）
+     */
+    pub heap_end: usize,
+
     /**
      * PCB页的地址
      */
@@ -315,6 +407,31 @@ pub struct TaskStruct {
      */
     pub exit_status: Option<TaskExitStatus>,
 
+    /**
+     * 该任务被哪个信号结束。正常退出的任务为None
+     */
+    pub term_signal: Option<signal::SignalNo>,
+
+    /**
+     * 待处理的信号。第n位为1，表示收到了第n号信号，还没有处理
+     */
+    pub pending_signals: u32,
+
+    /**
+     * 每个信号的动作。0表示默认动作，1表示忽略，其他值是用户处理函数的地址
+     */
+    pub signal_actions: [u32; signal::SIGNAL_CNT],
+
+    /**
+     * 调用用户信号处理函数的跳板函数地址
+     */
+    pub signal_trampoline: u32,
+
+    /**
+     * 停止之后，父进程有没有通过wait得知
+     */
+    pub stop_reported: bool,
+
     /**
      * 栈边界的魔数
      */
@@ -336,10 +453,13 @@ impl TaskStruct {
     #[inline(never)]
     fn init(&mut self, pid: Pid, name: &str, priority: u8, kernel_stack: u32, pcb_page_addr: u32) {
         self.pid = pid;
+        self.pgid = pid;
+        self.sid = pid;
         self.kernel_stack = kernel_stack;
         cstr_write!(&mut self.name, "{}", name);
         self.task_status = TaskStatus::TaskReady;
         self.priority = priority;
+        self.mlfq_level = 0;
         self.stack_magic = constants::TASK_STRUCT_STACK_MAGIC;
         self.left_ticks = priority;
         self.elapsed_ticks = 0;
@@ -348,6 +468,12 @@ impl TaskStruct {
         self.all_tag = LinkedNode::new();
         self.pcb_page_addr = pcb_page_addr;
         self.fd_table = TaskFileDescriptorTable::new();
+        self.exit_status = Option::None;
+        self.term_signal = Option::None;
+        self.pending_signals = 0;
+        self.interruptible_list = ptr::null_mut();
+        signal::reset_actions(self);
+        self.stop_reported = false;
     }
 
     #[inline(never)]
@@ -369,7 +495,30 @@ impl TaskStruct {
      * 重置该任务剩余的ticks
      */
     pub fn reset_ticks(&mut self) {
-        self.left_ticks = self.priority;
+        self.left_ticks = self.time_slice();
+    }
+
+    /**
+     * 该任务在当前级别的时间片。每往下一级，时间片翻倍
+     */
+    pub fn time_slice(&self) -> u8 {
+        self.priority << self.mlfq_level
+    }
+
+    /**
+     * 用完了整个时间片，降一级
+     */
+    pub fn demote(&mut self) {
+        if (self.mlfq_level as usize) + 1 < constants::MLFQ_LEVEL_CNT {
+            self.mlfq_level += 1;
+        }
+    }
+
+    /**
+     * 阻塞（等待I/O）之后被唤醒，升一级
+     */
+    pub fn boost(&mut self) {
+        self.mlfq_level = self.mlfq_level.saturating_sub(1);
     }
 
     pub fn activate_process(&mut self) {
@@ -467,6 +616,10 @@ pub enum TaskStatus {
     TaskBlocked,
     TaskWaiting,
     TaskHanging,
+    /**
+     * 收到了停止信号（SIGSTOP、SIGTSTP、SIGTTIN），直到收到SIGCONT
+     */
+    TaskStopped,
     TaskDied,
 }
 
@@ -478,6 +631,7 @@ impl TaskStatus {
             TaskStatus::TaskBlocked => "Blocked",
             TaskStatus::TaskWaiting => "Waiting",
             TaskStatus::TaskHanging => "Hanging",
+            TaskStatus::TaskStopped => "Stopped",
             TaskStatus::TaskDied => "Died",
         }
     }
@@ -643,18 +797,40 @@ impl InterruptStack {
         self.ss = SegmentSelector::UserDataSelector as u32;
     }
 
+    /**
+     * exec的时候，初始化中断栈
+     *    - entry: 程序的入口地址
+     *    - user_esp: 用户栈的栈顶。栈顶依次是argc、argv[]、envp[]
+     */
     #[inline(never)]
-    pub fn init_exec(&mut self, entry: u32, args: Option<&str>) {
-        if args.is_some() {
-            let args = args.unwrap();
-            self.ebx = args.as_ptr() as u32;
-            self.ecx = args.len().try_into().unwrap();
-        } else {
-            self.ebx = 0;
-            self.ecx = 0;
-        }
+    pub fn init_exec(&mut self, entry: u32, user_esp: u32) {
+        self.ebx = 0;
+        self.ecx = 0;
         self.eip = entry;
-        self.esp = 0xC0000000;
+        self.esp = user_esp;
+    }
+
+    /**
+     * 这个中断，是不是从用户态（特权级3）进入的
+     */
+    pub fn from_user(&self) -> bool {
+        self.cs & 0x3 == 0x3
+    }
+
+    pub fn get_eip(&self) -> u32 {
+        self.eip
+    }
+
+    pub fn get_esp(&self) -> u32 {
+        self.esp
+    }
+
+    /**
+     * 修改中断返回用户态之后，执行的地址和栈顶
+     */
+    pub fn set_user_context(&mut self, eip: u32, esp: u32) {
+        self.eip = eip;
+        self.esp = esp;
     }
 
     /**
@@ -705,13 +881,18 @@ impl Display for InterruptStack {
 pub fn wake_thread(task: &mut TaskStruct)  {
     // 关闭中断
     let old_status = instruction::disable_interrupt();
-    // 只能是这三种状态之一
-    let allow_status = [TaskStatus::TaskBlocked, TaskStatus::TaskHanging, TaskStatus::TaskWaiting];
+    // 只能是这几种状态之一
+    let allow_status = [TaskStatus::TaskBlocked, TaskStatus::TaskHanging, TaskStatus::TaskWaiting, TaskStatus::TaskStopped];
     if !allow_status.contains(&task.task_status) {
         MY_PANIC!("could not wake up thread; name:{}, status:{:?}", task.get_name(), &task.task_status);
     }
 
     ASSERT!(task.task_status != TaskStatus::TaskReady);
+    // 因为等待I/O（键盘、硬盘、管道等）阻塞的任务，被唤醒的时候升一级，时间片重新计算
+    if task.task_status == TaskStatus::TaskBlocked {
+        task.boost();
+        task.reset_ticks();
+    }
     // 设置为就绪状态
     task.task_status = TaskStatus::TaskReady;
     // 放入就绪队列
@@ -118,7 +118,7 @@ pub fn thread_yield() {
 
     // 关闭中断。防止该线程重复加入就绪队列
     let old_status = instruction::disable_interrupt();
-    ASSERT!(!thread::get_ready_thread().contains(&cur_task.general_tag));
+    ASSERT!(!thread::in_ready_thread(cur_task));
     
     // 当前线程加入就绪队列
     thread::append_read_thread(cur_task);
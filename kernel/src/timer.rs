@@ -0,0 +1,133 @@
+use os_in_rust_common::{constants, instruction, linked_list::LinkedList, racy_cell::RacyCell, ASSERT};
+
+use crate::{scheduler, thread::{self, TaskStatus, TaskStruct}};
+
+/**
+ * ************************************************************
+ * *       本文件是内核的时间：全局的tick计数，以及定时唤醒（时间轮）
+ * ************************************************************
+ *
+ * 时间轮一共有TIMER_WHEEL_SLOT_CNT个槽，每个槽是一个链表，放着睡眠中的任务：
+ *   - 任务在第wake_tick个tick醒来，那么放在第(wake_tick % TIMER_WHEEL_SLOT_CNT)个槽
+ *   - 每个tick，只检查当前tick对应的那个槽，把到期的任务唤醒
+ *   - 睡眠时间超过一圈的任务，会在槽里多待几圈
+ */
+
+/**
+ * 时间轮的槽的数量
+ */
+const TIMER_WHEEL_SLOT_CNT: usize = 64;
+
+/**
+ * 系统启动以来，一共经过了多少个tick
+ */
+static TICKS: RacyCell<u64> = RacyCell::new(0);
+
+/**
+ * 空的链表，用来初始化时间轮
+ */
+const EMPTY_SLOT: LinkedList = LinkedList::new();
+
+/**
+ * 时间轮。每个槽是一个睡眠任务的链表
+ */
+static TIMER_WHEEL: RacyCell<[LinkedList; TIMER_WHEEL_SLOT_CNT]> = RacyCell::new([EMPTY_SLOT; TIMER_WHEEL_SLOT_CNT]);
+
+/**
+ * 系统启动以来的tick数量
+ */
+#[inline(never)]
+pub fn ticks() -> u64 {
+    let old_status = instruction::disable_interrupt();
+    let ticks = *unsafe { TICKS.get_mut() };
+    instruction::set_interrupt(old_status);
+    ticks
+}
+
+/**
+ * 系统启动以来经过的毫秒数
+ */
+#[inline(never)]
+pub fn uptime_ms() -> u64 {
+    self::ticks() * 1000 / constants::TIMER_INTR_FREQUENCY as u64
+}
+
+/**
+ * 毫秒数，转成tick数（向上取整）
+ */
+pub fn ms_to_ticks(ms: u32) -> u64 {
+    (ms as u64 * constants::TIMER_INTR_FREQUENCY as u64 + 999) / 1000
+}
+
+/**
+ * 时钟中断的时候调用。tick数加一，唤醒到期的任务
+ */
+#[inline(never)]
+pub fn tick() {
+    ASSERT!(!instruction::is_intr_on());
+    let ticks = unsafe { TICKS.get_mut() };
+    *ticks += 1;
+    let now = *ticks;
+
+    let slot = self::get_slot(now);
+    // 唤醒之后，任务的tag会放入就绪队列，因此每次都重新遍历这个槽
+    loop {
+        // 还没到期的任务（要多等几圈），留在槽里
+        let expired_tag = slot.iter().find(|&task_tag| unsafe { &*TaskStruct::parse_by_general_tag(&*task_tag) }.wake_tick <= now);
+        if expired_tag.is_none() {
+            break;
+        }
+        let task = unsafe { &mut *TaskStruct::parse_by_general_tag(&*expired_tag.unwrap()) };
+        slot.remove(&task.general_tag);
+        thread::wake_thread(task);
+    }
+}
+
+/**
+ * 当前任务睡眠ms毫秒。睡眠期间不占用CPU
+ */
+#[inline(never)]
+pub fn sleep_ms(ms: u32) {
+    self::sleep_ticks(self::ms_to_ticks(ms));
+}
+
+/**
+ * 当前任务睡眠ticks个tick（至少1个tick）
+ */
+#[inline(never)]
+pub fn sleep_ticks(ticks: u64) {
+    let old_status = instruction::disable_interrupt();
+    let cur_task = &mut thread::current_thread().task_struct;
+    cur_task.wake_tick = *unsafe { TICKS.get_mut() } + ticks.max(1);
+
+    // 放入时间轮对应的槽，然后阻塞
+    let slot = self::get_slot(cur_task.wake_tick);
+    ASSERT!(!slot.contains(&cur_task.general_tag));
+    slot.append(&mut cur_task.general_tag);
+    scheduler::block_thread(cur_task, TaskStatus::TaskBlocked);
+
+    instruction::set_interrupt(old_status);
+}
+
+/**
+ * 如果任务正在时间轮中睡眠，提前唤醒它（比如收到了信号）。返回是否唤醒了
+ */
+#[inline(never)]
+pub fn cancel_sleep(task: &mut TaskStruct) -> bool {
+    let old_status = instruction::disable_interrupt();
+    let slot = self::get_slot(task.wake_tick);
+    let sleeping = task.task_status == TaskStatus::TaskBlocked && slot.contains(&task.general_tag);
+    if sleeping {
+        slot.remove(&task.general_tag);
+        thread::wake_thread(task);
+    }
+    instruction::set_interrupt(old_status);
+    sleeping
+}
+
+/**
+ * 某个tick对应的时间轮的槽
+ */
+fn get_slot(tick: u64) -> &'static mut LinkedList {
+    unsafe { &mut TIMER_WHEEL.get_mut()[(tick % TIMER_WHEEL_SLOT_CNT as u64) as usize] }
+}
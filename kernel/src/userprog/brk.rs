@@ -0,0 +1,50 @@
+use os_in_rust_common::constants;
+
+use crate::{memory, thread};
+
+/**
+ * brk系统调用。把当前进程的堆（program break）调整到new_end，返回调整后的结束地址
+ *    - new_end为0（或者小于堆的起始地址），只查询当前的结束地址
+ *    - 堆增长：新增的页只占用虚拟地址，物理页在缺页的时候才分配
+ *    - 堆收缩：不再使用的页释放掉
+ * 无法调整（地址被占用、超过范围），返回原来的结束地址
+ */
+#[inline(never)]
+pub fn brk(new_end: usize) -> usize {
+    let cur_task = &mut thread::current_thread().task_struct;
+    let old_end = cur_task.heap_end;
+    // 内核线程，或者不是exec加载的程序，没有brk堆
+    if cur_task.pgdir.is_null() || cur_task.heap_start == 0 {
+        return old_end;
+    }
+    if new_end < cur_task.heap_start || new_end >= constants::USER_STACK_LIMIT_ADDR {
+        return old_end;
+    }
+
+    let old_page_end = self::page_align_up(old_end);
+    let new_page_end = self::page_align_up(new_end);
+    if new_page_end > old_page_end {
+        // 新增的页，必须都还没有被占用（比如被malloc、mmap占用了）
+        let page_in_use = (old_page_end .. new_page_end).step_by(constants::PAGE_SIZE as usize)
+            .any(|page_addr| cur_task.vaddr_pool.is_set(page_addr));
+        if page_in_use {
+            return old_end;
+        }
+        for page_addr in (old_page_end .. new_page_end).step_by(constants::PAGE_SIZE as usize) {
+            cur_task.vaddr_pool.addr_set(page_addr);
+        }
+    } else {
+        for page_addr in (new_page_end .. old_page_end).step_by(constants::PAGE_SIZE as usize) {
+            memory::unmap_user_page(&mut cur_task.vaddr_pool, page_addr);
+        }
+    }
+    cur_task.heap_end = new_end;
+    new_end
+}
+
+/**
+ * 向上按页对齐
+ */
+pub fn page_align_up(addr: usize) -> usize {
+    (addr + constants::PAGE_SIZE as usize - 1) & !(constants::PAGE_SIZE as usize - 1)
+}
@@ -2,10 +2,11 @@ use core::ops::DerefMut;
 
 use os_in_rust_common::{constants, paging::PageTable, pool::MemPool, printk};
 
-use crate::{filesystem::{FileDescriptor, FileDescriptorType, StdFileDescriptor}, memory, pid_allocator::Pid, pipe, scheduler, thread::{self, TaskStatus, TaskStruct}};
+use crate::{filesystem::{FileDescriptor, FileDescriptorType, StdFileDescriptor}, memory, pipe, process, scheduler, signal::{self, SignalNo}, thread::{self, TaskStatus, TaskStruct}};
 
 pub type TaskExitStatus = u8;
 
+
 /**
  * exit系统调用。当某个用户进程调用exit，那么就需要释放这个用户进程的空间
  *   当前任务的所有资源都释放，只剩下当前任务的PCB还在
@@ -36,6 +37,9 @@ pub fn exit(status: TaskExitStatus) {
     self::release_vaddr_pool(&cur_task.vaddr_pool);
     cur_task.check_stack_magic("failed to release vaddr pool");
 
+    // 释放虚拟内存区域列表，关闭映射的文件
+    cur_task.vma_list.release();
+
     // 释放这个任务的页表
     self::release_dir_table(unsafe { &*cur_task.pgdir });
     cur_task.check_stack_magic("failed to release page dir table");
@@ -49,17 +53,47 @@ pub fn exit(status: TaskExitStatus) {
 }
 
 /**
- * 把cur_task任务的子进程过继给init进程
+ * 当前进程被信号sig结束。父进程wait的时候，可以知道是被哪个信号结束的
+ */
+#[inline(never)]
+pub fn exit_by_signal(sig: SignalNo) {
+    let cur_task = &mut thread::current_thread().task_struct;
+    if cur_task.task_status != TaskStatus::TaskHanging {
+        cur_task.term_signal = Option::Some(sig);
+    }
+    self::exit(signal::exit_status_of(sig));
+}
+
+/**
+ * 把cur_task任务的所有子进程过继给init进程，由init进程回收
  */
 #[inline(never)]
 fn trans_children_to_init(task: &TaskStruct) {
-    let child = task.find_child();
-    if child.is_none() {
+    let init_pid = process::get_init_pid();
+    if init_pid.is_none() || init_pid.unwrap() == task.pid {
+        return;
+    }
+    let init_pid = init_pid.unwrap();
+    let mut has_hanging_child = false;
+    for tag in thread::get_all_thread().iter() {
+        let child = unsafe { &mut *TaskStruct::parse_by_all_tag(&*tag) };
+        if child.parent_pid != Option::Some(task.pid) {
+            continue;
+        }
+        // 把这个任务的父，设定为init进程
+        child.parent_pid = Option::Some(init_pid);
+        if child.task_status == TaskStatus::TaskHanging {
+            has_hanging_child = true;
+        }
+    }
+    // 已经退出的子进程，需要唤醒init来回收
+    if !has_hanging_child {
         return;
     }
-    // 把这个任务的父，设定为init进程
-    // TODO 怎么确保init进程的pid就是1？
-    child.unwrap().parent_pid = Option::Some(Pid::new(1));
+    let init_task = thread::find_alive_task(init_pid);
+    if init_task.is_some() && init_task.as_ref().unwrap().task_status == TaskStatus::TaskWaiting {
+        thread::wake_thread(init_task.unwrap());
+    }
 }
 
 /**
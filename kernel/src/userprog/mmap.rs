@@ -0,0 +1,125 @@
+use os_in_rust_common::{constants, utils, ASSERT};
+
+use crate::{common::mmap_dto::MmapParam, filesystem::{self, FileError}, memory::{self, Vma, VmaBacking}, thread};
+
+#[derive(Debug)]
+pub enum MmapError {
+    Init,
+    /**
+     * 长度为0
+     */
+    InvalidLength,
+    /**
+     * 地址或者文件偏移量没有按页对齐
+     */
+    NotAligned,
+    /**
+     * 文件映射只支持只读
+     */
+    PermissionDenied,
+    /**
+     * 虚拟地址空间不够了
+     */
+    AddressExhausted,
+    /**
+     * VMA的数量超过了限制
+     */
+    TooManyRegions,
+    /**
+     * 要取消映射的范围，不在任何一个VMA中
+     */
+    NotMapped,
+    FileError(FileError),
+}
+
+/**
+ * mmap系统调用。在当前进程的用户空间中，申请一段虚拟地址，记录为一个VMA
+ *    - 这里只占用虚拟地址，物理页在缺页的时候才分配、填充
+ *    - 文件映射，会把文件的inode再打开一次，直到munmap或者进程退出
+ * 返回映射的起始地址
+ */
+#[inline(never)]
+pub fn mmap(param: &MmapParam) -> Result<usize, MmapError> {
+    let cur_task = &mut thread::current_thread().task_struct;
+    // 确保是用户进程在调用
+    ASSERT!(!cur_task.pgdir.is_null());
+    if param.len == 0 {
+        return Result::Err(MmapError::InvalidLength);
+    }
+    if param.len > constants::KERNEL_ADDR_START - constants::USER_PROCESS_ADDR_START {
+        return Result::Err(MmapError::AddressExhausted);
+    }
+    if !cur_task.vma_list.has_room(1) {
+        return Result::Err(MmapError::TooManyRegions);
+    }
+
+    // 数据来源
+    let backing = if param.fd.is_some() {
+        if param.prot & memory::PROT_WRITE == memory::PROT_WRITE {
+            return Result::Err(MmapError::PermissionDenied);
+        }
+        if param.offset % constants::PAGE_SIZE != 0 {
+            return Result::Err(MmapError::NotAligned);
+        }
+        let opened_file = match filesystem::get_file_by_fd(param.fd.unwrap()) {
+            Result::Ok(opened_file) => opened_file,
+            Result::Err(err) => return Result::Err(MmapError::FileError(err)),
+        };
+        VmaBacking::File { i_no: opened_file.get_inode().i_no, offset: param.offset }
+    } else {
+        VmaBacking::Anonymous
+    };
+
+    // 占用一段虚拟地址
+    let page_cnt = utils::div_ceil(param.len as u32, constants::PAGE_SIZE) as usize;
+    let start = cur_task.vaddr_pool.apply(page_cnt);
+    if start.is_err() {
+        return Result::Err(MmapError::AddressExhausted);
+    }
+    let start = start.unwrap();
+
+    // 文件映射，inode多打开一次。就算文件被关闭了，映射依然有效
+    if param.fd.is_some() {
+        filesystem::get_file_by_fd(param.fd.unwrap()).unwrap().reopen();
+    }
+    let vma = Vma::new(start, page_cnt * constants::PAGE_SIZE as usize, param.prot, backing);
+    ASSERT!(cur_task.vma_list.add(vma));
+    return Result::Ok(start);
+}
+
+/**
+ * munmap系统调用。取消[addr, addr + len)范围的映射
+ *    - 范围内已经映射的物理页释放掉，虚拟地址还给地址池
+ *    - 跨过这个范围的VMA，会被截断或者拆分
+ */
+#[inline(never)]
+pub fn munmap(addr: usize, len: usize) -> Result<(), MmapError> {
+    let cur_task = &mut thread::current_thread().task_struct;
+    if len == 0 {
+        return Result::Err(MmapError::InvalidLength);
+    }
+    if addr % constants::PAGE_SIZE as usize != 0 {
+        return Result::Err(MmapError::NotAligned);
+    }
+    if addr >= constants::KERNEL_ADDR_START || len > constants::KERNEL_ADDR_START - addr {
+        return Result::Err(MmapError::NotMapped);
+    }
+    let end = addr + utils::div_ceil(len as u32, constants::PAGE_SIZE) as usize * constants::PAGE_SIZE as usize;
+    if !cur_task.vma_list.iter().any(|vma| vma.overlaps(addr, end)) {
+        return Result::Err(MmapError::NotMapped);
+    }
+    // 从中间拆分一个VMA，需要多一个位置
+    if !cur_task.vma_list.has_room(1) {
+        return Result::Err(MmapError::TooManyRegions);
+    }
+
+    // 只释放属于某个VMA的页
+    for page_addr in (addr .. end).step_by(constants::PAGE_SIZE as usize) {
+        if cur_task.vma_list.find(page_addr).is_none() {
+            continue;
+        }
+        memory::unmap_user_page(&mut cur_task.vaddr_pool, page_addr);
+    }
+    cur_task.vma_list.remove_range(addr, end);
+    return Result::Ok(());
+}
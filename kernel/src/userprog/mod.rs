@@ -1,7 +1,26 @@
 mod exit;
 mod wait;
+mod mmap;
+mod brk;
+mod pgrp;
 
 
 pub use exit::exit;
 pub use exit::TaskExitStatus;
+pub use exit::exit_by_signal;
 pub use wait::wait;
+pub use wait::wait_pid;
+pub use wait::WaitResult;
+pub use wait::WaitTarget;
+pub use wait::ChildStatus;
+pub use wait::WNOHANG;
+pub use wait::WUNTRACED;
+pub use mmap::mmap;
+pub use mmap::munmap;
+pub use mmap::MmapError;
+pub use brk::brk;
+pub use brk::page_align_up;
+pub use pgrp::setpgid;
+pub use pgrp::getpgid;
+pub use pgrp::setsid;
+pub use pgrp::PgrpError;
@@ -0,0 +1,102 @@
+use crate::{pid_allocator::Pid, thread::{self, TaskStruct}};
+
+/**
+ * ************************************************************
+ * *       本文件是进程组和会话
+ * ************************************************************
+ *
+ * 每个进程属于一个进程组，每个进程组属于一个会话：
+ *   - fork出来的子进程，继承父进程的进程组和会话
+ *   - shell把一条命令（包括管道连接的所有命令）放在同一个进程组中，作为一个作业
+ *   - 键盘属于前台进程组（见signal模块），Ctrl+C、Ctrl+Z发送给整个前台进程组
+ */
+
+#[derive(Debug)]
+pub enum PgrpError {
+    Init,
+    /**
+     * 找不到这个进程
+     */
+    NoSuchProcess,
+    /**
+     * 不允许的操作。比如修改其他会话的进程、修改会话首进程的进程组
+     */
+    PermissionDenied,
+    /**
+     * 要加入的进程组，在当前会话中不存在
+     */
+    InvalidGroup,
+}
+
+/**
+ * setpgid系统调用。把进程pid放入进程组pgid
+ *   - pid: None表示当前进程。只能是当前进程，或者当前进程的子进程
+ *   - pgid: None表示以pid为组长，新建一个进程组
+ */
+#[inline(never)]
+pub fn setpgid(pid: Option<Pid>, pgid: Option<Pid>) -> Result<(), PgrpError> {
+    let cur_task = &thread::current_thread().task_struct;
+    let target = if pid.is_none() { thread::find_alive_task(cur_task.pid) } else { thread::find_alive_task(pid.unwrap()) };
+    if target.is_none() {
+        return Result::Err(PgrpError::NoSuchProcess);
+    }
+    let target = target.unwrap();
+    // 只能修改自己，或者自己的子进程
+    if target.pid != cur_task.pid && target.parent_pid != Option::Some(cur_task.pid) {
+        return Result::Err(PgrpError::NoSuchProcess);
+    }
+    // 不能修改其他会话的进程，也不能修改会话首进程
+    if target.sid != cur_task.sid || target.pid == target.sid {
+        return Result::Err(PgrpError::PermissionDenied);
+    }
+    let pgid = pgid.unwrap_or(target.pid);
+    // 加入已有的进程组，这个进程组必须在同一个会话中
+    if pgid != target.pid && !self::group_exists(pgid, cur_task.sid) {
+        return Result::Err(PgrpError::InvalidGroup);
+    }
+    target.pgid = pgid;
+    Result::Ok(())
+}
+
+/**
+ * getpgid系统调用。进程pid所在的进程组。pid为None表示当前进程
+ */
+#[inline(never)]
+pub fn getpgid(pid: Option<Pid>) -> Result<Pid, PgrpError> {
+    if pid.is_none() {
+        return Result::Ok(thread::current_thread().task_struct.pgid);
+    }
+    let task = thread::find_alive_task(pid.unwrap());
+    if task.is_none() {
+        return Result::Err(PgrpError::NoSuchProcess);
+    }
+    Result::Ok(task.unwrap().pgid)
+}
+
+/**
+ * setsid系统调用。新建一个会话，当前进程是会话首进程，也是新进程组的组长。返回新的会话id
+ *   - 已经是进程组组长的进程，不能新建会话
+ */
+#[inline(never)]
+pub fn setsid() -> Result<Pid, PgrpError> {
+    let cur_task = &mut thread::current_thread().task_struct;
+    let is_group_leader = thread::get_all_thread().iter()
+        .map(|tag| unsafe { &*TaskStruct::parse_by_all_tag(&*tag) })
+        .any(|task| task.pgid == cur_task.pid);
+    if is_group_leader {
+        return Result::Err(PgrpError::PermissionDenied);
+    }
+    cur_task.sid = cur_task.pid;
+    cur_task.pgid = cur_task.pid;
+    Result::Ok(cur_task.sid)
+}
+
+/**
+ * 会话sid中，是否存在进程组pgid。已经退出、还没被回收的进程，也算在进程组中
+ */
+#[inline(never)]
+fn group_exists(pgid: Pid, sid: Pid) -> bool {
+    thread::get_all_thread().iter()
+        .map(|tag| unsafe { &*TaskStruct::parse_by_all_tag(&*tag) })
+        .any(|task| task.pgid == pgid && task.sid == sid)
+}
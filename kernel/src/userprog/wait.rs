@@ -1,50 +1,160 @@
-use crate::{pid_allocator::Pid, scheduler, thread::{self, TaskStatus, TaskStruct}, thread_management};
+use crate::{pid_allocator::Pid, scheduler, signal::{self, SignalNo}, thread::{self, TaskStatus, TaskStruct}, thread_management};
 
 use super::TaskExitStatus;
 
+/**
+ * waitpid的选项：子进程都还在运行的时候，不阻塞，直接返回Running
+ */
+pub const WNOHANG: u32 = 0x1;
+
+/**
+ * waitpid的选项：子进程停止了，也要报告
+ */
+pub const WUNTRACED: u32 = 0x2;
+
 /**
  * 父进程等待子进程，使用wait等待，然后给子进程“收尸”
  */
 #[inline(never)]
 pub fn wait() -> Option<(Pid, Option<TaskExitStatus>)> {
-    let cur_task = &mut thread::current_thread().task_struct;
-    
-    loop {
-        // 找已经结束的子进程
-        let hanging_child = self::find_hanging_child(cur_task.pid);
-        if hanging_child.is_some() {
-            let hanging_child = hanging_child.unwrap();
-            // 子进程的信息
-            let child_exit_status = hanging_child.exit_status;
-            let child_pid = hanging_child.pid;
-
-            // 把child给回收掉（收尸）
-            thread_management::free_thread(hanging_child);
-
-            return Option::Some((child_pid, child_exit_status));
+    match self::wait_pid(WaitTarget::Any, 0) {
+        WaitResult::Exited(child_pid, child_status) => Option::Some((child_pid, Option::Some(child_status.exit_code()))),
+        _ => Option::None,
+    }
+}
+
+/**
+ * 要等待哪些子进程
+ */
+#[derive(Debug, Clone, Copy)]
+pub enum WaitTarget {
+    /**
+     * 任意一个子进程
+     */
+    Any,
+    /**
+     * 指定pid的子进程
+     */
+    Pid(Pid),
+    /**
+     * 进程组中的任意一个子进程
+     */
+    Group(Pid),
+}
+
+impl WaitTarget {
+    fn matches(&self, task: &TaskStruct) -> bool {
+        match self {
+            WaitTarget::Any => true,
+            WaitTarget::Pid(pid) => task.pid == *pid,
+            WaitTarget::Group(pgid) => task.pgid == *pgid,
         }
+    }
+}
 
-        // 如果连子进程都没有，结束
-        let child = cur_task.find_child();
-        if child.is_none() {
-            return Option::None;
+/**
+ * 子进程是怎么结束的
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChildStatus {
+    /**
+     * 调用exit正常退出，退出码
+     */
+    Exited(TaskExitStatus),
+    /**
+     * 被信号结束
+     */
+    Signaled(SignalNo),
+}
+
+impl ChildStatus {
+    /**
+     * 子进程的退出码。被信号结束的，是128 + 信号编号
+     */
+    pub fn exit_code(&self) -> TaskExitStatus {
+        match self {
+            ChildStatus::Exited(code) => *code,
+            ChildStatus::Signaled(sig) => signal::exit_status_of(*sig),
         }
+    }
 
-        // 阻塞当前线程
-        scheduler::block_thread(cur_task, TaskStatus::TaskWaiting);
+    /**
+     * 根据已经退出的任务，得到它的结束方式
+     */
+    pub fn of(task: &TaskStruct) -> Self {
+        if task.term_signal.is_some() {
+            return ChildStatus::Signaled(task.term_signal.unwrap());
+        }
+        ChildStatus::Exited(task.exit_status.unwrap_or(0))
     }
 }
 
+/**
+ * 等待子进程的结果
+ */
+#[derive(Debug, Clone, Copy)]
+pub enum WaitResult {
+    /**
+     * 子进程结束了，并且已经被回收
+     */
+    Exited(Pid, ChildStatus),
+    /**
+     * 子进程停止了（需要WUNTRACED）
+     */
+    Stopped(Pid),
+    /**
+     * 子进程都还在运行（需要WNOHANG）
+     */
+    Running,
+    /**
+     * 没有符合条件的子进程
+     */
+    NoChild,
+    /**
+     * 等待的时候收到了需要处理的信号
+     */
+    Interrupted,
+}
 
+/**
+ * 等待符合target的子进程结束（或者停止），并且回收结束的子进程
+ *   - options: WNOHANG、WUNTRACED的组合
+ * 停止的子进程，只会报告一次，直到它再次停止
+ */
 #[inline(never)]
-fn find_hanging_child(pid: Pid) -> Option<&'static mut TaskStruct> {
-    // 遍历所有任务
-    for tag in thread::get_all_thread().iter() {
-        let task = unsafe {&mut *TaskStruct::parse_by_all_tag(&*tag)};
-        let task_parent_id = task.parent_pid;
-        if task_parent_id.is_some() && task_parent_id.unwrap() == pid && TaskStatus::TaskHanging == task.task_status {
-            return Option::Some(task);
+pub fn wait_pid(target: WaitTarget, options: u32) -> WaitResult {
+    let cur_task = &mut thread::current_thread().task_struct;
+    loop {
+        let mut has_child = false;
+        for tag in thread::get_all_thread().iter() {
+            let task = unsafe { &mut *TaskStruct::parse_by_all_tag(&*tag) };
+            if task.parent_pid != Option::Some(cur_task.pid) || !target.matches(task) {
+                continue;
+            }
+            has_child = true;
+            // 结束了，回收
+            if task.task_status == TaskStatus::TaskHanging {
+                let (child_pid, child_status) = (task.pid, ChildStatus::of(task));
+                thread_management::free_thread(task);
+                return WaitResult::Exited(child_pid, child_status);
+            }
+            // 停止了，还没有报告过
+            if options & WUNTRACED != 0 && task.task_status == TaskStatus::TaskStopped && !task.stop_reported {
+                task.stop_reported = true;
+                return WaitResult::Stopped(task.pid);
+            }
         }
+        if !has_child {
+            return WaitResult::NoChild;
+        }
+        if options & WNOHANG != 0 {
+            return WaitResult::Running;
+        }
+        // 有需要处理的信号，不再等待。回到用户态之后处理信号
+        if signal::has_deliverable(cur_task) {
+            return WaitResult::Interrupted;
+        }
+        // 阻塞当前线程，子进程退出、停止，或者收到信号的时候唤醒
+        scheduler::block_thread(cur_task, TaskStatus::TaskWaiting);
     }
-    return Option::None;
 }
@@ -8,28 +8,43 @@ use kernel::sys_call;
 
 use crate::env;
 
+/**
+ * 用户进程的入口
+ * 内核exec的时候，栈顶依次是：argc、argv[]、envp[]。
+ * 因此这里不能有任何函数序言，直接把栈顶的地址当作参数，交给start_main处理
+ */
+#[naked]
 #[no_mangle]
-#[link_section = ".start"]
-pub fn _start() {
-    // 参数的地址
-    let arg_addr: u32;
-    // 参数的长度
-    let arg_len: u32;
+#[cfg(all(not(test), target_arch = "x86"))]
+pub extern "C" fn _start() {
     unsafe {
         asm!(
-            "mov {0:e}, ebx",
-            "mov {1:e}, ecx",
-            out(reg) arg_addr,
-            out(reg) arg_len,
+            // 把当前的栈顶（argc所在的地址）作为参数
+            "push esp",
+            "call {}",
+            sym start_main,
+            options(noreturn),
         )
     }
+}
 
-    // 执行这个用户进程，传递的参数
-    let args = unsafe { core::str::from_utf8(core::slice::from_raw_parts(arg_addr as *const u8, arg_len as usize)) };
-    if args.is_ok() {
-        // 如果有参数，那么就放到容器中
-        env::set_args(args.unwrap());
-    }
+#[cfg(all(not(target_arch = "x86")))]
+pub extern "C" fn _start() {
+    todo!()
+}
+
+/**
+ * 解析初始栈中的参数和环境变量，然后调用main函数
+ *   - stack_top: 用户进程开始运行时的栈顶
+ */
+#[inline(never)]
+extern "C" fn start_main(stack_top: *const u32) {
+    let argc = unsafe { *stack_top } as usize;
+    // argv紧跟在argc后面
+    let argv = unsafe { stack_top.add(1) } as usize;
+    // envp跟在argv的NULL后面
+    let envp = unsafe { stack_top.add(1 + argc + 1) } as usize;
+    env::init(argc, argv, envp);
 
     // 调用main函数
     unsafe {
@@ -39,4 +54,4 @@ pub fn _start() {
     }
     // 执行系统调用退出
     sys_call::exit(0);
-}
\ No newline at end of file
+}
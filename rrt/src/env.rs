@@ -1,22 +1,98 @@
-
 use os_in_rust_common::racy_cell::RacyCell;
 
 /**
- * 用来保存用户进程运行的时候，传递的参数
+ * 用户进程运行的时候，传递的参数个数
+ */
+static ARGC: RacyCell<usize> = RacyCell::new(0);
+
+/**
+ * 参数数组argv的地址。每一项都是一个以\0结尾的字符串的地址
+ */
+static ARGV: RacyCell<usize> = RacyCell::new(0);
+
+/**
+ * 环境变量数组envp的地址。以NULL结尾，每一项形如KEY=VALUE
  */
-static ARGS: RacyCell<Option<&str>> = RacyCell::new(Option::None);
+static ENVP: RacyCell<usize> = RacyCell::new(0);
 
 /**
- * 设置参数
+ * 保存进程的参数和环境变量（进程启动的时候，从初始栈中解析）
  */
-pub fn set_args(args: &'static str) {
-    *unsafe { ARGS.get_mut() } = Option::Some(args);
+pub fn init(argc: usize, argv: usize, envp: usize) {
+    *unsafe { ARGC.get_mut() } = argc;
+    *unsafe { ARGV.get_mut() } = argv;
+    *unsafe { ENVP.get_mut() } = envp;
 }
 
 /**
- * 获取参数
+ * 进程参数的迭代器。第一个参数是程序的路径
  */
-pub fn get_args() -> Option<&'static str> {
-    let args = unsafe { ARGS.get_mut() };
-    args.as_deref()
-}
\ No newline at end of file
+pub struct Args {
+    idx: usize,
+    argc: usize,
+    argv: usize,
+}
+
+impl Iterator for Args {
+    type Item = &'static str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.idx >= self.argc {
+            return Option::None;
+        }
+        let arg_addr = unsafe { *(self.argv as *const u32).add(self.idx) } as usize;
+        self.idx += 1;
+        Option::Some(self::read_cstr(arg_addr))
+    }
+}
+
+/**
+ * 获取进程的参数
+ */
+pub fn args() -> Args {
+    Args {
+        idx: 0,
+        argc: *unsafe { ARGC.get_mut() },
+        argv: *unsafe { ARGV.get_mut() },
+    }
+}
+
+/**
+ * 获取某个环境变量的值
+ */
+pub fn var(name: &str) -> Option<&'static str> {
+    let envp = *unsafe { ENVP.get_mut() };
+    if envp == 0 {
+        return Option::None;
+    }
+    let mut idx = 0;
+    loop {
+        let env_addr = unsafe { *(envp as *const u32).add(idx) } as usize;
+        // 遇到NULL，环境变量遍历完了
+        if env_addr == 0 {
+            return Option::None;
+        }
+        idx += 1;
+        let env = self::read_cstr(env_addr);
+        let kv = env.split_once("=");
+        if kv.is_none() {
+            continue;
+        }
+        let (key, value) = kv.unwrap();
+        if key == name {
+            return Option::Some(value);
+        }
+    }
+}
+
+/**
+ * 读取addr处，以\0结尾的字符串
+ */
+fn read_cstr(addr: usize) -> &'static str {
+    let mut len = 0;
+    while unsafe { *((addr + len) as *const u8) } != 0 {
+        len += 1;
+    }
+    let bytes = unsafe { core::slice::from_raw_parts(addr as *const u8, len) };
+    core::str::from_utf8(bytes).unwrap_or("")
+}
@@ -0,0 +1,179 @@
+use core::{alloc::{GlobalAlloc, Layout}, mem::{align_of, size_of}, ptr};
+
+use kernel::sys_call;
+use os_in_rust_common::racy_cell::RacyCell;
+
+/**
+ * 用户进程的堆内存分配器。基于brk系统调用
+ *
+ * 空闲的内存块，按照地址从小到大串成一个链表（首次适配）：
+ *   - 分配：找到第一个放得下的空闲块，切出需要的部分，剩下的部分还是空闲块
+ *   - 释放：放回链表，跟前后相邻的空闲块合并
+ *   - 放不下：通过brk把堆扩大，新的空间作为空闲块
+ * 释放的时候，根据Layout重新计算块的大小，因此不需要额外的块头
+ */
+
+/**
+ * 每次扩大堆，至少扩大这么多字节
+ */
+const HEAP_GROW_MIN: usize = 4096;
+
+/**
+ * 空闲块。放在空闲内存的开头
+ */
+struct FreeBlock {
+    size: usize,
+    next: *mut FreeBlock,
+}
+
+/**
+ * 最小的块大小。小于这个大小的空间，放不下空闲块的信息
+ */
+const MIN_BLOCK_SIZE: usize = size_of::<FreeBlock>();
+
+pub struct BrkAllocator {
+    /**
+     * 空闲块链表的头
+     */
+    free_list: RacyCell<*mut FreeBlock>,
+}
+
+unsafe impl Sync for BrkAllocator {}
+
+impl BrkAllocator {
+    pub const fn new() -> Self {
+        Self {
+            free_list: RacyCell::new(ptr::null_mut()),
+        }
+    }
+
+    /**
+     * 实际分配的大小和对齐。至少要能放下一个空闲块
+     */
+    fn size_align(layout: Layout) -> (usize, usize) {
+        let align = layout.align().max(align_of::<FreeBlock>());
+        let size = self::align_up(layout.size().max(MIN_BLOCK_SIZE), align_of::<FreeBlock>());
+        (size, align)
+    }
+
+    /**
+     * 从空闲链表中，切出size个字节（起始地址按照align对齐）。找不到，返回None
+     */
+    #[inline(never)]
+    unsafe fn take_from_free_list(&self, size: usize, align: usize) -> Option<usize> {
+        let mut prev: *mut *mut FreeBlock = self.free_list.get_mut();
+        while !(*prev).is_null() {
+            let block = *prev;
+            let block_start = block as usize;
+            let block_end = block_start + (*block).size;
+
+            // 前面空出来的部分，要么为0，要么放得下一个空闲块
+            let mut alloc_start = self::align_up(block_start, align);
+            if alloc_start != block_start && alloc_start - block_start < MIN_BLOCK_SIZE {
+                alloc_start = self::align_up(block_start + MIN_BLOCK_SIZE, align);
+            }
+            let alloc_end = alloc_start + size;
+            // 放不下，或者后面剩下的部分放不下一个空闲块
+            if alloc_end > block_end || (block_end - alloc_end != 0 && block_end - alloc_end < MIN_BLOCK_SIZE) {
+                prev = &mut (*block).next;
+                continue;
+            }
+
+            // 从链表中摘下这个块，剩余的部分放回去
+            *prev = (*block).next;
+            if alloc_start > block_start {
+                self.insert_free_block(block_start, alloc_start - block_start);
+            }
+            if block_end > alloc_end {
+                self.insert_free_block(alloc_end, block_end - alloc_end);
+            }
+            return Option::Some(alloc_start);
+        }
+        Option::None
+    }
+
+    /**
+     * 把[addr, addr + size)放回空闲链表（按地址排序），跟相邻的空闲块合并
+     */
+    #[inline(never)]
+    unsafe fn insert_free_block(&self, addr: usize, size: usize) {
+        let mut prev: *mut *mut FreeBlock = self.free_list.get_mut();
+        let mut prev_block: *mut FreeBlock = ptr::null_mut();
+        // 找到第一个地址比addr大的块
+        while !(*prev).is_null() && (*prev as usize) < addr {
+            prev_block = *prev;
+            prev = &mut (**prev).next;
+        }
+        let next_block = *prev;
+
+        let block = addr as *mut FreeBlock;
+        block.write(FreeBlock { size, next: next_block });
+        *prev = block;
+
+        // 跟后一个块相邻，合并
+        if !next_block.is_null() && addr + size == next_block as usize {
+            (*block).size += (*next_block).size;
+            (*block).next = (*next_block).next;
+        }
+        // 跟前一个块相邻，合并
+        if !prev_block.is_null() && prev_block as usize + (*prev_block).size == addr {
+            (*prev_block).size += (*block).size;
+            (*prev_block).next = (*block).next;
+        }
+    }
+
+    /**
+     * 通过brk把堆扩大，至少能放下size + align个字节
+     */
+    #[inline(never)]
+    unsafe fn grow(&self, size: usize, align: usize) -> bool {
+        let old_end = sys_call::brk(0);
+        if old_end == 0 {
+            return false;
+        }
+        let grow_size = self::align_up((size + align).max(HEAP_GROW_MIN), HEAP_GROW_MIN);
+        let new_end = sys_call::brk(old_end + grow_size);
+        if new_end != old_end + grow_size {
+            return false;
+        }
+        self.insert_free_block(old_end, grow_size);
+        true
+    }
+}
+
+unsafe impl GlobalAlloc for BrkAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let (size, align) = Self::size_align(layout);
+        let addr = self.take_from_free_list(size, align);
+        if addr.is_some() {
+            return addr.unwrap() as *mut u8;
+        }
+        // 空闲块都放不下，扩大堆之后再试一次
+        if !self.grow(size, align) {
+            return ptr::null_mut();
+        }
+        match self.take_from_free_list(size, align) {
+            Option::Some(addr) => addr as *mut u8,
+            Option::None => ptr::null_mut(),
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let (size, _) = Self::size_align(layout);
+        self.insert_free_block(ptr as usize, size);
+    }
+}
+
+/**
+ * 向上对齐
+ */
+fn align_up(addr: usize, align: usize) -> usize {
+    (addr + align - 1) & !(align - 1)
+}
+
+/**
+ * 用户进程的全局分配器。Vec、String、Box等都从这里分配内存
+ */
+#[cfg(not(test))]
+#[global_allocator]
+static ALLOCATOR: BrkAllocator = BrkAllocator::new();
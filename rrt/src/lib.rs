@@ -1,5 +1,8 @@
 #![no_std]
 #![no_main]
+#![feature(naked_functions)]
 
 pub mod _start;
-pub mod env;
\ No newline at end of file
+pub mod env;
+pub mod heap;
+pub mod signal;
\ No newline at end of file
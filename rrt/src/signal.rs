@@ -0,0 +1,72 @@
+use core::arch::asm;
+
+use kernel::{signal::SignalNo, sys_call::{self, SigAction, SignalError}};
+
+/**
+ * 用户程序的信号处理函数
+ */
+pub type SignalHandler = extern "C" fn(SignalNo);
+
+/**
+ * 注册信号sig的处理函数。返回原来的动作
+ */
+#[inline(never)]
+pub fn signal(sig: SignalNo, handler: SignalHandler) -> Result<SigAction, SignalError> {
+    let action = SigAction::Handler {
+        handler: handler as usize as u32,
+        trampoline: sig_trampoline as usize as u32,
+    };
+    sys_call::signal(sig, action)
+}
+
+/**
+ * 忽略信号sig
+ */
+#[inline(never)]
+pub fn ignore(sig: SignalNo) -> Result<SigAction, SignalError> {
+    sys_call::signal(sig, SigAction::Ignore)
+}
+
+/**
+ * 信号sig恢复默认动作
+ */
+#[inline(never)]
+pub fn default(sig: SignalNo) -> Result<SigAction, SignalError> {
+    sys_call::signal(sig, SigAction::Default)
+}
+
+/**
+ * 信号处理的跳板函数。内核处理信号的时候，让进程回到用户态之后，从这里开始执行
+ * 进入的时候，栈顶依次是：信号编号、处理函数的地址、被打断的eip
+ * 因此这里不能有任何函数序言；并且要保存所有的寄存器和eflags，处理完之后原样恢复
+ */
+#[naked]
+#[cfg(all(not(test), target_arch = "x86"))]
+extern "C" fn sig_trampoline() {
+    unsafe {
+        asm!(
+            // 保存被打断时的eflags和所有寄存器
+            "pushfd",
+            "pushad",
+            // 取出信号编号和处理函数（跳过pushad的32字节和pushfd的4字节）
+            "mov eax, [esp + 36]",
+            "mov ecx, [esp + 40]",
+            // 调用处理函数
+            "push eax",
+            "call ecx",
+            "add esp, 4",
+            // 恢复寄存器和eflags
+            "popad",
+            "popfd",
+            // 跳过信号编号和处理函数（lea不会修改eflags），回到被打断的地方
+            "lea esp, [esp + 8]",
+            "ret",
+            options(noreturn),
+        )
+    }
+}
+
+#[cfg(all(not(target_arch = "x86")))]
+extern "C" fn sig_trampoline() {
+    todo!()
+}
@@ -0,0 +1,51 @@
+use std::{env, path::Path, process};
+
+use os_in_rust_common::domain::LbaAddr;
+use tests::fs_image::FsImage;
+
+/**
+ * 默认检查的硬盘镜像
+ */
+const DEFAULT_IMAGE_PATH: &str = "build/hd80M.img";
+
+/**
+ * 检查硬盘镜像中的文件系统
+ * 用法：fsck [-r] [硬盘镜像路径] [分区起始LBA]。没有指定分区，检查第一个逻辑分区（sdb5）
+ * 退出码（和Linux的fsck一样）：0没有问题；1问题都修复了；4还有没修复的问题；8检查失败
+ */
+fn main() {
+    let mut repair = false;
+    let mut positional: Vec<String> = Vec::new();
+    for arg in env::args().skip(1) {
+        if arg == "-r" || arg == "--repair" {
+            repair = true;
+        } else {
+            positional.push(arg);
+        }
+    }
+    let image_path = positional.get(0).map(String::as_str).unwrap_or(DEFAULT_IMAGE_PATH);
+    let part_lba = positional.get(1).map(|lba| LbaAddr::new(lba.parse::<u32>().expect("invalid partition lba")));
+
+    let mut fs = match FsImage::open(Path::new(image_path), part_lba, repair) {
+        Result::Ok(fs) => fs,
+        Result::Err(err) => {
+            eprintln!("failed to open {}: {}", image_path, err);
+            process::exit(8);
+        },
+    };
+
+    let report = fs.check(repair);
+    for problem in report.problems() {
+        println!("{}", problem);
+    }
+    let listed = report.problems().count();
+    if report.problem_cnt as usize > listed {
+        println!("... {} more problems", report.problem_cnt as usize - listed);
+    }
+    println!("{}: {}", image_path, report);
+
+    if report.is_clean() {
+        process::exit(0);
+    }
+    process::exit(if report.repaired_cnt == report.problem_cnt { 1 } else { 4 });
+}
@@ -0,0 +1,109 @@
+use std::{env, io, path::Path, process};
+
+use kernel::filesystem::FileType;
+use tests::fs_image::FsImage;
+
+/**
+ * 新建硬盘镜像的默认大小。单位：MB
+ */
+const DEFAULT_IMAGE_SIZE_MB: u32 = 80;
+
+const USAGE: &str = "usage:
+  fsimg mkfs <image> [size_mb]          format the first logical partition, or create a new image
+  fsimg init <image>                    format the first logical partition only if it has no filesystem
+  fsimg put <image> <host_path> <path>  copy a host file or directory into the image
+  fsimg mkdir <image> <path>            create a directory (and its parents)
+  fsimg ls <image> [path]               list a directory
+  fsimg get <image> <path> <host_path>  copy a file or directory out of the image";
+
+/**
+ * 宿主机上的硬盘镜像工具
+ * 用法见USAGE。镜像中的文件系统，在第一个逻辑分区上（内核中的sdb5）
+ */
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let res = match args.as_slice() {
+        ["mkfs", image] => self::mkfs(image, Option::None),
+        ["mkfs", image, size_mb] => match size_mb.parse::<u32>() {
+            Result::Ok(size_mb) => self::mkfs(image, Option::Some(size_mb)),
+            Result::Err(_) => self::usage(),
+        },
+        ["init", image] => self::init(image),
+        ["put", image, host_path, path] => self::put(image, host_path, path),
+        ["mkdir", image, path] => FsImage::open(Path::new(image), Option::None, true)
+            .and_then(|mut fs| fs.create_dir_all(path).and_then(|_| fs.flush())),
+        ["ls", image] => self::ls(image, "/"),
+        ["ls", image, path] => self::ls(image, path),
+        ["get", image, path, host_path] => FsImage::open(Path::new(image), Option::None, false)
+            .and_then(|mut fs| fs.extract(path, Path::new(host_path))),
+        _ => self::usage(),
+    };
+    if res.is_err() {
+        eprintln!("fsimg: {}", res.unwrap_err());
+        process::exit(1);
+    }
+}
+
+fn usage() -> io::Result<()> {
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+/**
+ * 镜像已经存在（并且没有指定大小），重新格式化它的第一个逻辑分区；否则新建一个镜像
+ */
+fn mkfs(image: &str, size_mb: Option<u32>) -> io::Result<()> {
+    let path = Path::new(image);
+    let fs = if size_mb.is_none() && path.exists() {
+        FsImage::format_existing(path)?
+    } else {
+        FsImage::create(path, size_mb.unwrap_or(DEFAULT_IMAGE_SIZE_MB))?
+    };
+    println!("{}: filesystem created at lba {}, {} inodes, {} data blocks",
+        image, fs.super_block.lba_start.get_lba(), fs.super_block.inode_cnt, fs.super_block.data_block_secs);
+    Result::Ok(())
+}
+
+/**
+ * 第一个逻辑分区没有合法的超级块（比如刚复制的空镜像），才格式化；已经有文件系统，保留原来的内容
+ */
+fn init(image: &str) -> io::Result<()> {
+    match FsImage::open(Path::new(image), Option::None, false) {
+        Result::Ok(_) => Result::Ok(()),
+        Result::Err(err) if err.kind() == io::ErrorKind::InvalidData => self::mkfs(image, Option::None),
+        Result::Err(err) => Result::Err(err),
+    }
+}
+
+/**
+ * 把宿主机的文件或者目录复制到镜像中。path是已经存在的目录，复制到这个目录下
+ */
+fn put(image: &str, host_path: &str, path: &str) -> io::Result<()> {
+    let mut fs = FsImage::open(Path::new(image), Option::None, true)?;
+    let host_path = Path::new(host_path);
+    let mut target = path.to_string();
+    let existing = fs.lookup(path)?;
+    if existing.is_some() && { existing.unwrap().file_type } == FileType::Directory {
+        let name = host_path.file_name();
+        if name.is_none() {
+            return Result::Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid host path"));
+        }
+        target = format!("{}/{}", path.trim_end_matches('/'), name.unwrap().to_string_lossy());
+    }
+    fs.import(host_path, &target)?;
+    fs.flush()
+}
+
+fn ls(image: &str, path: &str) -> io::Result<()> {
+    let mut fs = FsImage::open(Path::new(image), Option::None, false)?;
+    for (entry, inode) in fs.list(path)? {
+        let type_char = match { entry.file_type } {
+            FileType::Directory => 'd',
+            FileType::Symlink => 'l',
+            _ => '-',
+        };
+        println!("{} {:>5} {:>3} {:>10} {}", type_char, entry.i_no.get_data(), { inode.i_nlink }, { inode.i_size }, entry.get_name());
+    }
+    Result::Ok(())
+}
@@ -0,0 +1,784 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, ErrorKind, Read, Seek, SeekFrom, Write},
+    mem::size_of,
+    path::Path,
+    ptr, slice,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use kernel::filesystem::{
+    constant,
+    fsck::{self, Fsck, FsckDevice, FsckReport},
+    inode::Inode,
+    metadata,
+    superblock::SuperBlock,
+    DirEntry, FileType,
+};
+use os_in_rust_common::{constants, domain::{InodeNo, LbaAddr}};
+
+/**
+ * 宿主机上的硬盘镜像工具：在镜像文件中创建我们的文件系统，复制文件进去，或者把文件复制出来
+ * 磁盘上的结构（超级块、inode、目录项）都直接使用kernel::filesystem里的定义，和内核保持一致
+ */
+
+/**
+ * 分区表在引导扇区中的偏移量
+ */
+const PART_TABLE_OFFSET: usize = 446;
+
+/**
+ * 分区表的项数
+ */
+const PART_TABLE_ENTRY_CNT: usize = 4;
+
+/**
+ * 一个分区表项的大小
+ */
+const PART_TABLE_ENTRY_SIZE: usize = 16;
+
+/**
+ * 引导扇区结束的魔数：0x55, 0xAA
+ */
+const BOOT_SIGNATURE: [u8; 2] = [0x55, 0xAA];
+
+/**
+ * 扩展分区的类型
+ */
+const PART_TYPE_EXTENDED: u8 = 0x5;
+
+/**
+ * Linux分区的类型。我们的文件系统用这个类型
+ */
+const PART_TYPE_LINUX: u8 = 0x83;
+
+/**
+ * 新建的镜像中：扩展分区的起始LBA，以及逻辑分区相对于EBR的偏移
+ */
+const PART_ALIGN_SECS: u32 = 2048;
+
+/**
+ * 新建的镜像，逻辑分区至少要有这么多扇区（inode位图、inode数组之外，还要有数据块）
+ */
+const MIN_PART_SECS: u32 = 2048;
+
+/**
+ * 硬盘镜像文件，按照扇区读写
+ */
+pub struct ImageFile {
+    file: File,
+}
+
+impl ImageFile {
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(buf)
+    }
+
+    fn write_at(&mut self, offset: u64, buf: &[u8]) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(buf)
+    }
+
+    /**
+     * 从lba开始，读取buf.len()个字节（扇区大小的倍数）
+     */
+    pub fn read(&mut self, lba: LbaAddr, buf: &mut [u8]) -> io::Result<()> {
+        self.read_at(usize::from(lba) as u64 * constants::DISK_SECTOR_SIZE as u64, buf)
+    }
+
+    /**
+     * 把buf写入到lba开始的扇区中
+     */
+    pub fn write(&mut self, lba: LbaAddr, buf: &[u8]) -> io::Result<()> {
+        self.write_at(usize::from(lba) as u64 * constants::DISK_SECTOR_SIZE as u64, buf)
+    }
+}
+
+impl FsckDevice for ImageFile {
+    fn read_sectors(&mut self, lba_start: LbaAddr, sec_cnt: usize, buf: &mut [u8]) {
+        self.read(lba_start, &mut buf[..sec_cnt * constants::DISK_SECTOR_SIZE]).expect("failed to read disk image");
+    }
+
+    fn write_sectors(&mut self, buf: &[u8], lba_start: LbaAddr, sec_cnt: usize) {
+        self.write(lba_start, &buf[..sec_cnt * constants::DISK_SECTOR_SIZE]).expect("failed to write disk image");
+    }
+}
+
+/**
+ * 找到硬盘镜像中第一个逻辑分区（内核中的sdb5）。返回（起始LBA，扇区数量）
+ */
+pub fn find_first_logical_part(image: &mut ImageFile) -> io::Result<(LbaAddr, u32)> {
+    let mut mbr = [0u8; constants::DISK_SECTOR_SIZE];
+    image.read(LbaAddr::new(0), &mut mbr)?;
+    let extended = (0 .. PART_TABLE_ENTRY_CNT)
+        .map(|idx| self::read_part_entry(&mbr, idx))
+        .find(|(part_type, _, _)| *part_type == PART_TYPE_EXTENDED);
+    if extended.is_none() {
+        return Result::Err(self::error(ErrorKind::NotFound, "no extended partition in disk image"));
+    }
+    let (_, ext_lba, _) = extended.unwrap();
+
+    // 扩展分区的第一个扇区是EBR，第一项就是逻辑分区（相对于EBR的偏移）
+    let mut ebr = [0u8; constants::DISK_SECTOR_SIZE];
+    image.read(LbaAddr::new(ext_lba), &mut ebr)?;
+    let (part_type, start_lba, sec_cnt) = self::read_part_entry(&ebr, 0);
+    if part_type == 0 || sec_cnt == 0 {
+        return Result::Err(self::error(ErrorKind::NotFound, "no logical partition in disk image"));
+    }
+    Result::Ok((LbaAddr::new(ext_lba + start_lba), sec_cnt))
+}
+
+/**
+ * 读取引导扇区中第idx个分区表项。返回（分区类型，起始LBA，扇区数量）
+ */
+fn read_part_entry(sector: &[u8], idx: usize) -> (u8, u32, u32) {
+    let entry = &sector[PART_TABLE_OFFSET + idx * PART_TABLE_ENTRY_SIZE ..];
+    let start_lba = u32::from_le_bytes([entry[8], entry[9], entry[10], entry[11]]);
+    let sec_cnt = u32::from_le_bytes([entry[12], entry[13], entry[14], entry[15]]);
+    (entry[4], start_lba, sec_cnt)
+}
+
+/**
+ * 写入引导扇区中第idx个分区表项，并且写入结束魔数
+ */
+fn write_part_entry(sector: &mut [u8], idx: usize, part_type: u8, start_lba: u32, sec_cnt: u32) {
+    let entry = &mut sector[PART_TABLE_OFFSET + idx * PART_TABLE_ENTRY_SIZE ..];
+    entry[4] = part_type;
+    entry[8 .. 12].copy_from_slice(&start_lba.to_le_bytes());
+    entry[12 .. 16].copy_from_slice(&sec_cnt.to_le_bytes());
+    sector[constants::DISK_SECTOR_SIZE - 2 ..].copy_from_slice(&BOOT_SIGNATURE);
+}
+
+fn error(kind: ErrorKind, msg: &str) -> io::Error {
+    io::Error::new(kind, msg)
+}
+
+fn as_bytes<T>(val: &T) -> &[u8] {
+    unsafe { slice::from_raw_parts(val as *const T as *const u8, size_of::<T>()) }
+}
+
+/**
+ * 当前的时间戳（秒）
+ */
+fn now() -> u32 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs() as u32).unwrap_or(0)
+}
+
+fn is_bit_set(bitmap: &[u8], bit_idx: usize) -> bool {
+    bitmap[bit_idx / 8] & (1 << (bit_idx % 8)) != 0
+}
+
+fn set_bit(bitmap: &mut [u8], bit_idx: usize, val: bool) {
+    if val {
+        bitmap[bit_idx / 8] |= 1 << (bit_idx % 8);
+    } else {
+        bitmap[bit_idx / 8] &= !(1 << (bit_idx % 8));
+    }
+}
+
+/**
+ * 把路径拆分成（父目录，文件名）
+ */
+fn split_path(path: &str) -> (&str, &str) {
+    let path = path.trim_end_matches('/');
+    match path.rfind('/') {
+        Option::Some(idx) => (&path[.. idx], &path[idx + 1 ..]),
+        Option::None => ("", path),
+    }
+}
+
+/**
+ * 硬盘镜像中的一个文件系统
+ */
+pub struct FsImage {
+    image: ImageFile,
+    pub super_block: SuperBlock,
+    /**
+     * inode位图。修改之后，flush的时候写回镜像
+     */
+    inode_bitmap: Vec<u8>,
+    /**
+     * 块位图。修改之后，flush的时候写回镜像
+     */
+    block_bitmap: Vec<u8>,
+}
+
+impl FsImage {
+    /**
+     * 创建一个size_mb大小的硬盘镜像：一个扩展分区，里面一个逻辑分区，并且在逻辑分区上安装文件系统
+     */
+    pub fn create(path: &Path, size_mb: u32) -> io::Result<Self> {
+        let sec_cnt = size_mb * 1024 * 1024 / constants::DISK_SECTOR_SIZE as u32;
+        if sec_cnt < PART_ALIGN_SECS * 2 + MIN_PART_SECS {
+            return Result::Err(self::error(ErrorKind::InvalidInput, "disk image too small"));
+        }
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        file.set_len(sec_cnt as u64 * constants::DISK_SECTOR_SIZE as u64)?;
+        let mut image = ImageFile { file };
+
+        // MBR：只有一个扩展分区，占满整个硬盘
+        let mut mbr = [0u8; constants::DISK_SECTOR_SIZE];
+        self::write_part_entry(&mut mbr, 0, PART_TYPE_EXTENDED, PART_ALIGN_SECS, sec_cnt - PART_ALIGN_SECS);
+        image.write(LbaAddr::new(0), &mbr)?;
+
+        // EBR：只有一个逻辑分区
+        let part_secs = sec_cnt - PART_ALIGN_SECS * 2;
+        let mut ebr = [0u8; constants::DISK_SECTOR_SIZE];
+        self::write_part_entry(&mut ebr, 0, PART_TYPE_LINUX, PART_ALIGN_SECS, part_secs);
+        image.write(LbaAddr::new(PART_ALIGN_SECS), &ebr)?;
+
+        Self::format(image, LbaAddr::new(PART_ALIGN_SECS * 2), part_secs)
+    }
+
+    /**
+     * 在已有的硬盘镜像中，重新格式化第一个逻辑分区
+     */
+    pub fn format_existing(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let mut image = ImageFile { file };
+        let (part_lba, part_secs) = self::find_first_logical_part(&mut image)?;
+        Self::format(image, part_lba, part_secs)
+    }
+
+    /**
+     * 在镜像part_lba开始的分区中安装文件系统。布局和内核的install_filesystem一样
+     */
+    fn format(mut image: ImageFile, part_lba: LbaAddr, part_secs: u32) -> io::Result<Self> {
+        let super_block = SuperBlock::new(part_lba, part_secs);
+        image.write(part_lba.add(1), self::as_bytes(&super_block))?;
+        // inode数组清零
+        image.write(super_block.inode_table_lba, &vec![0u8; super_block.inode_table_secs as usize * constants::DISK_SECTOR_SIZE])?;
+
+        let mut fs = Self {
+            image,
+            inode_bitmap: vec![0u8; super_block.inode_bitmap_secs as usize * constants::DISK_SECTOR_SIZE],
+            block_bitmap: vec![0u8; super_block.block_bitmap_secs as usize * constants::DISK_SECTOR_SIZE],
+            super_block,
+        };
+        // 块位图中，超出数据块数量的位，都设置为已占用
+        for bit_idx in fs.super_block.data_block_secs as usize .. fs.block_bitmap.len() * 8 {
+            self::set_bit(&mut fs.block_bitmap, bit_idx, true);
+        }
+
+        // 根目录：0号inode，数据在第一个数据块
+        self::set_bit(&mut fs.inode_bitmap, 0, true);
+        let root_block = fs.apply_block()?;
+        let mut root = Inode::new(fs.super_block.root_inode_no);
+        metadata::init_inode(&mut root, FileType::Directory, self::now());
+        root.direct_sectors[0] = root_block;
+        fs.write_inode(&root)?;
+        let root_i_no = fs.super_block.root_inode_no;
+        fs.add_entry(root_i_no, DirEntry::new(root_i_no, ".", FileType::Directory))?;
+        fs.add_entry(root_i_no, DirEntry::new(root_i_no, "..", FileType::Directory))?;
+
+        fs.flush()?;
+        Result::Ok(fs)
+    }
+
+    /**
+     * 打开硬盘镜像中的文件系统
+     *  - part_lba：分区的起始LBA。为空，使用第一个逻辑分区
+     *  - writable：是否需要修改镜像
+     */
+    pub fn open(path: &Path, part_lba: Option<LbaAddr>, writable: bool) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(writable).open(path)?;
+        let mut image = ImageFile { file };
+        let part_lba = match part_lba {
+            Option::Some(part_lba) => part_lba,
+            Option::None => self::find_first_logical_part(&mut image)?.0,
+        };
+
+        // 超级块在分区的第2个扇区
+        let mut sb_buf = [0u8; constants::DISK_SECTOR_SIZE];
+        image.read(part_lba.add(1), &mut sb_buf)?;
+        let super_block: SuperBlock = unsafe { ptr::read_unaligned(sb_buf.as_ptr() as *const SuperBlock) };
+        if !super_block.is_valid() {
+            return Result::Err(self::error(ErrorKind::InvalidData, "no filesystem found in partition"));
+        }
+        if !super_block.is_supported() {
+            return Result::Err(self::error(ErrorKind::Unsupported, "unsupported filesystem version"));
+        }
+
+        let mut inode_bitmap = vec![0u8; super_block.inode_bitmap_secs as usize * constants::DISK_SECTOR_SIZE];
+        image.read(super_block.inode_bitmap_lba, &mut inode_bitmap)?;
+        let mut block_bitmap = vec![0u8; super_block.block_bitmap_secs as usize * constants::DISK_SECTOR_SIZE];
+        image.read(super_block.block_bitmap_lba, &mut block_bitmap)?;
+        Result::Ok(Self { image, super_block, inode_bitmap, block_bitmap })
+    }
+
+    /**
+     * 把位图写回镜像
+     */
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.image.write(self.super_block.inode_bitmap_lba, &self.inode_bitmap)?;
+        self.image.write(self.super_block.block_bitmap_lba, &self.block_bitmap)?;
+        self.image.file.flush()
+    }
+
+    /**
+     * 用fsck检查（并且修复）这个文件系统
+     */
+    pub fn check(&mut self, repair: bool) -> FsckReport {
+        let mut scratch = vec![0u8; fsck::scratch_size(&self.super_block)];
+        Fsck::new(&mut self.image, &self.super_block, &mut self.inode_bitmap, &mut self.block_bitmap, &mut scratch).run(repair)
+    }
+
+    /**
+     * 申请一个inode
+     */
+    fn apply_inode(&mut self) -> io::Result<InodeNo> {
+        let bit_idx = (0 .. self.super_block.inode_cnt as usize).find(|idx| !self::is_bit_set(&self.inode_bitmap, *idx));
+        if bit_idx.is_none() {
+            return Result::Err(self::error(ErrorKind::Other, "no free inode"));
+        }
+        let bit_idx = bit_idx.unwrap();
+        self::set_bit(&mut self.inode_bitmap, bit_idx, true);
+        Result::Ok(InodeNo::from(bit_idx))
+    }
+
+    /**
+     * 申请一个数据块，并且清零
+     */
+    fn apply_block(&mut self) -> io::Result<LbaAddr> {
+        let bit_idx = (0 .. self.super_block.data_block_secs as usize).find(|idx| !self::is_bit_set(&self.block_bitmap, *idx));
+        if bit_idx.is_none() {
+            return Result::Err(self::error(ErrorKind::Other, "no free data block"));
+        }
+        let bit_idx = bit_idx.unwrap();
+        self::set_bit(&mut self.block_bitmap, bit_idx, true);
+        let lba = self.super_block.data_lba_start.add(bit_idx as u32);
+        self.image.write(lba, &[0u8; constants::DISK_SECTOR_SIZE])?;
+        Result::Ok(lba)
+    }
+
+    fn release_block(&mut self, lba: LbaAddr) {
+        let bit_idx = (lba.get_lba() - self.super_block.data_lba_start.get_lba()) as usize;
+        self::set_bit(&mut self.block_bitmap, bit_idx, false);
+    }
+
+    /**
+     * inode在镜像中的字节偏移量
+     */
+    fn inode_offset(&self, i_no: InodeNo) -> u64 {
+        (usize::from(self.super_block.inode_table_lba) * constants::DISK_SECTOR_SIZE + usize::from(i_no) * self.super_block.inode_size()) as u64
+    }
+
+    fn read_inode(&mut self, i_no: InodeNo) -> io::Result<Inode> {
+        let mut inode = Inode::empty();
+        let inode_size = self.super_block.inode_size();
+        let inode_buf = unsafe { slice::from_raw_parts_mut(&mut inode as *mut _ as *mut u8, inode_size) };
+        self.image.read_at(self.inode_offset(i_no), inode_buf)?;
+        // 旧版本的inode，没有的字段使用默认值
+        if !self.super_block.support_link_count() {
+            inode.i_nlink = 1;
+        }
+        if !self.super_block.support_metadata() {
+            inode.i_mode = metadata::LEGACY_PERM;
+        }
+        Result::Ok(inode)
+    }
+
+    fn write_inode(&mut self, inode: &Inode) -> io::Result<()> {
+        let offset = self.inode_offset(inode.i_no);
+        let inode_size = self.super_block.inode_size();
+        self.image.write_at(offset, &self::as_bytes(inode)[.. inode_size])
+    }
+
+    /**
+     * inode的第block_idx个数据块
+     *  - create：数据块（以及中间的间接块）不存在的时候，是否申请
+     */
+    fn data_block(&mut self, inode: &mut Inode, block_idx: usize, create: bool) -> io::Result<Option<LbaAddr>> {
+        if block_idx < constant::INODE_DIRECT_DATA_SECS {
+            if inode.direct_sectors[block_idx].is_empty() {
+                if !create {
+                    return Result::Ok(Option::None);
+                }
+                inode.direct_sectors[block_idx] = self.apply_block()?;
+            }
+            return Result::Ok(Option::Some(inode.direct_sectors[block_idx]));
+        }
+        let mut idx = block_idx - constant::INODE_DIRECT_DATA_SECS;
+        if idx < constant::INODE_SINGLE_INDIRECT_BLOCKS {
+            let mut root = inode.indirect_sector;
+            let res = self.index_block(&mut root, 1, idx, create);
+            inode.indirect_sector = root;
+            return res;
+        }
+        idx -= constant::INODE_SINGLE_INDIRECT_BLOCKS;
+        if !self.super_block.support_deep_indirect() {
+            return Result::Err(self::error(ErrorKind::Other, "file too large for this filesystem version"));
+        }
+        if idx < constant::INODE_DOUBLE_INDIRECT_BLOCKS {
+            let mut root = inode.double_indirect_sector;
+            let res = self.index_block(&mut root, 2, idx, create);
+            inode.double_indirect_sector = root;
+            return res;
+        }
+        idx -= constant::INODE_DOUBLE_INDIRECT_BLOCKS;
+        if idx < constant::INODE_TRIPLE_INDIRECT_BLOCKS {
+            let mut root = inode.triple_indirect_sector;
+            let res = self.index_block(&mut root, 3, idx, create);
+            inode.triple_indirect_sector = root;
+            return res;
+        }
+        Result::Err(self::error(ErrorKind::Other, "file too large"))
+    }
+
+    /**
+     * 从levels级间接块root开始，找到第idx个数据块
+     */
+    fn index_block(&mut self, root: &mut LbaAddr, levels: u32, idx: usize, create: bool) -> io::Result<Option<LbaAddr>> {
+        if root.is_empty() {
+            if !create {
+                return Result::Ok(Option::None);
+            }
+            *root = self.apply_block()?;
+        }
+        let mut table_lba = *root;
+        let mut left = idx;
+        for level in (0 .. levels).rev() {
+            let span = constant::LBA_PER_BLOCK.pow(level);
+            let slot = left / span;
+            left %= span;
+
+            let mut table = [0u8; constants::DISK_SECTOR_SIZE];
+            self.image.read(table_lba, &mut table)?;
+            let off = slot * size_of::<LbaAddr>();
+            let mut lba = LbaAddr::new(u32::from_ne_bytes([table[off], table[off + 1], table[off + 2], table[off + 3]]));
+            if lba.is_empty() {
+                if !create {
+                    return Result::Ok(Option::None);
+                }
+                lba = self.apply_block()?;
+                table[off .. off + size_of::<LbaAddr>()].copy_from_slice(&lba.get_lba().to_ne_bytes());
+                self.image.write(table_lba, &table)?;
+            }
+            table_lba = lba;
+        }
+        Result::Ok(Option::Some(table_lba))
+    }
+
+    /**
+     * 释放inode所有的数据块（包括间接块）
+     */
+    fn release_data(&mut self, inode: &mut Inode) -> io::Result<()> {
+        for idx in 0 .. constant::INODE_DIRECT_DATA_SECS {
+            let lba = inode.direct_sectors[idx];
+            if !lba.is_empty() {
+                self.release_block(lba);
+                inode.direct_sectors[idx] = LbaAddr::empty();
+            }
+        }
+        let roots = [(inode.indirect_sector, 1), (inode.double_indirect_sector, 2), (inode.triple_indirect_sector, 3)];
+        for (root, levels) in roots {
+            if !root.is_empty() {
+                self.release_index_block(root, levels)?;
+            }
+        }
+        inode.indirect_sector = LbaAddr::empty();
+        inode.double_indirect_sector = LbaAddr::empty();
+        inode.triple_indirect_sector = LbaAddr::empty();
+        inode.i_size = 0;
+        Result::Ok(())
+    }
+
+    fn release_index_block(&mut self, table_lba: LbaAddr, levels: u32) -> io::Result<()> {
+        let mut table = [0u8; constants::DISK_SECTOR_SIZE];
+        self.image.read(table_lba, &mut table)?;
+        for entry in table.chunks(size_of::<LbaAddr>()) {
+            let lba = LbaAddr::new(u32::from_ne_bytes([entry[0], entry[1], entry[2], entry[3]]));
+            if lba.is_empty() {
+                continue;
+            }
+            if levels > 1 {
+                self.release_index_block(lba, levels - 1)?;
+            } else {
+                self.release_block(lba);
+            }
+        }
+        self.release_block(table_lba);
+        Result::Ok(())
+    }
+
+    /**
+     * 目录dir的所有目录项（包括.和..）
+     */
+    fn dir_entries(&mut self, dir: &mut Inode) -> io::Result<Vec<DirEntry>> {
+        let mut entries = Vec::new();
+        // 目录只使用直接块和一级间接块
+        for block_idx in 0 .. constant::INODE_DIRECT_DATA_SECS + constant::INODE_SINGLE_INDIRECT_BLOCKS {
+            let lba = self.data_block(dir, block_idx, false)?;
+            if lba.is_none() {
+                continue;
+            }
+            let mut block = [0u8; constants::DISK_SECTOR_SIZE];
+            self.image.read(lba.unwrap(), &mut block)?;
+            for idx in 0 .. constant::MAX_ENTRY_IN_BLOCK {
+                let entry: DirEntry = unsafe { ptr::read_unaligned(block.as_ptr().add(idx * size_of::<DirEntry>()) as *const DirEntry) };
+                if !entry.is_empty() && { entry.file_type } != FileType::Unknown {
+                    entries.push(entry);
+                }
+            }
+        }
+        Result::Ok(entries)
+    }
+
+    /**
+     * 在目录dir_i_no中写入目录项entry
+     * 和内核一样：先找最后一个数据块中的空位，没有空位了再申请一个新的数据块
+     */
+    fn add_entry(&mut self, dir_i_no: InodeNo, entry: DirEntry) -> io::Result<()> {
+        let mut dir = self.read_inode(dir_i_no)?;
+        let max_blocks = constant::INODE_DIRECT_DATA_SECS + constant::INODE_SINGLE_INDIRECT_BLOCKS;
+        let mut first_empty = Option::None;
+        for block_idx in 0 .. max_blocks {
+            if self.data_block(&mut dir, block_idx, false)?.is_none() {
+                first_empty = Option::Some(block_idx);
+                break;
+            }
+        }
+        // 最后一个已经使用的数据块
+        let last_used = match first_empty {
+            Option::Some(0) => Option::None,
+            Option::Some(block_idx) => Option::Some(block_idx - 1),
+            Option::None => Option::Some(max_blocks - 1),
+        };
+
+        let mut block = [0u8; constants::DISK_SECTOR_SIZE];
+        let mut target = Option::None;
+        if last_used.is_some() {
+            let lba = self.data_block(&mut dir, last_used.unwrap(), false)?.unwrap();
+            self.image.read(lba, &mut block)?;
+            let slot = (0 .. constant::MAX_ENTRY_IN_BLOCK)
+                .find(|idx| unsafe { ptr::read_unaligned(block.as_ptr().add(idx * size_of::<DirEntry>()) as *const DirEntry) }.is_empty());
+            if slot.is_some() {
+                target = Option::Some((lba, slot.unwrap()));
+            }
+        }
+        if target.is_none() {
+            if first_empty.is_none() {
+                return Result::Err(self::error(ErrorKind::Other, "directory is full"));
+            }
+            let lba = self.data_block(&mut dir, first_empty.unwrap(), true)?.unwrap();
+            block = [0u8; constants::DISK_SECTOR_SIZE];
+            target = Option::Some((lba, 0));
+        }
+
+        let (lba, slot) = target.unwrap();
+        let off = slot * size_of::<DirEntry>();
+        block[off .. off + size_of::<DirEntry>()].copy_from_slice(self::as_bytes(&entry));
+        self.image.write(lba, &block)?;
+
+        dir.i_size += self.super_block.dir_entry_size;
+        dir.i_mtime = self::now();
+        self.write_inode(&dir)
+    }
+
+    /**
+     * 找到path（绝对路径）对应的目录项。不存在返回None
+     */
+    pub fn lookup(&mut self, path: &str) -> io::Result<Option<DirEntry>> {
+        let root_i_no = self.super_block.root_inode_no;
+        let mut cur = DirEntry::new(root_i_no, "/", FileType::Directory);
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            if { cur.file_type } != FileType::Directory {
+                return Result::Ok(Option::None);
+            }
+            let mut dir = self.read_inode(cur.i_no)?;
+            let entry = self.dir_entries(&mut dir)?.into_iter().find(|entry| entry.get_name() == name);
+            if entry.is_none() {
+                return Result::Ok(Option::None);
+            }
+            cur = entry.unwrap();
+        }
+        Result::Ok(Option::Some(cur))
+    }
+
+    /**
+     * 找到path的父目录，并且检查文件名。返回（父目录inode号，文件名）
+     */
+    fn parent_of<'a>(&mut self, path: &'a str) -> io::Result<(InodeNo, &'a str)> {
+        let (parent, name) = self::split_path(path);
+        if name.is_empty() || name == "." || name == ".." {
+            return Result::Err(self::error(ErrorKind::InvalidInput, "invalid file name"));
+        }
+        // 目录项中的文件名，结尾还要有一个\0
+        if name.len() >= constant::MAX_FILE_NAME {
+            return Result::Err(self::error(ErrorKind::InvalidInput, "file name too long"));
+        }
+        let parent_entry = self.lookup(parent)?;
+        if parent_entry.is_none() {
+            return Result::Err(self::error(ErrorKind::NotFound, "parent directory not found"));
+        }
+        let parent_entry = parent_entry.unwrap();
+        if { parent_entry.file_type } != FileType::Directory {
+            return Result::Err(self::error(ErrorKind::InvalidInput, "parent is not a directory"));
+        }
+        Result::Ok((parent_entry.i_no, name))
+    }
+
+    /**
+     * 创建目录path
+     */
+    pub fn create_dir(&mut self, path: &str) -> io::Result<InodeNo> {
+        let (parent_i_no, name) = self.parent_of(path)?;
+        if self.lookup(path)?.is_some() {
+            return Result::Err(self::error(ErrorKind::AlreadyExists, "file already exists"));
+        }
+        let i_no = self.apply_inode()?;
+        let mut inode = Inode::new(i_no);
+        metadata::init_inode(&mut inode, FileType::Directory, self::now());
+        self.write_inode(&inode)?;
+        // 和内核的mkdir一样，先..再.
+        self.add_entry(i_no, DirEntry::new(parent_i_no, "..", FileType::Directory))?;
+        self.add_entry(i_no, DirEntry::new(i_no, ".", FileType::Directory))?;
+        self.add_entry(parent_i_no, DirEntry::new(i_no, name, FileType::Directory))?;
+        Result::Ok(i_no)
+    }
+
+    /**
+     * 创建目录path，以及所有不存在的上级目录
+     */
+    pub fn create_dir_all(&mut self, path: &str) -> io::Result<()> {
+        let mut cur = String::new();
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            cur.push('/');
+            cur.push_str(name);
+            let entry = self.lookup(&cur)?;
+            if entry.is_none() {
+                self.create_dir(&cur)?;
+            } else if { entry.unwrap().file_type } != FileType::Directory {
+                return Result::Err(self::error(ErrorKind::AlreadyExists, "not a directory"));
+            }
+        }
+        Result::Ok(())
+    }
+
+    /**
+     * 把data写入到文件path中。文件已经存在，覆盖原来的内容
+     */
+    pub fn write_file(&mut self, path: &str, data: &[u8]) -> io::Result<InodeNo> {
+        let (parent_i_no, name) = self.parent_of(path)?;
+        let existing = self.lookup(path)?;
+        let mut inode = if existing.is_some() {
+            let existing = existing.unwrap();
+            if { existing.file_type } != FileType::Regular {
+                return Result::Err(self::error(ErrorKind::AlreadyExists, "not a regular file"));
+            }
+            let mut inode = self.read_inode(existing.i_no)?;
+            self.release_data(&mut inode)?;
+            inode
+        } else {
+            let i_no = self.apply_inode()?;
+            let mut inode = Inode::new(i_no);
+            metadata::init_inode(&mut inode, FileType::Regular, self::now());
+            self.write_inode(&inode)?;
+            self.add_entry(parent_i_no, DirEntry::new(i_no, name, FileType::Regular))?;
+            inode
+        };
+
+        for (block_idx, chunk) in data.chunks(constants::DISK_SECTOR_SIZE).enumerate() {
+            let lba = self.data_block(&mut inode, block_idx, true)?.unwrap();
+            let mut block = [0u8; constants::DISK_SECTOR_SIZE];
+            block[.. chunk.len()].copy_from_slice(chunk);
+            self.image.write(lba, &block)?;
+        }
+        inode.i_size = data.len() as u32;
+        inode.i_mtime = self::now();
+        self.write_inode(&inode)?;
+        Result::Ok(inode.i_no)
+    }
+
+    /**
+     * 读取文件path的全部内容
+     */
+    pub fn read_file(&mut self, path: &str) -> io::Result<Vec<u8>> {
+        let entry = self.lookup(path)?;
+        if entry.is_none() {
+            return Result::Err(self::error(ErrorKind::NotFound, "file not found"));
+        }
+        let entry = entry.unwrap();
+        if { entry.file_type } == FileType::Directory {
+            return Result::Err(self::error(ErrorKind::InvalidInput, "is a directory"));
+        }
+        let mut inode = self.read_inode(entry.i_no)?;
+        let size = inode.i_size as usize;
+        let mut data = vec![0u8; size.div_ceil(constants::DISK_SECTOR_SIZE) * constants::DISK_SECTOR_SIZE];
+        for (block_idx, block) in data.chunks_mut(constants::DISK_SECTOR_SIZE).enumerate() {
+            // 没有数据块的地方（空洞），都是0
+            let lba = self.data_block(&mut inode, block_idx, false)?;
+            if lba.is_some() {
+                self.image.read(lba.unwrap(), block)?;
+            }
+        }
+        data.truncate(size);
+        Result::Ok(data)
+    }
+
+    /**
+     * 列出目录path中的所有目录项，以及目录项对应的inode
+     */
+    pub fn list(&mut self, path: &str) -> io::Result<Vec<(DirEntry, Inode)>> {
+        let entry = self.lookup(path)?;
+        if entry.is_none() {
+            return Result::Err(self::error(ErrorKind::NotFound, "file not found"));
+        }
+        let entry = entry.unwrap();
+        if { entry.file_type } != FileType::Directory {
+            let inode = self.read_inode(entry.i_no)?;
+            return Result::Ok(vec![(entry, inode)]);
+        }
+        let mut dir = self.read_inode(entry.i_no)?;
+        let mut res = Vec::new();
+        for entry in self.dir_entries(&mut dir)? {
+            let inode = self.read_inode(entry.i_no)?;
+            res.push((entry, inode));
+        }
+        Result::Ok(res)
+    }
+
+    /**
+     * 把宿主机的文件或者目录（递归）复制到镜像的path中
+     */
+    pub fn import(&mut self, host_path: &Path, path: &str) -> io::Result<()> {
+        if !host_path.is_dir() {
+            let data = fs::read(host_path)?;
+            return self.write_file(path, &data).map(|_| ());
+        }
+        self.create_dir_all(path)?;
+        let mut children: Vec<_> = fs::read_dir(host_path)?.collect::<io::Result<_>>()?;
+        children.sort_by_key(|child| child.file_name());
+        for child in children {
+            let name = child.file_name();
+            let child_path = format!("{}/{}", path.trim_end_matches('/'), name.to_string_lossy());
+            self.import(&child.path(), &child_path)?;
+        }
+        Result::Ok(())
+    }
+
+    /**
+     * 把镜像中的文件或者目录path（递归）复制到宿主机的host_path中
+     * 符号链接复制成普通文件，内容是链接的目标路径
+     */
+    pub fn extract(&mut self, path: &str, host_path: &Path) -> io::Result<()> {
+        let entry = self.lookup(path)?;
+        if entry.is_none() {
+            return Result::Err(self::error(ErrorKind::NotFound, "file not found"));
+        }
+        if { entry.unwrap().file_type } != FileType::Directory {
+            return fs::write(host_path, self.read_file(path)?);
+        }
+        fs::create_dir_all(host_path)?;
+        for (child, _) in self.list(path)? {
+            if child.is_dot_entry() {
+                continue;
+            }
+            let name = child.get_name();
+            let child_path = format!("{}/{}", path.trim_end_matches('/'), name);
+            self.extract(&child_path, &host_path.join(name))?;
+        }
+        Result::Ok(())
+    }
+}
@@ -1 +1,2 @@
-pub mod file_system;
\ No newline at end of file
+pub mod file_system;
+pub mod fs_image;
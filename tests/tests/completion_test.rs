@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+    use kernel::shell::completion::{self, Candidates, Completion};
+
+    #[test]
+    fn test_current_word() {
+        assert_eq!(completion::current_word("ec", 2), (0, true));
+        assert_eq!(completion::current_word("  ec", 4), (2, true));
+        assert_eq!(completion::current_word("cat /usr/lo", 11), (4, false));
+        assert_eq!(completion::current_word("cat a | gr", 10), (8, true));
+        assert_eq!(completion::current_word("cat a |gr", 9), (7, true));
+        assert_eq!(completion::current_word("cd a; ec", 8), (6, true));
+        assert_eq!(completion::current_word("cd a&&ec", 8), (6, true));
+        // 光标在行中间，只看光标前面的内容
+        assert_eq!(completion::current_word("cat abc", 5), (4, false));
+    }
+
+    #[test]
+    fn test_split_word() {
+        assert_eq!(completion::split_word("/usr/lo"), ("/usr/", "lo"));
+        assert_eq!(completion::split_word("lo"), ("", "lo"));
+        assert_eq!(completion::split_word("dir/"), ("dir/", ""));
+    }
+
+    #[test]
+    fn test_complete() {
+        let mut candidates = Candidates::new();
+        assert_eq!(completion::complete("gr", &candidates), Completion::None);
+
+        candidates.add("grep", false);
+        assert_eq!(completion::complete("gr", &candidates), Completion::Insert("ep", Option::Some(' ')));
+
+        candidates.clear();
+        candidates.add("docs", true);
+        assert_eq!(completion::complete("d", &candidates), Completion::Insert("ocs", Option::Some('/')));
+
+        // 多个候选，补全公共前缀
+        candidates.clear();
+        candidates.add("mkdir", false);
+        candidates.add("mkfs", false);
+        candidates.add("mkfs", false);
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates.common_prefix(), "mk");
+        assert_eq!(completion::complete("m", &candidates), Completion::Insert("k", Option::None));
+        // 公共前缀已经输入了，列出所有候选
+        assert_eq!(completion::complete("mk", &candidates), Completion::List);
+    }
+}
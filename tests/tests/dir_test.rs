@@ -17,6 +17,15 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_file_type_on_disk() {
+        // 目录项中的文件类型会写入硬盘，新增的类型不能改变已有类型的值
+        assert_eq!(FileType::Regular as u32, 0);
+        assert_eq!(FileType::Directory as u32, 1);
+        assert_eq!(FileType::Unknown as u32, 2);
+        assert_eq!(FileType::Symlink as u32, 3);
+    }
+
     #[test]
     fn entry_count_in_sector() {
         println!("{}", constants::DISK_SECTOR_SIZE / size_of::<DirEntry>());
@@ -0,0 +1,93 @@
+pub mod test {
+    use kernel::elf::{Elf32Header, Elf32ProgramHeader, ElfError};
+
+    /**
+     * 构造一个最小的ELF32可执行文件头
+     */
+    fn build_header() -> Elf32Header {
+        let mut header = Elf32Header::empty();
+        header.e_ident[0..6].copy_from_slice(&[0x7f, b'E', b'L', b'F', 1, 1]);
+        header.e_type = 2;
+        header.e_machine = 3;
+        header.e_entry = 0xc048000;
+        header.e_phoff = 52;
+        header.e_phentsize = size_of::<Elf32ProgramHeader>() as u16;
+        header.e_phnum = 2;
+        header
+    }
+
+    #[test]
+    fn test_struct_size() {
+        assert_eq!(size_of::<Elf32Header>(), 52);
+        assert_eq!(size_of::<Elf32ProgramHeader>(), 32);
+    }
+
+    #[test]
+    fn test_check_header() {
+        let header = build_header();
+        assert_eq!(header.check(), Result::Ok(()));
+        assert_eq!(header.program_header_off(1), 52 + 32);
+
+        let mut not_elf = build_header();
+        not_elf.e_ident[1] = b'X';
+        assert_eq!(not_elf.check(), Result::Err(ElfError::NotElf));
+
+        let mut elf64 = build_header();
+        elf64.e_ident[4] = 2;
+        assert_eq!(elf64.check(), Result::Err(ElfError::UnsupportedClass));
+
+        let mut x86_64 = build_header();
+        x86_64.e_machine = 62;
+        assert_eq!(x86_64.check(), Result::Err(ElfError::UnsupportedMachine));
+    }
+
+    #[test]
+    fn test_parse_from_bytes() {
+        let mut bytes = [0u8; 32];
+        // PT_LOAD, offset=0x100, vaddr=0xc048000, filesz=0x20, memsz=0x40
+        bytes[0..4].copy_from_slice(&1u32.to_le_bytes());
+        bytes[4..8].copy_from_slice(&0x100u32.to_le_bytes());
+        bytes[8..12].copy_from_slice(&0xc048000u32.to_le_bytes());
+        bytes[16..20].copy_from_slice(&0x20u32.to_le_bytes());
+        bytes[20..24].copy_from_slice(&0x40u32.to_le_bytes());
+
+        let mut program_header = Elf32ProgramHeader::empty();
+        program_header.as_buff().copy_from_slice(&bytes);
+        assert!(program_header.is_load());
+        assert_eq!({ program_header.p_vaddr }, 0xc048000);
+        assert_eq!({ program_header.p_memsz }, 0x40);
+    }
+
+    /**
+     * 构造一个PT_LOAD的程序头
+     */
+    fn build_segment(vaddr: u32, filesz: u32, memsz: u32) -> Elf32ProgramHeader {
+        let mut program_header = Elf32ProgramHeader::empty();
+        program_header.p_type = 1;
+        program_header.p_vaddr = vaddr;
+        program_header.p_filesz = filesz;
+        program_header.p_memsz = memsz;
+        program_header
+    }
+
+    #[test]
+    fn test_check_range() {
+        let limit = 0xc0000000;
+        assert_eq!(build_segment(0xc048000, 0x20, 0x40).check_range(limit), Result::Ok(0xc048040));
+        // 按页对齐之后，刚好到limit
+        assert_eq!(build_segment(0xbffff000, 0x10, 0x1000).check_range(limit), Result::Ok(0xc0000000));
+        assert_eq!(build_segment(0xbffff000, 0x10, 0x1001).check_range(limit), Result::Err(ElfError::InvalidSegment));
+        // 写到内核空间
+        assert_eq!(build_segment(0xc0000000, 0, 0x10).check_range(limit), Result::Err(ElfError::InvalidSegment));
+        // 地址溢出
+        assert_eq!(build_segment(0xfffff000, 0, 0xffffffff).check_range(usize::MAX), Result::Err(ElfError::InvalidSegment));
+        // 文件中的大小比内存中的大小还大
+        assert_eq!(build_segment(0xc048000, 0x41, 0x40).check_range(limit), Result::Err(ElfError::InvalidSegment));
+
+        let segment = build_segment(0xc048000, 0x20, 0x40);
+        assert!(segment.contains(0xc048000));
+        assert!(segment.contains(0xc04803f));
+        assert!(!segment.contains(0xc048040));
+        assert!(!segment.contains(0xc047fff));
+    }
+}
@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, io, mem, path::PathBuf, process};
+
+    use kernel::filesystem::{constant, superblock::SuperBlock, FileType};
+    use os_in_rust_common::constants;
+    use tests::fs_image::FsImage;
+
+    /**
+     * 测试用的镜像大小（MB）
+     */
+    const IMAGE_SIZE_MB: u32 = 4;
+
+    /**
+     * 临时目录中，这个测试独占的路径
+     */
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("fs_image_test_{}_{}", process::id(), name))
+    }
+
+    #[test]
+    fn test_write_and_read_file() {
+        let image_path = temp_path("rw.img");
+        let mut fs = FsImage::create(&image_path, IMAGE_SIZE_MB).unwrap();
+        // 超过直接块和一级间接块，用到二级间接块
+        let big: Vec<u8> = (0 .. 100 * 1024).map(|idx| (idx % 251) as u8).collect();
+        fs.create_dir_all("/bin/sub").unwrap();
+        fs.write_file("/bin/big", &big).unwrap();
+        fs.write_file("/hello.txt", b"hello").unwrap();
+        fs.flush().unwrap();
+
+        let mut fs = FsImage::open(&image_path, Option::None, true).unwrap();
+        assert_eq!(fs.read_file("/bin/big").unwrap(), big);
+        assert_eq!(fs.read_file("/hello.txt").unwrap(), b"hello");
+        let names: Vec<String> = fs.list("/bin").unwrap().iter().map(|(entry, _)| entry.get_name().to_string()).collect();
+        assert_eq!(names, ["..", ".", "sub", "big"]);
+        assert!(matches!(fs.lookup("/bin/sub").unwrap(), Option::Some(entry) if { entry.file_type } == FileType::Directory));
+        assert!(fs.lookup("/bin/none").unwrap().is_none());
+
+        let report = fs.check(false);
+        assert!(report.is_clean(), "{}", report);
+        assert_eq!(report.inode_cnt, 5);
+        fs::remove_file(&image_path).unwrap();
+    }
+
+    #[test]
+    fn test_overwrite_releases_blocks() {
+        let image_path = temp_path("overwrite.img");
+        let mut fs = FsImage::create(&image_path, IMAGE_SIZE_MB).unwrap();
+        fs.write_file("/a", &vec![1u8; 80 * 1024]).unwrap();
+        fs.write_file("/a", b"short").unwrap();
+        fs.flush().unwrap();
+        assert_eq!(fs.read_file("/a").unwrap(), b"short");
+        let report = fs.check(false);
+        assert!(report.is_clean(), "{}", report);
+        // 根目录一个块，文件一个块
+        assert_eq!(report.block_cnt, 2);
+        fs::remove_file(&image_path).unwrap();
+    }
+
+    #[test]
+    fn test_reject_future_version() {
+        let image_path = temp_path("future.img");
+        let fs = FsImage::create(&image_path, IMAGE_SIZE_MB).unwrap();
+        // 超级块在inode位图的前一个扇区。改成更新的版本
+        let sb_offset = (fs.super_block.inode_bitmap_lba.get_lba() as usize - 1) * constants::DISK_SECTOR_SIZE;
+        let version_offset = sb_offset + mem::offset_of!(SuperBlock, version);
+        let mut bytes = fs::read(&image_path).unwrap();
+        bytes[version_offset .. version_offset + 4].copy_from_slice(&(constant::FILESYSTEM_VERSION_CURRENT + 1).to_le_bytes());
+        fs::write(&image_path, &bytes).unwrap();
+
+        // 不认识的版本，不能当作没有文件系统（否则会被重新格式化）
+        let err = FsImage::open(&image_path, Option::None, false).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+        fs::remove_file(&image_path).unwrap();
+    }
+
+    #[test]
+    fn test_import_and_extract() {
+        let host_dir = temp_path("host");
+        fs::create_dir_all(host_dir.join("etc")).unwrap();
+        fs::write(host_dir.join("etc/profile"), b"echo hi\n").unwrap();
+        fs::write(host_dir.join("readme"), b"readme").unwrap();
+
+        let image_path = temp_path("import.img");
+        let mut image = FsImage::create(&image_path, IMAGE_SIZE_MB).unwrap();
+        image.import(&host_dir, "/root").unwrap();
+        image.flush().unwrap();
+        assert_eq!(image.read_file("/root/etc/profile").unwrap(), b"echo hi\n");
+        assert!(image.check(false).is_clean());
+
+        let out_dir = temp_path("out");
+        image.extract("/root", &out_dir).unwrap();
+        assert_eq!(fs::read(out_dir.join("etc/profile")).unwrap(), b"echo hi\n");
+        assert_eq!(fs::read(out_dir.join("readme")).unwrap(), b"readme");
+
+        fs::remove_dir_all(&host_dir).unwrap();
+        fs::remove_dir_all(&out_dir).unwrap();
+        fs::remove_file(&image_path).unwrap();
+    }
+}
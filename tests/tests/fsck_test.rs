@@ -0,0 +1,185 @@
+#[cfg(test)]
+mod tests {
+    use std::{mem::size_of, slice};
+
+    use kernel::filesystem::{fsck::{self, Fsck, FsckDevice, FsckProblem, FsckReport}, inode::Inode, superblock::SuperBlock, DirEntry, FileType};
+    use os_in_rust_common::{constants, domain::{InodeNo, LbaAddr}};
+
+    /**
+     * 测试用的分区扇区数量
+     */
+    const PART_SECS: u32 = 4096;
+
+    /**
+     * 内存中的硬盘镜像
+     */
+    struct MemDevice {
+        image: Vec<u8>,
+    }
+
+    impl FsckDevice for MemDevice {
+        fn read_sectors(&mut self, lba_start: LbaAddr, sec_cnt: usize, buf: &mut [u8]) {
+            let start = usize::from(lba_start) * constants::DISK_SECTOR_SIZE;
+            let len = sec_cnt * constants::DISK_SECTOR_SIZE;
+            buf[..len].copy_from_slice(&self.image[start .. start + len]);
+        }
+
+        fn write_sectors(&mut self, buf: &[u8], lba_start: LbaAddr, sec_cnt: usize) {
+            let start = usize::from(lba_start) * constants::DISK_SECTOR_SIZE;
+            let len = sec_cnt * constants::DISK_SECTOR_SIZE;
+            self.image[start .. start + len].copy_from_slice(&buf[..len]);
+        }
+    }
+
+    /**
+     * 一个只有根目录的文件系统
+     */
+    struct TestFs {
+        super_block: SuperBlock,
+        device: MemDevice,
+        /**
+         * 根目录中下一个空闲目录项的下标
+         */
+        next_entry: usize,
+    }
+
+    impl TestFs {
+        fn new() -> Self {
+            let super_block = SuperBlock::new(LbaAddr::new(0), PART_SECS);
+            let mut fs = Self {
+                device: MemDevice { image: vec![0; PART_SECS as usize * constants::DISK_SECTOR_SIZE] },
+                super_block,
+                next_entry: 0,
+            };
+            let root_lba = fs.super_block.data_lba_start;
+            fs.mark_inode(0, true);
+            fs.mark_block(root_lba, true);
+            let mut root = Inode::new(InodeNo::new(0));
+            root.direct_sectors[0] = root_lba;
+            fs.write_inode(&root);
+            fs.add_entry(DirEntry::new(InodeNo::new(0), ".", FileType::Directory));
+            fs.add_entry(DirEntry::new(InodeNo::new(0), "..", FileType::Directory));
+            fs
+        }
+
+        fn data_block(&self, idx: u32) -> LbaAddr {
+            self.super_block.data_lba_start.add(idx)
+        }
+
+        fn set_bit(&mut self, bitmap_lba: LbaAddr, bit_idx: usize, val: bool) {
+            let byte = &mut self.device.image[usize::from(bitmap_lba) * constants::DISK_SECTOR_SIZE + bit_idx / 8];
+            if val {
+                *byte |= 1 << (bit_idx % 8);
+            } else {
+                *byte &= !(1 << (bit_idx % 8));
+            }
+        }
+
+        fn mark_inode(&mut self, i_no: u32, used: bool) {
+            self.set_bit(self.super_block.inode_bitmap_lba, i_no as usize, used);
+        }
+
+        fn mark_block(&mut self, lba: LbaAddr, used: bool) {
+            let bit_idx = (lba.get_lba() - self.super_block.data_lba_start.get_lba()) as usize;
+            self.set_bit(self.super_block.block_bitmap_lba, bit_idx, used);
+        }
+
+        fn write_inode(&mut self, inode: &Inode) {
+            let start = usize::from(self.super_block.inode_table_lba) * constants::DISK_SECTOR_SIZE + usize::from(inode.i_no) * size_of::<Inode>();
+            let bytes = unsafe { slice::from_raw_parts(inode as *const _ as *const u8, size_of::<Inode>()) };
+            self.device.image[start .. start + size_of::<Inode>()].copy_from_slice(bytes);
+        }
+
+        fn add_entry(&mut self, entry: DirEntry) {
+            let start = usize::from(self.super_block.data_lba_start) * constants::DISK_SECTOR_SIZE + self.next_entry * size_of::<DirEntry>();
+            let bytes = unsafe { slice::from_raw_parts(&entry as *const _ as *const u8, size_of::<DirEntry>()) };
+            self.device.image[start .. start + size_of::<DirEntry>()].copy_from_slice(bytes);
+            self.next_entry += 1;
+        }
+
+        /**
+         * 在根目录下创建一个普通文件，数据块是blocks
+         */
+        fn add_file(&mut self, i_no: u32, name: &str, blocks: &[LbaAddr]) {
+            let mut inode = Inode::new(InodeNo::new(i_no));
+            for (idx, block) in blocks.iter().enumerate() {
+                inode.direct_sectors[idx] = *block;
+                self.mark_block(*block, true);
+            }
+            self.mark_inode(i_no, true);
+            self.write_inode(&inode);
+            self.add_entry(DirEntry::new(InodeNo::new(i_no), name, FileType::Regular));
+        }
+
+        fn check(&mut self, repair: bool) -> FsckReport {
+            let sb = &self.super_block;
+            let mut inode_bitmap = vec![0u8; sb.inode_bitmap_secs as usize * constants::DISK_SECTOR_SIZE];
+            self.device.read_sectors(sb.inode_bitmap_lba, sb.inode_bitmap_secs as usize, &mut inode_bitmap);
+            let mut block_bitmap = vec![0u8; sb.block_bitmap_secs as usize * constants::DISK_SECTOR_SIZE];
+            self.device.read_sectors(sb.block_bitmap_lba, sb.block_bitmap_secs as usize, &mut block_bitmap);
+            let mut scratch = vec![0u8; fsck::scratch_size(sb)];
+            Fsck::new(&mut self.device, sb, &mut inode_bitmap, &mut block_bitmap, &mut scratch).run(repair)
+        }
+    }
+
+    #[test]
+    fn test_clean_filesystem() {
+        let mut fs = TestFs::new();
+        let blocks = [fs.data_block(1), fs.data_block(2)];
+        fs.add_file(1, "a.txt", &blocks);
+        let report = fs.check(false);
+        assert!(report.is_clean(), "{}", report);
+        assert_eq!(report.inode_cnt, 2);
+        assert_eq!(report.block_cnt, 3);
+    }
+
+    #[test]
+    fn test_repair_orphans() {
+        let mut fs = TestFs::new();
+        // 写文件的过程中崩溃：申请了块和inode，但是还没有写入目录项
+        let orphan_block = fs.data_block(5);
+        fs.mark_block(orphan_block, true);
+        fs.mark_inode(7, true);
+        // 文件使用的块，在块位图中是空闲的
+        let unmarked_block = fs.data_block(3);
+        fs.add_file(1, "a.txt", &[unmarked_block]);
+        fs.mark_block(unmarked_block, false);
+
+        let report = fs.check(false);
+        assert_eq!(report.problem_cnt, 3);
+        assert_eq!(report.repaired_cnt, 0);
+        assert!(report.problems().any(|problem| matches!(problem, FsckProblem::OrphanBlock { lba } if lba.get_lba() == orphan_block.get_lba())));
+        assert!(report.problems().any(|problem| matches!(problem, FsckProblem::OrphanInode { i_no } if i_no.get_data() == 7)));
+        assert!(report.problems().any(|problem| matches!(problem, FsckProblem::UnmarkedBlock { lba, .. } if lba.get_lba() == unmarked_block.get_lba())));
+
+        let report = fs.check(true);
+        assert_eq!(report.repaired_cnt, 3);
+        assert!(fs.check(false).is_clean());
+    }
+
+    #[test]
+    fn test_doubly_claimed_block() {
+        let mut fs = TestFs::new();
+        let shared = fs.data_block(1);
+        fs.add_file(1, "a.txt", &[shared]);
+        fs.add_file(2, "b.txt", &[shared]);
+        let report = fs.check(true);
+        assert_eq!(report.problem_cnt, 1);
+        // 不知道哪个文件是对的，不修复
+        assert_eq!(report.repaired_cnt, 0);
+        assert!(matches!(report.problems().next(), Option::Some(FsckProblem::DoublyClaimedBlock { i_no, .. }) if i_no.get_data() == 2));
+    }
+
+    #[test]
+    fn test_repair_link_count() {
+        let mut fs = TestFs::new();
+        let block = fs.data_block(1);
+        fs.add_file(1, "a.txt", &[block]);
+        // 第二个硬链接，但是硬链接数还是1
+        fs.add_entry(DirEntry::new(InodeNo::new(1), "b.txt", FileType::Regular));
+        let report = fs.check(true);
+        assert_eq!(report.problem_cnt, 1);
+        assert!(matches!(report.problems().next(), Option::Some(FsckProblem::WrongLinkCount { recorded: 1, actual: 2, .. })));
+        assert!(fs.check(false).is_clean());
+    }
+}
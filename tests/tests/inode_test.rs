@@ -2,7 +2,8 @@
 mod tests {
     use std::{fs::File, mem::size_of, slice};
 
-    use kernel::filesystem::{DirEntry, inode::{Inode, OpenedInode}, superblock::SuperBlock};
+    use kernel::filesystem::{DirEntry, FileType, Metadata, inode::{Inode, OpenedInode}, superblock::SuperBlock};
+    use os_in_rust_common::domain::LbaAddr;
     use tests::file_system;
     
 
@@ -14,6 +15,55 @@ mod tests {
         println!("cache size:{}", opened_inode.get_data_blocks_ref().len())
     }
 
+    #[test]
+    fn test_super_block_version() {
+        let metadata_size = 6 * size_of::<u32>();
+        // 新安装的文件系统，inode有二级、三级间接块，硬链接数，以及元信息
+        let mut super_block = SuperBlock::new(LbaAddr::new(59894), 163296);
+        assert!(super_block.support_deep_indirect());
+        assert!(super_block.support_link_count());
+        assert!(super_block.support_metadata());
+        assert_eq!(super_block.inode_size(), size_of::<Inode>());
+
+        // 版本2的硬盘镜像，inode没有元信息
+        super_block.version = 2;
+        assert!(super_block.support_link_count());
+        assert!(!super_block.support_metadata());
+        assert_eq!(super_block.inode_size(), size_of::<Inode>() - metadata_size);
+
+        // 版本1的硬盘镜像，inode没有硬链接数
+        super_block.version = 1;
+        assert!(super_block.support_deep_indirect());
+        assert!(!super_block.support_link_count());
+        assert_eq!(super_block.inode_size(), size_of::<Inode>() - metadata_size - size_of::<u32>());
+
+        // 旧的硬盘镜像，超级块中没有版本号，inode没有二级、三级间接块
+        super_block.version = 0;
+        assert!(!super_block.support_deep_indirect());
+        assert!(!super_block.support_link_count());
+        assert_eq!(super_block.inode_size(), size_of::<Inode>() - metadata_size - size_of::<u32>() - 2 * size_of::<LbaAddr>());
+
+        // 认识的版本号
+        assert!(super_block.is_supported());
+        // 不认识的版本号（更新的版本），挂载的时候拒绝
+        super_block.version = 0xFFFF_FFFF;
+        assert!(!super_block.is_supported());
+    }
+
+    #[test]
+    fn test_metadata_mode() {
+        let mut inode = OpenedInode::new(Inode::empty());
+        inode.i_mode = 0o040755;
+        let metadata = Metadata::new(&inode, FileType::Directory);
+        let mut buf = [0u8; 10];
+        assert_eq!(metadata.mode_str(&mut buf), "drwxr-xr-x");
+        assert_eq!(metadata.permissions(), 0o755);
+
+        inode.i_mode = 0o100640;
+        let metadata = Metadata::new(&inode, FileType::Regular);
+        assert_eq!(metadata.mode_str(&mut buf), "-rw-r-----");
+    }
+
     #[test]
     fn test_inode_size() {
         println!("inode size:{} ", size_of::<Inode>());
@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests {
+    use kernel::shell::{history::History, line_editor::LineEditor};
+
+    #[test]
+    fn test_edit_in_middle() {
+        let mut editor: LineEditor<10> = LineEditor::new([0; 10]);
+        for byte in b"ecoh" {
+            assert!(editor.insert(*byte));
+        }
+        // ecoh -> echo：光标左移两格，删除o，再在h后面插入o
+        editor.move_left();
+        editor.move_left();
+        assert!(editor.delete());
+        assert_eq!(editor.get_line(), "ech");
+        editor.move_right();
+        editor.insert(b'o');
+        assert_eq!(editor.get_line(), "echo");
+        assert_eq!(editor.get_tail(), "");
+
+        assert_eq!(editor.move_home(), 4);
+        assert!(!editor.backspace());
+        assert!(!editor.move_left());
+        editor.insert(b' ');
+        assert_eq!(editor.get_line(), " echo");
+        assert_eq!(editor.get_tail(), "echo");
+        assert_eq!(editor.move_end(), 4);
+        assert!(!editor.delete());
+        assert!(editor.backspace());
+        assert_eq!(editor.get_line(), " ech");
+    }
+
+    #[test]
+    fn test_editor_full() {
+        let mut editor: LineEditor<3> = LineEditor::new([0; 3]);
+        assert!(editor.insert(b'a'));
+        assert!(editor.insert(b'b'));
+        assert!(editor.insert(b'c'));
+        assert!(!editor.insert(b'd'));
+        editor.set_line("hello");
+        assert_eq!(editor.get_line(), "hel");
+        assert_eq!(editor.get_cursor(), 3);
+    }
+
+    #[test]
+    fn test_history_browse() {
+        let mut history: History<3, 20> = History::new();
+        history.push("ls");
+        history.push("ls");
+        history.push("  ");
+        history.push("pwd");
+        assert_eq!(history.len(), 2);
+
+        assert_eq!(history.prev("ec"), Option::Some("pwd"));
+        assert_eq!(history.prev("ignored"), Option::Some("ls"));
+        assert_eq!(history.prev("ignored"), Option::None);
+        assert_eq!(history.next(), Option::Some("pwd"));
+        // 回到开始浏览之前输入的内容
+        assert_eq!(history.next(), Option::Some("ec"));
+        assert_eq!(history.next(), Option::None);
+    }
+
+    #[test]
+    fn test_history_ring() {
+        let mut history: History<3, 20> = History::new();
+        for line in ["a", "b", "c", "d", "e"] {
+            history.push(line);
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.get(0), Option::Some("c"));
+        assert_eq!(history.get(2), Option::Some("e"));
+        assert_eq!(history.get(3), Option::None);
+        assert_eq!(history.number_of(0), 3);
+        assert_eq!(history.number_of(2), 5);
+        assert_eq!(history.prev(""), Option::Some("e"));
+        history.reset_browsing();
+        assert_eq!(history.prev(""), Option::Some("e"));
+    }
+}
@@ -6,4 +6,22 @@ mod tests {
         let entry = paging::PageTableEntry::new_default(0x1234);
         println!("entry: {}", unsafe {*(&entry as *const paging::PageTableEntry as *const u32)});
     }
+
+    #[test]
+    fn test_copy_on_write_flag() {
+        let mut entry = paging::PageTableEntry::new_default(0x1000);
+        assert!(entry.writable());
+        assert!(!entry.is_cow());
+
+        entry.set_writable(false);
+        entry.set_cow(true);
+        assert!(!entry.writable());
+        assert!(entry.is_cow());
+        assert!(entry.present());
+        assert_eq!(entry.get_phy_addr(), 0x1000);
+
+        entry.set_cow(false);
+        entry.set_writable(true);
+        assert_eq!(entry.get_data(), paging::PageTableEntry::new_default(0x1000).get_data());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod tests {
+    use kernel::shell::{parser::{CommandLine, Connector, ParseError, RedirectKind}, variable::ShellVars};
+
+    /**
+     * 解析一行命令，返回每个命令的单词
+     */
+    fn words_of(line: &CommandLine) -> Vec<Vec<String>> {
+        line.pipelines().iter()
+            .flat_map(|pipeline| line.get_cmds(pipeline).iter())
+            .map(|cmd| line.get_words(cmd).map(String::from).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_quote_and_escape() {
+        let vars = ShellVars::new();
+        let mut line = CommandLine::new();
+        line.parse(r#"grep "a | b" 'x > y' my\ file "q\"t" 'a\b'"#, &vars).unwrap();
+        assert_eq!(line.pipelines().len(), 1);
+        assert_eq!(words_of(&line), vec![vec!["grep", "a | b", "x > y", "my file", "q\"t", "a\\b"]]);
+
+        // 空的引号也是一个参数
+        line.parse(r#"echo "" a"#, &vars).unwrap();
+        assert_eq!(words_of(&line), vec![vec!["echo", "", "a"]]);
+
+        assert_eq!(line.parse("echo 'abc", &vars), Result::Err(ParseError::UnterminatedQuote));
+        assert_eq!(line.parse("echo \"abc", &vars), Result::Err(ParseError::UnterminatedQuote));
+    }
+
+    #[test]
+    fn test_expand() {
+        let mut vars = ShellVars::new();
+        assert!(vars.set("NAME", "os in rust"));
+        assert!(!vars.set("1A", "x"));
+        vars.set_status(127);
+        let mut line = CommandLine::new();
+        line.parse(r#"echo $NAME "${NAME}!" '$NAME' \$NAME $? $ $MISSING"#, &vars).unwrap();
+        // 展开之后不再按照空白拆分
+        assert_eq!(words_of(&line), vec![vec!["echo", "os in rust", "os in rust!", "$NAME", "$NAME", "127", "$"]]);
+        assert_eq!(line.parse("echo ${NAME", &vars), Result::Err(ParseError::BadSubstitution));
+
+        // 命令前面的NAME=value是变量赋值
+        line.parse(r#"A=1 B="x y" echo C=2"#, &vars).unwrap();
+        let cmd = &line.get_cmds(&line.pipelines()[0])[0];
+        assert_eq!(line.get_assigns(cmd).collect::<Vec<_>>(), vec!["A=1", "B=x y"]);
+        assert_eq!(line.get_words(cmd).collect::<Vec<_>>(), vec!["echo", "C=2"]);
+        line.parse(r#""A"=1"#, &vars).unwrap();
+        let cmd = &line.get_cmds(&line.pipelines()[0])[0];
+        assert_eq!(line.get_assigns(cmd).count(), 0);
+    }
+
+    #[test]
+    fn test_params_and_comment() {
+        let mut vars = ShellVars::new();
+        vars.set_params("/init.sh", &["a b", "c"]);
+        let mut line = CommandLine::new();
+        line.parse(r#"echo $0 "$1" $2 $3 $# $10 a#b # comment | grep x"#, &vars).unwrap();
+        assert_eq!(line.pipelines().len(), 1);
+        assert_eq!(words_of(&line), vec![vec!["echo", "/init.sh", "a b", "c", "2", "a b0", "a#b"]]);
+
+        // 没有设置位置参数
+        line.parse("echo $# $1", &ShellVars::new()).unwrap();
+        assert_eq!(words_of(&line), vec![vec!["echo", "0"]]);
+        line.parse("# only comment", &vars).unwrap();
+        assert!(line.pipelines().is_empty());
+    }
+
+    #[test]
+    fn test_command_list() {
+        let vars = ShellVars::new();
+        let mut line = CommandLine::new();
+        line.parse("cat a.txt | grep x > out >> log && echo ok || echo fail; sleep 1 &", &vars).unwrap();
+        let pipelines = line.pipelines();
+        assert_eq!(pipelines.len(), 4);
+        assert_eq!(pipelines.iter().map(|p| p.get_connector()).collect::<Vec<_>>(),
+            vec![Connector::Always, Connector::And, Connector::Or, Connector::Always]);
+        assert_eq!(pipelines.iter().map(|p| p.is_background()).collect::<Vec<_>>(), vec![false, false, false, true]);
+        assert_eq!(line.get_text(&pipelines[0]), "cat a.txt | grep x > out >> log");
+        assert_eq!(line.get_text(&pipelines[3]), "sleep 1");
+
+        let cmds = line.get_cmds(&pipelines[0]);
+        assert_eq!(cmds.len(), 2);
+        assert_eq!(line.get_words(&cmds[1]).collect::<Vec<_>>(), vec!["grep", "x"]);
+        assert_eq!(line.get_redirects(&cmds[1]).collect::<Vec<_>>(),
+            vec![(RedirectKind::Truncate, "out"), (RedirectKind::Append, "log")]);
+        let (_, args) = line.get_cmd(&cmds[0]);
+        assert_eq!(args.as_slice(), ["a.txt"]);
+
+        // 空行没有任何管道
+        line.parse("   ", &vars).unwrap();
+        assert!(line.pipelines().is_empty());
+    }
+
+    #[test]
+    fn test_redirect() {
+        let vars = ShellVars::new();
+        let mut line = CommandLine::new();
+        line.parse(r#"grep a <in.txt 2>err.log >out 2>&1; grep x <<< "a x b" 2>> all.log"#, &vars).unwrap();
+        let pipelines = line.pipelines();
+        assert_eq!(pipelines.len(), 2);
+        let cmd = &line.get_cmds(&pipelines[0])[0];
+        assert_eq!(line.get_words(cmd).collect::<Vec<_>>(), vec!["grep", "a"]);
+        assert_eq!(line.get_redirects(cmd).collect::<Vec<_>>(), vec![
+            (RedirectKind::Input, "in.txt"),
+            (RedirectKind::ErrorTruncate, "err.log"),
+            (RedirectKind::Truncate, "out"),
+            (RedirectKind::ErrorToOutput, ""),
+        ]);
+        let cmd = &line.get_cmds(&pipelines[1])[0];
+        assert_eq!(line.get_redirects(cmd).collect::<Vec<_>>(), 
+            vec![(RedirectKind::HereString, "a x b"), (RedirectKind::ErrorAppend, "all.log")]);
+
+        // 不在单词开头的2，是单词的一部分
+        line.parse("echo a2>f 2", &vars).unwrap();
+        let cmd = &line.get_cmds(&line.pipelines()[0])[0];
+        assert_eq!(line.get_words(cmd).collect::<Vec<_>>(), vec!["echo", "a2", "2"]);
+        assert_eq!(line.get_redirects(cmd).collect::<Vec<_>>(), vec![(RedirectKind::Truncate, "f")]);
+
+        assert_eq!(line.parse("cat <", &vars), Result::Err(ParseError::UnexpectedEnd));
+        assert_eq!(line.parse("cat 2> <<< a", &vars), Result::Err(ParseError::UnexpectedToken("<<<")));
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let vars = ShellVars::new();
+        let mut line = CommandLine::new();
+        assert_eq!(line.parse("| ls", &vars), Result::Err(ParseError::UnexpectedToken("|")));
+        assert_eq!(line.parse("ls ;; ls", &vars), Result::Err(ParseError::UnexpectedToken(";")));
+        assert_eq!(line.parse("ls > > a", &vars), Result::Err(ParseError::UnexpectedToken(">")));
+        assert_eq!(line.parse("ls &&", &vars), Result::Err(ParseError::UnexpectedEnd));
+        assert_eq!(line.parse("ls >", &vars), Result::Err(ParseError::UnexpectedEnd));
+        assert!(line.parse("ls;", &vars).is_ok());
+    }
+}
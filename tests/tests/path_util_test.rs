@@ -29,6 +29,17 @@ mod tests {
         let s = "ls -alh -s".split_once(" ");
         println!("s: {:?}", s);
     }
+    #[test]
+    fn test_parse_cmd() {
+        let (cmd, args) = shell_util::parse_cmd("grep  hello   world ");
+        assert_eq!(format!("{:?}", cmd), "Custom(\"grep\")");
+        assert_eq!(args.as_slice(), &["hello", "world"]);
+
+        let (_, args) = shell_util::parse_cmd("ls");
+        assert!(args.is_empty());
+        assert_eq!(args.first(), Option::None);
+    }
+
     #[test]
     fn test_split() {
         let mut s = "/home/jackson".split("-");
@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+    use kernel::{shell::{parser::CommandLine, script::{Script, ScriptEnv, ScriptError}, variable::ShellVars}, userprog::TaskExitStatus};
+
+    /**
+     * 模拟shell：记录执行的命令（展开变量之后），true返回0，false返回1，more返回0直到次数用完
+     */
+    struct FakeEnv {
+        vars: ShellVars,
+        line: CommandLine,
+        ran: Vec<String>,
+        more_cnt: usize,
+    }
+
+    impl FakeEnv {
+        fn new() -> Self {
+            Self { vars: ShellVars::new(), line: CommandLine::new(), ran: Vec::new(), more_cnt: 0 }
+        }
+    }
+
+    impl ScriptEnv for FakeEnv {
+        fn run(&mut self, line: &str) -> TaskExitStatus {
+            self.line.parse(line, &self.vars).unwrap();
+            let cmd = &self.line.get_cmds(&self.line.pipelines()[0])[0];
+            let words: Vec<&str> = self.line.get_words(cmd).collect();
+            self.ran.push(words.join(" "));
+            match words[0] {
+                "false" => 1,
+                "more" if self.more_cnt == 0 => 1,
+                "more" => {
+                    self.more_cnt -= 1;
+                    0
+                },
+                _ => 0,
+            }
+        }
+
+        fn get_vars(&mut self) -> &mut ShellVars {
+            &mut self.vars
+        }
+    }
+
+    fn run_script(text: &str, env: &mut FakeEnv) -> TaskExitStatus {
+        let script = Script::parse(text).unwrap();
+        let mut words = CommandLine::new();
+        script.execute(env, &mut words)
+    }
+
+    #[test]
+    fn test_if_else() {
+        let text = "#!/sh\n\
+                    # comment\n\
+                    \n\
+                    if true; then\n  echo yes\nelse\n  echo no\nfi\n\
+                    if false\nthen\n  echo yes\nelse\n  echo no\n  false\nfi\n\
+                    if false; then\n  echo skipped\nfi\n";
+        let mut env = FakeEnv::new();
+        let status = run_script(text, &mut env);
+        assert_eq!(env.ran, vec!["true", "echo yes", "false", "echo no", "false", "false"]);
+        // 条件不成立、没有else的if，退出码是0
+        assert_eq!(status, 0);
+    }
+
+    #[test]
+    fn test_loops() {
+        let text = "for x in a \"b c\" $1; do\n  echo $x\ndone\n\
+                    while more\ndo\n  for y in 1 2\n  do\n    echo $x$y\n  done\ndone\n\
+                    for z in\ndo\n  echo never\ndone\n\
+                    echo $# $0";
+        let mut env = FakeEnv::new();
+        env.vars.set_params("/loop.sh", &["d"]);
+        env.more_cnt = 2;
+        let status = run_script(text, &mut env);
+        assert_eq!(env.ran, vec![
+            "echo a", "echo b c", "echo d",
+            "more", "echo d1", "echo d2", "more", "echo d1", "echo d2", "more",
+            "echo 1 /loop.sh",
+        ]);
+        assert_eq!(status, 0);
+    }
+
+    #[test]
+    fn test_script_error() {
+        assert_eq!(Script::parse("if true; then\necho a\n").err(), Option::Some(ScriptError::Unterminated(1)));
+        assert_eq!(Script::parse("echo a\nfi").err(), Option::Some(ScriptError::Unexpected(2)));
+        assert_eq!(Script::parse("while true; do\nelse\ndone").err(), Option::Some(ScriptError::Unexpected(2)));
+        assert_eq!(Script::parse("if true\nelse\nelse\nfi").err(), Option::Some(ScriptError::Unexpected(3)));
+        assert_eq!(Script::parse("for 1x in a; do\ndone").err(), Option::Some(ScriptError::BadSyntax(1)));
+        assert_eq!(Script::parse("if; then\nfi").err(), Option::Some(ScriptError::BadSyntax(1)));
+        assert_eq!(Script::parse("\n# only comment\n").unwrap().len(), 0);
+    }
+}
@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod tests {
+    use kernel::{signal::{self, SigAction}, thread::TaskStruct, userprog::ChildStatus};
+
+    #[test]
+    fn test_signal_no() {
+        assert!(!signal::is_valid(0));
+        assert!(signal::is_valid(signal::SIGINT));
+        assert!(signal::is_valid(signal::SIGCHLD));
+        assert!(!signal::is_valid(signal::SIGNAL_CNT as u32));
+
+        // 被信号结束的进程，退出码是128 + 信号编号
+        assert_eq!(signal::exit_status_of(signal::SIGINT), 130);
+        assert_eq!(signal::exit_status_of(signal::SIGKILL), 137);
+
+        assert!(signal::terminate_by_default(signal::SIGINT));
+        assert!(signal::terminate_by_default(signal::SIGTERM));
+        assert!(!signal::terminate_by_default(signal::SIGCHLD));
+
+        // 停止信号的默认动作是停止，SIGCONT默认忽略
+        assert!(signal::stop_by_default(signal::SIGSTOP));
+        assert!(signal::stop_by_default(signal::SIGTSTP));
+        assert!(signal::stop_by_default(signal::SIGTTIN));
+        assert!(!signal::stop_by_default(signal::SIGINT));
+        assert!(!signal::terminate_by_default(signal::SIGTSTP));
+        assert!(!signal::terminate_by_default(signal::SIGCONT));
+    }
+
+    #[test]
+    fn test_signal_action() {
+        let mut task: TaskStruct = unsafe { std::mem::zeroed() };
+        // 全0就是默认动作
+        assert_eq!(signal::get_action(&task, signal::SIGINT), SigAction::Default);
+
+        task.signal_actions[signal::SIGINT as usize] = 1;
+        assert_eq!(signal::get_action(&task, signal::SIGINT), SigAction::Ignore);
+
+        task.signal_actions[signal::SIGTERM as usize] = 0xc048100;
+        task.signal_trampoline = 0xc048200;
+        assert_eq!(signal::get_action(&task, signal::SIGTERM), SigAction::Handler { handler: 0xc048100, trampoline: 0xc048200 });
+
+        // exec之后，全部恢复默认
+        signal::reset_actions(&mut task);
+        assert_eq!(signal::get_action(&task, signal::SIGINT), SigAction::Default);
+        assert_eq!(signal::get_action(&task, signal::SIGTERM), SigAction::Default);
+    }
+
+    #[test]
+    fn test_deliverable() {
+        let mut task: TaskStruct = unsafe { std::mem::zeroed() };
+        assert!(!signal::has_deliverable(&task));
+
+        // SIGCHLD默认忽略，不打断阻塞
+        task.pending_signals = 1 << signal::SIGCHLD;
+        assert!(!signal::is_deliverable(&task, signal::SIGCHLD));
+        assert!(!signal::has_deliverable(&task));
+
+        // SIGINT默认结束进程，打断阻塞
+        task.pending_signals |= 1 << signal::SIGINT;
+        assert!(signal::has_deliverable(&task));
+
+        // 忽略了SIGINT，不打断
+        task.signal_actions[signal::SIGINT as usize] = 1;
+        assert!(!signal::has_deliverable(&task));
+
+        // 注册了处理函数的信号，打断
+        task.signal_actions[signal::SIGCHLD as usize] = 0xc048100;
+        assert!(signal::has_deliverable(&task));
+
+        // 停止信号，打断
+        task.pending_signals = 1 << signal::SIGTSTP;
+        assert!(signal::is_deliverable(&task, signal::SIGTSTP));
+        assert!(signal::has_deliverable(&task));
+    }
+
+    #[test]
+    fn test_child_status() {
+        let mut task: TaskStruct = unsafe { std::mem::zeroed() };
+        task.exit_status = Option::Some(3);
+        assert_eq!(ChildStatus::of(&task), ChildStatus::Exited(3));
+        assert_eq!(ChildStatus::of(&task).exit_code(), 3);
+
+        // 被信号结束的，优先报告信号
+        task.term_signal = Option::Some(signal::SIGKILL);
+        task.exit_status = Option::Some(signal::exit_status_of(signal::SIGKILL));
+        assert_eq!(ChildStatus::of(&task), ChildStatus::Signaled(signal::SIGKILL));
+        assert_eq!(ChildStatus::of(&task).exit_code(), 137);
+    }
+}
@@ -2,10 +2,38 @@ mod test {
     use std::mem::size_of;
 
     use kernel::thread::TaskStruct;
+    use os_in_rust_common::constants;
 
     #[test]
     pub fn task_struct_size() {
         println!("size: 0x{:x}", size_of::<TaskStruct>());
         println!("max addr in task: 0x{:x}", 0xc009e000 + size_of::<TaskStruct>());
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn mlfq_level_change() {
+        let mut task: TaskStruct = unsafe { std::mem::zeroed() };
+        task.priority = 5;
+        task.reset_ticks();
+        assert_eq!(task.left_ticks, 5);
+
+        // 用完时间片，降级。时间片翻倍
+        task.demote();
+        task.reset_ticks();
+        assert_eq!(task.mlfq_level, 1);
+        assert_eq!(task.left_ticks, 10);
+
+        // 最多降到最低一级
+        for _ in 0 .. constants::MLFQ_LEVEL_CNT {
+            task.demote();
+        }
+        assert_eq!(task.mlfq_level as usize, constants::MLFQ_LEVEL_CNT - 1);
+
+        // 等待I/O之后，升级
+        task.boost();
+        assert_eq!(task.mlfq_level as usize, constants::MLFQ_LEVEL_CNT - 2);
+        task.mlfq_level = 0;
+        task.boost();
+        assert_eq!(task.mlfq_level, 0);
+    }
+}
@@ -0,0 +1,32 @@
+#[cfg(test)]
+mod tests {
+    use kernel::device;
+    use os_in_rust_common::time::DateTime;
+
+    #[test]
+    fn test_from_timestamp() {
+        assert_eq!(DateTime::from_timestamp(0), DateTime { year: 1970, month: 1, day: 1, hour: 0, minute: 0, second: 0 });
+        // 闰年的2月29日
+        assert_eq!(DateTime::from_timestamp(951782400), DateTime { year: 2000, month: 2, day: 29, hour: 0, minute: 0, second: 0 });
+        assert_eq!(DateTime::from_timestamp(1700000000), DateTime { year: 2023, month: 11, day: 14, hour: 22, minute: 13, second: 20 });
+    }
+
+    #[test]
+    fn test_to_timestamp() {
+        let date_time = DateTime { year: 2024, month: 3, day: 1, hour: 8, minute: 30, second: 5 };
+        assert_eq!(DateTime::from_timestamp(date_time.to_timestamp()), date_time);
+        assert_eq!(DateTime { year: 2023, month: 11, day: 14, hour: 22, minute: 13, second: 20 }.to_timestamp(), 1700000000);
+        assert_eq!(format!("{}", date_time), "2024-03-01 08:30:05");
+    }
+
+    #[test]
+    fn test_hour_to_24() {
+        // 上午12点是0点，下午12点是中午
+        assert_eq!(device::hour_to_24(12, false), 0);
+        assert_eq!(device::hour_to_24(12, true), 12);
+        assert_eq!(device::hour_to_24(1, false), 1);
+        assert_eq!(device::hour_to_24(11, false), 11);
+        assert_eq!(device::hour_to_24(1, true), 13);
+        assert_eq!(device::hour_to_24(11, true), 23);
+    }
+}
@@ -0,0 +1,14 @@
+#[cfg(test)]
+mod tests {
+    use kernel::timer;
+
+    #[test]
+    fn test_ms_to_ticks() {
+        // 100Hz，一个tick是10毫秒。不足一个tick的，向上取整
+        assert_eq!(timer::ms_to_ticks(0), 0);
+        assert_eq!(timer::ms_to_ticks(1), 1);
+        assert_eq!(timer::ms_to_ticks(10), 1);
+        assert_eq!(timer::ms_to_ticks(11), 2);
+        assert_eq!(timer::ms_to_ticks(1000), 100);
+    }
+}
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+    use kernel::memory::{self, Vma, VmaBacking, VmaList};
+    use os_in_rust_common::domain::InodeNo;
+
+    #[test]
+    fn test_vma_range() {
+        let vma = Vma::new(0x8049000, 0x2000, memory::PROT_READ | memory::PROT_WRITE, VmaBacking::Anonymous);
+        assert_eq!(vma.len(), 0x2000);
+        assert!(vma.contains(0x8049000));
+        assert!(vma.contains(0x804afff));
+        assert!(!vma.contains(0x804b000));
+        assert!(vma.writable());
+
+        assert!(vma.overlaps(0x804a000, 0x804c000));
+        assert!(!vma.overlaps(0x804b000, 0x804c000));
+        assert!(!vma.overlaps(0x8048000, 0x8049000));
+    }
+
+    #[test]
+    fn test_file_vma_read_only() {
+        let vma = Vma::new(0x8049000, 0x1000, memory::PROT_READ, VmaBacking::File { i_no: InodeNo::new(3), offset: 0x1000 });
+        assert!(!vma.writable());
+        match vma.backing {
+            VmaBacking::File { i_no, offset } => {
+                assert_eq!(i_no.get_data(), 3);
+                assert_eq!(offset, 0x1000);
+            },
+            VmaBacking::Anonymous => panic!("should be file backed"),
+        }
+    }
+
+    /**
+     * 用泄漏的堆空间，构建一个VMA列表
+     */
+    fn new_list() -> VmaList {
+        VmaList::from_slots(Box::leak(vec![Option::None; 8].into_boxed_slice()))
+    }
+
+    /**
+     * 列表中所有VMA的范围，按照起始地址排序
+     */
+    fn ranges(list: &VmaList) -> Vec<(usize, usize)> {
+        let mut ranges: Vec<(usize, usize)> = list.iter().map(|vma| (vma.start, vma.end)).collect();
+        ranges.sort();
+        ranges
+    }
+
+    #[test]
+    fn test_remove_head_and_tail() {
+        let mut list = new_list();
+        // 移除开头的部分，文件映射的偏移量跟着移动
+        assert!(list.add(Vma::new(0x8049000, 0x4000, memory::PROT_READ, VmaBacking::File { i_no: InodeNo::new(3), offset: 0x1000 })));
+        list.remove_range(0x8048000, 0x804a000);
+        assert_eq!(ranges(&list), [(0x804a000, 0x804d000)]);
+        match list.find(0x804a000).unwrap().backing {
+            VmaBacking::File { offset, .. } => assert_eq!(offset, 0x2000),
+            VmaBacking::Anonymous => panic!("should be file backed"),
+        }
+
+        // 移除末尾的部分，偏移量不变
+        list.remove_range(0x804c000, 0x804e000);
+        assert_eq!(ranges(&list), [(0x804a000, 0x804c000)]);
+        match list.find(0x804a000).unwrap().backing {
+            VmaBacking::File { offset, .. } => assert_eq!(offset, 0x2000),
+            VmaBacking::Anonymous => panic!("should be file backed"),
+        }
+    }
+
+    #[test]
+    fn test_remove_middle() {
+        let mut list = new_list();
+        assert!(list.add(Vma::new(0x8049000, 0x4000, memory::PROT_READ | memory::PROT_WRITE, VmaBacking::Anonymous)));
+        // 从中间移除，拆分成两段
+        list.remove_range(0x804a000, 0x804b000);
+        assert_eq!(ranges(&list), [(0x8049000, 0x804a000), (0x804b000, 0x804d000)]);
+        assert!(list.find(0x804a000).is_none());
+        assert!(list.iter().all(|vma| vma.writable()));
+
+        // 整个移除
+        list.remove_range(0x8049000, 0x804a000);
+        assert_eq!(ranges(&list), [(0x804b000, 0x804d000)]);
+    }
+
+    #[test]
+    fn test_remove_across_vmas() {
+        let mut list = new_list();
+        assert!(list.add(Vma::new(0x8049000, 0x2000, memory::PROT_READ, VmaBacking::Anonymous)));
+        assert!(list.add(Vma::new(0x804b000, 0x2000, memory::PROT_READ | memory::PROT_WRITE, VmaBacking::Anonymous)));
+        // 跨过两个相邻的VMA：前一个截掉末尾，后一个截掉开头
+        list.remove_range(0x804a000, 0x804c000);
+        assert_eq!(ranges(&list), [(0x8049000, 0x804a000), (0x804c000, 0x804d000)]);
+        assert!(!list.find(0x8049000).unwrap().writable());
+        assert!(list.find(0x804c000).unwrap().writable());
+        assert!(list.has_room(6));
+    }
+}
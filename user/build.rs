@@ -5,5 +5,7 @@ fn main() {
     println!(
         "cargo:rustc-link-arg-bins=--script={}",
         local_path.join("user-linker.ld").display()
-    )
+    );
+    // 段不需要按页对齐，让ELF文件更紧凑（内核加载的时候，按照p_offset逐段拷贝）
+    println!("cargo:rustc-link-arg-bins=--nmagic");
 }
\ No newline at end of file
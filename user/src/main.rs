@@ -13,11 +13,12 @@ pub extern "C" fn main() {
     
     
     // 获取传过来的参数
-    let arg = env::get_args();
-    if arg.is_some() {
-        println!("args: {}", arg.unwrap());
-    } else {
-        println!("no args");
+    for (idx, arg) in env::args().enumerate() {
+        println!("argv[{}]: {}", idx, arg);
+    }
+    let path = env::var("PATH");
+    if path.is_some() {
+        println!("PATH: {}", path.unwrap());
     }
     // 调用系统调用退出，错误码是10
     sys_call::exit(10);